@@ -196,7 +196,7 @@ impl Client {
         debug!(target: "demo", "Loading dao-mint.zk");
         let zk_dao_mint_bincode = include_bytes!("../proof/dao-mint.zk.bin");
         let zk_dao_mint_bin = ZkBinary::decode(zk_dao_mint_bincode)?;
-        self.zk_bins.add_contract("dao-mint".to_string(), zk_dao_mint_bin, 13);
+        self.zk_bins.add_contract("dao-mint".to_string(), zk_dao_mint_bin);
 
         debug!(target: "demo", "Loading money-transfer contracts");
         let start = Instant::now();
@@ -217,22 +217,22 @@ impl Client {
         debug!(target: "demo", "Loading dao-propose-main.zk");
         let zk_dao_propose_main_bincode = include_bytes!("../proof/dao-propose-main.zk.bin");
         let zk_dao_propose_main_bin = ZkBinary::decode(zk_dao_propose_main_bincode)?;
-        self.zk_bins.add_contract("dao-propose-main".to_string(), zk_dao_propose_main_bin, 13);
+        self.zk_bins.add_contract("dao-propose-main".to_string(), zk_dao_propose_main_bin);
         debug!(target: "demo", "Loading dao-propose-burn.zk");
         let zk_dao_propose_burn_bincode = include_bytes!("../proof/dao-propose-burn.zk.bin");
         let zk_dao_propose_burn_bin = ZkBinary::decode(zk_dao_propose_burn_bincode)?;
-        self.zk_bins.add_contract("dao-propose-burn".to_string(), zk_dao_propose_burn_bin, 13);
+        self.zk_bins.add_contract("dao-propose-burn".to_string(), zk_dao_propose_burn_bin);
         debug!(target: "demo", "Loading dao-vote-main.zk");
         let zk_dao_vote_main_bincode = include_bytes!("../proof/dao-vote-main.zk.bin");
         let zk_dao_vote_main_bin = ZkBinary::decode(zk_dao_vote_main_bincode)?;
-        self.zk_bins.add_contract("dao-vote-main".to_string(), zk_dao_vote_main_bin, 13);
+        self.zk_bins.add_contract("dao-vote-main".to_string(), zk_dao_vote_main_bin);
         debug!(target: "demo", "Loading dao-vote-burn.zk");
         let zk_dao_vote_burn_bincode = include_bytes!("../proof/dao-vote-burn.zk.bin");
         let zk_dao_vote_burn_bin = ZkBinary::decode(zk_dao_vote_burn_bincode)?;
-        self.zk_bins.add_contract("dao-vote-burn".to_string(), zk_dao_vote_burn_bin, 13);
+        self.zk_bins.add_contract("dao-vote-burn".to_string(), zk_dao_vote_burn_bin);
         let zk_dao_exec_bincode = include_bytes!("../proof/dao-exec.zk.bin");
         let zk_dao_exec_bin = ZkBinary::decode(zk_dao_exec_bincode)?;
-        self.zk_bins.add_contract("dao-exec".to_string(), zk_dao_exec_bin, 13);
+        self.zk_bins.add_contract("dao-exec".to_string(), zk_dao_exec_bin);
 
         let cashier_signature_public = self.cashier_wallet.signature_public();
 
@@ -100,7 +100,8 @@ impl ZkContractTable {
         Self { table: HashMap::new() }
     }
 
-    pub fn add_contract(&mut self, key: String, bincode: ZkBinary, k_param: u32) {
+    pub fn add_contract(&mut self, key: String, bincode: ZkBinary) {
+        let k_param = bincode.k;
         let witnesses = empty_witnesses(&bincode);
         let circuit = ZkCircuit::new(witnesses, bincode.clone());
         let proving_key = ProvingKey::build(k_param, &circuit);
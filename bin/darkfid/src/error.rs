@@ -36,6 +36,8 @@ pub enum RpcError {
     // Transaction-related errors
     TxSimulationFail = -32110,
     TxBroadcastFail = -32111,
+    TxNotFound = -32112,
+    TxRemoveFail = -32113,
 
     // State-related errors,
     NotSynced = -32120,
@@ -63,6 +65,8 @@ fn to_tuple(e: RpcError) -> (i64, String) {
         // Transaction-related errors
         RpcError::TxSimulationFail => "Failed simulating transaction state change",
         RpcError::TxBroadcastFail => "Failed broadcasting transaction",
+        RpcError::TxNotFound => "Transaction not found in mempool",
+        RpcError::TxRemoveFail => "Failed removing transaction from mempool",
         // State-related errors
         RpcError::NotSynced => "Blockchain is not synced",
         RpcError::UnknownSlot => "Did not find slot",
@@ -29,9 +29,10 @@ use darkfi::{
     async_daemonize, cli_desc,
     consensus::{
         constants::{
-            MAINNET_BOOTSTRAP_TIMESTAMP, MAINNET_GENESIS_HASH_BYTES, MAINNET_GENESIS_TIMESTAMP,
-            MAINNET_INITIAL_DISTRIBUTION, TESTNET_BOOTSTRAP_TIMESTAMP, TESTNET_GENESIS_HASH_BYTES,
-            TESTNET_GENESIS_TIMESTAMP, TESTNET_INITIAL_DISTRIBUTION,
+            MAINNET_BOOTSTRAP_TIMESTAMP, MAINNET_EMISSION, MAINNET_GENESIS_HASH_BYTES,
+            MAINNET_GENESIS_TIMESTAMP, MAINNET_INITIAL_DISTRIBUTION, TESTNET_BOOTSTRAP_TIMESTAMP,
+            TESTNET_EMISSION, TESTNET_GENESIS_HASH_BYTES, TESTNET_GENESIS_TIMESTAMP,
+            TESTNET_INITIAL_DISTRIBUTION,
         },
         proto::{ProtocolProposal, ProtocolSync, ProtocolSyncConsensus, ProtocolTx},
         task::{block_sync_task, proposal_task},
@@ -183,6 +184,7 @@ pub struct Darkfid {
 // JSON-RPC methods
 mod rpc_blockchain;
 mod rpc_misc;
+mod rpc_p2p;
 mod rpc_tx;
 mod rpc_wallet;
 
@@ -207,6 +209,14 @@ impl RequestHandler for Darkfid {
             Some("get_info") => return self.misc_get_info(req.id, params).await,
             Some("get_consensus_info") => return self.misc_get_consensus_info(req.id, params).await,
 
+            // ===========
+            // P2P methods
+            // ===========
+            Some("p2p.get_bans") => return self.p2p_get_bans(req.id, params).await,
+            Some("p2p.get_stats") => return self.p2p_get_stats(req.id, params).await,
+            Some("p2p.ban") => return self.p2p_ban(req.id, params).await,
+            Some("p2p.unban") => return self.p2p_unban(req.id, params).await,
+
             // ==================
             // Blockchain methods
             // ==================
@@ -214,6 +224,12 @@ impl RequestHandler for Darkfid {
             Some("blockchain.last_known_slot") => {
                 return self.blockchain_last_known_slot(req.id, params).await
             }
+            Some("blockchain.current_slot") => {
+                return self.blockchain_current_slot(req.id, params).await
+            }
+            Some("blockchain.get_supply") => {
+                return self.blockchain_get_supply(req.id, params).await
+            }
             Some("blockchain.merkle_roots") => {
                 return self.blockchain_merkle_roots(req.id, params).await
             }
@@ -228,6 +244,8 @@ impl RequestHandler for Darkfid {
             // Transaction methods
             // ===================
             Some("tx.broadcast") => return self.tx_broadcast(req.id, params).await,
+            Some("tx.pending") => return self.tx_pending(req.id, params).await,
+            Some("tx.remove") => return self.tx_remove(req.id, params).await,
 
             // ==============
             // Wallet methods
@@ -299,24 +317,27 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'_>>) -> Result<()> {
     let sled_db = sled::open(&db_path)?;
 
     // Initialize validator state
-    let (bootstrap_ts, genesis_ts, genesis_data, initial_distribution) = match args.chain.as_str() {
-        "mainnet" => (
-            *MAINNET_BOOTSTRAP_TIMESTAMP,
-            *MAINNET_GENESIS_TIMESTAMP,
-            *MAINNET_GENESIS_HASH_BYTES,
-            *MAINNET_INITIAL_DISTRIBUTION,
-        ),
-        "testnet" => (
-            *TESTNET_BOOTSTRAP_TIMESTAMP,
-            *TESTNET_GENESIS_TIMESTAMP,
-            *TESTNET_GENESIS_HASH_BYTES,
-            *TESTNET_INITIAL_DISTRIBUTION,
-        ),
-        x => {
-            error!("Unsupported chain `{}`", x);
-            return Err(Error::UnsupportedChain)
-        }
-    };
+    let (bootstrap_ts, genesis_ts, genesis_data, initial_distribution, emission) =
+        match args.chain.as_str() {
+            "mainnet" => (
+                *MAINNET_BOOTSTRAP_TIMESTAMP,
+                *MAINNET_GENESIS_TIMESTAMP,
+                *MAINNET_GENESIS_HASH_BYTES,
+                *MAINNET_INITIAL_DISTRIBUTION,
+                *MAINNET_EMISSION,
+            ),
+            "testnet" => (
+                *TESTNET_BOOTSTRAP_TIMESTAMP,
+                *TESTNET_GENESIS_TIMESTAMP,
+                *TESTNET_GENESIS_HASH_BYTES,
+                *TESTNET_INITIAL_DISTRIBUTION,
+                *TESTNET_EMISSION,
+            ),
+            x => {
+                error!("Unsupported chain `{}`", x);
+                return Err(Error::UnsupportedChain)
+            }
+        };
 
     // Parse faucet addresses
     let mut faucet_pubkeys = vec![];
@@ -338,6 +359,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'_>>) -> Result<()> {
         genesis_ts,
         genesis_data,
         initial_distribution,
+        emission,
         wallet.clone(),
         faucet_pubkeys,
         args.consensus,
@@ -83,6 +83,51 @@ impl Darkfid {
         JsonResponse::new(json!(last_slot.0), id).into()
     }
 
+    // RPCAPI:
+    // Returns the current consensus slot, derived from the genesis timestamp.
+    // Transactions broadcasted now are verified against this slot.
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.current_slot", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": 1234, "id": 1}
+    pub async fn blockchain_current_slot(&self, id: Value, params: &[Value]) -> JsonResult {
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let current_slot = self.validator_state.read().await.consensus.current_slot();
+        JsonResponse::new(json!(current_slot), id).into()
+    }
+
+    // RPCAPI:
+    // Returns the native token supply: the initial distribution, the amount
+    // emitted through block rewards by finalized blocks, and their total.
+    // Also returns the reward of the current slot and the maximum emission
+    // set by the emission schedule, null if uncapped.
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.get_supply", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"initial": 1000, "emitted": 160, "total": 1160, "reward": 16, "max_emission": 1000000}, "id": 1}
+    pub async fn blockchain_get_supply(&self, id: Value, params: &[Value]) -> JsonResult {
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let validator_state = self.validator_state.read().await;
+        let consensus = &validator_state.consensus;
+
+        let emitted = consensus.emitted_supply();
+        let initial = consensus.initial_distribution;
+        let reward = consensus.reward(consensus.current_slot());
+        let supply = json!({
+            "initial": initial,
+            "emitted": emitted,
+            "total": initial.saturating_add(emitted),
+            "reward": reward,
+            "max_emission": consensus.emission.max_emission,
+        });
+
+        JsonResponse::new(supply, id).into()
+    }
+
     // RPCAPI:
     // Queries the blockchain database for all available merkle roots.
     //
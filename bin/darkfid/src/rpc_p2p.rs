@@ -0,0 +1,120 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use log::info;
+use serde_json::{json, Value};
+
+use darkfi::{
+    net::P2pPtr,
+    rpc::jsonrpc::{ErrorCode::InvalidParams, JsonError, JsonResponse, JsonResult},
+};
+
+use super::Darkfid;
+
+impl Darkfid {
+    /// Auxiliary function returning all the P2P networks we run.
+    fn p2p_networks(&self) -> Vec<&P2pPtr> {
+        self.sync_p2p.iter().chain(self.consensus_p2p.iter()).collect()
+    }
+
+    // RPCAPI:
+    // Returns the active peer bans of the sync and consensus P2P networks,
+    // as pairs of banned host and ban expiry (UNIX timestamp).
+    //
+    // --> {"jsonrpc": "2.0", "method": "p2p.get_bans", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"sync": [["1.2.3.4", 1674000000]], "consensus": []}, "id": 1}
+    pub async fn p2p_get_bans(&self, id: Value, _params: &[Value]) -> JsonResult {
+        let sync = match &self.sync_p2p {
+            Some(p2p) => p2p.hosts().bans().await,
+            None => vec![],
+        };
+
+        let consensus = match &self.consensus_p2p {
+            Some(p2p) => p2p.hosts().bans().await,
+            None => vec![],
+        };
+
+        JsonResponse::new(json!({"sync": sync, "consensus": consensus}), id).into()
+    }
+
+    // RPCAPI:
+    // Returns the traffic counters of the sync and consensus P2P networks:
+    // payload bytes and messages sent and received per connected channel,
+    // per message command, and in total.
+    //
+    // --> {"jsonrpc": "2.0", "method": "p2p.get_stats", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"sync": {"channels": {"tcp://1.2.3.4:8342": {"total": {...}, "commands": {...}}}, "total": {...}}, "consensus": null}, "id": 1}
+    pub async fn p2p_get_stats(&self, id: Value, _params: &[Value]) -> JsonResult {
+        let sync = match &self.sync_p2p {
+            Some(p2p) => p2p.get_stats().await,
+            None => Value::Null,
+        };
+
+        let consensus = match &self.consensus_p2p {
+            Some(p2p) => p2p.get_stats().await,
+            None => Value::Null,
+        };
+
+        JsonResponse::new(json!({"sync": sync, "consensus": consensus}), id).into()
+    }
+
+    // RPCAPI:
+    // Bans a peer host on all P2P networks for the given number of seconds,
+    // disconnecting it if it's currently connected.
+    // Returns `true` upon success.
+    //
+    // --> {"jsonrpc": "2.0", "method": "p2p.ban", "params": ["1.2.3.4", 86400], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+    pub async fn p2p_ban(&self, id: Value, params: &[Value]) -> JsonResult {
+        if params.len() != 2 || !params[0].is_string() || !params[1].is_u64() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let host = params[0].as_str().unwrap();
+        let duration = params[1].as_u64().unwrap();
+
+        info!("[RPC] p2p.ban: Banning {} for {} seconds", host, duration);
+        for p2p in self.p2p_networks() {
+            p2p.ban(host, duration).await;
+        }
+
+        JsonResponse::new(json!(true), id).into()
+    }
+
+    // RPCAPI:
+    // Lifts the ban of a peer host on all P2P networks.
+    // Returns `true` if the host was banned on any of them.
+    //
+    // --> {"jsonrpc": "2.0", "method": "p2p.unban", "params": ["1.2.3.4"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+    pub async fn p2p_unban(&self, id: Value, params: &[Value]) -> JsonResult {
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let host = params[0].as_str().unwrap();
+
+        info!("[RPC] p2p.unban: Lifting ban of {}", host);
+        let mut lifted = false;
+        for p2p in self.p2p_networks() {
+            lifted |= p2p.hosts().unban(host).await;
+        }
+
+        JsonResponse::new(json!(lifted), id).into()
+    }
+}
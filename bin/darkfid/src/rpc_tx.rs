@@ -110,9 +110,16 @@ impl Darkfid {
         if self.consensus_p2p.is_some() {
             // Consider we're participating in consensus here?
             // The append_tx function performs a state transition check.
-            if !self.validator_state.write().await.append_tx(tx.clone()).await {
-                error!("[RPC] tx.broadcast: Failed to append transaction to mempool");
-                return server_error(RpcError::TxBroadcastFail, id, None)
+            match self.validator_state.write().await.append_tx(tx.clone()).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    error!("[RPC] tx.broadcast: Failed to append transaction to mempool");
+                    return server_error(RpcError::TxBroadcastFail, id, None)
+                }
+                Err(e) => {
+                    error!("[RPC] tx.broadcast: Failed to validate state transition: {}", e);
+                    return server_error(RpcError::TxSimulationFail, id, None)
+                }
             }
         } else {
             // We'll perform the state transition check here.
@@ -137,4 +144,60 @@ impl Darkfid {
         let tx_hash = blake3::hash(&serialize(&tx)).to_hex().as_str().to_string();
         JsonResponse::new(json!(tx_hash), id).into()
     }
+
+    // RPCAPI:
+    // Queries the node's mempool for its pending transactions.
+    // Returns a list of transaction IDs in their arrival order.
+    //
+    // --> {"jsonrpc": "2.0", "method": "tx.pending", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": ["txID...", ...], "id": 1}
+    pub async fn tx_pending(&self, id: Value, params: &[Value]) -> JsonResult {
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let tx_hashes: Vec<String> = self
+            .validator_state
+            .read()
+            .await
+            .mempool
+            .hashes()
+            .iter()
+            .map(|x| x.to_hex().as_str().to_string())
+            .collect();
+
+        JsonResponse::new(json!(tx_hashes), id).into()
+    }
+
+    // RPCAPI:
+    // Removes a pending transaction from the node's mempool, given its ID.
+    // Returns `true` if the transaction was removed, otherwise, a corresponding
+    // error.
+    //
+    // --> {"jsonrpc": "2.0", "method": "tx.remove", "params": ["txID..."], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+    pub async fn tx_remove(&self, id: Value, params: &[Value]) -> JsonResult {
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let tx_hash = match blake3::Hash::from_hex(params[0].as_str().unwrap().trim()) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("[RPC] tx.remove: Failed decoding transaction ID: {}", e);
+                return server_error(RpcError::ParseError, id, None)
+            }
+        };
+
+        match self.validator_state.write().await.mempool.remove(&tx_hash) {
+            Ok(Some(_)) => {}
+            Ok(None) => return server_error(RpcError::TxNotFound, id, None),
+            Err(e) => {
+                error!("[RPC] tx.remove: Failed removing transaction from mempool: {}", e);
+                return server_error(RpcError::TxRemoveFail, id, None)
+            }
+        }
+
+        JsonResponse::new(json!(true), id).into()
+    }
 }
@@ -117,6 +117,7 @@ pub struct ConnectInfo {
     pub last_msg: String,
     pub last_status: String,
     pub remote_node_id: String,
+    pub traffic: TrafficInfo,
 }
 
 impl ConnectInfo {
@@ -131,11 +132,32 @@ impl ConnectInfo {
         last_msg: String,
         last_status: String,
         remote_node_id: String,
+        traffic: TrafficInfo,
     ) -> Self {
-        Self { id, addr, state, parent, msg_log, is_empty, last_msg, last_status, remote_node_id }
+        Self {
+            id,
+            addr,
+            state,
+            parent,
+            msg_log,
+            is_empty,
+            last_msg,
+            last_status,
+            remote_node_id,
+            traffic,
+        }
     }
 }
 
+/// Payload bytes and messages sent and received over a channel.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize, Eq)]
+pub struct TrafficInfo {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub msgs_sent: u64,
+    pub msgs_received: u64,
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, Eq)]
 pub struct LilithInfo {
     pub id: String,
@@ -31,7 +31,7 @@ use crate::{
     error::{DnetViewError, DnetViewResult},
     model::{
         ConnectInfo, LilithInfo, Model, NetworkInfo, NodeInfo, SelectableObject, Session,
-        SessionInfo,
+        SessionInfo, TrafficInfo,
     },
     rpc::RpcConnect,
     util::{is_empty_session, make_connect_id, make_empty_id, make_node_id, make_session_id},
@@ -135,6 +135,7 @@ impl DataParser {
         let last_msg = "Null".to_string();
         let last_status = "Null".to_string();
         let remote_node_id = "Null".to_string();
+        let traffic = TrafficInfo::default();
         let connect_info = ConnectInfo::new(
             id,
             addr,
@@ -145,6 +146,7 @@ impl DataParser {
             last_msg,
             last_status,
             remote_node_id,
+            traffic,
         );
         connects.push(connect_info.clone());
 
@@ -334,6 +336,7 @@ impl DataParser {
                         let last_msg = "Null".to_string();
                         let last_status = "Null".to_string();
                         let remote_node_id = "Null".to_string();
+                        let traffic = TrafficInfo::default();
                         let connect_info = ConnectInfo::new(
                             id,
                             addr,
@@ -344,6 +347,7 @@ impl DataParser {
                             last_msg,
                             last_status,
                             remote_node_id,
+                            traffic,
                         );
                         connects.push(connect_info);
                     }
@@ -401,6 +405,7 @@ impl DataParser {
                                 true => "no remote id".to_string(),
                                 false => remote_node_id,
                             };
+                            let traffic = parse_traffic(&info2.unwrap()["stats"]);
                             let connect_info = ConnectInfo::new(
                                 id,
                                 addr,
@@ -411,6 +416,7 @@ impl DataParser {
                                 last_msg,
                                 last_status,
                                 r_node_id,
+                                traffic,
                             );
                             connects.push(connect_info.clone());
                         }
@@ -457,6 +463,7 @@ impl DataParser {
         let msg = "Null".to_string();
         let status = "Null".to_string();
         let remote_node_id = "Null".to_string();
+        let traffic = TrafficInfo::default();
         let connect_info = ConnectInfo::new(
             connect_id.clone(),
             addr,
@@ -467,6 +474,7 @@ impl DataParser {
             msg,
             status,
             remote_node_id,
+            traffic,
         );
         connects.push(connect_info);
         let parent = connect_id;
@@ -517,6 +525,7 @@ impl DataParser {
                             let last_msg = "Null".to_string();
                             let last_status = "Null".to_string();
                             let remote_node_id = "Null".to_string();
+                            let traffic = TrafficInfo::default();
                             let connect_info = ConnectInfo::new(
                                 id,
                                 addr,
@@ -527,6 +536,7 @@ impl DataParser {
                                 last_msg,
                                 last_status,
                                 remote_node_id,
+                                traffic,
                             );
                             connects.push(connect_info.clone());
                         }
@@ -556,6 +566,7 @@ impl DataParser {
                                 true => "no remote id".to_string(),
                                 false => remote_node_id,
                             };
+                            let traffic = parse_traffic(&channel["stats"]);
                             let connect_info = ConnectInfo::new(
                                 id,
                                 addr,
@@ -566,6 +577,7 @@ impl DataParser {
                                 last_msg,
                                 last_status,
                                 r_node_id,
+                                traffic,
                             );
                             connects.push(connect_info.clone());
                         }
@@ -598,3 +610,9 @@ impl DataParser {
         }
     }
 }
+
+/// Parse the traffic counters of a channel. Nodes running older versions
+/// don't report them, in which case they're left empty.
+fn parse_traffic(stats: &Value) -> TrafficInfo {
+    serde_json::from_value(stats.clone()).unwrap_or_default()
+}
@@ -121,3 +121,20 @@ pub fn make_empty_id(node_id: &str, session: &Session, count: u64) -> Result<Str
 pub fn is_empty_session(connects: &[ConnectInfo]) -> bool {
     return connects.iter().all(|conn| conn.is_empty)
 }
+
+/// Format a number of bytes using binary unit prefixes.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{} {}", bytes, UNITS[0]),
+        _ => format!("{:.1} {}", value, UNITS[unit]),
+    }
+}
@@ -32,6 +32,7 @@ use darkfi::util::time::NanoTimestamp;
 use crate::{
     error::{DnetViewError, DnetViewResult},
     model::{NodeInfo, SelectableObject},
+    util::format_bytes,
 };
 
 //use log::debug;
@@ -225,8 +226,11 @@ impl<'a> View {
                                         addr => {
                                             let name = Span::styled(
                                                 format!(
-                                                    "        {} ({})",
-                                                    addr, connection.remote_node_id
+                                                    "        {} ({}) [sent {} / recv {}]",
+                                                    addr,
+                                                    connection.remote_node_id,
+                                                    format_bytes(connection.traffic.bytes_sent),
+                                                    format_bytes(connection.traffic.bytes_received)
                                                 ),
                                                 style,
                                             );
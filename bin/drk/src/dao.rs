@@ -20,6 +20,7 @@ use darkfi_sdk::{
     crypto::{SecretKey, TokenId},
     incrementalmerkletree::Position,
     pasta::pallas,
+    ContractCall,
 };
 use darkfi_serial::{SerialDecodable, SerialEncodable};
 
@@ -66,3 +67,23 @@ pub struct Dao {
     /// The call index in the transaction where the DAO was deployed
     pub call_index: Option<u32>,
 }
+
+#[derive(Debug, Clone)]
+/// A DAO proposal stored in the wallet
+pub struct DaoProposal {
+    /// Numeric identifier of the proposal in the wallet
+    pub id: u64,
+    /// Contract calls executed once the proposal passes
+    pub calls: Vec<ContractCall>,
+    /// Slot the proposal was created at
+    pub creation_slot: u64,
+    /// Number of slots the proposal can be voted on
+    pub duration: u64,
+}
+
+impl DaoProposal {
+    /// Slot at which voting ends and the proposal can be executed
+    pub fn deadline(&self) -> u64 {
+        self.creation_slot.saturating_add(self.duration)
+    }
+}
@@ -17,23 +17,25 @@
  */
 
 use std::{
-    env::set_current_dir,
     fs::{read, read_dir, read_to_string, File},
     io::{ErrorKind, Write},
-    path::{Path, PathBuf},
+    path::Path,
     str::FromStr,
 };
 
-use rand::{rngs::OsRng, RngCore};
-
+use anyhow::{anyhow, Result};
 use darkfi::{
-    crypto::keypair::SecretKey,
-    node::{MemoryState, State},
-    runtime::vm_runtime::Runtime,
+    tx::Transaction,
     util::cli::{fg_green, fg_red},
     zkas::ZkBinary,
-    Error, Result,
 };
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey, SecretKey, DEPLOYOOOR_CONTRACT_ID},
+    deploy::DeployParamsV1,
+    tx::{ContractCall, DEFAULT_GAS_LIMIT},
+};
+use darkfi_serial::serialize;
+use rand::{rngs::OsRng, CryptoRng, RngCore};
 
 const CIRCUIT_DIR_NAME: &str = "proof";
 const CONTRACT_FILE_NAME: &str = "contract.wasm";
@@ -43,22 +45,31 @@ const DEPLOY_KEY_NAME: &str = "deploy.key";
 /// This key allows to update the wasm code and the zk circuits on chain
 /// by creating a signature. When deployed, the contract can be accessed
 /// by requesting the public counterpart of this secret key.
-pub fn create_deploy_key(mut rng: impl RngCore, path: &Path) -> Result<SecretKey> {
+pub fn create_deploy_key(mut rng: impl CryptoRng + RngCore, path: &Path) -> Result<SecretKey> {
     let secret = SecretKey::random(&mut rng);
-    let mut file = File::create(path)?;
-    file.write_all(bs58::encode(&secret.to_bytes()).into_string().as_bytes())?;
+    write_deploy_key(secret, path)?;
     Ok(secret)
 }
 
+/// Writes a given deploy key, e.g. one derived from a wallet's mnemonic,
+/// to a file on the filesystem.
+pub fn write_deploy_key(secret: SecretKey, path: &Path) -> Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(secret.to_string().as_bytes())?;
+    Ok(())
+}
+
 /// Reads a deploy key from a file on the filesystem and returns it.
-fn read_deploy_key(s: &Path) -> core::result::Result<SecretKey, std::io::Error> {
-    eprintln!("Trying to read deploy key from file: {:?}", s);
-    let contents = read_to_string(s)?;
-    let secret = SecretKey::from_str(&contents).unwrap();
-    Ok(secret)
+fn read_deploy_key(path: &Path) -> std::result::Result<SecretKey, std::io::Error> {
+    eprintln!("Trying to read deploy key from file: {:?}", path);
+    let contents = read_to_string(path)?;
+    match SecretKey::from_str(contents.trim()) {
+        Ok(v) => Ok(v),
+        Err(e) => Err(std::io::Error::new(ErrorKind::InvalidData, e.to_string())),
+    }
 }
 
-/// Creates necessary data to deploy a given smart contract on the network.
+/// Creates a transaction deploying a given smart contract on the network.
 /// For consistency, we point this function to a directory where our smart
 /// contract and the compiled circuits are contained. This is going to give
 /// us a uniform approach to scm and gives a generic layout of the source:
@@ -68,114 +79,102 @@ fn read_deploy_key(s: &Path) -> core::result::Result<SecretKey, std::io::Error>
 /// ├── deploy.key
 /// ├── Makefile
 /// ├── proof
-/// │   ├── circuit0.zk
-/// │   ├── circuit0.zk.bin
-/// │   ├── circuit1.zk
-/// │   └── circuit1.zk.bin
+/// │   ├── circuit0.zk
+/// │   ├── circuit0.zk.bin
+/// │   ├── circuit1.zk
+/// │   └── circuit1.zk.bin
 /// ├── contract.wasm
 /// ├── src
-/// │   └── lib.rs
+/// │   └── lib.rs
 /// └── tests
 /// ```
-//pub fn create_deploy_data(path: &Path) -> Result<ContractDeploy> {
-pub fn create_deploy_data(path: &Path) -> Result<()> {
-    // Try to chdir into the contract directory
-    if let Err(e) = set_current_dir(path) {
-        eprintln!("Failed to chdir into {:?}", path);
-        return Err(e.into())
-    }
-
-    let deploy_key: SecretKey;
-
-    let deploy_key = match read_deploy_key(&PathBuf::from(DEPLOY_KEY_NAME)) {
-        Ok(v) => deploy_key = v,
-        Err(e) => {
-            if e.kind() == ErrorKind::NotFound {
-                // We didn't find a deploy key, generate a new one.
-                eprintln!("Did not find an existing key, creating a new one.");
-                match create_deploy_key(&mut OsRng, &PathBuf::from(DEPLOY_KEY_NAME)) {
-                    Ok(v) => {
-                        eprintln!("Created new deploy key in \"{}\".", DEPLOY_KEY_NAME);
-                        deploy_key = v;
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to create new deploy key");
-                        return Err(e)
-                    }
+/// If there is no deploy key in the directory, `new_key` is written there, or a
+/// random one is created if it is `None`. Deploying again with the same key
+/// upgrades the contract on chain. `ix` is passed to the contract's init function.
+pub fn create_deploy_tx(
+    path: &Path,
+    ix: Vec<u8>,
+    new_key: Option<SecretKey>,
+) -> Result<Transaction> {
+    let deploy_key_path = path.join(DEPLOY_KEY_NAME);
+    let deploy_key = match read_deploy_key(&deploy_key_path) {
+        Ok(v) => v,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            // We didn't find a deploy key, generate a new one.
+            eprintln!("Did not find an existing key, creating a new one.");
+            let v = match new_key {
+                Some(v) => {
+                    write_deploy_key(v, &deploy_key_path)?;
+                    v
                 }
-            }
+                None => create_deploy_key(&mut OsRng, &deploy_key_path)?,
+            };
+            eprintln!("Created new deploy key in {:?}", deploy_key_path);
+            v
+        }
+        Err(e) => {
             eprintln!("Failed to read deploy key");
             return Err(e.into())
         }
     };
 
-    // Search for ZK circuits in the directory. If none are found, we'll bail.
-    // The logic searches for `.zk.bin` files created by zkas.
-    eprintln!("Searching for compiled ZK circuits in \"{}\" ...", CIRCUIT_DIR_NAME);
-    let mut circuits = vec![];
-    for i in read_dir(CIRCUIT_DIR_NAME)? {
-        if let Err(e) = i {
-            eprintln!("Error iterating over \"{}\" directory", CIRCUIT_DIR_NAME);
-            return Err(e.into())
-        }
+    let public_key = PublicKey::from_secret(deploy_key);
+    eprintln!("Contract ID: {}", ContractId::derive(deploy_key));
+
+    // Search for ZK circuits in the directory. The logic searches for
+    // `.zk.bin` files created by zkas.
+    let circuit_dir = path.join(CIRCUIT_DIR_NAME);
+    eprintln!("Searching for compiled ZK circuits in {:?} ...", circuit_dir);
+    let mut zkas_bincodes = vec![];
+    if circuit_dir.is_dir() {
+        for i in read_dir(&circuit_dir)? {
+            let f = i?;
+            let fname = f.file_name();
+            let Some(fname) = fname.to_str() else { continue };
+
+            if fname.ends_with(".zk.bin") {
+                // Validate that the files can be properly decoded
+                eprintln!("{} {}", fg_green("Found:"), f.path().display());
+                let buf = read(f.path())?;
+                if let Err(e) = ZkBinary::decode(&buf) {
+                    eprintln!(
+                        "{} Failed to decode zkas bincode in {:?}",
+                        fg_red("Error:"),
+                        f.path()
+                    );
+                    return Err(e.into())
+                }
 
-        let f = i.unwrap();
-        let fname = f.file_name();
-        let fname = fname.to_str().unwrap();
-
-        if fname.ends_with(".zk.bin") {
-            // Validate that the files can be properly decoded
-            eprintln!("{} {}", fg_green("Found:"), f.path().display());
-            let buf = read(f.path())?;
-            if let Err(e) = ZkBinary::decode(&buf) {
-                eprintln!("{} Failed to decode zkas bincode in {:?}", fg_red("Error:"), f.path());
-                return Err(e)
+                zkas_bincodes.push(buf);
             }
-
-            circuits.push(buf.clone());
         }
     }
 
-    if circuits.is_empty() {
-        return Err(Error::Custom("Found no valid ZK circuits".to_string()))
+    if zkas_bincodes.is_empty() {
+        eprintln!("Found no ZK circuits, deploying the contract without any.");
     }
 
-    /* FIXME
-    // Validate wasm binary. We inspect the bincode and try to load it into
-    // the wasm runtime. If loaded, we then look for the `ENTRYPOINT` function
-    // which we hardcode into our sdk and runtime and is the canonical way to
-    // run wasm binaries on chain.
-    eprintln!("Inspecting wasm binary in \"{}\"", CONTRACT_FILE_NAME);
-    let wasm_bytes = read(CONTRACT_FILE_NAME)?;
-    eprintln!("Initializing moch wasm runtime to check validity");
-    let runtime = match Runtime::new(&wasm_bytes, MemoryState::new(State::dummy()?)) {
-        Ok(v) => {
-            eprintln!("Found {} wasm binary", fg_green("valid"));
-            v
-        }
-        Err(e) => {
-            eprintln!("Failed to initialize wasm runtime");
-            return Err(e)
-        }
+    // The wasm bincode itself gets validated by the nodes when the
+    // transaction is executed.
+    let wasm_path = path.join(CONTRACT_FILE_NAME);
+    eprintln!("Reading wasm bincode from {:?}", wasm_path);
+    let wasm_bincode = match read(&wasm_path) {
+        Ok(v) => v,
+        Err(e) => return Err(anyhow!("Failed to read {:?}: {}", wasm_path, e)),
     };
 
-    eprintln!("Looking for entrypoint function inside the wasm");
-    let cs = ContractSection::Exec;
-    if let Err(e) = runtime.instance.exports.get_function(cs.name()) {
-        eprintln!("{} Could not find entrypoint function", fg_red("Error:"));
-        return Err(e.into())
-    }
+    let params = DeployParamsV1 { wasm_bincode, zkas_bincodes, public_key, ix };
+    let calls = vec![ContractCall {
+        contract_id: *DEPLOYOOOR_CONTRACT_ID,
+        data: serialize(&params),
+        gas_limit: DEFAULT_GAS_LIMIT,
+    }];
 
-    // TODO: Create a ZK proof enforcing the deploy key relations with their public
-    // counterparts (public key and contract address)
-    let mut total_bytes = 0;
-    total_bytes += wasm_bytes.len();
-    for circuit in circuits {
-        total_bytes += circuit.len();
-    }
-    */
+    // The transaction is signed with the deploy key, which proves we're
+    // allowed to deploy to this contract ID.
+    let mut tx = Transaction { calls, proofs: vec![vec![]], signatures: vec![] };
+    let sigs = tx.create_sigs(&mut OsRng, &[deploy_key])?;
+    tx.signatures = vec![sigs];
 
-    // TODO: Return the data back to the main function, and work further in creating
-    // a transaction and broadcasting it.
-    Ok(())
+    Ok(tx)
 }
@@ -0,0 +1,174 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Hierarchical deterministic derivation of Pallas secret keys.
+//!
+//! A BIP39 mnemonic is turned into a 64 byte seed, from which a master key
+//! and chain code are derived. Child keys are derived along the path
+//! `m/purpose/index`, where `purpose` separates the kinds of keys the wallet
+//! uses. Since the scheme has no public derivation, every step is hardened.
+
+use anyhow::{anyhow, Result};
+use bip39::Mnemonic;
+use darkfi_sdk::{
+    crypto::{util::hash_to_base, SecretKey},
+    pasta::group::ff::PrimeField,
+};
+use rand::{rngs::OsRng, RngCore};
+
+/// Derivation purpose for Money contract keypairs
+pub const HD_PURPOSE_MONEY: u32 = 0;
+/// Derivation purpose for DAO secret keys
+pub const HD_PURPOSE_DAO: u32 = 1;
+/// Derivation purpose for contract deploy keys
+pub const HD_PURPOSE_DEPLOY: u32 = 2;
+
+/// Amount of Money keys derived and scanned for when restoring a wallet
+pub const HD_RESTORE_KEYS: u32 = 20;
+
+const MASTER_SECRET_PERSONA: &[u8] = b"DarkFi_HDMasterS";
+const MASTER_CHAIN_PERSONA: &[u8] = b"DarkFi_HDMasterC";
+const CHILD_SECRET_PERSONA: &[u8] = b"DarkFi_HDChild_S";
+const CHILD_CHAIN_PERSONA: &[u8] = b"DarkFi_HDChild_C";
+
+/// A secret key along with the chain code used to derive its children
+#[derive(Copy, Clone)]
+pub struct ExtendedSecretKey {
+    pub secret: SecretKey,
+    chain_code: [u8; 32],
+}
+
+impl ExtendedSecretKey {
+    /// Derive the master key from a mnemonic seed
+    pub fn from_seed(seed: &[u8]) -> Self {
+        let secret = SecretKey::from(hash_to_base(MASTER_SECRET_PERSONA, seed));
+        let chain_code = hash_to_chain_code(MASTER_CHAIN_PERSONA, seed);
+        Self { secret, chain_code }
+    }
+
+    /// Derive the child key at `index`
+    pub fn derive_child(&self, index: u32) -> Self {
+        let mut data = Vec::with_capacity(68);
+        data.extend_from_slice(&self.chain_code);
+        data.extend_from_slice(&self.secret.inner().to_repr());
+        data.extend_from_slice(&index.to_le_bytes());
+
+        let secret = SecretKey::from(hash_to_base(CHILD_SECRET_PERSONA, &data));
+        let chain_code = hash_to_chain_code(CHILD_CHAIN_PERSONA, &data);
+        Self { secret, chain_code }
+    }
+
+    /// Derive the key found at the given path, starting from this key
+    pub fn derive_path(&self, path: &[u32]) -> Self {
+        path.iter().fold(*self, |key, index| key.derive_child(*index))
+    }
+}
+
+fn hash_to_chain_code(persona: &[u8], data: &[u8]) -> [u8; 32] {
+    let hash = blake2b_simd::Params::new().hash_length(32).personal(persona).hash(data);
+    hash.as_bytes().try_into().unwrap()
+}
+
+/// Derive the secret key for `purpose` at `index` from a mnemonic seed
+pub fn derive_secret(seed: &[u8], purpose: u32, index: u32) -> SecretKey {
+    ExtendedSecretKey::from_seed(seed).derive_path(&[purpose, index]).secret
+}
+
+/// Generate a new random 24 word mnemonic
+pub fn generate_mnemonic() -> Result<Mnemonic> {
+    let mut entropy = [0u8; 32];
+    OsRng.fill_bytes(&mut entropy);
+    Mnemonic::from_entropy(&entropy).map_err(|e| anyhow!("Failed to create mnemonic: {}", e))
+}
+
+/// Parse a mnemonic phrase and return the seed keys are derived from
+pub fn mnemonic_to_seed(phrase: &str) -> Result<[u8; 64]> {
+    let mnemonic =
+        Mnemonic::parse(phrase.trim()).map_err(|e| anyhow!("Invalid mnemonic: {}", e))?;
+    Ok(mnemonic.to_seed(""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // BIP39 test vector, with an empty passphrase
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon \
+                          abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn derive_secret_deterministic() {
+        let seed = mnemonic_to_seed(PHRASE).unwrap();
+        let seed_again = mnemonic_to_seed(PHRASE).unwrap();
+        assert_eq!(
+            derive_secret(&seed, HD_PURPOSE_MONEY, 0),
+            derive_secret(&seed_again, HD_PURPOSE_MONEY, 0)
+        );
+
+        // Every path derives a different key
+        let paths = [(HD_PURPOSE_MONEY, 0), (HD_PURPOSE_MONEY, 1), (HD_PURPOSE_DAO, 0)];
+        for (i, (purpose, index)) in paths.iter().enumerate() {
+            for (other_purpose, other_index) in &paths[i + 1..] {
+                assert_ne!(
+                    derive_secret(&seed, *purpose, *index),
+                    derive_secret(&seed, *other_purpose, *other_index)
+                );
+            }
+        }
+
+        // Deriving the path step by step gives the same key
+        let key = ExtendedSecretKey::from_seed(&seed).derive_child(HD_PURPOSE_DAO).derive_child(0);
+        assert_eq!(key.secret, derive_secret(&seed, HD_PURPOSE_DAO, 0));
+
+        let mnemonic = generate_mnemonic().unwrap();
+        assert_eq!(mnemonic_to_seed(&mnemonic.to_string()).unwrap(), mnemonic.to_seed(""));
+    }
+
+    #[test]
+    fn derive_secret_vectors() {
+        let seed = mnemonic_to_seed(PHRASE).unwrap();
+
+        let vectors = [
+            (
+                HD_PURPOSE_MONEY,
+                0,
+                "0x3677e30bc788e4c19df1bab6e9ce3bfb6580f6e6f3951e8323ab5e18555274b8",
+            ),
+            (
+                HD_PURPOSE_MONEY,
+                1,
+                "0x32e627089e96de42fade51bde03d1c698c0ed8123233e58ae4428ce31db0186a",
+            ),
+            (
+                HD_PURPOSE_DAO,
+                0,
+                "0x0ed712ca37e3e077ad77c119fba8e654dd8544b58eb2ef4342b9960e77d43155",
+            ),
+            (
+                HD_PURPOSE_DEPLOY,
+                0,
+                "0x3585b2cb84f58caa36dd83599cb98ef440ba68ecf72cff9b634a95f1278a7df9",
+            ),
+        ];
+
+        for (purpose, index, expected) in vectors {
+            let secret = derive_secret(&seed, purpose, index);
+            assert_eq!(format!("{:?}", secret.inner()), expected);
+        }
+    }
+}
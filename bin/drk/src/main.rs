@@ -18,6 +18,7 @@
 
 use std::{
     io::{stdin, Read},
+    path::PathBuf,
     process::exit,
     str::FromStr,
     time::Instant,
@@ -53,6 +54,9 @@ mod rpc_airdrop;
 /// Payment methods
 mod rpc_transfer;
 
+/// Token issuance methods
+mod rpc_token;
+
 /// Swap methods
 mod rpc_swap;
 use rpc_swap::PartialSwapData;
@@ -74,6 +78,14 @@ use cli_util::{parse_token_pair, parse_value_pair};
 mod dao;
 use dao::DaoParams;
 
+/// Contract deployment
+mod deploy_contract;
+use deploy_contract::create_deploy_tx;
+
+/// Hierarchical deterministic key derivation
+mod hd;
+use hd::{HD_PURPOSE_DAO, HD_PURPOSE_DEPLOY};
+
 #[derive(Parser)]
 #[command(about = cli_desc!())]
 struct Args {
@@ -127,6 +139,18 @@ enum Subcmd {
         #[arg(long)]
         /// Print all the coins in the wallet
         coins: bool,
+
+        #[arg(long)]
+        /// Generate a mnemonic to back up the wallet, new keys are derived from it
+        mnemonic: bool,
+
+        #[arg(long)]
+        /// Restore keys from a mnemonic in stdin and rescan the blockchain for their coins
+        restore_mnemonic: bool,
+
+        #[arg(long, requires = "restore_mnemonic")]
+        /// Replace a different mnemonic seed the wallet already holds when restoring
+        force: bool,
     },
 
     /// Unspend a coin
@@ -167,6 +191,10 @@ enum Subcmd {
     #[command(subcommand)]
     Otc(OtcSubcmd),
 
+    /// Token functionalities
+    #[command(subcommand)]
+    Token(TokenSubcmd),
+
     /// Inspect a transaction from stdin
     Inspect,
 
@@ -185,6 +213,10 @@ enum Subcmd {
     #[command(subcommand)]
     Dao(DaoSubcmd),
 
+    /// Contract functionalities
+    #[command(subcommand)]
+    Contract(ContractSubcmd),
+
     /// Scan the blockchain and parse relevant transactions
     Scan {
         #[arg(long)]
@@ -224,6 +256,54 @@ enum OtcSubcmd {
     Sign,
 }
 
+#[derive(Subcommand)]
+enum TokenSubcmd {
+    /// Generate a new mint authority and print the ID of its token
+    GenerateMint,
+
+    /// List the mint authorities in the wallet
+    List,
+
+    /// Create a transaction minting tokens of a token we hold the mint authority for
+    Mint {
+        /// Token ID to mint
+        token: String,
+
+        /// Amount to mint
+        amount: String,
+
+        /// Recipient address
+        recipient: String,
+
+        #[arg(long)]
+        /// Make this the last mint of the token, fixing its supply
+        fixed_supply: bool,
+    },
+
+    /// Create a transaction freezing the supply of a token, so it can't be minted anymore
+    Freeze {
+        /// Token ID to freeze
+        token: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ContractSubcmd {
+    /// Create a transaction deploying (or upgrading) the contract found in
+    /// the given directory, signed with its deploy key
+    Deploy {
+        /// Path to the contract directory
+        path: PathBuf,
+
+        /// Optional base58-encoded payload for the contract's init function
+        ix: Option<String>,
+
+        #[arg(long)]
+        /// Derive a new deploy key from the wallet's mnemonic if none exists
+        derive: bool,
+    },
+}
+
 #[derive(Subcommand)]
 enum DaoSubcmd {
     /// Create DAO parameters
@@ -236,11 +316,40 @@ enum DaoSubcmd {
         approval_ratio: f64,
         /// DAO's governance token ID
         gov_token_id: String,
+
+        #[arg(long)]
+        /// Derive the DAO secret key from the wallet's mnemonic
+        derive: bool,
     },
 
     /// View DAO data from stdin
     View,
 
+    /// Create updated DAO parameters from the DAO data in stdin.
+    /// The new DAO replaces the old one once a proposal calling
+    /// `DAO::update()` with it passes and is executed.
+    Update {
+        #[arg(long)]
+        /// New minimum amount of governance tokens needed to open a proposal
+        proposer_limit: Option<u64>,
+
+        #[arg(long)]
+        /// New minimal threshold of participating total tokens needed for a proposal to pass
+        quorum: Option<u64>,
+
+        #[arg(long)]
+        /// New ratio of winning votes/total votes needed for a proposal to pass (2 decimals)
+        approval_ratio: Option<f64>,
+
+        #[arg(long)]
+        /// New governance token ID
+        gov_token_id: Option<String>,
+
+        #[arg(long)]
+        /// Generate a new DAO keypair
+        rotate_keys: bool,
+    },
+
     /// Import DAO data from stdin
     Import {
         /// Named identifier for the DAO
@@ -356,6 +465,9 @@ async fn main() -> Result<()> {
             import_secrets,
             tree,
             coins,
+            mnemonic,
+            restore_mnemonic,
+            force,
         } => {
             if !initialize &&
                 !keygen &&
@@ -364,7 +476,9 @@ async fn main() -> Result<()> {
                 !secrets &&
                 !tree &&
                 !coins &&
-                !import_secrets
+                !import_secrets &&
+                !mnemonic &&
+                !restore_mnemonic
             {
                 eprintln!("Error: You must use at least one flag for this subcommand");
                 eprintln!("Run with \"wallet -h\" to see the subcommand usage.");
@@ -482,6 +596,36 @@ async fn main() -> Result<()> {
                 return Ok(())
             }
 
+            if mnemonic {
+                let phrase =
+                    drk.wallet_mnemonic().await.with_context(|| "Failed to generate mnemonic")?;
+
+                drk.rpc_client.close().await?;
+
+                eprintln!("Write down this mnemonic, it can restore the keys derived from now on:");
+                println!("{}", phrase);
+
+                return Ok(())
+            }
+
+            if restore_mnemonic {
+                let mut buf = String::new();
+                stdin().read_to_string(&mut buf)?;
+
+                let pubkeys = drk
+                    .wallet_restore_mnemonic(&buf, force)
+                    .await
+                    .with_context(|| "Failed to restore wallet from mnemonic")?;
+
+                drk.rpc_client.close().await?;
+
+                for key in pubkeys {
+                    println!("{}", key);
+                }
+
+                return Ok(())
+            }
+
             unreachable!()
         }
 
@@ -613,6 +757,60 @@ async fn main() -> Result<()> {
             }
         }
 
+        Subcmd::Token(cmd) => {
+            let rpc_client = RpcClient::new(args.endpoint)
+                .await
+                .with_context(|| "Could not connect to darkfid RPC endpoint")?;
+
+            let drk = Drk { rpc_client };
+
+            match cmd {
+                TokenSubcmd::GenerateMint => {
+                    let token_id = drk
+                        .token_generate_mint()
+                        .await
+                        .with_context(|| "Failed to generate mint authority")?;
+
+                    println!("Token ID: {}", token_id);
+                    Ok(())
+                }
+
+                TokenSubcmd::List => {
+                    drk.token_list().await.with_context(|| "Failed to list mint authorities")?;
+                    Ok(())
+                }
+
+                TokenSubcmd::Mint { token, amount, recipient, fixed_supply } => {
+                    let _ = f64::from_str(&amount).with_context(|| "Invalid amount")?;
+                    let token_id =
+                        TokenId::try_from(token.as_str()).with_context(|| "Invalid Token ID")?;
+                    let rcpt =
+                        PublicKey::from_str(&recipient).with_context(|| "Invalid recipient")?;
+
+                    let tx = drk
+                        .mint_token(&amount, rcpt, token_id, fixed_supply)
+                        .await
+                        .with_context(|| "Failed to create token mint transaction")?;
+
+                    println!("{}", bs58::encode(&serialize(&tx)).into_string());
+                    Ok(())
+                }
+
+                TokenSubcmd::Freeze { token } => {
+                    let token_id =
+                        TokenId::try_from(token.as_str()).with_context(|| "Invalid Token ID")?;
+
+                    let tx = drk
+                        .freeze_token(token_id)
+                        .await
+                        .with_context(|| "Failed to create token freeze transaction")?;
+
+                    println!("{}", bs58::encode(&serialize(&tx)).into_string());
+                    Ok(())
+                }
+            }
+        }
+
         Subcmd::Inspect => {
             let mut buf = String::new();
             stdin().read_to_string(&mut buf)?;
@@ -657,6 +855,38 @@ async fn main() -> Result<()> {
             Ok(())
         }
 
+        Subcmd::Contract(cmd) => match cmd {
+            ContractSubcmd::Deploy { path, ix, derive } => {
+                let ix = match ix {
+                    Some(v) => bs58::decode(v).into_vec().with_context(|| "Invalid payload")?,
+                    None => vec![],
+                };
+
+                let new_key = if derive {
+                    let rpc_client = RpcClient::new(args.endpoint)
+                        .await
+                        .with_context(|| "Could not connect to darkfid RPC endpoint")?;
+
+                    let drk = Drk { rpc_client };
+                    let Some(secret) = drk.wallet_hd_derive(HD_PURPOSE_DEPLOY).await? else {
+                        eprintln!("Error: Wallet holds no mnemonic to derive the key from");
+                        exit(1);
+                    };
+                    drk.rpc_client.close().await?;
+                    Some(secret)
+                } else {
+                    None
+                };
+
+                let tx = create_deploy_tx(&path, ix, new_key)
+                    .with_context(|| "Failed to create contract deployment transaction")?;
+
+                println!("{}", bs58::encode(&serialize(&tx)).into_string());
+
+                Ok(())
+            }
+        },
+
         Subcmd::Scan { reset, list, checkpoint } => {
             let rpc_client = RpcClient::new(args.endpoint)
                 .await
@@ -692,7 +922,7 @@ async fn main() -> Result<()> {
         }
 
         Subcmd::Dao(cmd) => match cmd {
-            DaoSubcmd::Create { proposer_limit, quorum, approval_ratio, gov_token_id } => {
+            DaoSubcmd::Create { proposer_limit, quorum, approval_ratio, gov_token_id, derive } => {
                 if approval_ratio > 1.0 {
                     eprintln!("Error: Approval ratio cannot be >1.0");
                     exit(1);
@@ -704,7 +934,22 @@ async fn main() -> Result<()> {
                 let gov_token_id =
                     TokenId::try_from(gov_token_id.as_str()).with_context(|| "Invalid Token ID")?;
 
-                let secret_key = SecretKey::random(&mut OsRng);
+                let secret_key = if derive {
+                    let rpc_client = RpcClient::new(args.endpoint)
+                        .await
+                        .with_context(|| "Could not connect to darkfid RPC endpoint")?;
+
+                    let drk = Drk { rpc_client };
+                    let Some(secret) = drk.wallet_hd_derive(HD_PURPOSE_DAO).await? else {
+                        eprintln!("Error: Wallet holds no mnemonic to derive the key from");
+                        exit(1);
+                    };
+                    drk.rpc_client.close().await?;
+                    secret
+                } else {
+                    SecretKey::random(&mut OsRng)
+                };
+
                 let bulla_blind = pallas::Base::random(&mut OsRng);
 
                 let dao_params = DaoParams {
@@ -740,6 +985,55 @@ async fn main() -> Result<()> {
                 Ok(())
             }
 
+            DaoSubcmd::Update {
+                proposer_limit,
+                quorum,
+                approval_ratio,
+                gov_token_id,
+                rotate_keys,
+            } => {
+                let mut buf = String::new();
+                stdin().read_to_string(&mut buf)?;
+                let bytes = bs58::decode(&buf.trim()).into_vec()?;
+                let mut dao_params: DaoParams = deserialize(&bytes)?;
+
+                if let Some(proposer_limit) = proposer_limit {
+                    dao_params.proposer_limit = proposer_limit;
+                }
+
+                if let Some(quorum) = quorum {
+                    dao_params.quorum = quorum;
+                }
+
+                if let Some(approval_ratio) = approval_ratio {
+                    if approval_ratio > 1.0 {
+                        eprintln!("Error: Approval ratio cannot be >1.0");
+                        exit(1);
+                    }
+
+                    dao_params.approval_ratio_quot = 100_u64;
+                    dao_params.approval_ratio_base =
+                        (approval_ratio * dao_params.approval_ratio_quot as f64) as u64;
+                }
+
+                if let Some(gov_token_id) = gov_token_id {
+                    dao_params.gov_token_id = TokenId::try_from(gov_token_id.as_str())
+                        .with_context(|| "Invalid Token ID")?;
+                }
+
+                if rotate_keys {
+                    dao_params.secret_key = SecretKey::random(&mut OsRng);
+                }
+
+                // A fresh blind is always used, so the new DAO bulla differs
+                // from the old one even if no parameters changed.
+                dao_params.bulla_blind = pallas::Base::random(&mut OsRng);
+
+                let encoded = bs58::encode(&serialize(&dao_params)).into_string();
+                println!("{}", encoded);
+                Ok(())
+            }
+
             DaoSubcmd::Import { dao_name } => {
                 let mut buf = String::new();
                 stdin().read_to_string(&mut buf)?;
@@ -765,7 +1059,19 @@ async fn main() -> Result<()> {
 
             DaoSubcmd::Propose { dao_name, recv_pubkey, amount, token_id, serial } => todo!(),
 
-            DaoSubcmd::Proposals { dao_name } => todo!(),
+            DaoSubcmd::Proposals { dao_name } => {
+                let rpc_client = RpcClient::new(args.endpoint.clone())
+                    .await
+                    .with_context(|| "Could not connect to darkfid RPC endpoint")?;
+
+                let drk = Drk { rpc_client };
+
+                drk.dao_list_proposals(&dao_name)
+                    .await
+                    .with_context(|| "Failed to list DAO proposals")?;
+
+                Ok(())
+            }
 
             DaoSubcmd::Proposal { dao_name, proposal } => todo!(),
 
@@ -30,18 +30,22 @@ use darkfi::{
 };
 use darkfi_money_contract::{
     client::{
-        Coin, EncryptedNote, OwnCoin, MONEY_COINS_COL_COIN, MONEY_COINS_COL_COIN_BLIND,
+        Coin, EncryptedNote, Note, OwnCoin, MONEY_COINS_COL_COIN, MONEY_COINS_COL_COIN_BLIND,
         MONEY_COINS_COL_IS_SPENT, MONEY_COINS_COL_LEAF_POSITION, MONEY_COINS_COL_MEMO,
         MONEY_COINS_COL_NULLIFIER, MONEY_COINS_COL_SECRET, MONEY_COINS_COL_SERIAL,
         MONEY_COINS_COL_TOKEN_BLIND, MONEY_COINS_COL_TOKEN_ID, MONEY_COINS_COL_VALUE,
         MONEY_COINS_COL_VALUE_BLIND, MONEY_COINS_TABLE, MONEY_INFO_COL_LAST_SCANNED_SLOT,
         MONEY_INFO_TABLE,
     },
-    model::{MoneyTransferParams, Output},
+    model::{MoneyFeeParams, MoneyTokenMintParams, MoneyTransferParams, Output},
     MoneyFunction,
 };
 use darkfi_sdk::{
-    crypto::{contract_id::MONEY_CONTRACT_ID, poseidon_hash, ContractId, MerkleNode, Nullifier},
+    crypto::{
+        contract_id::MONEY_CONTRACT_ID, pallas, pasta_prelude::*, poseidon_hash, ContractId,
+        MerkleNode, Nullifier, PublicKey, ValueBlind, DARK_TOKEN_ID,
+    },
+    fee::FeeClaimParamsV1,
     incrementalmerkletree::Tree,
 };
 use darkfi_serial::{deserialize, serialize};
@@ -53,6 +57,14 @@ use url::Url;
 use super::Drk;
 use crate::cli_util::kaching;
 
+/// A coin minted by a money contract call found while scanning a block
+enum ScannedCoin {
+    /// Coin carrying an encrypted note for its recipient
+    Output(Output),
+    /// Public coin minted for a block proposer's fee claim
+    FeeClaim(FeeClaimParamsV1),
+}
+
 impl Drk {
     /// Subscribes to darkfid's JSON-RPC notification endpoint that serves
     /// new finalized blocks. Upon receiving them, all the transactions are
@@ -132,7 +144,7 @@ impl Drk {
         eprintln!("Iterating over {} transactions", block.txs.len());
 
         let mut nullifiers: Vec<Nullifier> = vec![];
-        let mut outputs: Vec<Output> = vec![];
+        let mut outputs: Vec<ScannedCoin> = vec![];
 
         let contract_id = *MONEY_CONTRACT_ID;
 
@@ -146,7 +158,7 @@ impl Drk {
                         nullifiers.push(input.nullifier);
                     }
                     for output in params.outputs {
-                        outputs.push(output);
+                        outputs.push(ScannedCoin::Output(output));
                     }
                     continue
                 }
@@ -158,10 +170,33 @@ impl Drk {
                         nullifiers.push(input.nullifier);
                     }
                     for output in params.outputs {
-                        outputs.push(output);
+                        outputs.push(ScannedCoin::Output(output));
                     }
                     continue
                 }
+
+                if call.contract_id == contract_id && call.data[0] == MoneyFunction::Mint as u8 {
+                    eprintln!("Found Money::Mint in call {} in tx {}", j, i);
+                    let params: MoneyTokenMintParams = deserialize(&call.data[1..])?;
+                    outputs.push(ScannedCoin::Output(params.output));
+                    continue
+                }
+
+                if call.contract_id == contract_id && call.data[0] == MoneyFunction::Fee as u8 {
+                    eprintln!("Found Money::Fee in call {} in tx {}", j, i);
+                    let params: MoneyFeeParams = deserialize(&call.data[1..])?;
+                    nullifiers.push(params.input.nullifier);
+                    outputs.push(ScannedCoin::Output(params.output));
+                    continue
+                }
+
+                if call.contract_id == contract_id && call.data[0] == MoneyFunction::FeeClaim as u8
+                {
+                    eprintln!("Found Money::FeeClaim in call {} in tx {}", j, i);
+                    let params: FeeClaimParamsV1 = deserialize(&call.data[1..])?;
+                    outputs.push(ScannedCoin::FeeClaim(params));
+                    continue
+                }
             }
         }
 
@@ -178,31 +213,72 @@ impl Drk {
         let mut owncoins = vec![];
 
         for output in outputs {
-            let coin = output.coin;
+            let (coin, enc_note, claim) = match output {
+                ScannedCoin::Output(output) => {
+                    let enc_note = EncryptedNote {
+                        ciphertext: output.ciphertext,
+                        ephem_public: output.ephem_public,
+                    };
+                    (output.coin, Some(enc_note), None)
+                }
+                ScannedCoin::FeeClaim(params) => {
+                    let (pub_x, pub_y) = params.public_key.xy();
+                    let coin = poseidon_hash([
+                        pub_x,
+                        pub_y,
+                        pallas::Base::from(params.value),
+                        DARK_TOKEN_ID.inner(),
+                        params.serial,
+                        pallas::Base::zero(),
+                        pallas::Base::zero(),
+                        params.coin_blind,
+                    ]);
+                    (coin, None, Some(params))
+                }
+            };
 
             // Append the new coin to the Merkle tree. Every coin has to be added.
             tree.append(&MerkleNode::from(coin));
 
-            // Attempt to decrypt the note
-            let enc_note =
-                EncryptedNote { ciphertext: output.ciphertext, ephem_public: output.ephem_public };
-
             for secret in &secrets {
-                if let Ok(note) = enc_note.decrypt(secret) {
-                    eprintln!("Successfully decrypted a note");
-                    eprintln!("Witnessing coin in Merkle tree");
-                    let leaf_position = tree.witness().unwrap();
-
-                    let owncoin = OwnCoin {
-                        coin: Coin::from(coin),
-                        note: note.clone(),
-                        secret: *secret,
-                        nullifier: Nullifier::from(poseidon_hash([secret.inner(), note.serial])),
-                        leaf_position,
-                    };
+                // Attempt to decrypt the note, or for fee claims rebuild it
+                // from the public parameters if the coin is ours.
+                let note = match (&enc_note, &claim) {
+                    (Some(enc_note), _) => match enc_note.decrypt(secret) {
+                        Ok(note) => note,
+                        Err(_) => continue,
+                    },
+                    (None, Some(params))
+                        if params.public_key == PublicKey::from_secret(*secret) =>
+                    {
+                        Note {
+                            serial: params.serial,
+                            value: params.value,
+                            token_id: *DARK_TOKEN_ID,
+                            spend_hook: pallas::Base::zero(),
+                            user_data: pallas::Base::zero(),
+                            coin_blind: params.coin_blind,
+                            value_blind: ValueBlind::zero(),
+                            token_blind: ValueBlind::zero(),
+                            memo: vec![],
+                        }
+                    }
+                    _ => continue,
+                };
 
-                    owncoins.push(owncoin);
-                }
+                eprintln!("Successfully found a coin for us");
+                eprintln!("Witnessing coin in Merkle tree");
+                let leaf_position = tree.witness().unwrap();
+
+                let owncoin = OwnCoin {
+                    coin: Coin::from(coin),
+                    note: note.clone(),
+                    secret: *secret,
+                    nullifier: Nullifier::from(poseidon_hash([secret.inner(), note.serial])),
+                    leaf_position,
+                };
+
+                owncoins.push(owncoin);
             }
         }
 
@@ -313,6 +389,15 @@ impl Drk {
         Ok(txid)
     }
 
+    /// Queries darkfid for the current consensus slot
+    pub async fn current_slot(&self) -> Result<u64> {
+        let req = JsonRequest::new("blockchain.current_slot", json!([]));
+        let rep = self.rpc_client.request(req).await?;
+
+        let slot = serde_json::from_value(rep)?;
+        Ok(slot)
+    }
+
     /// Queries darkfid for a block with given slot
     async fn get_block_by_slot(&self, slot: u64) -> Result<Option<BlockInfo>> {
         let req = JsonRequest::new("blockchain.get_slot", json!([slot]));
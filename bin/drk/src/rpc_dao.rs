@@ -15,15 +15,141 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use darkfi::{
+    consensus::constants::SLOT_TIME, rpc::jsonrpc::JsonRequest, wallet::walletdb::QueryType,
+};
+use darkfi_dao_contract::dao_client::{
+    DAO_DAOS_COL_DAO_ID, DAO_DAOS_COL_NAME, DAO_DAOS_TABLE, DAO_PROPOSALS_COL_CALLS,
+    DAO_PROPOSALS_COL_CREATION_SLOT, DAO_PROPOSALS_COL_DAO_ID, DAO_PROPOSALS_COL_DURATION,
+    DAO_PROPOSALS_COL_PROPOSAL_ID, DAO_PROPOSALS_TABLE,
+};
+use darkfi_serial::{deserialize, serialize};
+use prettytable::{format, row, Table};
+use serde_json::json;
 
 use super::Drk;
-use crate::DaoParams;
+use crate::{dao::DaoProposal, DaoParams};
 
 impl Drk {
     /// Import given DAO into the wallet
     pub async fn dao_import(&self, dao_name: String, dao_params: DaoParams) -> Result<()> {
         Ok(())
     }
+
+    /// Find the wallet ID of the DAO with the given name
+    async fn dao_id(&self, dao_name: &str) -> Result<u64> {
+        let query = format!("SELECT * FROM {};", DAO_DAOS_TABLE);
+
+        let params = json!([
+            query,
+            QueryType::Integer as u8,
+            DAO_DAOS_COL_DAO_ID,
+            QueryType::Blob as u8,
+            DAO_DAOS_COL_NAME,
+        ]);
+
+        let req = JsonRequest::new("wallet.query_row_multi", params);
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(rows) = rep.as_array() else {
+            return Err(anyhow!("Unexpected response from darkfid: {}", rep))
+        };
+
+        let name = serialize(&dao_name.to_string());
+        for row in rows {
+            let Some(row) = row.as_array() else {
+                return Err(anyhow!("Unexpected response from darkfid: {}", rep))
+            };
+
+            let dao_id: u64 = serde_json::from_value(row[0].clone())?;
+            let name_bytes: Vec<u8> = serde_json::from_value(row[1].clone())?;
+            if name_bytes == name {
+                return Ok(dao_id)
+            }
+        }
+
+        Err(anyhow!("DAO \"{}\" not found in wallet", dao_name))
+    }
+
+    /// Fetch all the proposals of the given DAO from the wallet
+    pub async fn dao_proposals(&self, dao_name: &str) -> Result<Vec<DaoProposal>> {
+        let dao_id = self.dao_id(dao_name).await?;
+
+        let query = format!(
+            "SELECT * FROM {} WHERE {} = {};",
+            DAO_PROPOSALS_TABLE, DAO_PROPOSALS_COL_DAO_ID, dao_id
+        );
+
+        let params = json!([
+            query,
+            QueryType::Integer as u8,
+            DAO_PROPOSALS_COL_PROPOSAL_ID,
+            QueryType::Blob as u8,
+            DAO_PROPOSALS_COL_CALLS,
+            QueryType::Integer as u8,
+            DAO_PROPOSALS_COL_CREATION_SLOT,
+            QueryType::Integer as u8,
+            DAO_PROPOSALS_COL_DURATION,
+        ]);
+
+        let req = JsonRequest::new("wallet.query_row_multi", params);
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(rows) = rep.as_array() else {
+            return Err(anyhow!("Unexpected response from darkfid: {}", rep))
+        };
+
+        let mut ret = vec![];
+
+        for row in rows {
+            let Some(row) = row.as_array() else {
+                return Err(anyhow!("Unexpected response from darkfid: {}", rep))
+            };
+
+            let id: u64 = serde_json::from_value(row[0].clone())?;
+
+            let calls_bytes: Vec<u8> = serde_json::from_value(row[1].clone())?;
+            let calls = deserialize(&calls_bytes)?;
+
+            let creation_slot: u64 = serde_json::from_value(row[2].clone())?;
+            let duration: u64 = serde_json::from_value(row[3].clone())?;
+
+            ret.push(DaoProposal { id, calls, creation_slot, duration });
+        }
+
+        Ok(ret)
+    }
+
+    /// Print the proposals of the given DAO as a table, along with the
+    /// time left to vote on them.
+    pub async fn dao_list_proposals(&self, dao_name: &str) -> Result<()> {
+        let proposals = self.dao_proposals(dao_name).await?;
+        let current_slot = self.current_slot().await?;
+
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+        table.set_titles(row!["ID", "Calls", "Voting ends at slot", "Time left"]);
+
+        for proposal in proposals {
+            let deadline = proposal.deadline();
+            let time_left = if current_slot < deadline {
+                let slots = deadline - current_slot;
+                let minutes = slots * SLOT_TIME / 60;
+                format!("{} slots (~{}h {}m)", slots, minutes / 60, minutes % 60)
+            } else {
+                "Ended".to_string()
+            };
+
+            table.add_row(row![proposal.id, proposal.calls.len(), deadline, time_left]);
+        }
+
+        if table.is_empty() {
+            println!("No proposals found for DAO \"{}\"", dao_name);
+        } else {
+            println!("{}", table);
+        }
+
+        Ok(())
+    }
 }
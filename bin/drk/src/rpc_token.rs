@@ -0,0 +1,264 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::{anyhow, Result};
+use darkfi::{
+    rpc::jsonrpc::JsonRequest,
+    tx::Transaction,
+    util::parse::decode_base10,
+    wallet::walletdb::QueryType,
+    zk::{proof::ProvingKey, vm::ZkCircuit, vm_stack::empty_witnesses},
+    zkas::ZkBinary,
+};
+use darkfi_money_contract::{
+    client::{
+        build_token_mint_tx, MONEY_TOKENS_COL_IS_FROZEN, MONEY_TOKENS_COL_MINT_AUTHORITY,
+        MONEY_TOKENS_COL_TOKEN_ID, MONEY_TOKENS_TABLE,
+    },
+    model::MoneyTokenFreezeParams,
+    MoneyFunction, MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1,
+};
+use darkfi_sdk::{
+    crypto::{contract_id::MONEY_CONTRACT_ID, Keypair, PublicKey, SecretKey, TokenId},
+    tx::{ContractCall, DEFAULT_GAS_LIMIT},
+};
+use darkfi_serial::{deserialize, serialize, Encodable};
+use prettytable::{format, row, Table};
+use rand::rngs::OsRng;
+use serde_json::json;
+
+use super::Drk;
+
+impl Drk {
+    /// Generate a new mint authority and put it in the wallet. Returns
+    /// the ID of the token it can mint.
+    pub async fn token_generate_mint(&self) -> Result<TokenId> {
+        let mint_authority = SecretKey::random(&mut OsRng);
+        let token_id = TokenId::derive(mint_authority);
+        let is_frozen = 0;
+
+        let query = format!(
+            "INSERT INTO {} ({}, {}, {}) VALUES (?1, ?2, ?3);",
+            MONEY_TOKENS_TABLE,
+            MONEY_TOKENS_COL_MINT_AUTHORITY,
+            MONEY_TOKENS_COL_TOKEN_ID,
+            MONEY_TOKENS_COL_IS_FROZEN,
+        );
+
+        let params = json!([
+            query,
+            QueryType::Blob as u8,
+            serialize(&mint_authority),
+            QueryType::Blob as u8,
+            serialize(&token_id),
+            QueryType::Integer as u8,
+            is_frozen,
+        ]);
+
+        let req = JsonRequest::new("wallet.exec_sql", params);
+        let rep = self.rpc_client.request(req).await?;
+
+        if rep != true {
+            return Err(anyhow!("Got unexpected reply from darkfid: {}", rep))
+        }
+
+        Ok(token_id)
+    }
+
+    /// Fetch all the mint authorities from the wallet. The boolean in the
+    /// return tuple marks if the token's supply has been frozen.
+    pub async fn wallet_mint_authorities(&self) -> Result<Vec<(TokenId, SecretKey, bool)>> {
+        let query = format!("SELECT * FROM {};", MONEY_TOKENS_TABLE);
+
+        let params = json!([
+            query,
+            QueryType::Blob as u8,
+            MONEY_TOKENS_COL_MINT_AUTHORITY,
+            QueryType::Blob as u8,
+            MONEY_TOKENS_COL_TOKEN_ID,
+            QueryType::Integer as u8,
+            MONEY_TOKENS_COL_IS_FROZEN,
+        ]);
+
+        let req = JsonRequest::new("wallet.query_row_multi", params);
+        let rep = self.rpc_client.request(req).await?;
+
+        // The returned thing should be an array of found rows.
+        let Some(rows) = rep.as_array() else {
+            return Err(anyhow!("Unexpected response from darkfid: {}", rep))
+        };
+
+        let mut ret = vec![];
+
+        for row in rows {
+            let Some(row) = row.as_array() else {
+                return Err(anyhow!("Unexpected response from darkfid: {}", rep))
+            };
+
+            let mint_authority_bytes: Vec<u8> = serde_json::from_value(row[0].clone())?;
+            let mint_authority: SecretKey = deserialize(&mint_authority_bytes)?;
+
+            let token_id_bytes: Vec<u8> = serde_json::from_value(row[1].clone())?;
+            let token_id: TokenId = deserialize(&token_id_bytes)?;
+
+            let is_frozen: u64 = serde_json::from_value(row[2].clone())?;
+
+            ret.push((token_id, mint_authority, is_frozen > 0));
+        }
+
+        Ok(ret)
+    }
+
+    /// Print the mint authorities in the wallet as a table.
+    pub async fn token_list(&self) -> Result<()> {
+        let tokens = self.wallet_mint_authorities().await?;
+
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+        table.set_titles(row!["Token ID", "Mint Authority", "Frozen"]);
+
+        for (token_id, mint_authority, is_frozen) in tokens {
+            table.add_row(row![token_id, PublicKey::from_secret(mint_authority), is_frozen]);
+        }
+
+        if table.is_empty() {
+            println!("No mint authorities found");
+        } else {
+            println!("{}", table);
+        }
+
+        Ok(())
+    }
+
+    /// Fetch the mint authority of the given token from the wallet, making
+    /// sure its supply is not frozen.
+    async fn wallet_mint_authority(&self, token_id: TokenId) -> Result<Keypair> {
+        let tokens = self.wallet_mint_authorities().await?;
+        let Some((_, mint_authority, is_frozen)) = tokens.iter().find(|x| x.0 == token_id) else {
+            return Err(anyhow!("Did not find mint authority for token ID: {}", token_id))
+        };
+
+        if *is_frozen {
+            return Err(anyhow!("Token {} has a fixed supply", token_id))
+        }
+
+        Ok(Keypair::new(*mint_authority))
+    }
+
+    /// Mark a token in the wallet as frozen
+    async fn mark_token_frozen(&self, token_id: TokenId) -> Result<()> {
+        let query = format!(
+            "UPDATE {} SET {} = ?1 WHERE {} = ?2;",
+            MONEY_TOKENS_TABLE, MONEY_TOKENS_COL_IS_FROZEN, MONEY_TOKENS_COL_TOKEN_ID,
+        );
+
+        let params = json!([
+            query,
+            QueryType::Integer as u8,
+            1,
+            QueryType::Blob as u8,
+            serialize(&token_id)
+        ]);
+
+        let req = JsonRequest::new("wallet.exec_sql", params);
+        let _ = self.rpc_client.request(req).await?;
+
+        Ok(())
+    }
+
+    /// Create a token mint transaction. If `fixed_supply` is set, the token
+    /// can't be minted anymore afterwards. Returns the transaction object
+    /// on success.
+    pub async fn mint_token(
+        &self,
+        amount: &str,
+        recipient: PublicKey,
+        token_id: TokenId,
+        fixed_supply: bool,
+    ) -> Result<Transaction> {
+        let mint_authority = self.wallet_mint_authority(token_id).await?;
+
+        // FIXME: Do not hardcode 8 decimals
+        let amount = decode_base10(amount, 8, false)?;
+
+        let contract_id = *MONEY_CONTRACT_ID;
+
+        let zkas_bins = self.lookup_zkas(&contract_id).await?;
+
+        let Some(token_mint_zkbin) =
+            zkas_bins.iter().find(|x| x.0 == MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1)
+        else {
+            return Err(anyhow!("Token mint circuit not found"))
+        };
+
+        let token_mint_zkbin = ZkBinary::decode(&token_mint_zkbin.1)?;
+        let token_mint_circuit =
+            ZkCircuit::new(empty_witnesses(&token_mint_zkbin), token_mint_zkbin.clone());
+
+        eprintln!("Creating TokenMint circuit proving key");
+        let token_mint_pk = ProvingKey::build(token_mint_zkbin.k, &token_mint_circuit);
+
+        let (params, proofs, secrets) = build_token_mint_tx(
+            &mint_authority,
+            amount,
+            recipient,
+            fixed_supply,
+            &token_mint_zkbin,
+            &token_mint_pk,
+        )?;
+
+        // Encode and sign the transaction
+        let mut data = vec![MoneyFunction::Mint as u8];
+        params.encode(&mut data)?;
+        let calls = vec![ContractCall { contract_id, data, gas_limit: DEFAULT_GAS_LIMIT }];
+        let proofs = vec![proofs];
+        let mut tx = Transaction { calls, proofs, signatures: vec![] };
+        let sigs = tx.create_sigs(&mut OsRng, &secrets)?;
+        tx.signatures = vec![sigs];
+
+        if fixed_supply {
+            self.mark_token_frozen(token_id).await?;
+        }
+
+        Ok(tx)
+    }
+
+    /// Create a transaction freezing the supply of a token, so it can't be
+    /// minted anymore. Returns the transaction object on success.
+    pub async fn freeze_token(&self, token_id: TokenId) -> Result<Transaction> {
+        let mint_authority = self.wallet_mint_authority(token_id).await?;
+
+        let params = MoneyTokenFreezeParams { mint_public: mint_authority.public };
+
+        // Encode and sign the transaction
+        let mut data = vec![MoneyFunction::Freeze as u8];
+        params.encode(&mut data)?;
+        let calls = vec![ContractCall {
+            contract_id: *MONEY_CONTRACT_ID,
+            data,
+            gas_limit: DEFAULT_GAS_LIMIT,
+        }];
+        let mut tx = Transaction { calls, proofs: vec![vec![]], signatures: vec![] };
+        let sigs = tx.create_sigs(&mut OsRng, &[mint_authority.secret])?;
+        tx.signatures = vec![sigs];
+
+        self.mark_token_frozen(token_id).await?;
+
+        Ok(tx)
+    }
+}
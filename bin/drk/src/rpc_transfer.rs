@@ -29,7 +29,7 @@ use darkfi_money_contract::{
 };
 use darkfi_sdk::{
     crypto::{contract_id::MONEY_CONTRACT_ID, Keypair, PublicKey, TokenId},
-    tx::ContractCall,
+    tx::{ContractCall, DEFAULT_GAS_LIMIT},
 };
 use darkfi_serial::Encodable;
 use rand::rngs::OsRng;
@@ -83,25 +83,26 @@ impl Drk {
         // We also do this through the RPC.
         let zkas_bins = self.lookup_zkas(&contract_id).await?;
 
-        let Some(mint_zkbin) = zkas_bins.iter().find(|x| x.0 == MONEY_CONTRACT_ZKAS_MINT_NS_V1) else {
+        let Some(mint_zkbin) = zkas_bins.iter().find(|x| x.0 == MONEY_CONTRACT_ZKAS_MINT_NS_V1)
+        else {
             return Err(anyhow!("Mint circuit not found"))
         };
 
-        let Some(burn_zkbin) = zkas_bins.iter().find(|x| x.0 == MONEY_CONTRACT_ZKAS_BURN_NS_V1) else {
+        let Some(burn_zkbin) = zkas_bins.iter().find(|x| x.0 == MONEY_CONTRACT_ZKAS_BURN_NS_V1)
+        else {
             return Err(anyhow!("Burn circuit not found"))
         };
 
         let mint_zkbin = ZkBinary::decode(&mint_zkbin.1)?;
         let burn_zkbin = ZkBinary::decode(&burn_zkbin.1)?;
 
-        let k = 13;
         let mint_circuit = ZkCircuit::new(empty_witnesses(&mint_zkbin), mint_zkbin.clone());
         let burn_circuit = ZkCircuit::new(empty_witnesses(&burn_zkbin), burn_zkbin.clone());
 
         eprintln!("Creating Mint circuit proving key");
-        let mint_pk = ProvingKey::build(k, &mint_circuit);
+        let mint_pk = ProvingKey::build(mint_zkbin.k, &mint_circuit);
         eprintln!("Creating Burn circuit proving key");
-        let burn_pk = ProvingKey::build(k, &burn_circuit);
+        let burn_pk = ProvingKey::build(burn_zkbin.k, &burn_circuit);
 
         // Now we should have everything we need to build the transaction
         let (params, proofs, secrets, spent_coins) = build_transfer_tx(
@@ -121,7 +122,7 @@ impl Drk {
         // Encode and sign the transaction
         let mut data = vec![MoneyFunction::Transfer as u8];
         params.encode(&mut data)?;
-        let calls = vec![ContractCall { contract_id, data }];
+        let calls = vec![ContractCall { contract_id, data, gas_limit: DEFAULT_GAS_LIMIT }];
         let proofs = vec![proofs];
         let mut tx = Transaction { calls, proofs, signatures: vec![] };
         let sigs = tx.create_sigs(&mut OsRng, &secrets)?;
@@ -29,9 +29,11 @@ use darkfi_money_contract::client::{
     MONEY_COINS_COL_NULLIFIER, MONEY_COINS_COL_SECRET, MONEY_COINS_COL_SERIAL,
     MONEY_COINS_COL_SPEND_HOOK, MONEY_COINS_COL_TOKEN_BLIND, MONEY_COINS_COL_TOKEN_ID,
     MONEY_COINS_COL_USER_DATA, MONEY_COINS_COL_VALUE, MONEY_COINS_COL_VALUE_BLIND,
-    MONEY_COINS_TABLE, MONEY_INFO_COL_LAST_SCANNED_SLOT, MONEY_INFO_TABLE,
-    MONEY_KEYS_COL_IS_DEFAULT, MONEY_KEYS_COL_PUBLIC, MONEY_KEYS_COL_SECRET, MONEY_KEYS_TABLE,
-    MONEY_TREE_COL_TREE, MONEY_TREE_TABLE,
+    MONEY_COINS_TABLE, MONEY_HD_INDEXES_COL_NEXT_INDEX, MONEY_HD_INDEXES_COL_PURPOSE,
+    MONEY_HD_INDEXES_TABLE, MONEY_HD_SEED_COL_SEED, MONEY_HD_SEED_TABLE,
+    MONEY_INFO_COL_LAST_SCANNED_SLOT, MONEY_INFO_TABLE, MONEY_KEYS_COL_IS_DEFAULT,
+    MONEY_KEYS_COL_PUBLIC, MONEY_KEYS_COL_SECRET, MONEY_KEYS_TABLE, MONEY_TREE_COL_TREE,
+    MONEY_TREE_TABLE,
 };
 use darkfi_sdk::{
     crypto::{
@@ -47,7 +49,10 @@ use prettytable::{format, row, Table};
 use rand::rngs::OsRng;
 use serde_json::json;
 
-use super::Drk;
+use super::{
+    hd::{derive_secret, generate_mnemonic, mnemonic_to_seed, HD_PURPOSE_MONEY, HD_RESTORE_KEYS},
+    Drk,
+};
 
 impl Drk {
     /// Initialize wallet with tables for the Money Contract.
@@ -152,10 +157,13 @@ impl Drk {
     }
 
     /// Generate a new wallet keypair and put it in the according wallet table.
+    /// If the wallet holds a mnemonic seed, the keypair is derived from it.
     pub async fn wallet_keygen(&self) -> Result<()> {
         println!("Generating a new keypair");
-        // TODO: We might want to have hierarchical deterministic key derivation.
-        let keypair = Keypair::random(&mut OsRng);
+        let keypair = match self.wallet_hd_derive(HD_PURPOSE_MONEY).await? {
+            Some(secret) => Keypair::new(secret),
+            None => Keypair::random(&mut OsRng),
+        };
         let public = serialize(&keypair.public);
         let secret = serialize(&keypair.secret);
         let is_default = 0;
@@ -191,6 +199,146 @@ impl Drk {
         Ok(())
     }
 
+    /// Fetch the mnemonic seed from the wallet, if it holds one.
+    pub async fn wallet_hd_seed(&self) -> Result<Option<Vec<u8>>> {
+        let query = format!("SELECT {} FROM {};", MONEY_HD_SEED_COL_SEED, MONEY_HD_SEED_TABLE);
+        let params = json!([query, QueryType::Blob as u8, MONEY_HD_SEED_COL_SEED]);
+        let req = JsonRequest::new("wallet.query_row_multi", params);
+        let rep = self.rpc_client.request(req).await?;
+
+        // The returned thing should be an array of found rows,
+        // and finding none means there is no seed.
+        let Some(rows) = rep.as_array() else {
+            return Err(anyhow!("Unexpected response from darkfid: {}", rep))
+        };
+        let Some(row) = rows.first() else { return Ok(None) };
+
+        let seed: Vec<u8> = serde_json::from_value(row[0].clone())?;
+        Ok(Some(seed))
+    }
+
+    /// Replace the mnemonic seed in the wallet. The derivation indexes are
+    /// kept, so keys that were already derived aren't handed out again.
+    async fn put_hd_seed(&self, seed: &[u8]) -> Result<()> {
+        let query = format!(
+            "DELETE FROM {}; INSERT INTO {} ({}) VALUES (?1);",
+            MONEY_HD_SEED_TABLE, MONEY_HD_SEED_TABLE, MONEY_HD_SEED_COL_SEED
+        );
+
+        let params = json!([query, QueryType::Blob as u8, seed]);
+        let req = JsonRequest::new("wallet.exec_sql", params);
+        let _ = self.rpc_client.request(req).await?;
+
+        Ok(())
+    }
+
+    /// Get the next index to derive a key at for the given purpose.
+    async fn wallet_hd_next_index(&self, purpose: u32) -> Result<u32> {
+        let query = format!(
+            "SELECT {} FROM {} WHERE {} = {};",
+            MONEY_HD_INDEXES_COL_NEXT_INDEX,
+            MONEY_HD_INDEXES_TABLE,
+            MONEY_HD_INDEXES_COL_PURPOSE,
+            purpose
+        );
+
+        let params = json!([query, QueryType::Integer as u8, MONEY_HD_INDEXES_COL_NEXT_INDEX]);
+        let req = JsonRequest::new("wallet.query_row_multi", params);
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(rows) = rep.as_array() else {
+            return Err(anyhow!("Unexpected response from darkfid: {}", rep))
+        };
+
+        // Nothing was derived for this purpose yet
+        let Some(row) = rows.first() else { return Ok(0) };
+
+        Ok(serde_json::from_value(row[0].clone())?)
+    }
+
+    /// Set the next index to derive a key at for the given purpose.
+    async fn put_hd_next_index(&self, purpose: u32, index: u32) -> Result<()> {
+        let query = format!(
+            "INSERT OR REPLACE INTO {} ({}, {}) VALUES (?1, ?2);",
+            MONEY_HD_INDEXES_TABLE, MONEY_HD_INDEXES_COL_PURPOSE, MONEY_HD_INDEXES_COL_NEXT_INDEX
+        );
+
+        let params =
+            json!([query, QueryType::Integer as u8, purpose, QueryType::Integer as u8, index]);
+        let req = JsonRequest::new("wallet.exec_sql", params);
+        let _ = self.rpc_client.request(req).await?;
+
+        Ok(())
+    }
+
+    /// Derive the next secret key for the given purpose from the wallet's
+    /// mnemonic seed. Returns `None` if the wallet holds no seed.
+    pub async fn wallet_hd_derive(&self, purpose: u32) -> Result<Option<SecretKey>> {
+        let Some(seed) = self.wallet_hd_seed().await? else { return Ok(None) };
+
+        let index = self.wallet_hd_next_index(purpose).await?;
+        eprintln!("Deriving key at path m/{}/{}", purpose, index);
+        let secret = derive_secret(&seed, purpose, index);
+        self.put_hd_next_index(purpose, index + 1).await?;
+
+        Ok(Some(secret))
+    }
+
+    /// Generate a new mnemonic and store its seed in the wallet, so that keys
+    /// created from now on are derived from it. Keys that already exist in the
+    /// wallet are not covered by the mnemonic. Returns the phrase to back up.
+    pub async fn wallet_mnemonic(&self) -> Result<String> {
+        if self.wallet_hd_seed().await?.is_some() {
+            return Err(anyhow!("Wallet already holds a mnemonic seed"))
+        }
+
+        let mnemonic = generate_mnemonic()?;
+        self.put_hd_seed(&mnemonic.to_seed("")).await?;
+
+        Ok(mnemonic.to_string())
+    }
+
+    /// Restore the wallet from a mnemonic phrase. The seed is stored in the wallet,
+    /// the first `HD_RESTORE_KEYS` Money keys are derived and imported, and the
+    /// blockchain is rescanned from the start to rediscover their coins.
+    /// DAO and deploy key indexes are left untouched: in a new wallet these keys
+    /// are derived again in the order they were created, while an existing wallet
+    /// doesn't reuse the keys it already holds.
+    /// If the wallet already holds a different seed, the restore is refused
+    /// unless `force` is set, as keys derived from that seed would be lost.
+    pub async fn wallet_restore_mnemonic(
+        &self,
+        phrase: &str,
+        force: bool,
+    ) -> Result<Vec<PublicKey>> {
+        let seed = mnemonic_to_seed(phrase)?;
+
+        match self.wallet_hd_seed().await? {
+            Some(existing) if existing == seed => {}
+            Some(_) if !force => {
+                return Err(anyhow!(
+                    "Wallet already holds a different mnemonic seed, use --force to replace it"
+                ))
+            }
+            _ => self.put_hd_seed(&seed).await?,
+        }
+
+        let known = self.wallet_secrets().await?;
+        let secrets = (0..HD_RESTORE_KEYS)
+            .map(|i| derive_secret(&seed, HD_PURPOSE_MONEY, i))
+            .filter(|secret| !known.contains(secret))
+            .collect();
+
+        let pubkeys = self.wallet_import_secrets(secrets).await?;
+        let next_index = self.wallet_hd_next_index(HD_PURPOSE_MONEY).await?;
+        self.put_hd_next_index(HD_PURPOSE_MONEY, next_index.max(HD_RESTORE_KEYS)).await?;
+
+        eprintln!("Rescanning the blockchain for coins of the restored keys");
+        self.scan_blocks(true).await?;
+
+        Ok(pubkeys)
+    }
+
     /// Fetch all coins and their metadata from the wallet, optionally also spent ones.
     /// The boolean in the return tuple marks if the coin is marked as spent.
     pub async fn wallet_coins(&self, fetch_spent: bool) -> Result<Vec<(OwnCoin, bool)>> {
@@ -41,7 +41,7 @@ use darkfi_sdk::{
     db::SMART_CONTRACT_ZKAS_DB_NAME,
     incrementalmerkletree::bridgetree::BridgeTree,
     pasta::group::ff::PrimeField,
-    tx::ContractCall,
+    tx::{ContractCall, DEFAULT_GAS_LIMIT},
 };
 use darkfi_serial::{deserialize, serialize, Encodable};
 use log::{debug, error, info};
@@ -55,9 +55,10 @@ use darkfi::{
     async_daemonize, cli_desc,
     consensus::{
         constants::{
-            MAINNET_BOOTSTRAP_TIMESTAMP, MAINNET_GENESIS_HASH_BYTES, MAINNET_GENESIS_TIMESTAMP,
-            MAINNET_INITIAL_DISTRIBUTION, TESTNET_BOOTSTRAP_TIMESTAMP, TESTNET_GENESIS_HASH_BYTES,
-            TESTNET_GENESIS_TIMESTAMP, TESTNET_INITIAL_DISTRIBUTION,
+            MAINNET_BOOTSTRAP_TIMESTAMP, MAINNET_EMISSION, MAINNET_GENESIS_HASH_BYTES,
+            MAINNET_GENESIS_TIMESTAMP, MAINNET_INITIAL_DISTRIBUTION, TESTNET_BOOTSTRAP_TIMESTAMP,
+            TESTNET_EMISSION, TESTNET_GENESIS_HASH_BYTES, TESTNET_GENESIS_TIMESTAMP,
+            TESTNET_INITIAL_DISTRIBUTION,
         },
         proto::{ProtocolSync, ProtocolTx},
         task::block_sync_task,
@@ -231,14 +232,13 @@ impl Faucetd {
         let mint_zkbin = ZkBinary::decode(&mint_zkbin)?;
         let burn_zkbin = ZkBinary::decode(&burn_zkbin)?;
 
-        let k = 13;
         let mint_circuit = ZkCircuit::new(empty_witnesses(&mint_zkbin), mint_zkbin.clone());
         let burn_circuit = ZkCircuit::new(empty_witnesses(&burn_zkbin), burn_zkbin.clone());
 
         info!("Creating mint circuit proving key");
-        let mint_provingkey = ProvingKey::build(k, &mint_circuit);
+        let mint_provingkey = ProvingKey::build(mint_zkbin.k, &mint_circuit);
         info!("Creating burn circuit proving key");
-        let burn_provingkey = ProvingKey::build(k, &burn_circuit);
+        let burn_provingkey = ProvingKey::build(burn_zkbin.k, &burn_circuit);
 
         {
             let provingkeys = vec![
@@ -454,7 +454,7 @@ impl Faucetd {
         // Build transaction
         let mut data = vec![MoneyFunction::Transfer as u8];
         params.encode(&mut data).unwrap();
-        let calls = vec![ContractCall { contract_id: cid, data }];
+        let calls = vec![ContractCall { contract_id: cid, data, gas_limit: DEFAULT_GAS_LIMIT }];
         let proofs = vec![proofs];
         let mut tx = Transaction { calls, proofs, signatures: vec![] };
         let sigs = tx.create_sigs(&mut OsRng, &secret_keys).unwrap();
@@ -529,24 +529,27 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'_>>) -> Result<()> {
     let sled_db = sled::open(&db_path)?;
 
     // Initialize validator state
-    let (bootstrap_ts, genesis_ts, genesis_data, initial_distribution) = match args.chain.as_str() {
-        "mainnet" => (
-            *MAINNET_BOOTSTRAP_TIMESTAMP,
-            *MAINNET_GENESIS_TIMESTAMP,
-            *MAINNET_GENESIS_HASH_BYTES,
-            *MAINNET_INITIAL_DISTRIBUTION,
-        ),
-        "testnet" => (
-            *TESTNET_BOOTSTRAP_TIMESTAMP,
-            *TESTNET_GENESIS_TIMESTAMP,
-            *TESTNET_GENESIS_HASH_BYTES,
-            *TESTNET_INITIAL_DISTRIBUTION,
-        ),
-        x => {
-            error!("Unsupported chain `{}`", x);
-            return Err(Error::UnsupportedChain)
-        }
-    };
+    let (bootstrap_ts, genesis_ts, genesis_data, initial_distribution, emission) =
+        match args.chain.as_str() {
+            "mainnet" => (
+                *MAINNET_BOOTSTRAP_TIMESTAMP,
+                *MAINNET_GENESIS_TIMESTAMP,
+                *MAINNET_GENESIS_HASH_BYTES,
+                *MAINNET_INITIAL_DISTRIBUTION,
+                *MAINNET_EMISSION,
+            ),
+            "testnet" => (
+                *TESTNET_BOOTSTRAP_TIMESTAMP,
+                *TESTNET_GENESIS_TIMESTAMP,
+                *TESTNET_GENESIS_HASH_BYTES,
+                *TESTNET_INITIAL_DISTRIBUTION,
+                *TESTNET_EMISSION,
+            ),
+            x => {
+                error!("Unsupported chain `{}`", x);
+                return Err(Error::UnsupportedChain)
+            }
+        };
 
     // Parse faucet addresses
     let mut faucet_pubkeys = vec![];
@@ -568,6 +571,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'_>>) -> Result<()> {
         genesis_ts,
         genesis_data,
         initial_distribution,
+        emission,
         wallet.clone(),
         faucet_pubkeys,
         false,
@@ -81,7 +81,7 @@ fn main() {
     // the initial AST, not caring much about the semantics, just enforcing
     // syntax and general structure.
     let parser = Parser::new(filename, source.chars(), tokens);
-    let (namespace, constants, witnesses, statements) = parser.parse();
+    let (k, namespace, constants, witnesses, statements) = parser.parse();
 
     // The analyzer goes through the initial AST provided by the parser and
     // converts return and variable types to their correct forms, and also
@@ -104,6 +104,7 @@ fn main() {
     let compiler = Compiler::new(
         filename,
         source.chars(),
+        k,
         namespace,
         analyzer.constants,
         analyzer.witnesses,
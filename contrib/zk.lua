@@ -20,7 +20,7 @@ local number = token(l.NUMBER, l.integer)
 
 -- Keywords.
 local keyword = token(l.KEYWORD, word_match{
-  'constant', 'contract', 'circuit',
+  'constant', 'contract', 'circuit', 'function', 'import', 'return',
 })
 
 -- Constants.
@@ -39,10 +39,11 @@ local type = token(l.TYPE, word_match{
 -- Instructions.
 local instruction = token('instruction', word_match{
   'ec_add', 'ec_mul', 'ec_mul_base', 'ec_mul_short', 'ec_mul_var_base',
-  'ec_get_x', 'ec_get_y',
+  'ec_get_x', 'ec_get_y', 'ec_cond_select',
   'base_add', 'base_mul', 'base_sub',
   'poseidon_hash', 'merkle_root',
   'range_check', 'less_than_strict', 'less_than_loose', 'bool_check',
+  'cond_select', 'zero_cond', 'is_zero',
   'witness_base',
   'constrain_equal_base', 'constrain_equal_point',
   'constrain_instance',
@@ -11,6 +11,9 @@ syn keyword zkasKeyword
     \ constant
     \ contract
     \ circuit
+    \ function
+    \ import
+    \ return
 
 syn keyword zkasType 
     \ EcPoint EcFixedPoint EcFixedPointBase EcFixedPointShort EcNiPoint
@@ -19,10 +22,11 @@ syn keyword zkasType
 
 syn keyword zkasInstruction
     \ ec_add ec_mul ec_mul_base ec_mul_short ec_mul_var_base
-    \ ec_get_x ec_get_y
+    \ ec_get_x ec_get_y ec_cond_select
     \ base_add base_mul base_sub
     \ poseidon_hash merkle_root
     \ range_check less_than_strict less_than_loose  bool_check
+    \ cond_select zero_cond is_zero
     \ witness_base
     \ constrain_equal_base constrain_equal_point
     \ constrain_instance
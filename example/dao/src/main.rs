@@ -137,7 +137,7 @@ pub async fn example() -> Result<()> {
 
     let zk_example_foo_bincode = include_bytes!("../proof/foo.zk.bin");
     let zk_example_foo_bin = ZkBinary::decode(zk_example_foo_bincode)?;
-    zk_bins.add_contract("example-foo".to_string(), zk_example_foo_bin, 13);
+    zk_bins.add_contract("example-foo".to_string(), zk_example_foo_bin);
 
     let example_state = example::state::State::new();
     states.register(*example::CONTRACT_ID, example_state);
@@ -215,7 +215,7 @@ async fn main() -> Result<()> {
     debug!(target: "demo", "Loading dao-mint.zk");
     let zk_dao_mint_bincode = include_bytes!("../proof/dao-mint.zk.bin");
     let zk_dao_mint_bin = ZkBinary::decode(zk_dao_mint_bincode)?;
-    zk_bins.add_contract("dao-mint".to_string(), zk_dao_mint_bin, 13);
+    zk_bins.add_contract("dao-mint".to_string(), zk_dao_mint_bin);
 
     debug!(target: "demo", "Loading money-transfer contracts");
     {
@@ -238,22 +238,22 @@ async fn main() -> Result<()> {
     debug!(target: "demo", "Loading dao-propose-main.zk");
     let zk_dao_propose_main_bincode = include_bytes!("../proof/dao-propose-main.zk.bin");
     let zk_dao_propose_main_bin = ZkBinary::decode(zk_dao_propose_main_bincode)?;
-    zk_bins.add_contract("dao-propose-main".to_string(), zk_dao_propose_main_bin, 13);
+    zk_bins.add_contract("dao-propose-main".to_string(), zk_dao_propose_main_bin);
     debug!(target: "demo", "Loading dao-propose-burn.zk");
     let zk_dao_propose_burn_bincode = include_bytes!("../proof/dao-propose-burn.zk.bin");
     let zk_dao_propose_burn_bin = ZkBinary::decode(zk_dao_propose_burn_bincode)?;
-    zk_bins.add_contract("dao-propose-burn".to_string(), zk_dao_propose_burn_bin, 13);
+    zk_bins.add_contract("dao-propose-burn".to_string(), zk_dao_propose_burn_bin);
     debug!(target: "demo", "Loading dao-vote-main.zk");
     let zk_dao_vote_main_bincode = include_bytes!("../proof/dao-vote-main.zk.bin");
     let zk_dao_vote_main_bin = ZkBinary::decode(zk_dao_vote_main_bincode)?;
-    zk_bins.add_contract("dao-vote-main".to_string(), zk_dao_vote_main_bin, 13);
+    zk_bins.add_contract("dao-vote-main".to_string(), zk_dao_vote_main_bin);
     debug!(target: "demo", "Loading dao-vote-burn.zk");
     let zk_dao_vote_burn_bincode = include_bytes!("../proof/dao-vote-burn.zk.bin");
     let zk_dao_vote_burn_bin = ZkBinary::decode(zk_dao_vote_burn_bincode)?;
-    zk_bins.add_contract("dao-vote-burn".to_string(), zk_dao_vote_burn_bin, 13);
+    zk_bins.add_contract("dao-vote-burn".to_string(), zk_dao_vote_burn_bin);
     let zk_dao_exec_bincode = include_bytes!("../proof/dao-exec.zk.bin");
     let zk_dao_exec_bin = ZkBinary::decode(zk_dao_exec_bincode)?;
-    zk_bins.add_contract("dao-exec".to_string(), zk_dao_exec_bin, 13);
+    zk_bins.add_contract("dao-exec".to_string(), zk_dao_exec_bin);
 
     // State for money contracts
     let cashier_signature_secret = SecretKey::random(&mut OsRng);
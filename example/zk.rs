@@ -53,8 +53,8 @@ fn main() -> Result<()> {
     // Prover
     // ======
     // Bigger k = more rows, but slower circuit
-    // Number of rows is 2^k
-    let k = 13;
+    // Number of rows is 2^k, and k is set in the zkas source
+    let k = zkbin.k;
 
     // Witness values
     let value = 42;
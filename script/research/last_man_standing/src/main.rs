@@ -22,7 +22,7 @@ use darkfi::{
         constants,
         leadcoin::{LeadCoin, LeadCoinSecrets},
         utils::fbig2base,
-        Float10,
+        Float10, TESTNET_EMISSION,
     },
     util::time::Timestamp,
     Result,
@@ -207,18 +207,18 @@ impl ConsensusState {
         f
     }
 
-    /// Leadership reward, assuming constant reward
-    /// TODO (res) implement reward mechanism with accord to DRK,DARK token-economics
+    /// Leadership reward of the current slot, set by the emission schedule
     fn reward(&self) -> u64 {
-        constants::REWARD
+        TESTNET_EMISSION.reward(self.current_slot)
     }
 
-    /// Network total stake, assuming constant reward.
+    /// Network total stake, assuming every slot since genesis was rewarded.
     /// Only used for fine-tuning. At genesis epoch first slot, of absolute index 0,
     /// if no stake was distributed, the total stake would be 0.
     /// To avoid division by zero, we assume total stake at first division is GENESIS_TOTAL_STAKE(1).
     fn total_stake(&self) -> u64 {
-        let rewards = (self.current_slot - 1) * self.reward();
+        let slots: Vec<u64> = (1..self.current_slot).collect();
+        let rewards = TESTNET_EMISSION.emitted(&slots);
         let total_stake = rewards + self.initial_distribution;
         if total_stake == 0 {
             return constants::GENESIS_TOTAL_STAKE
@@ -391,7 +391,9 @@ async fn main() -> Result<()> {
                 println!("Slot {slot} leader: {leader}");
                 // Rewarding leader
                 let mut coins_tree = nodes[leader].coins_tree.clone();
-                nodes[leader].coins[0] = nodes[leader].coins[0].derive_coin(&mut coins_tree);
+                let reward = nodes[leader].reward();
+                nodes[leader].coins[0] =
+                    nodes[leader].coins[0].derive_coin(reward, &mut coins_tree);
                 nodes[leader].coins_tree = coins_tree;
                 break
             }
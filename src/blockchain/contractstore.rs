@@ -24,6 +24,7 @@ use crate::{Error, Result};
 
 const SLED_CONTRACTS_TREE: &[u8] = b"_contracts";
 const SLED_BINCODE_TREE: &[u8] = b"_wasm_bincode";
+const SLED_MODULE_TREE: &[u8] = b"_wasm_modules";
 
 /// The `WasmStore` is a `sled` tree that stores the wasm bincode for deployed
 /// contracts.
@@ -61,6 +62,66 @@ impl WasmStore {
 
         Ok(())
     }
+
+    /// Retrieve the IDs of all contracts that have their bincode stored.
+    pub fn contract_ids(&self) -> Result<Vec<ContractId>> {
+        let mut contract_ids = vec![];
+
+        for contract in self.0.iter() {
+            let (key, _) = contract?;
+            contract_ids.push(deserialize(&key)?);
+        }
+
+        Ok(contract_ids)
+    }
+}
+
+/// The `WasmModuleStore` is a `sled` tree that stores the serialized compiled
+/// wasm modules of deployed contracts, so they don't have to be compiled
+/// again after a restart.
+#[derive(Clone)]
+pub struct WasmModuleStore(sled::Tree);
+
+impl WasmModuleStore {
+    /// Opens or creates a `WasmModuleStore`. This tree holds the compiled
+    /// modules along with the hash of the wasm bincode they were compiled from.
+    /// The layout looks like this:
+    /// ```plaintext
+    ///  tree: "_wasm_modules"
+    ///   key: ContractId
+    /// value: (blake3(bincode), Vec<u8>)
+    /// ```
+    pub fn new(db: &sled::Db) -> Result<Self> {
+        let tree = db.open_tree(SLED_MODULE_TREE)?;
+        Ok(Self(tree))
+    }
+
+    /// Fetches the bincode hash and serialized module for a given ContractId,
+    /// if it was stored.
+    pub fn get(&self, contract_id: ContractId) -> Result<Option<([u8; 32], Vec<u8>)>> {
+        if let Some(found) = self.0.get(&serialize(&contract_id))? {
+            return Ok(Some(deserialize(&found)?))
+        }
+
+        Ok(None)
+    }
+
+    /// Inserts or replaces the serialized module for a given ContractId
+    pub fn insert(&self, contract_id: ContractId, hash: &[u8; 32], module: &[u8]) -> Result<()> {
+        let value = serialize(&(*hash, module.to_vec()));
+        if let Err(e) = self.0.insert(&serialize(&contract_id), value) {
+            error!(target: "blockchain::contractstore", "Failed to insert module to WasmModuleStore: {}", e);
+            return Err(e.into())
+        }
+
+        Ok(())
+    }
+
+    /// Removes the serialized module for a given ContractId
+    pub fn remove(&self, contract_id: ContractId) -> Result<()> {
+        self.0.remove(&serialize(&contract_id))?;
+        Ok(())
+    }
 }
 
 /// The `ContractStateStore` is a `sled` tree that stores pointers to contracts'
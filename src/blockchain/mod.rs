@@ -25,6 +25,9 @@ use crate::{
     Error, Result,
 };
 
+pub mod schemastore;
+pub use schemastore::{SchemaStore, SCHEMA_VERSION};
+
 pub mod blockstore;
 pub use blockstore::{BlockOrderStore, BlockStore, HeaderStore};
 
@@ -41,13 +44,15 @@ pub mod txstore;
 pub use txstore::TxStore;
 
 pub mod contractstore;
-pub use contractstore::{ContractStateStore, WasmStore};
+pub use contractstore::{ContractStateStore, WasmModuleStore, WasmStore};
 
 /// Structure holding all sled trees that define the concept of Blockchain.
 #[derive(Clone)]
 pub struct Blockchain {
     /// Main pointer to the sled db connection
     pub sled_db: sled::Db,
+    /// Schema version sled tree
+    pub schema: SchemaStore,
     /// Headers sled tree
     pub headers: HeaderStore,
     /// Blocks sled tree
@@ -66,11 +71,15 @@ pub struct Blockchain {
     pub contracts: ContractStateStore,
     /// Wasm bincodes
     pub wasm_bincode: WasmStore,
+    /// Compiled wasm modules
+    pub wasm_modules: WasmModuleStore,
 }
 
 impl Blockchain {
     /// Instantiate a new `Blockchain` with the given `sled` database.
+    /// Records written by an older schema version are migrated first.
     pub fn new(db: &sled::Db, genesis_ts: Timestamp, genesis_data: blake3::Hash) -> Result<Self> {
+        let schema = SchemaStore::new(db)?;
         let headers = HeaderStore::new(db, genesis_ts, genesis_data)?;
         let blocks = BlockStore::new(db, genesis_ts, genesis_data)?;
         let order = BlockOrderStore::new(db, genesis_ts, genesis_data)?;
@@ -80,9 +89,11 @@ impl Blockchain {
         let merkle_roots = RootStore::new(db)?;
         let contracts = ContractStateStore::new(db)?;
         let wasm_bincode = WasmStore::new(db)?;
+        let wasm_modules = WasmModuleStore::new(db)?;
 
         Ok(Self {
             sled_db: db.clone(),
+            schema,
             headers,
             blocks,
             order,
@@ -92,6 +103,7 @@ impl Blockchain {
             merkle_roots,
             contracts,
             wasm_bincode,
+            wasm_modules,
         })
     }
 
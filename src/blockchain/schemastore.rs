@@ -0,0 +1,185 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use log::info;
+
+use crate::{Error, Result};
+
+const SLED_SCHEMA_TREE: &[u8] = b"_schema";
+const SLED_SCHEMA_VERSION_KEY: &[u8] = b"version";
+
+/// Name of the tree sled creates by default, which holds no blockchain data.
+const SLED_DEFAULT_TREE: &[u8] = b"__sled__default";
+
+/// Layout version of the records kept in the blockchain trees.
+/// Bump it whenever the serialized form of a stored record changes,
+/// and append the step converting the previous layout to [`MIGRATIONS`].
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Migration steps, where `MIGRATIONS[n]` converts a database from
+/// version `n` to version `n + 1`.
+const MIGRATIONS: [fn(&sled::Db) -> Result<()>; SCHEMA_VERSION as usize] = [migrate_v0];
+
+/// Version 0 databases were written before the schema version was
+/// recorded. Their header, block, transaction and slot checkpoint records
+/// are untagged and already use the version 1 layout, so they are kept
+/// as they are.
+fn migrate_v0(_db: &sled::Db) -> Result<()> {
+    Ok(())
+}
+
+/// The `SchemaStore` is a `sled` tree holding the layout version of the
+/// records stored in the rest of the blockchain database.
+#[derive(Clone)]
+pub struct SchemaStore(sled::Tree);
+
+impl SchemaStore {
+    /// Opens the `SchemaStore` on the given sled database and migrates
+    /// its records to [`SCHEMA_VERSION`]. This has to run before any other
+    /// blockchain store is opened, since those initialize empty trees.
+    pub fn new(db: &sled::Db) -> Result<Self> {
+        let tree = db.open_tree(SLED_SCHEMA_TREE)?;
+        let store = Self(tree);
+
+        let version = match store.version()? {
+            Some(version) => version,
+            // A database holding data but no version predates it
+            None if Self::has_data(db)? => 0,
+            None => SCHEMA_VERSION,
+        };
+
+        if version > SCHEMA_VERSION {
+            return Err(Error::UnsupportedSchemaVersion(version, SCHEMA_VERSION))
+        }
+
+        for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+            info!(target: "blockchain", "Migrating database schema from version {}", from);
+            migration(db)?;
+            store.set_version(from as u32 + 1)?;
+        }
+
+        store.set_version(SCHEMA_VERSION)?;
+        Ok(store)
+    }
+
+    /// Fetch the schema version recorded in the store, if any.
+    pub fn version(&self) -> Result<Option<u32>> {
+        let Some(found) = self.0.get(SLED_SCHEMA_VERSION_KEY)? else { return Ok(None) };
+        let bytes: [u8; 4] = found.as_ref().try_into()?;
+        Ok(Some(u32::from_be_bytes(bytes)))
+    }
+
+    fn set_version(&self, version: u32) -> Result<()> {
+        self.0.insert(SLED_SCHEMA_VERSION_KEY, &version.to_be_bytes())?;
+        Ok(())
+    }
+
+    /// Check if any tree of the database, besides the schema one, holds records.
+    fn has_data(db: &sled::Db) -> Result<bool> {
+        for name in db.tree_names() {
+            if name == SLED_DEFAULT_TREE || name == SLED_SCHEMA_TREE {
+                continue
+            }
+
+            if !db.open_tree(name)?.is_empty() {
+                return Ok(true)
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi_serial::serialize;
+
+    use super::*;
+    use crate::{
+        blockchain::Blockchain,
+        consensus::{Block, SlotCheckpoint},
+        tx::Transaction,
+        util::time::Timestamp,
+    };
+
+    #[test]
+    fn schema_fresh_database() -> Result<()> {
+        let db = sled::Config::new().temporary(true).open()?;
+        let genesis_data = blake3::hash(b"genesis");
+
+        let blockchain = Blockchain::new(&db, Timestamp(0), genesis_data)?;
+        assert_eq!(blockchain.schema.version()?, Some(SCHEMA_VERSION));
+
+        // Reopening keeps the version and the genesis block
+        let blockchain = Blockchain::new(&db, Timestamp(0), genesis_data)?;
+        assert_eq!(blockchain.schema.version()?, Some(SCHEMA_VERSION));
+        assert_eq!(blockchain.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn schema_legacy_database() -> Result<()> {
+        let db = sled::Config::new().temporary(true).open()?;
+        let genesis_data = blake3::hash(b"genesis");
+
+        // Write untagged records the way nodes did before the schema
+        // version was recorded.
+        let block = Block::genesis_block(Timestamp(0), genesis_data);
+        let blockhash = blake3::hash(&serialize(&block));
+        db.open_tree(b"_blocks")?.insert(blockhash.as_bytes(), serialize(&block))?;
+
+        let tx = Transaction { calls: vec![], proofs: vec![], signatures: vec![] };
+        let txhash = blake3::hash(&serialize(&tx));
+        db.open_tree(b"_transactions")?.insert(txhash.as_bytes(), serialize(&tx))?;
+
+        let mut checkpoint = SlotCheckpoint::genesis_slot_checkpoint();
+        checkpoint.slot = 1;
+        db.open_tree(b"_slot_checkpoints")?
+            .insert(checkpoint.slot.to_be_bytes(), serialize(&checkpoint))?;
+
+        let blockchain = Blockchain::new(&db, Timestamp(0), genesis_data)?;
+        assert_eq!(blockchain.schema.version()?, Some(SCHEMA_VERSION));
+
+        let blocks = blockchain.blocks.get(&[blockhash], true)?;
+        assert_eq!(blocks[0].as_ref().unwrap().blockhash(), blockhash);
+
+        let txs = blockchain.transactions.get(&[txhash], true)?;
+        assert_eq!(txs[0], Some(tx));
+
+        let checkpoints = blockchain.slot_checkpoints.get(&[1], true)?;
+        assert_eq!(serialize(checkpoints[0].as_ref().unwrap()), serialize(&checkpoint));
+
+        Ok(())
+    }
+
+    #[test]
+    fn schema_newer_database() -> Result<()> {
+        let db = sled::Config::new().temporary(true).open()?;
+        db.open_tree(SLED_SCHEMA_TREE)?
+            .insert(SLED_SCHEMA_VERSION_KEY, &(SCHEMA_VERSION + 1).to_be_bytes())?;
+
+        let genesis_data = blake3::hash(b"genesis");
+        assert!(matches!(
+            Blockchain::new(&db, Timestamp(0), genesis_data),
+            Err(Error::UnsupportedSchemaVersion(_, SCHEMA_VERSION))
+        ));
+
+        Ok(())
+    }
+}
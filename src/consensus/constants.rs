@@ -18,7 +18,10 @@
 
 use lazy_static::lazy_static;
 
-use crate::{consensus::Float10, util::time::Timestamp};
+use crate::{
+    consensus::{EmissionSchedule, Float10},
+    util::time::Timestamp,
+};
 
 lazy_static! {
     /// Genesis hash for the mainnet chain
@@ -35,6 +38,14 @@ lazy_static! {
     /// Total sum of initial staking coins for the mainnet chain
     pub static ref MAINNET_INITIAL_DISTRIBUTION: u64 = 1000;
 
+    /// Block leader reward emission schedule for the mainnet chain
+    pub static ref MAINNET_EMISSION: EmissionSchedule = EmissionSchedule {
+        initial_reward: 16,
+        halving_interval: 1000,
+        tail_emission: Some(1),
+        max_emission: Some(1_000_000),
+    };
+
     /// Genesis hash for the testnet chain
     pub static ref TESTNET_GENESIS_HASH_BYTES: blake3::Hash = blake3::hash(b"darkfi_testnet");
 
@@ -47,6 +58,14 @@ lazy_static! {
     /// Total sum of initial staking coins for the testnet chain
     pub static ref TESTNET_INITIAL_DISTRIBUTION: u64 = 1000;
 
+    /// Block leader reward emission schedule for the testnet chain
+    pub static ref TESTNET_EMISSION: EmissionSchedule = EmissionSchedule {
+        initial_reward: 16,
+        halving_interval: 1000,
+        tail_emission: Some(1),
+        max_emission: Some(1_000_000),
+    };
+
     // Commonly used Float10
     pub static ref FLOAT10_ZERO: Float10 = Float10::try_from("0").unwrap();
     pub static ref FLOAT10_ONE: Float10 = Float10::try_from("1").unwrap();
@@ -98,11 +117,11 @@ pub const SYNC_MAX_RETRIES: u64 = 10;
 /// Transactions included in a block cap
 pub const TXS_CAP: usize = 50;
 
-/// Block leader reward
-pub const REWARD: u64 = 1;
+/// Maximum number of pending transactions kept in the mempool
+pub const MEMPOOL_MAX_TXS: usize = 5000;
 
-/// Leader proofs k for zk proof rows (rows=2^k)
-pub const LEADER_PROOF_K: u32 = 13;
+/// Maximum total size of the pending transactions kept in the mempool, in bytes
+pub const MEMPOOL_MAX_BYTES: usize = 32 * 1024 * 1024;
 
 // TODO: Describe these constants
 pub const RADIX_BITS: usize = 76;
@@ -118,4 +137,5 @@ pub const PI_MU_Y_INDEX: usize = 8;
 pub const PI_MU_RHO_INDEX: usize = 10;
 pub const PI_SIGMA1_INDEX: usize = 12;
 pub const PI_SIGMA2_INDEX: usize = 13;
+pub const PI_REWARD_INDEX: usize = 14;
 pub const GENESIS_TOTAL_STAKE: u64 = 1;
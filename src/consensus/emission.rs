@@ -0,0 +1,150 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::constants::EPOCH_LENGTH;
+
+/// Block leader reward emission schedule.
+///
+/// The reward starts at `initial_reward` and halves every `halving_interval`
+/// epochs, never dropping below the tail emission. Rewards only depend on
+/// the slot, so the emission cap is enforced against the maximum possible
+/// emission, as if a block was produced on every slot. This way all nodes
+/// agree on the reward of a slot without looking at the chain, and the
+/// actual emission never exceeds the cap.
+///
+/// The schedule is part of a chain's genesis parameters, so all nodes of
+/// a network use the same one, see [`super::constants::TESTNET_EMISSION`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmissionSchedule {
+    /// Block leader reward of the first epochs
+    pub initial_reward: u64,
+    /// Number of epochs after which the reward halves, 0 to never halve
+    pub halving_interval: u64,
+    /// Reward floor, once halvings bring the reward below it
+    pub tail_emission: Option<u64>,
+    /// Maximum amount emitted through rewards, on top of the initial distribution
+    pub max_emission: Option<u64>,
+}
+
+impl EmissionSchedule {
+    /// Number of halvings applied to the reward of the provided epoch.
+    fn halvings(&self, epoch: u64) -> u64 {
+        if self.halving_interval == 0 {
+            return 0
+        }
+
+        epoch / self.halving_interval
+    }
+
+    /// Block leader reward of the provided epoch, without the emission cap.
+    pub fn epoch_reward(&self, epoch: u64) -> u64 {
+        let halvings = u32::try_from(self.halvings(epoch)).unwrap_or(u32::MAX);
+        let reward = self.initial_reward.checked_shr(halvings).unwrap_or(0);
+
+        match self.tail_emission {
+            Some(tail) => reward.max(tail),
+            None => reward,
+        }
+    }
+
+    /// Maximum amount emitted by the slots before the provided one,
+    /// without the emission cap.
+    pub fn max_emitted_before(&self, slot: u64) -> u64 {
+        let epoch_length = EPOCH_LENGTH as u64;
+        let period = self.halving_interval.saturating_mul(epoch_length);
+
+        // The reward is constant within a halving period, and after
+        // enough halvings it doesn't change anymore.
+        let mut emitted = 0_u64;
+        let mut start = 0_u64;
+        while start < slot {
+            let epoch = start / epoch_length;
+            let end = if period == 0 || self.halvings(epoch) >= u64::BITS as u64 {
+                slot
+            } else {
+                slot.min((self.halvings(epoch) + 1).saturating_mul(period))
+            };
+
+            let slots = end - start;
+            emitted = emitted.saturating_add(slots.saturating_mul(self.epoch_reward(epoch)));
+            start = end;
+        }
+
+        emitted
+    }
+
+    /// Block leader reward of the provided slot.
+    pub fn reward(&self, slot: u64) -> u64 {
+        let reward = self.epoch_reward(slot / EPOCH_LENGTH as u64);
+
+        match self.max_emission {
+            Some(cap) => reward.min(cap.saturating_sub(self.max_emitted_before(slot))),
+            None => reward,
+        }
+    }
+
+    /// Total amount emitted by blocks produced in the provided slots.
+    pub fn emitted(&self, slots: &[u64]) -> u64 {
+        slots.iter().fold(0, |acc, slot| acc.saturating_add(self.reward(*slot)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EmissionSchedule;
+    use crate::consensus::constants::EPOCH_LENGTH;
+
+    #[test]
+    fn emission_schedule() {
+        let epoch_length = EPOCH_LENGTH as u64;
+        let schedule = EmissionSchedule {
+            initial_reward: 8,
+            halving_interval: 2,
+            tail_emission: Some(1),
+            max_emission: None,
+        };
+
+        // Halvings every 2 epochs, until the tail emission is reached
+        assert_eq!(schedule.epoch_reward(0), 8);
+        assert_eq!(schedule.epoch_reward(1), 8);
+        assert_eq!(schedule.epoch_reward(2), 4);
+        assert_eq!(schedule.epoch_reward(4), 2);
+        assert_eq!(schedule.epoch_reward(6), 1);
+        assert_eq!(schedule.epoch_reward(1000), 1);
+        assert_eq!(schedule.reward(2 * epoch_length), 4);
+
+        let emitted = schedule.max_emitted_before(8 * epoch_length);
+        assert_eq!(emitted, 2 * epoch_length * (8 + 4 + 2 + 1));
+        let slots: Vec<u64> = (0..8 * epoch_length).collect();
+        assert_eq!(schedule.emitted(&slots), emitted);
+
+        // Without tail emission, the reward eventually runs out
+        let schedule = EmissionSchedule { tail_emission: None, ..schedule };
+        assert_eq!(schedule.epoch_reward(6), 1);
+        assert_eq!(schedule.epoch_reward(8), 0);
+        assert_eq!(schedule.epoch_reward(u64::MAX), 0);
+
+        // The cap bounds the total emission, cutting the last reward short
+        let schedule = EmissionSchedule { max_emission: Some(8 * epoch_length + 3), ..schedule };
+        assert_eq!(schedule.reward(epoch_length - 1), 8);
+        assert_eq!(schedule.reward(epoch_length), 3);
+        assert_eq!(schedule.reward(epoch_length + 1), 0);
+        let slots: Vec<u64> = (0..8 * epoch_length).collect();
+        assert_eq!(schedule.emitted(&slots), 8 * epoch_length + 3);
+    }
+}
@@ -32,7 +32,7 @@ use rand::rngs::OsRng;
 
 use super::constants::EPOCH_LENGTH;
 use crate::{
-    consensus::{constants, TransferStx, TxRcpt},
+    consensus::{TransferStx, TxRcpt},
     zk::{
         proof::{Proof, ProvingKey},
         vm::ZkCircuit,
@@ -64,8 +64,6 @@ pub struct LeadCoin {
     pub nonce: pallas::Base,
     /// Commitment for coin1
     pub coin1_commitment: pallas::Point,
-    /// Commitment for coin2 (rcpt coin)
-    pub coin2_commitment: pallas::Point,
     /// Merkle root of coin1 commitment
     pub coin1_commitment_root: MerkleNode,
     /// Coin commitment position
@@ -120,9 +118,6 @@ impl LeadCoin {
         let coin2_blind = pallas::Scalar::random(&mut OsRng);
         // pk
         let pk = Self::util_pk(coin1_sk_root, slot);
-        // Derive the nonce for coin2
-        let coin2_seed = Self::util_derived_rho(coin1_sk_root, seed);
-        info!(target: "consensus::leadcoin", "coin2_seed[{}]: {:?}", slot, coin2_seed);
         let coin1_commitment = Self::commitment(pk, pallas::Base::from(value), seed, coin1_blind);
         // Hash its coordinates to get a base field element
         let c1_cm_coords = coin1_commitment.to_affine().coordinates().unwrap();
@@ -135,13 +130,6 @@ impl LeadCoin {
         let coin1_commitment_merkle_path = coin_commitment_tree
             .authentication_path(coin1_commitment_pos, &coin1_commitment_root)
             .unwrap();
-        // Create commitment to coin2
-        let coin2_commitment = Self::commitment(
-            pk,
-            pallas::Base::from(value + constants::REWARD),
-            coin2_seed,
-            coin2_blind,
-        );
         // Derive election seeds
         let (y_mu, rho_mu) = Self::election_seeds_u64(eta, slot);
         // Return the object
@@ -150,7 +138,6 @@ impl LeadCoin {
             slot,
             nonce: seed,
             coin1_commitment,
-            coin2_commitment,
             coin1_commitment_root,
             coin1_commitment_pos: u32::try_from(usize::from(coin1_commitment_pos)).unwrap(),
             coin1_commitment_merkle_path: coin1_commitment_merkle_path.try_into().unwrap(),
@@ -193,13 +180,19 @@ impl LeadCoin {
     }
 
     /// Create a vector of `pallas::Base` elements from the `LeadCoin` to be
-    /// used as public inputs for the ZK proof.
-    pub fn public_inputs(&self, sigma1: pallas::Base, sigma2: pallas::Base) -> Vec<pallas::Base> {
+    /// used as public inputs for the ZK proof, claiming the provided reward.
+    pub fn public_inputs(
+        &self,
+        sigma1: pallas::Base,
+        sigma2: pallas::Base,
+        reward: u64,
+    ) -> Vec<pallas::Base> {
         // pk
         let pk = self.pk();
         // coin 1-2 cm/commitment
         let c1_cm = self.coin1_commitment.to_affine().coordinates().unwrap();
-        let c2_cm = self.coin2_commitment.to_affine().coordinates().unwrap();
+        let c2_cm =
+            self.derived_commitment(self.coin2_blind, reward).to_affine().coordinates().unwrap();
         // lottery seed
         let seed_msg =
             [pallas::Base::from(PREFIX_SEED), self.coin1_sk_root.inner(), self.nonce, ZERO];
@@ -225,6 +218,7 @@ impl LeadCoin {
             rho,
             sigma1,
             sigma2,
+            pallas::Base::from(reward),
         ];
         public_inputs
     }
@@ -283,29 +277,25 @@ impl LeadCoin {
         let commit_v = poseidon_hash(commit_msg);
         pedersen_commitment_base(commit_v, blind)
     }
-    /// calculated derived coin commitment
-    pub fn derived_commitment(&self, blind: pallas::Scalar) -> pallas::Point {
+    /// calculated derived coin commitment, holding the coin value
+    /// plus the provided reward
+    pub fn derived_commitment(&self, blind: pallas::Scalar, reward: u64) -> pallas::Point {
         let pk = self.pk();
         let rho = self.derived_rho();
-        Self::commitment(pk, pallas::Base::from(self.value + constants::REWARD), rho, blind)
+        Self::commitment(pk, pallas::Base::from(self.value + reward), rho, blind)
     }
 
     /// the new coin to be minted after the current coin is spent
-    /// in lottery.
+    /// in lottery, claiming the provided reward.
     pub fn derive_coin(
         &self,
+        reward: u64,
         coin_commitment_tree: &mut BridgeTree<MerkleNode, MERKLE_DEPTH>,
     ) -> LeadCoin {
         info!(target: "consensus::leadcoin", "derive_coin(): Deriving new coin!");
         let derived_c1_rho = self.derived_rho();
         let blind = pallas::Scalar::random(&mut OsRng);
-        let derived_c2_cm = Self::commitment(
-            self.pk(),
-            pallas::Base::from(self.value + 2 * constants::REWARD),
-            Self::util_derived_rho(self.coin1_sk_root, derived_c1_rho),
-            blind,
-        );
-        let derived_c1_cm = { self.derived_commitment(self.coin2_blind) };
+        let derived_c1_cm = self.derived_commitment(self.coin2_blind, reward);
         let derived_c1_cm_coord = derived_c1_cm.to_affine().coordinates().unwrap();
         let derived_c1_cm_msg = [*derived_c1_cm_coord.x(), *derived_c1_cm_coord.y()];
         let derived_c1_cm_base = poseidon_hash(derived_c1_cm_msg);
@@ -315,11 +305,10 @@ impl LeadCoin {
         let commitment_merkle_path =
             coin_commitment_tree.authentication_path(leaf_pos, &commitment_root).unwrap();
         LeadCoin {
-            value: self.value + constants::REWARD,
+            value: self.value + reward,
             slot: self.slot,
             nonce: derived_c1_rho,
-            coin1_commitment: self.coin2_commitment,
-            coin2_commitment: derived_c2_cm,
+            coin1_commitment: derived_c1_cm,
             coin1_commitment_root: commitment_root,
             coin1_commitment_pos: u32::try_from(usize::from(leaf_pos)).unwrap(),
             coin1_commitment_merkle_path: commitment_merkle_path.try_into().unwrap(),
@@ -341,11 +330,12 @@ impl LeadCoin {
         poseidon_hash(c1_cm_msg)
     }
 
-    /// Try to create a ZK proof of consensus leadership
+    /// Try to create a ZK proof of consensus leadership, claiming the provided reward
     pub fn create_lead_proof(
         &self,
         sigma1: pallas::Base,
         sigma2: pallas::Base,
+        reward: u64,
         pk: &ProvingKey,
     ) -> (Result<Proof>, Vec<pallas::Base>) {
         let bincode = include_bytes!("../../proof/lead.zk.bin");
@@ -366,9 +356,10 @@ impl LeadCoin {
             Witness::Base(Value::known(self.y_mu)),
             Witness::Base(Value::known(sigma1)),
             Witness::Base(Value::known(sigma2)),
+            Witness::Base(Value::known(pallas::Base::from(reward))),
         ];
         let circuit = ZkCircuit::new(witnesses, zkbin);
-        let public_inputs = self.public_inputs(sigma1, sigma2);
+        let public_inputs = self.public_inputs(sigma1, sigma2, reward);
         (Ok(Proof::create(pk, &[circuit], &public_inputs, &mut OsRng).unwrap()), public_inputs)
     }
 
@@ -415,7 +406,9 @@ impl LeadCoin {
             Witness::Base(Value::known(xferval)),
         ];
         let circuit = ZkCircuit::new(witnesses, zkbin);
-        let proof = Proof::create(pk, &[circuit], &self.public_inputs(sigma1, sigma2), &mut OsRng)?;
+        // Transfers don't claim any reward
+        let public_inputs = self.public_inputs(sigma1, sigma2, 0);
+        let proof = Proof::create(pk, &[circuit], &public_inputs, &mut OsRng)?;
         let cm3_msg_in = [
             pallas::Base::from(PREFIX_CM),
             change_pk,
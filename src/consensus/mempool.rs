@@ -0,0 +1,288 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashSet;
+
+use darkfi_serial::{deserialize, serialize};
+use log::warn;
+
+use crate::{tx::Transaction, Result};
+
+const SLED_MEMPOOL_TREE: &[u8] = b"_mempool";
+
+/// A value a transaction spends or creates (like a nullifier or a coin),
+/// along with the contract it belongs to. Two transactions claiming the
+/// same key can't both be included in a block.
+pub type ConflictKey = ([u8; 32], [u8; 32]);
+
+/// A pending transaction, along with the values the validator needs to
+/// decide which transactions to keep, computed once when it's inserted.
+#[derive(Clone)]
+pub struct MempoolEntry {
+    /// Hash of the serialized transaction
+    pub hash: blake3::Hash,
+    /// The pending transaction
+    pub tx: Transaction,
+    /// Fee paid by the transaction
+    pub fee: u64,
+    /// Serialized size of the transaction, in bytes
+    pub size: usize,
+    /// Conflict keys claimed by the transaction
+    pub conflict_keys: Vec<ConflictKey>,
+}
+
+/// The `Mempool` holds the pending transactions the node has seen, which
+/// are not yet part of a finalized block. They are kept in memory in their
+/// arrival order, and mirrored to a `sled` tree, where the key is the
+/// transaction hash and the value is the fee it pays and its conflict keys,
+/// followed by the serialized transaction, so they survive restarts.
+///
+/// The mempool itself doesn't verify anything, or enforce any limits.
+/// This is done by the validator before transactions get inserted.
+pub struct Mempool {
+    /// Persisted pending transactions
+    tree: sled::Tree,
+    /// Pending transactions in their arrival order
+    entries: Vec<MempoolEntry>,
+    /// Hashes of the pending transactions
+    hashes: HashSet<blake3::Hash>,
+    /// Total serialized size of the pending transactions
+    size: usize,
+}
+
+impl Mempool {
+    /// Opens a new or existing `Mempool` on the given sled database, and
+    /// loads the transactions persisted in it. Their arrival order is not
+    /// kept across restarts. Loaded transactions are not verified, so this
+    /// should be done against the current state before using them.
+    /// Malformed entries are removed from the tree and skipped.
+    pub fn new(db: &sled::Db) -> Result<Self> {
+        let tree = db.open_tree(SLED_MEMPOOL_TREE)?;
+
+        let mut entries = vec![];
+        let mut hashes = HashSet::new();
+        let mut size = 0;
+        let mut malformed = vec![];
+        for entry in tree.iter() {
+            let (key, value) = entry?;
+            let Some(entry) = Self::decode_entry(&key, &value) else {
+                warn!(target: "consensus::mempool", "Removing malformed mempool entry {:?}", key);
+                malformed.push(key);
+                continue
+            };
+
+            size += entry.size;
+            hashes.insert(entry.hash);
+            entries.push(entry);
+        }
+
+        for key in malformed {
+            tree.remove(key)?;
+        }
+
+        Ok(Self { tree, entries, hashes, size })
+    }
+
+    /// Decode a persisted entry, checking that its key is the hash of the
+    /// transaction it holds. Returns `None` if it's malformed.
+    fn decode_entry(key: &[u8], value: &[u8]) -> Option<MempoolEntry> {
+        let hash = blake3::Hash::from(<[u8; 32]>::try_from(key).ok()?);
+        let (fee, conflict_keys, tx): (u64, Vec<ConflictKey>, Transaction) =
+            deserialize(value).ok()?;
+        let serialized = serialize(&tx);
+        if blake3::hash(&serialized) != hash {
+            return None
+        }
+
+        Some(MempoolEntry { hash, tx, fee, size: serialized.len(), conflict_keys })
+    }
+
+    /// Number of pending transactions.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if there are no pending transactions.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Total serialized size of the pending transactions, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Check if the mempool contains a given transaction hash.
+    pub fn contains(&self, tx_hash: &blake3::Hash) -> bool {
+        self.hashes.contains(tx_hash)
+    }
+
+    /// Fetch the pending transaction with the given hash, if it exists.
+    pub fn get(&self, tx_hash: &blake3::Hash) -> Option<&Transaction> {
+        if !self.contains(tx_hash) {
+            return None
+        }
+
+        self.entries.iter().find(|entry| entry.hash == *tx_hash).map(|entry| &entry.tx)
+    }
+
+    /// Retrieve all pending transactions in their arrival order, along with
+    /// their hashes, fees, sizes and conflict keys.
+    pub fn entries(&self) -> &[MempoolEntry] {
+        &self.entries
+    }
+
+    /// Retrieve the pending transactions claiming any of the given conflict keys.
+    pub fn conflicting(&self, conflict_keys: &[ConflictKey]) -> Vec<&MempoolEntry> {
+        let conflict_keys: HashSet<_> = conflict_keys.iter().collect();
+        self.entries
+            .iter()
+            .filter(|entry| entry.conflict_keys.iter().any(|key| conflict_keys.contains(key)))
+            .collect()
+    }
+
+    /// Retrieve all pending transactions in their arrival order.
+    pub fn txs(&self) -> Vec<Transaction> {
+        self.entries.iter().map(|entry| entry.tx.clone()).collect()
+    }
+
+    /// Retrieve the hashes of all pending transactions in their arrival order.
+    pub fn hashes(&self) -> Vec<blake3::Hash> {
+        self.entries.iter().map(|entry| entry.hash).collect()
+    }
+
+    /// Insert a transaction paying `fee` and claiming the given conflict keys
+    /// into the mempool, after all the existing ones. Returns the transaction
+    /// hash, and does nothing if the transaction is already pending.
+    /// Conflicts with pending transactions are not checked here.
+    pub fn insert(
+        &mut self,
+        tx: Transaction,
+        fee: u64,
+        conflict_keys: Vec<ConflictKey>,
+    ) -> Result<blake3::Hash> {
+        let serialized = serialize(&tx);
+        let tx_hash = blake3::hash(&serialized);
+        if self.contains(&tx_hash) {
+            return Ok(tx_hash)
+        }
+
+        let value = serialize(&(fee, conflict_keys.clone(), tx.clone()));
+        self.tree.insert(tx_hash.as_bytes(), value)?;
+        let size = serialized.len();
+        self.size += size;
+        self.hashes.insert(tx_hash);
+        self.entries.push(MempoolEntry { hash: tx_hash, tx, fee, size, conflict_keys });
+        Ok(tx_hash)
+    }
+
+    /// Remove the transaction with the given hash from the mempool.
+    /// Returns the removed transaction, or `None` if it was not pending.
+    pub fn remove(&mut self, tx_hash: &blake3::Hash) -> Result<Option<Transaction>> {
+        if !self.contains(tx_hash) {
+            return Ok(None)
+        }
+
+        let Some(pos) = self.entries.iter().position(|entry| entry.hash == *tx_hash) else {
+            return Ok(None)
+        };
+
+        self.tree.remove(tx_hash.as_bytes())?;
+        self.hashes.remove(tx_hash);
+        let entry = self.entries.remove(pos);
+        self.size -= entry.size;
+        Ok(Some(entry.tx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi_sdk::{crypto::MONEY_CONTRACT_ID, tx::ContractCall};
+
+    use super::*;
+
+    fn dummy_tx(data: u8) -> Transaction {
+        let call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data: vec![data], gas_limit: 0 };
+        Transaction { calls: vec![call], proofs: vec![vec![]], signatures: vec![vec![]] }
+    }
+
+    #[test]
+    fn mempool_persistence() -> Result<()> {
+        let db = sled::Config::new().temporary(true).open()?;
+
+        let mut mempool = Mempool::new(&db)?;
+        assert!(mempool.is_empty());
+
+        let tx_a = dummy_tx(0);
+        let tx_b = dummy_tx(1);
+        let key_a = ([0; 32], [1; 32]);
+        let key_b = ([0; 32], [2; 32]);
+        let hash_a = mempool.insert(tx_a.clone(), 10, vec![key_a])?;
+        let hash_b = mempool.insert(tx_b.clone(), 20, vec![key_b])?;
+        assert_eq!(mempool.insert(tx_a.clone(), 10, vec![key_a])?, hash_a);
+        assert_eq!(mempool.txs(), vec![tx_a.clone(), tx_b.clone()]);
+        assert_eq!(mempool.size(), serialize(&tx_a).len() + serialize(&tx_b).len());
+
+        // Pending transactions survive reopening the mempool
+        let mut mempool = Mempool::new(&db)?;
+        assert_eq!(mempool.len(), 2);
+        assert!(mempool.contains(&hash_a) && mempool.contains(&hash_b));
+        assert_eq!(mempool.get(&hash_b), Some(&tx_b));
+        assert_eq!(mempool.entries().iter().map(|entry| entry.fee).sum::<u64>(), 30);
+
+        // Conflict keys are kept too
+        let conflicting = mempool.conflicting(&[key_b, ([3; 32], [1; 32])]);
+        assert_eq!(conflicting.iter().map(|entry| entry.hash).collect::<Vec<_>>(), vec![hash_b]);
+        assert!(mempool.conflicting(&[([0; 32], [3; 32])]).is_empty());
+
+        assert_eq!(mempool.remove(&hash_a)?, Some(tx_a));
+        assert_eq!(mempool.remove(&hash_a)?, None);
+        assert_eq!(mempool.size(), serialize(&tx_b).len());
+
+        let mempool = Mempool::new(&db)?;
+        assert_eq!(mempool.hashes(), vec![hash_b]);
+        assert_eq!(mempool.entries()[0].fee, 20);
+
+        Ok(())
+    }
+
+    #[test]
+    fn mempool_malformed_entry() -> Result<()> {
+        let db = sled::Config::new().temporary(true).open()?;
+        let tree = db.open_tree(SLED_MEMPOOL_TREE)?;
+
+        let mut mempool = Mempool::new(&db)?;
+        let tx = dummy_tx(0);
+        let tx_hash = mempool.insert(tx.clone(), 10, vec![])?;
+
+        // A key that isn't a hash, a key that isn't the hash of its
+        // transaction, and a value that can't be decoded
+        let no_keys: Vec<ConflictKey> = vec![];
+        tree.insert(b"short", serialize(&(0_u64, no_keys.clone(), dummy_tx(1))))?;
+        tree.insert([1_u8; 32], serialize(&(0_u64, no_keys, dummy_tx(2))))?;
+        tree.insert([2_u8; 32], vec![0xff; 3])?;
+
+        // Malformed entries are dropped, and the valid one is still loaded
+        let mempool = Mempool::new(&db)?;
+        assert_eq!(mempool.hashes(), vec![tx_hash]);
+        assert_eq!(mempool.get(&tx_hash), Some(&tx));
+        assert_eq!(tree.len(), 1);
+
+        Ok(())
+    }
+}
@@ -23,10 +23,14 @@ pub use block::{Block, BlockInfo, BlockProposal, Header};
 /// Constants
 pub mod constants;
 pub use constants::{
-    TESTNET_BOOTSTRAP_TIMESTAMP, TESTNET_GENESIS_HASH_BYTES, TESTNET_GENESIS_TIMESTAMP,
-    TESTNET_INITIAL_DISTRIBUTION,
+    TESTNET_BOOTSTRAP_TIMESTAMP, TESTNET_EMISSION, TESTNET_GENESIS_HASH_BYTES,
+    TESTNET_GENESIS_TIMESTAMP, TESTNET_INITIAL_DISTRIBUTION,
 };
 
+/// Block leader reward emission schedule
+pub mod emission;
+pub use emission::EmissionSchedule;
+
 /// Consensus block leader information
 pub mod lead_info;
 pub use lead_info::{LeadInfo, LeadProof};
@@ -35,6 +39,10 @@ pub use lead_info::{LeadInfo, LeadProof};
 pub mod state;
 pub use state::SlotCheckpoint;
 
+/// Pending transactions pool
+pub mod mempool;
+pub use mempool::Mempool;
+
 /// Consensus validator state
 pub mod validator;
 pub use validator::{ValidatorState, ValidatorStatePtr};
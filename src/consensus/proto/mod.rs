@@ -16,6 +16,8 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use crate::{Error, VerifyFailed};
+
 /// Block proposal protocol
 mod protocol_proposal;
 pub use protocol_proposal::ProtocolProposal;
@@ -31,3 +33,27 @@ pub use protocol_sync::ProtocolSync;
 /// Validator consensus sync protocol
 mod protocol_sync_consensus;
 pub use protocol_sync_consensus::ProtocolSyncConsensus;
+
+/// Returns `true` if a verification error means that the relayed data could
+/// not have been accepted by any honest node: it's malformed, or carries
+/// invalid proofs or signatures. Errors that may come from races or stale
+/// state, like double spends, and local failures are not held against peers.
+fn is_invalid_data(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::InvalidSignature |
+            Error::GasLimitTooHigh(..) |
+            Error::DecodeError(_) |
+            Error::NonMinimalVarInt |
+            Error::VerifyFailed(
+                VerifyFailed::ProofVerifyFailed(_) |
+                    VerifyFailed::MintProof(_) |
+                    VerifyFailed::BurnProof(_) |
+                    VerifyFailed::InputSignature(_) |
+                    VerifyFailed::ClearInputSignature(_) |
+                    VerifyFailed::DeployTooLarge(_) |
+                    VerifyFailed::UnexpectedFeeClaim(_) |
+                    VerifyFailed::FeeClaimExceedsFees(..)
+            )
+    )
+}
@@ -20,15 +20,14 @@ use async_std::sync::Arc;
 use async_trait::async_trait;
 use log::{debug, error, trace};
 use smol::Executor;
-use url::Url;
 
 use crate::{
     consensus::{BlockProposal, ValidatorStatePtr},
     net::{
-        ChannelPtr, MessageSubscription, P2pPtr, ProtocolBase, ProtocolBasePtr,
-        ProtocolJobsManager, ProtocolJobsManagerPtr,
+        constants::MISBEHAVIOUR_INVALID_PROPOSAL, ChannelPtr, MessageSubscription, P2pPtr,
+        ProtocolBase, ProtocolBasePtr, ProtocolJobsManager, ProtocolJobsManagerPtr,
     },
-    Result,
+    Error, Result,
 };
 
 pub struct ProtocolProposal {
@@ -36,7 +35,7 @@ pub struct ProtocolProposal {
     jobsman: ProtocolJobsManagerPtr,
     state: ValidatorStatePtr,
     p2p: P2pPtr,
-    channel_address: Url,
+    channel: ChannelPtr,
 }
 
 impl ProtocolProposal {
@@ -51,21 +50,19 @@ impl ProtocolProposal {
 
         let proposal_sub = channel.subscribe_msg::<BlockProposal>().await?;
 
-        let channel_address = channel.address();
-
         Ok(Arc::new(Self {
             proposal_sub,
-            jobsman: ProtocolJobsManager::new("ProposalProtocol", channel),
+            jobsman: ProtocolJobsManager::new("ProposalProtocol", channel.clone()),
             state,
             p2p,
-            channel_address,
+            channel,
         }))
     }
 
     async fn handle_receive_proposal(self: Arc<Self>) -> Result<()> {
         debug!(target: "consensus::protocol_proposal::handle_receive_proposal()", "START");
 
-        let exclude_list = vec![self.channel_address.clone()];
+        let exclude_list = vec![self.channel.address()];
         loop {
             let proposal = match self.proposal_sub.receive().await {
                 Ok(v) => v,
@@ -121,6 +118,19 @@ impl ProtocolProposal {
                         "receive_proposal error: {}",
                         e
                     );
+
+                    // Proposals for another slot or fork might just be late, and
+                    // eligibility is checked against our own, possibly stale, state.
+                    // These can only come from a faulty or malicious node.
+                    if matches!(
+                        e,
+                        Error::InvalidSignature |
+                            Error::ProposalTxsExceedCapError |
+                            Error::ProposalHashesMissmatchError
+                    ) {
+                        drop(lock);
+                        self.channel.misbehaved(MISBEHAVIOUR_INVALID_PROPOSAL).await;
+                    }
                     continue
                 }
             }
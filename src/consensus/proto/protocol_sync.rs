@@ -21,6 +21,7 @@ use async_trait::async_trait;
 use log::{debug, error, info};
 use smol::Executor;
 
+use super::is_invalid_data;
 use crate::{
     consensus::{
         block::{BlockInfo, BlockOrder, BlockResponse},
@@ -28,8 +29,8 @@ use crate::{
         ValidatorStatePtr,
     },
     net::{
-        ChannelPtr, MessageSubscription, P2pPtr, ProtocolBase, ProtocolBasePtr,
-        ProtocolJobsManager, ProtocolJobsManagerPtr,
+        constants::MISBEHAVIOUR_INVALID_BLOCK, ChannelPtr, MessageSubscription, P2pPtr,
+        ProtocolBase, ProtocolBasePtr, ProtocolJobsManager, ProtocolJobsManagerPtr,
     },
     Result,
 };
@@ -205,6 +206,9 @@ impl ProtocolSync {
                         "error processing finalized block: {}",
                         e
                     );
+                    if is_invalid_data(&e) {
+                        self.channel.misbehaved(MISBEHAVIOUR_INVALID_BLOCK).await;
+                    }
                 }
             };
         }
@@ -99,7 +99,7 @@ impl ProtocolSyncConsensus {
             for fork in &lock.consensus.forks {
                 forks.push(fork.clone().into());
             }
-            let unconfirmed_txs = lock.unconfirmed_txs.clone();
+            let unconfirmed_txs = lock.mempool.txs();
             let slot_checkpoints = lock.consensus.slot_checkpoints.clone();
             let leaders_history = lock.consensus.leaders_history.clone();
             let nullifiers = lock.consensus.nullifiers.clone();
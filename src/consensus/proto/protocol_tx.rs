@@ -20,14 +20,14 @@ use async_std::sync::Arc;
 use async_trait::async_trait;
 use log::{debug, error};
 use smol::Executor;
-use url::Url;
 
+use super::is_invalid_data;
 use crate::{
     consensus::ValidatorStatePtr,
     net,
     net::{
-        ChannelPtr, MessageSubscription, P2pPtr, ProtocolBase, ProtocolBasePtr,
-        ProtocolJobsManager, ProtocolJobsManagerPtr,
+        constants::MISBEHAVIOUR_INVALID_TX, ChannelPtr, MessageSubscription, P2pPtr, ProtocolBase,
+        ProtocolBasePtr, ProtocolJobsManager, ProtocolJobsManagerPtr,
     },
     tx::Transaction,
     Result,
@@ -38,7 +38,7 @@ pub struct ProtocolTx {
     jobsman: ProtocolJobsManagerPtr,
     state: ValidatorStatePtr,
     p2p: P2pPtr,
-    channel_address: Url,
+    channel: ChannelPtr,
 }
 
 impl net::Message for Transaction {
@@ -61,14 +61,13 @@ impl ProtocolTx {
         msg_subsystem.add_dispatch::<Transaction>().await;
 
         let tx_sub = channel.subscribe_msg::<Transaction>().await?;
-        let channel_address = channel.address();
 
         Ok(Arc::new(Self {
             tx_sub,
-            jobsman: ProtocolJobsManager::new("TxProtocol", channel),
+            jobsman: ProtocolJobsManager::new("TxProtocol", channel.clone()),
             state,
             p2p,
-            channel_address,
+            channel,
         }))
     }
 
@@ -77,7 +76,7 @@ impl ProtocolTx {
             target: "consensus::protocol_tx::handle_receive_tx()",
             "START"
         );
-        let exclude_list = vec![self.channel_address.clone()];
+        let exclude_list = vec![self.channel.address()];
         loop {
             let tx = match self.tx_sub.receive().await {
                 Ok(v) => v,
@@ -93,15 +92,27 @@ impl ProtocolTx {
 
             let tx_copy = (*tx).clone();
 
-            // Nodes use unconfirmed_txs vector as seen_txs pool.
-            if self.state.write().await.append_tx(tx_copy.clone()).await {
-                if let Err(e) = self.p2p.broadcast_with_exclude(tx_copy, &exclude_list).await {
-                    error!(
-                        target: "consensus::protocol_tx::handle_receive_tx()",
-                        "p2p broadcast fail: {}",
-                        e
-                    );
-                };
+            // Nodes use the mempool as seen_txs pool.
+            let appended = self.state.write().await.append_tx(tx_copy.clone()).await;
+            match appended {
+                Ok(true) => {
+                    if let Err(e) = self.p2p.broadcast_with_exclude(tx_copy, &exclude_list).await {
+                        error!(
+                            target: "consensus::protocol_tx::handle_receive_tx()",
+                            "p2p broadcast fail: {}",
+                            e
+                        );
+                    };
+                }
+                Ok(false) => {}
+                // Relaying transactions with invalid proofs or signatures is
+                // misbehaviour, but double spends or stale transactions may
+                // just have raced with our own state.
+                Err(e) => {
+                    if is_invalid_data(&e) {
+                        self.channel.misbehaved(MISBEHAVIOUR_INVALID_TX).await;
+                    }
+                }
             }
         }
     }
@@ -32,7 +32,7 @@ use super::{
     constants,
     leadcoin::{LeadCoin, LeadCoinSecrets},
     utils::fbig2base,
-    Block, BlockProposal, Float10,
+    Block, BlockInfo, BlockProposal, EmissionSchedule, Float10,
 };
 use crate::{blockchain::Blockchain, net, tx::Transaction, util::time::Timestamp, Error, Result};
 
@@ -48,6 +48,10 @@ pub struct ConsensusState {
     pub genesis_block: blake3::Hash,
     /// Total sum of initial staking coins
     pub initial_distribution: u64,
+    /// Block leader reward emission schedule
+    pub emission: EmissionSchedule,
+    /// Amount emitted through leadership rewards by the finalized blocks
+    pub emitted: u64,
     /// Slot the network was bootstrapped
     pub bootstrap_slot: u64,
     /// Participating start slot
@@ -84,14 +88,29 @@ impl ConsensusState {
         genesis_ts: Timestamp,
         genesis_data: blake3::Hash,
         initial_distribution: u64,
+        emission: EmissionSchedule,
     ) -> Result<Self> {
         let genesis_block = Block::genesis_block(genesis_ts, genesis_data).blockhash();
+
+        // Rewards of the blocks finalized before a restart. From here on,
+        // the emitted amount is kept up to date on finalization.
+        let slots: Vec<u64> = blockchain
+            .order
+            .get_all()?
+            .into_iter()
+            .map(|(slot, _)| slot)
+            .filter(|slot| *slot != 0)
+            .collect();
+        let emitted = emission.emitted(&slots);
+
         Ok(Self {
             blockchain,
             bootstrap_ts,
             genesis_ts,
             genesis_block,
             initial_distribution,
+            emission,
+            emitted,
             bootstrap_slot: 0,
             participating: None,
             proposing: false,
@@ -294,10 +313,22 @@ impl ConsensusState {
         Ok(coins)
     }
 
-    /// Leadership reward, assuming constant reward
-    /// TODO (res) implement reward mechanism with accord to DRK,DARK token-economics
-    fn reward(&self) -> u64 {
-        constants::REWARD
+    /// Leadership reward of the provided slot, set by the emission schedule
+    pub fn reward(&self, slot: u64) -> u64 {
+        self.emission.reward(slot)
+    }
+
+    /// Amount emitted through leadership rewards by the finalized blocks.
+    pub fn emitted_supply(&self) -> u64 {
+        self.emitted
+    }
+
+    /// Account for the leadership rewards of newly finalized blocks.
+    pub fn add_finalized_rewards(&mut self, blocks: &[BlockInfo]) {
+        // Genesis block carries no reward
+        let slots: Vec<u64> =
+            blocks.iter().map(|block| block.header.slot).filter(|slot| *slot != 0).collect();
+        self.emitted = self.emitted.saturating_add(self.emission.emitted(&slots));
     }
 
     /// Auxillary function to receive current slot offset.
@@ -315,34 +346,24 @@ impl ConsensusState {
         self.offset.unwrap()
     }
 
-    /// Auxillary function to calculate overall empty slots.
-    /// We keep an offset from genesis indicating when the first slot actually started.
-    /// This offset is shared between nodes.
-    fn overall_empty_slots(&mut self, current_slot: u64) -> u64 {
-        // Retrieve existing blocks excluding genesis
-        let blocks = (self.blockchain.len() as u64) - 1;
-        // Setup offset if only have genesis and havent received offset from other nodes
-        if blocks == 0 && self.offset.is_none() {
-            info!(
-                target: "consensus::state",
-                "overall_empty_slots(): Blockchain contains only genesis, setting slot offset: {}",
-                current_slot
-            );
-            self.offset = Some(current_slot);
-        }
-        // Retrieve longest fork length, to also those proposals in the calculation
-        let max_fork_length = self.longest_chain_length() as u64;
-        current_slot - blocks - self.get_current_offset(current_slot) - max_fork_length
-    }
-
-    /// Network total stake, assuming constant reward.
+    /// Network total stake, summing the rewards of the finalized blocks and
+    /// the longest fork proposals, as set by the emission schedule.
     /// Only used for fine-tuning. At genesis epoch first slot, of absolute index 0,
     /// if no stake was distributed, the total stake would be 0.
     /// To avoid division by zero, we asume total stake at first division is GENESIS_TOTAL_STAKE(1).
-    fn total_stake(&mut self) -> u64 {
-        let current_slot = self.current_slot();
-        let rewarded_slots = current_slot - self.overall_empty_slots(current_slot) - 1;
-        let rewards = rewarded_slots * self.reward();
+    fn total_stake(&self) -> u64 {
+        // Retrieve longest fork proposals slots, to also include their rewards
+        let index = self.longest_chain_index();
+        let fork_slots: Vec<u64> = if index < 0 {
+            vec![]
+        } else {
+            self.forks[index as usize]
+                .sequence
+                .iter()
+                .map(|state_checkpoint| state_checkpoint.proposal.block.header.slot)
+                .collect()
+        };
+        let rewards = self.emitted.saturating_add(self.emission.emitted(&fork_slots));
         let total_stake = rewards + self.initial_distribution;
         if total_stake == 0 {
             return constants::GENESIS_TOTAL_STAKE
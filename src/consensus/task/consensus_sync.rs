@@ -143,7 +143,13 @@ pub async fn consensus_sync_task(p2p: P2pPtr, state: ValidatorStatePtr) -> Resul
     }
     lock.consensus.bootstrap_slot = response.bootstrap_slot;
     lock.consensus.forks = forks;
-    lock.unconfirmed_txs = response.unconfirmed_txs.clone();
+    // Pending transactions of the peer are merged into our mempool,
+    // going through the same checks as any other transaction.
+    for tx in &response.unconfirmed_txs {
+        if let Err(e) = lock.append_tx(tx.clone()).await {
+            warn!(target: "consensus::consensus_sync", "Skipping pending transaction: {}", e);
+        }
+    }
     lock.consensus.slot_checkpoints = response.slot_checkpoints.clone();
     lock.consensus.leaders_history = response.leaders_history.clone();
     lock.consensus.nullifiers = response.nullifiers.clone();
@@ -16,21 +16,32 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{collections::HashMap, io::Cursor};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    io::Cursor,
+};
 
 use async_std::sync::{Arc, RwLock};
 use darkfi_sdk::{
     crypto::{
         constants::MERKLE_DEPTH,
-        contract_id::{DAO_CONTRACT_ID, MONEY_CONTRACT_ID},
+        contract_id::{DAO_CONTRACT_ID, DEPLOYOOOR_CONTRACT_ID, MONEY_CONTRACT_ID},
         schnorr::{SchnorrPublic, SchnorrSecret},
-        MerkleNode, PublicKey, SecretKey,
+        ContractId, MerkleNode, PublicKey, SecretKey,
     },
     db::SMART_CONTRACT_ZKAS_DB_NAME,
+    deploy::DeployParamsV1,
+    fee::{FeeClaimParamsV1, MONEY_CONTRACT_FEE_CLAIM},
     incrementalmerkletree::{bridgetree::BridgeTree, Tree},
-    pasta::{group::ff::PrimeField, pallas},
+    pasta::{
+        group::ff::{Field, PrimeField},
+        pallas,
+    },
+    tx::ContractCall,
 };
 use darkfi_serial::{deserialize, serialize, Decodable, Encodable, WriteExt};
+use futures::future::join_all;
 use log::{debug, error, info, warn};
 use rand::rngs::OsRng;
 use serde_json::json;
@@ -38,14 +49,18 @@ use serde_json::json;
 use super::{
     constants,
     leadcoin::LeadCoin,
+    mempool::{ConflictKey, Mempool},
     state::{ConsensusState, Fork, SlotCheckpoint, StateCheckpoint},
-    BlockInfo, BlockProposal, Header, LeadInfo, LeadProof,
+    BlockInfo, BlockProposal, EmissionSchedule, Header, LeadInfo, LeadProof,
 };
 
 use crate::{
     blockchain::Blockchain,
     rpc::jsonrpc::JsonNotification,
-    runtime::vm_runtime::Runtime,
+    runtime::{
+        module_cache::ModuleCache,
+        vm_runtime::{ContractSection, Runtime, MAX_GAS_LIMIT},
+    },
     system::{Subscriber, SubscriberPtr},
     tx::Transaction,
     util::time::Timestamp,
@@ -56,12 +71,21 @@ use crate::{
         vm_stack::empty_witnesses,
     },
     zkas::ZkBinary,
-    Error, Result,
+    Error, Result, VerifyFailed,
 };
 
 /// Atomic pointer to validator state.
 pub type ValidatorStatePtr = Arc<RwLock<ValidatorState>>;
 
+/// Maximum size of the wasm bincode of a deployed contract
+pub const MAX_DEPLOY_WASM_SIZE: usize = 2 * 1024 * 1024;
+/// Maximum number of zkas circuits a contract deployment can provide
+pub const MAX_DEPLOY_CIRCUITS: usize = 16;
+/// Gas charged per byte of a deployed wasm bincode
+pub const DEPLOY_GAS_PER_WASM_BYTE: u64 = 10;
+/// Gas charged per row (`2^k`) of a deployed zkas circuit
+pub const DEPLOY_GAS_PER_CIRCUIT_ROW: u64 = 100;
+
 type VerifyingKeyMap = Arc<RwLock<HashMap<[u8; 32], Vec<(String, VerifyingKey)>>>>;
 
 /// This struct represents the state of a validator node.
@@ -75,7 +99,7 @@ pub struct ValidatorState {
     /// Canonical (finalized) blockchain
     pub blockchain: Blockchain,
     /// Pending transactions
-    pub unconfirmed_txs: Vec<Transaction>,
+    pub mempool: Mempool,
     /// A map of various subscribers exporting live info from the blockchain
     /// TODO: Instead of JsonNotification, it can be an enum of internal objects,
     ///       and then we don't have to deal with json in this module but only
@@ -83,17 +107,21 @@ pub struct ValidatorState {
     pub subscribers: HashMap<&'static str, SubscriberPtr<JsonNotification>>,
     /// ZK proof verifying keys for smart contract calls
     pub verifying_keys: VerifyingKeyMap,
+    /// Compiled wasm modules of smart contracts
+    pub module_cache: ModuleCache,
     /// Wallet interface
     pub wallet: WalletPtr,
 }
 
 impl ValidatorState {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         db: &sled::Db, // <-- TODO: Avoid this with some wrapping, sled should only be in blockchain
         bootstrap_ts: Timestamp,
         genesis_ts: Timestamp,
         genesis_data: blake3::Hash,
         initial_distribution: u64,
+        emission: EmissionSchedule,
         wallet: WalletPtr,
         faucet_pubkeys: Vec<PublicKey>,
         enable_participation: bool,
@@ -108,19 +136,17 @@ impl ValidatorState {
         //wallet.exec_sql(consensus_tree_init_query).await?;
         //wallet.exec_sql(consensus_keys_init_query).await?;
 
-        debug!(target: "consensus::validator", "Generating leader proof keys with k: {}", constants::LEADER_PROOF_K);
         let bincode = include_bytes!("../../proof/lead.zk.bin");
         let zkbin = ZkBinary::decode(bincode)?;
+        let k = zkbin.k;
+        debug!(target: "consensus::validator", "Generating leader proof keys with k: {}", k);
         let witnesses = empty_witnesses(&zkbin);
         let circuit = ZkCircuit::new(witnesses, zkbin);
 
-        let lead_verifying_key = VerifyingKey::build(constants::LEADER_PROOF_K, &circuit);
+        let lead_verifying_key = VerifyingKey::build(k, &circuit);
         // We only need this proving key if we're going to participate in the consensus.
-        let lead_proving_key = if enable_participation {
-            Some(ProvingKey::build(constants::LEADER_PROOF_K, &circuit))
-        } else {
-            None
-        };
+        let lead_proving_key =
+            if enable_participation { Some(ProvingKey::build(k, &circuit)) } else { None };
 
         let blockchain = Blockchain::new(db, genesis_ts, genesis_data)?;
         let consensus = ConsensusState::new(
@@ -129,9 +155,10 @@ impl ValidatorState {
             genesis_ts,
             genesis_data,
             initial_distribution,
+            emission,
         )?;
 
-        let unconfirmed_txs = vec![];
+        let mempool = Mempool::new(db)?;
 
         // -----NATIVE WASM CONTRACTS-----
         // This is the current place where native contracts are being deployed.
@@ -152,9 +179,13 @@ impl ValidatorState {
         let dao_contract_deploy_payload = vec![];
 
         // In this hashmap, we keep references to ZK proof verifying keys needed
-        // for the circuits our native contracts provide.
+        // for the circuits deployed contracts provide.
         let mut verifying_keys = HashMap::new();
 
+        // Compiled contract modules are kept around, so contracts don't have
+        // to be compiled again for every call that gets executed.
+        let module_cache = ModuleCache::with_store(blockchain.wasm_modules.clone());
+
         let native_contracts = vec![
             (
                 "Money Contract",
@@ -173,40 +204,29 @@ impl ValidatorState {
         info!(target: "consensus::validator", "Deploying native wasm contracts");
         for nc in native_contracts {
             info!(target: "consensus::validator", "Deploying {} with ContractID {}", nc.0, nc.1);
-            let mut runtime = Runtime::new(&nc.2[..], blockchain.clone(), nc.1)?;
+            let mut runtime =
+                Runtime::new(&nc.2[..], blockchain.clone(), &module_cache, nc.1, MAX_GAS_LIMIT)?;
             runtime.deploy(&nc.3)?;
+            module_cache.persist(nc.1, &nc.2)?;
             info!(target: "consensus::validator", "Successfully deployed {}", nc.0);
-
-            // When deployed, we can do a lookup for the zkas circuits and
-            // initialize verifying keys for them.
-            info!(target: "consensus::validator", "Creating ZK verifying keys for {} zkas circuits", nc.0);
-            info!(target: "consensus::validator", "Looking up zkas db for {} (ContractID: {})", nc.0, nc.1);
-            let zkas_db = blockchain.contracts.lookup(
-                &blockchain.sled_db,
-                &nc.1,
-                SMART_CONTRACT_ZKAS_DB_NAME,
-            )?;
-
-            let mut vks = vec![];
-            for i in zkas_db.iter() {
-                info!(target: "consensus::validator", "Iterating over zkas db");
-                let (zkas_ns, zkas_bincode) = i?;
-                info!(target: "consensus::validator", "Deserializing namespace");
-                let zkas_ns: String = deserialize(&zkas_ns)?;
-                info!(target: "consensus::validator", "Creating VerifyingKey for zkas circuit with namespace {}", zkas_ns);
-                let zkbin = ZkBinary::decode(&zkas_bincode)?;
-                let circuit = ZkCircuit::new(empty_witnesses(&zkbin), zkbin);
-                // FIXME: This k=13 man...
-                let vk = VerifyingKey::build(13, &circuit);
-                vks.push((zkas_ns, vk));
-            }
-
-            info!(target: "consensus::validator", "Finished creating VerifyingKey objects for {} (ContractID: {})", nc.0, nc.1);
-            verifying_keys.insert(nc.1.to_bytes(), vks);
         }
         info!(target: "consensus::validator", "Finished deployment of native wasm contracts");
         // -----NATIVE WASM CONTRACTS-----
 
+        // When deployed, we can do a lookup for the zkas circuits of all the
+        // contracts on chain, native or not, and initialize verifying keys
+        // for them.
+        for contract_id in blockchain.wasm_bincode.contract_ids()? {
+            info!(target: "consensus::validator", "Creating ZK verifying keys for ContractID {}", contract_id);
+            let vks = match build_verifying_keys(&blockchain, &contract_id) {
+                Ok(v) => v,
+                // The contract never initialized a zkas db
+                Err(Error::ContractNotFound(_)) | Err(Error::ContractStateNotFound) => vec![],
+                Err(e) => return Err(e),
+            };
+            verifying_keys.insert(contract_id.to_bytes(), vks);
+        }
+
         // Here we initialize various subscribers that can export live consensus/blockchain data.
         let mut subscribers = HashMap::new();
         let block_subscriber = Subscriber::new();
@@ -217,46 +237,183 @@ impl ValidatorState {
             lead_verifying_key,
             consensus,
             blockchain,
-            unconfirmed_txs,
+            mempool,
             subscribers,
             verifying_keys: Arc::new(RwLock::new(verifying_keys)),
+            module_cache,
             wallet,
         }));
 
+        // Transactions persisted by a previous run might have become invalid
+        // in the meantime, so they're verified again.
+        state.write().await.revalidate_mempool().await?;
+
         Ok(state)
     }
 
+    /// Verify all pending transactions against the current state, evicting
+    /// the ones that fail from the mempool.
+    pub async fn revalidate_mempool(&mut self) -> Result<()> {
+        info!(target: "consensus::validator", "revalidate_mempool(): Verifying {} pending transactions", self.mempool.len());
+        for entry in self.mempool.entries().to_vec() {
+            let tx_hash = entry.hash;
+            let res = match self.verify_transactions(&[entry.tx.clone()], false).await {
+                Ok(_) => self.tx_fees(&entry.tx),
+                Err(e) => Err(e),
+            };
+
+            match res {
+                Ok((_, 0)) => {}
+                Ok(_) => {
+                    warn!(target: "consensus::validator", "revalidate_mempool(): Evicting tx {}: claims fees", tx_hash);
+                    self.mempool.remove(&tx_hash)?;
+                }
+                Err(e) => {
+                    warn!(target: "consensus::validator", "revalidate_mempool(): Evicting tx {}: {}", tx_hash, e);
+                    self.mempool.remove(&tx_hash)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// The node retrieves a transaction, validates its state transition,
     /// and appends it to the unconfirmed transactions list.
-    pub async fn append_tx(&mut self, tx: Transaction) -> bool {
+    /// Returns `false` if the transaction was already seen, and an
+    /// error if it failed verification.
+    pub async fn append_tx(&mut self, tx: Transaction) -> Result<bool> {
         let tx_hash = blake3::hash(&serialize(&tx));
         let tx_in_txstore = match self.blockchain.transactions.contains(&tx_hash) {
             Ok(v) => v,
             Err(e) => {
                 error!(target: "consensus::validator", "append_tx(): Failed querying txstore: {}", e);
-                return false
+                return Ok(false)
             }
         };
 
-        if self.unconfirmed_txs.contains(&tx) || tx_in_txstore {
+        if self.mempool.contains(&tx_hash) || tx_in_txstore {
             info!(target: "consensus::validator", "append_tx(): We have already seen this tx.");
-            return false
+            return Ok(false)
         }
 
         info!(target: "consensus::validator", "append_tx(): Starting state transition validation");
-        if let Err(e) = self.verify_transactions(&[tx.clone()], false).await {
-            error!(target: "consensus::validator", "append_tx(): Failed to verify transaction: {}", e);
-            return false
+        let slot = self.consensus.current_slot();
+        let conflict_keys = match self.verify_txs_conflict_keys(&[tx.clone()], slot, false).await {
+            Ok(mut v) => v.pop().map(|(_, keys)| keys).unwrap_or_default(),
+            Err(e) => {
+                error!(target: "consensus::validator", "append_tx(): Failed to verify transaction: {}", e);
+                return Err(e)
+            }
         };
 
+        // Fees can only be claimed by block proposers
+        let (fee_paid, fee_claimed) = self.tx_fees(&tx)?;
+        if fee_claimed > 0 {
+            error!(target: "consensus::validator", "append_tx(): Transaction claims fees");
+            return Err(VerifyFailed::UnexpectedFeeClaim(tx_hash.to_hex().to_string()).into())
+        }
+
+        // Only one of the pending transactions claiming the same values can
+        // make it into a block. The new one replaces them if it pays a higher
+        // fee than each of them, otherwise it's rejected.
+        let mut replaced = HashSet::new();
+        for entry in self.mempool.conflicting(&conflict_keys) {
+            if entry.fee >= fee_paid {
+                error!(
+                    target: "consensus::validator",
+                    "append_tx(): Transaction conflicts with pending tx {} paying {} in fees",
+                    entry.hash, entry.fee
+                );
+                return Err(Error::MempoolConflict(
+                    tx_hash.to_hex().to_string(),
+                    entry.hash.to_hex().to_string(),
+                ))
+            }
+            replaced.insert(entry.hash);
+        }
+
+        if let Err(e) = self.mempool_make_room(&tx_hash, serialize(&tx).len(), fee_paid, &replaced)
+        {
+            error!(target: "consensus::validator", "append_tx(): {}", e);
+            return Err(e)
+        }
+
+        for pending_hash in replaced {
+            info!(target: "consensus::validator", "append_tx(): Replacing conflicting tx {}", pending_hash);
+            self.mempool.remove(&pending_hash)?;
+        }
+
         info!(target: "consensus::validator", "append_tx(): Appended tx to mempool");
-        self.unconfirmed_txs.push(tx);
-        true
+        self.mempool.insert(tx, fee_paid, conflict_keys)?;
+        Ok(true)
+    }
+
+    /// Make room in the mempool for a new transaction of the given size,
+    /// paying `fee`, which replaces the `replaced` pending transactions.
+    /// If the mempool is full, pending transactions paying lower fees are
+    /// evicted, starting with the lowest, and the newest among equal fees.
+    /// If that's not enough, nothing gets evicted and the new transaction
+    /// is rejected. The replaced transactions are left for the caller to
+    /// remove.
+    fn mempool_make_room(
+        &mut self,
+        tx_hash: &blake3::Hash,
+        tx_size: usize,
+        fee: u64,
+        replaced: &HashSet<blake3::Hash>,
+    ) -> Result<()> {
+        let replaced_size: usize = self
+            .mempool
+            .entries()
+            .iter()
+            .filter(|entry| replaced.contains(&entry.hash))
+            .map(|entry| entry.size)
+            .sum();
+
+        let mut count = self.mempool.len() + 1 - replaced.len();
+        let mut size = self.mempool.size() + tx_size - replaced_size;
+        if count <= constants::MEMPOOL_MAX_TXS && size <= constants::MEMPOOL_MAX_BYTES {
+            return Ok(())
+        }
+
+        // Eviction candidates, in the order they'd be evicted
+        let mut candidates: Vec<_> = self
+            .mempool
+            .entries()
+            .iter()
+            .filter(|entry| entry.fee < fee && !replaced.contains(&entry.hash))
+            .map(|entry| (entry.fee, entry.hash, entry.size))
+            .collect();
+        candidates.reverse();
+        candidates.sort_by_key(|(pending_fee, _, _)| *pending_fee);
+
+        let mut evicted = vec![];
+        for (_, pending_hash, pending_size) in candidates {
+            if count <= constants::MEMPOOL_MAX_TXS && size <= constants::MEMPOOL_MAX_BYTES {
+                break
+            }
+            count -= 1;
+            size -= pending_size;
+            evicted.push(pending_hash);
+        }
+
+        if count > constants::MEMPOOL_MAX_TXS || size > constants::MEMPOOL_MAX_BYTES {
+            return Err(Error::MempoolFull(tx_hash.to_hex().to_string()))
+        }
+
+        for pending_hash in evicted {
+            info!(target: "consensus::validator", "mempool_make_room(): Evicting tx {}", pending_hash);
+            self.mempool.remove(&pending_hash)?;
+        }
+
+        Ok(())
     }
 
     /// Generate a block proposal for the current slot, containing all
     /// unconfirmed transactions. Proposal extends the longest fork
-    /// chain the node is holding.
+    /// chain the node is holding. If the transactions pay any fees,
+    /// a transaction claiming them for the proposer is appended.
     pub fn propose(
         &mut self,
         slot: u64,
@@ -270,8 +427,37 @@ impl ValidatorState {
             return Ok(None)
         }
 
+        // Checking if extending a fork or canonical
+        let (prev_hash, coin) = if fork_index == -1 {
+            (self.blockchain.last()?.1, self.consensus.coins[coin_index])
+        } else {
+            let checkpoint = self.consensus.forks[fork_index as usize].sequence.last().unwrap();
+            (checkpoint.proposal.hash, checkpoint.coins[coin_index])
+        };
+
+        let secret_key = coin.coin1_sk;
+        let public_key = PublicKey::from_secret(secret_key.into());
+
         // Generate proposal
-        let unproposed_txs = self.unproposed_txs(fork_index);
+        let mut unproposed_txs = self.unproposed_txs_with_fees(fork_index);
+
+        // Collect the fees paid by the proposed transactions. The claim needs
+        // room in the block, so if it's full, the transaction paying the lowest
+        // fee is left out.
+        let mut fees = unproposed_txs.iter().fold(0_u64, |acc, (_, fee)| acc.saturating_add(*fee));
+        if unproposed_txs.len() == constants::TXS_CAP && fees > 0 {
+            if let Some((_, fee)) = unproposed_txs.pop() {
+                fees -= fee;
+            }
+        }
+
+        let mut unproposed_txs: Vec<Transaction> =
+            unproposed_txs.into_iter().map(|(tx, _)| tx).collect();
+        if fees > 0 {
+            info!(target: "consensus::validator", "propose(): Claiming {} in fees", fees);
+            unproposed_txs.push(fee_claim_tx(public_key, fees)?);
+        }
+
         let mut tree = BridgeTree::<MerkleNode, MERKLE_DEPTH>::new(100);
         // The following is pretty weird, so something better should be done.
         for tx in &unproposed_txs {
@@ -281,20 +467,12 @@ impl ValidatorState {
         }
         let root = tree.root(0).unwrap();
 
-        // Checking if extending a fork or canonical
-        let (prev_hash, coin) = if fork_index == -1 {
-            (self.blockchain.last()?.1, self.consensus.coins[coin_index])
-        } else {
-            let checkpoint = self.consensus.forks[fork_index as usize].sequence.last().unwrap();
-            (checkpoint.proposal.hash, checkpoint.coins[coin_index])
-        };
-
-        // Generating leader proof
+        // Generating leader proof, claiming the slot reward
+        let reward = self.consensus.reward(slot);
         let (proof, public_inputs) =
-            coin.create_lead_proof(sigma1, sigma2, self.lead_proving_key.as_ref().unwrap());
+            coin.create_lead_proof(sigma1, sigma2, reward, self.lead_proving_key.as_ref().unwrap());
 
         // Signing using coin
-        let secret_key = coin.coin1_sk;
         let header = Header::new(
             prev_hash,
             self.consensus.slot_epoch(slot),
@@ -304,7 +482,6 @@ impl ValidatorState {
         );
         let signed_proposal =
             SecretKey::from(secret_key).sign(&mut OsRng, &header.headerhash().as_bytes()[..]);
-        let public_key = PublicKey::from_secret(secret_key.into());
 
         let lead_info = LeadInfo::new(
             signed_proposal,
@@ -321,31 +498,53 @@ impl ValidatorState {
     }
 
     /// Retrieve all unconfirmed transactions not proposed in previous blocks
-    /// of provided index chain.
+    /// of provided index chain, ordered by the fees they pay, highest first.
     pub fn unproposed_txs(&self, index: i64) -> Vec<Transaction> {
-        let unproposed_txs = if index == -1 {
-            // If index is -1 (canonical blockchain) a new fork will be generated,
-            // therefore all unproposed transactions can be included in the proposal.
-            self.unconfirmed_txs.clone()
-        } else {
-            // We iterate over the fork chain proposals to find already proposed
-            // transactions and remove them from the local unproposed_txs vector.
-            let mut filtered_txs = self.unconfirmed_txs.clone();
+        self.unproposed_txs_with_fees(index).into_iter().map(|(tx, _)| tx).collect()
+    }
+
+    /// Retrieve all unconfirmed transactions not proposed in previous blocks
+    /// of provided index chain, along with the fees they pay, highest first.
+    /// Transactions conflicting with proposed or higher paying ones are left out.
+    fn unproposed_txs_with_fees(&self, index: i64) -> Vec<(Transaction, u64)> {
+        // If index is -1 (canonical blockchain) a new fork will be generated,
+        // therefore all unproposed transactions can be included in the proposal.
+        // Otherwise, we iterate over the fork chain proposals to find already
+        // proposed transactions and leave them out.
+        let mut proposed = HashSet::new();
+        if index != -1 {
             let chain = &self.consensus.forks[index as usize];
             for state_checkpoint in &chain.sequence {
                 for tx in &state_checkpoint.proposal.block.txs {
-                    if let Some(pos) = filtered_txs.iter().position(|txs| *txs == *tx) {
-                        filtered_txs.remove(pos);
-                    }
+                    proposed.insert(blake3::hash(&serialize(tx)));
                 }
             }
-            filtered_txs
-        };
+        }
+
+        // The fees and conflict keys were retrieved when the transactions
+        // entered the mempool
+        let (in_fork, mut pending): (Vec<_>, Vec<_>) =
+            self.mempool.entries().iter().partition(|entry| proposed.contains(&entry.hash));
+        let mut claimed: HashSet<_> =
+            in_fork.iter().flat_map(|entry| entry.conflict_keys.iter()).collect();
+
+        // Transactions paying the same fee keep their arrival order
+        pending.sort_by_key(|entry| Reverse(entry.fee));
+
+        let mut unproposed_txs = vec![];
+        for entry in pending {
+            // Check if transactions exceed configured cap
+            if unproposed_txs.len() == constants::TXS_CAP {
+                break
+            }
 
-        // Check if transactions exceed configured cap
-        let cap = constants::TXS_CAP;
-        if unproposed_txs.len() > cap {
-            return unproposed_txs[0..cap].to_vec()
+            if entry.conflict_keys.iter().any(|key| claimed.contains(key)) {
+                debug!(target: "consensus::validator", "unproposed_txs_with_fees(): Skipping conflicting tx {}", entry.hash);
+                continue
+            }
+
+            claimed.extend(entry.conflict_keys.iter());
+            unproposed_txs.push((entry.tx.clone(), entry.fee));
         }
 
         unproposed_txs
@@ -560,16 +759,32 @@ impl ValidatorState {
         // Validate state transition against canonical state
         // TODO: This should be validated against fork state
         info!(target: "consensus::validator", "receive_proposal(): Starting state transition validation");
-        if let Err(e) = self.verify_transactions(&proposal.block.txs, false).await {
+        let slot = proposal.block.header.slot;
+        if let Err(e) = self.verify_transactions_at_slot(&proposal.block.txs, slot, false).await {
             error!(target: "consensus::validator", "receive_proposal(): Transaction verifications failed: {}", e);
             return Err(e)
         };
 
-        // TODO: [PLACEHOLDER] Add rewards validation
+        if let Err(e) = self.verify_fees(&proposal.block.txs) {
+            error!(target: "consensus::validator", "receive_proposal(): Fee verification failed: {}", e);
+            return Err(e)
+        };
+
+        // Validate proposal reward against the emission schedule
+        let reward = self.consensus.reward(hdr.slot);
+        let prop_reward = lf.public_inputs[constants::PI_REWARD_INDEX];
+        if pallas::Base::from(reward) != prop_reward {
+            error!(
+                target: "consensus::validator",
+                "receive_proposal(): Failed to verify reward: {}, proposed: {:?}",
+                reward, prop_reward
+            );
+            return Err(Error::ProposalPublicValuesMismatched)
+        }
 
         // If proposal came fromself, we derive new coin
         if let Some((idx, c)) = coin {
-            state_checkpoint.coins[idx] = c.derive_coin(&mut state_checkpoint.coins_tree);
+            state_checkpoint.coins[idx] = c.derive_coin(reward, &mut state_checkpoint.coins_tree);
         }
         // Store proposal coins nullifiers
         state_checkpoint.nullifiers.push(prop_sn);
@@ -588,11 +803,35 @@ impl ValidatorState {
         Ok(true)
     }
 
-    /// Remove provided transactions vector from unconfirmed_txs if they exist.
+    /// Remove provided transactions vector from the mempool if they exist.
     pub fn remove_txs(&mut self, transactions: &Vec<Transaction>) -> Result<()> {
         for tx in transactions {
-            if let Some(pos) = self.unconfirmed_txs.iter().position(|txs| txs == tx) {
-                self.unconfirmed_txs.remove(pos);
+            self.mempool.remove(&blake3::hash(&serialize(tx)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Execute the pending transactions against the current state, evicting
+    /// the ones that became invalid, for example because a finalized block
+    /// spent their nullifiers. Their ZK proofs don't depend on the state, so
+    /// they're not verified again.
+    pub async fn evict_invalid_txs(&mut self) -> Result<()> {
+        let hashes = self.mempool.hashes();
+        let slot = self.consensus.current_slot();
+        let mut tasks = Vec::with_capacity(hashes.len());
+        for tx in self.mempool.txs() {
+            let blockchain = self.blockchain.clone();
+            let module_cache = self.module_cache.clone();
+            tasks.push(smol::unblock(move || {
+                execute_transaction(&blockchain, &module_cache, &tx, slot)
+            }));
+        }
+
+        for (tx_hash, result) in hashes.iter().zip(join_all(tasks).await) {
+            if let Err(e) = result {
+                info!(target: "consensus::validator", "evict_invalid_txs(): Evicting tx {}: {}", tx_hash, e);
+                self.mempool.remove(tx_hash)?;
             }
         }
 
@@ -686,6 +925,7 @@ impl ValidatorState {
                 return Err(e)
             }
         };
+        self.consensus.add_finalized_rewards(&finalized);
 
         let blocks_subscriber = self.subscribers.get("blocks").unwrap().clone();
 
@@ -696,7 +936,8 @@ impl ValidatorState {
             // TODO: FIXME: The state transitions have already been written, they have to be in memory
             //              until this point.
             info!(target: "consensus::validator", "Applying state transition for finalized block");
-            if let Err(e) = self.verify_transactions(&proposal.txs, true).await {
+            let slot = proposal.header.slot;
+            if let Err(e) = self.verify_transactions_at_slot(&proposal.txs, slot, true).await {
                 error!(target: "consensus::validator", "Finalized block transaction verifications failed: {}", e);
                 return Err(e)
             }
@@ -714,6 +955,12 @@ impl ValidatorState {
             blocks_subscriber.notify(notif).await;
         }
 
+        // Pending transactions might conflict with the finalized ones
+        if let Err(e) = self.evict_invalid_txs().await {
+            error!(target: "consensus::validator", "Evicting invalid pending transactions failed: {}", e);
+            return Err(e)
+        }
+
         // Setting leaders history to last proposal leaders count
         self.consensus.leaders_history =
             vec![fork.sequence.last().unwrap().proposal.block.lead_info.leaders];
@@ -778,7 +1025,14 @@ impl ValidatorState {
         // Verify state transitions for all blocks and their respective transactions.
         info!(target: "consensus::validator", "receive_blocks(): Starting state transition validations");
         for block in blocks {
-            if let Err(e) = self.verify_transactions(&block.txs, true).await {
+            if let Err(e) = self.verify_fees(&block.txs) {
+                error!(target: "consensus::validator", "receive_blocks(): Fee verification failed: {}", e);
+                return Err(e)
+            }
+
+            if let Err(e) =
+                self.verify_transactions_at_slot(&block.txs, block.header.slot, true).await
+            {
                 error!(target: "consensus::validator", "receive_blocks(): Transaction verifications failed: {}", e);
                 return Err(e)
             }
@@ -787,6 +1041,7 @@ impl ValidatorState {
         info!(target: "consensus::validator", "receive_blocks(): All state transitions passed");
         info!(target: "consensus::validator", "receive_blocks(): Appending blocks to ledger");
         self.blockchain.add(blocks)?;
+        self.consensus.add_finalized_rewards(blocks);
 
         Ok(())
     }
@@ -817,8 +1072,9 @@ impl ValidatorState {
         info!(target: "consensus::validator", "consensus: Sending notification about finalized block");
         blocks_subscriber.notify(notif).await;
 
-        info!(target: "consensus::validator", "receive_finalized_block(): Removing block transactions from unconfirmed_txs");
+        info!(target: "consensus::validator", "receive_finalized_block(): Removing block transactions from mempool");
         self.remove_txs(&block.txs)?;
+        self.evict_invalid_txs().await?;
 
         Ok(true)
     }
@@ -853,172 +1109,152 @@ impl ValidatorState {
 
         // TODO: Don't hardcode this:
         let blocks_subscriber = self.subscribers.get("blocks").unwrap();
-        for block in new_blocks {
-            let params = json!([bs58::encode(&serialize(&block)).into_string()]);
+        for block in &new_blocks {
+            let params = json!([bs58::encode(&serialize(block)).into_string()]);
             let notif = JsonNotification::new("blockchain.subscribe_blocks", params);
             info!(target: "consensus::validator", "consensus: Sending notification about finalized block");
             blocks_subscriber.notify(notif).await;
         }
 
+        info!(target: "consensus::validator", "receive_sync_blocks(): Removing block transactions from mempool");
+        for block in &new_blocks {
+            self.remove_txs(&block.txs)?;
+        }
+        self.evict_invalid_txs().await?;
+
         Ok(())
     }
 
     /// Validate signatures, wasm execution, and zk proofs for given transactions.
     /// If all of those succeed, try to execute a state update for the contract calls.
-    /// Currently the verifications are sequential, and the function will fail if any
-    /// of the verifications fail.
+    /// The function will fail if any of the verifications fail.
+    /// The calls of all the transactions are executed concurrently, and then
+    /// their ZK proofs are verified together. Since the transactions are all
+    /// executed against the same state, any values (like nullifiers) claimed by
+    /// more than one of them are detected before anything else, and make the
+    /// whole batch fail. This also means a transaction can't depend on another
+    /// one in the same batch, like spending a coin or using a Merkle root it
+    /// creates, or calling a contract it deploys. Such a transaction fails, and
+    /// has to be included in a later block.
     /// The function takes a boolean called `write` which tells it to actually write
-    /// the state transitions to the database.
-    // TODO: This should be paralellized as if even one tx in the batch fails to verify,
-    //       we can drop everything.
-    pub async fn verify_transactions(&self, txs: &[Transaction], write: bool) -> Result<()> {
-        info!(target: "consensus::validator", "Verifying {} transaction(s)", txs.len());
-        for tx in txs {
-            let tx_hash = blake3::hash(&serialize(tx));
-            info!(target: "consensus::validator", "Verifying transaction {}", tx_hash);
-
-            // Table of public inputs used for ZK proof verification
-            let mut zkp_table = vec![];
-            // Table of public keys used for signature verification
-            let mut sig_table = vec![];
-            // State updates produced by contract execcution
-            let mut updates = vec![];
-
-            // Iterate over all calls to get the metadata
-            for (idx, call) in tx.calls.iter().enumerate() {
-                info!(target: "consensus::validator", "Executing contract call {}", idx);
-                let wasm = match self.blockchain.wasm_bincode.get(call.contract_id) {
-                    Ok(v) => {
-                        info!(target: "consensus::validator", "Found wasm bincode for {}", call.contract_id);
-                        v
-                    }
-                    Err(e) => {
-                        error!(
-                            target: "consensus::validator",
-                            "Could not find wasm bincode for contract {}: {}",
-                            call.contract_id, e
-                        );
-                        return Err(Error::ContractNotFound(call.contract_id.to_string()))
-                    }
-                };
+    /// the state transitions to the database. This is done sequentially, in the
+    /// order the transactions were given.
+    /// Each contract call is executed with the gas limit it carries, and a transaction
+    /// is rejected if any of its calls exceeds it. On success, the total gas consumed
+    /// by each transaction is returned, which includes the state updates if `write`
+    /// is set.
+    /// The transactions are verified against the current slot, see
+    /// [`ValidatorState::verify_transactions_at_slot`].
+    pub async fn verify_transactions(&self, txs: &[Transaction], write: bool) -> Result<Vec<u64>> {
+        self.verify_transactions_at_slot(txs, self.consensus.current_slot(), write).await
+    }
 
-                // Write the actual payload data
-                let mut payload = vec![];
-                payload.write_u32(idx as u32)?; // Call index
-                tx.calls.encode(&mut payload)?; // Actual call data
+    /// Same as [`ValidatorState::verify_transactions`], but the contract calls
+    /// are executed as part of the given slot, which they can use to enforce
+    /// time bounds. Transactions of a block are verified against its slot.
+    pub async fn verify_transactions_at_slot(
+        &self,
+        txs: &[Transaction],
+        slot: u64,
+        write: bool,
+    ) -> Result<Vec<u64>> {
+        let verified = self.verify_txs_conflict_keys(txs, slot, write).await?;
+        Ok(verified.into_iter().map(|(gas, _)| gas).collect())
+    }
 
-                // Instantiate the wasm runtime
-                let mut runtime =
-                    match Runtime::new(&wasm, self.blockchain.clone(), call.contract_id) {
-                        Ok(v) => v,
-                        Err(e) => {
-                            error!(
-                                target: "consensus::validator",
-                                "Failed to instantiate WASM runtime for contract {}",
-                                call.contract_id
-                            );
-                            return Err(e)
-                        }
-                    };
+    /// Same as [`ValidatorState::verify_transactions_at_slot`], but the
+    /// conflict keys claimed by each transaction are returned along with
+    /// the gas it consumed.
+    async fn verify_txs_conflict_keys(
+        &self,
+        txs: &[Transaction],
+        slot: u64,
+        write: bool,
+    ) -> Result<Vec<(u64, Vec<ConflictKey>)>> {
+        info!(target: "consensus::validator", "Verifying {} transaction(s) at slot {}", txs.len(), slot);
 
-                info!(target: "consensus::validator", "Executing \"metadata\" call");
-                let metadata = match runtime.metadata(&payload) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        error!(target: "consensus::validator", "Failed to execute \"metadata\" call: {}", e);
-                        return Err(e)
-                    }
-                };
-
-                // Decode the metadata retrieved from the execution
-                let mut decoder = Cursor::new(&metadata);
-                let zkp_pub: Vec<(String, Vec<pallas::Base>)> = match Decodable::decode(
-                    &mut decoder,
-                ) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        error!(target: "consensus::validator", "Failed to decode ZK public inputs from metadata: {}", e);
-                        return Err(e.into())
-                    }
-                };
+        // None of the transactions write anything to the state while they're
+        // executed, so we can run them all at once.
+        let mut tasks = Vec::with_capacity(txs.len());
+        for tx in txs {
+            let tx = tx.clone();
+            let blockchain = self.blockchain.clone();
+            let module_cache = self.module_cache.clone();
+            tasks.push(smol::unblock(move || {
+                execute_transaction(&blockchain, &module_cache, &tx, slot)
+            }));
+        }
 
-                let sig_pub: Vec<PublicKey> = match Decodable::decode(&mut decoder) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        error!(target: "consensus::validator", "Failed to decode signature pubkeys from metadata: {}", e);
-                        return Err(e.into())
-                    }
-                };
-
-                // TODO: Make sure we've read all the bytes above.
-                info!(target: "consensus::validator", "Successfully executed \"metadata\" call");
-                zkp_table.push(zkp_pub);
-                sig_table.push(sig_pub);
-
-                // After getting the metadata, we run the "exec" function with the same
-                // runtime and the same payload.
-                info!(target: "consensus::validator", "Executing \"exec\" call");
-                match runtime.exec(&payload) {
-                    Ok(v) => {
-                        info!(target: "consensus::validator", "Successfully executed \"exec\" call");
-                        updates.push(v);
-                    }
-                    Err(e) => {
-                        error!(
-                            target: "consensus::validator",
-                            "Failed to execute \"exec\" call for contract id {}: {}",
-                            call.contract_id, e
-                        );
-                        return Err(e)
-                    }
-                };
-                // At this point we're done with the call and move on to the next one.
-            }
+        let mut executed = vec![];
+        for result in join_all(tasks).await {
+            executed.push(result?);
+        }
 
-            // When we're done looping and executing over the tx's contract calls, we
-            // move on with verification. First we verify the signatures as that's
-            // cheaper, and then finally we verify the ZK proofs.
-            info!(target: "consensus::validator", "Verifying signatures for transaction {}", tx_hash);
-            if sig_table.len() != tx.signatures.len() {
-                error!(target: "consensus::validator", "Incorrect number of signatures in tx {}", tx_hash);
-                return Err(Error::InvalidSignature)
+        // Look for values claimed by more than one transaction. Each of them
+        // would be valid on its own, but not together.
+        info!(target: "consensus::validator", "Checking transactions for conflicts");
+        let mut claimed = HashSet::new();
+        for (tx, exec) in txs.iter().zip(executed.iter()) {
+            let keys: HashSet<_> = exec.conflict_keys.iter().collect();
+            if keys.iter().any(|key| claimed.contains(*key)) {
+                let tx_hash = blake3::hash(&serialize(tx)).to_hex().to_string();
+                error!(target: "consensus::validator", "Transaction {} conflicts with another transaction", tx_hash);
+                return Err(VerifyFailed::ConflictingTransaction(tx_hash).into())
             }
+            claimed.extend(keys);
+        }
 
-            match tx.verify_sigs(sig_table) {
-                Ok(()) => {
-                    info!(target: "consensus::validator", "Signatures verification for tx {} successful", tx_hash)
-                }
-                Err(e) => {
-                    error!(target: "consensus::validator", "Signature verification for tx {} failed: {}", tx_hash, e);
-                    return Err(e)
-                }
-            };
+        // NOTE: When it comes to the ZK proofs, we first do a lookup of the
+        // verifying keys, but if we do not find them, we'll generate them
+        // inside of this function. This can be kinda expensive, so open to
+        // alternatives.
+        info!(target: "consensus::validator", "Verifying ZK proofs for {} transaction(s)", txs.len());
+        let zkp_batch: Vec<_> = txs
+            .iter()
+            .zip(executed.iter_mut())
+            .map(|(tx, exec)| (tx, std::mem::take(&mut exec.zkp_table)))
+            .collect();
+
+        match Transaction::verify_zkps_batch(&zkp_batch, self.verifying_keys.clone()).await {
+            Ok(()) => {
+                info!(target: "consensus::validator", "ZK proof verification successful")
+            }
+            Err(e) => {
+                error!(target: "consensus::validator", "ZK proof verification failed: {}", e);
+                return Err(e)
+            }
+        };
 
-            // NOTE: When it comes to the ZK proofs, we first do a lookup of the
-            // verifying keys, but if we do not find them, we'll generate them
-            // inside of this function. This can be kinda expensive, so open to
-            // alternatives.
-            info!(target: "consensus::validator", "Verifying ZK proofs for transaction {}", tx_hash);
-            match tx.verify_zkps(self.verifying_keys.clone(), zkp_table).await {
-                Ok(()) => {
-                    info!(target: "consensus::validator", "ZK proof verification for tx {} successful", tx_hash)
-                }
-                Err(e) => {
-                    error!(target: "consensus::validator", "ZK proof verification for tx {} failed: {}", tx_hash, e);
-                    return Err(e)
-                }
-            };
+        let mut txs_gas = Vec::with_capacity(txs.len());
+        for (tx, exec) in txs.iter().zip(executed.iter_mut()) {
+            let tx_hash = blake3::hash(&serialize(tx));
 
             // After the verifications stage passes, if we're told to write, we
             // apply the state updates.
-            assert!(tx.calls.len() == updates.len());
+            assert!(tx.calls.len() == exec.updates.len());
             if write {
-                info!(target: "consensus::validator", "Performing state updates");
-                for (call, update) in tx.calls.iter().zip(updates.iter()) {
-                    // For this we instantiate the runtimes again.
+                info!(target: "consensus::validator", "Performing state updates for tx {}", tx_hash);
+                for ((call, update), call_gas) in
+                    tx.calls.iter().zip(exec.updates.iter()).zip(exec.calls_gas.iter_mut())
+                {
+                    if call.contract_id == *DEPLOYOOOR_CONTRACT_ID {
+                        info!(target: "consensus::validator", "Applying contract deployment");
+                        match self.apply_deploy(update, call.gas_limit - *call_gas).await {
+                            Ok(deploy_gas) => {
+                                info!(target: "consensus::validator", "Contract deployed successfully");
+                                *call_gas += deploy_gas;
+                            }
+                            Err(e) => {
+                                error!(target: "consensus::validator", "Failed to apply contract deployment: {}", e);
+                                return Err(e)
+                            }
+                        }
+                        continue
+                    }
+
+                    // For this we instantiate the runtimes again, with the gas
+                    // left over from executing the call.
                     // TODO: Optimize this
-                    // TODO: Sum up the gas costs of previous calls during execution
-                    //       and verification and these.
                     let wasm = match self.blockchain.wasm_bincode.get(call.contract_id) {
                         Ok(v) => {
                             info!(target: "consensus::validator", "Found wasm bincode for {}", call.contract_id);
@@ -1034,24 +1270,32 @@ impl ValidatorState {
                         }
                     };
 
-                    let mut runtime =
-                        match Runtime::new(&wasm, self.blockchain.clone(), call.contract_id) {
-                            Ok(v) => v,
-                            Err(e) => {
-                                error!(
-                                    target: "consensus::validator",
-                                    "Failed to instantiate WASM runtime for contract {}",
-                                    call.contract_id
-                                );
-                                return Err(e)
-                            }
-                        };
+                    let mut runtime = match Runtime::new(
+                        &wasm,
+                        self.blockchain.clone(),
+                        &self.module_cache,
+                        call.contract_id,
+                        call.gas_limit - *call_gas,
+                    ) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            error!(
+                                target: "consensus::validator",
+                                "Failed to instantiate WASM runtime for contract {}",
+                                call.contract_id
+                            );
+                            return Err(e)
+                        }
+                    };
+
+                    runtime.ctx.as_mut(&mut runtime.store).verifying_slot = slot;
 
                     info!(target: "consensus::validator", "Executing \"apply\" call");
                     match runtime.apply(update) {
                         // TODO: FIXME: This should be done in an atomic tx/batch
-                        Ok(()) => {
-                            info!(target: "consensus::validator", "State update applied successfully")
+                        Ok(apply_gas) => {
+                            info!(target: "consensus::validator", "State update applied successfully");
+                            *call_gas += apply_gas;
                         }
                         Err(e) => {
                             error!(target: "consensus::validator", "Failed to apply state update: {}", e);
@@ -1063,7 +1307,123 @@ impl ValidatorState {
                 info!(target: "consensus::validator", "Skipping apply of state updates because write=false");
             }
 
-            info!(target: "consensus::validator", "Transaction {} verified successfully", tx_hash);
+            let tx_gas = exec.calls_gas.iter().sum();
+            info!(target: "consensus::validator", "Transaction {} verified successfully, gas used: {}", tx_hash, tx_gas);
+            txs_gas.push((tx_gas, std::mem::take(&mut exec.conflict_keys)));
+        }
+
+        Ok(txs_gas)
+    }
+
+    /// Apply a verified contract deployment. The contract's init function is
+    /// executed, its zkas circuits are stored in its zkas db, and verifying
+    /// keys are created for them. If the contract was already deployed, its
+    /// wasm bincode and circuits are replaced. Returns the gas consumed by
+    /// the init function.
+    async fn apply_deploy(&self, update: &[u8], gas_limit: u64) -> Result<u64> {
+        let params: DeployParamsV1 = deserialize(update)?;
+        let contract_id = ContractId::derive_public(params.public_key);
+        info!(target: "consensus::validator", "Deploying contract {}", contract_id);
+
+        // Circuits of a previous deployment are removed, so an upgrade
+        // doesn't leave behind namespaces it no longer provides. This is
+        // done before the init function runs, as it may set up circuits too.
+        let zkas_db = match self.blockchain.contracts.lookup(
+            &self.blockchain.sled_db,
+            &contract_id,
+            SMART_CONTRACT_ZKAS_DB_NAME,
+        ) {
+            Ok(v) => {
+                v.clear()?;
+                v
+            }
+            Err(_) => self.blockchain.contracts.init(
+                &self.blockchain.sled_db,
+                &contract_id,
+                SMART_CONTRACT_ZKAS_DB_NAME,
+            )?,
+        };
+
+        // The module cache notices the new bincode on its own, and compiles
+        // it in place of the one compiled from the old bincode.
+        let mut runtime = Runtime::new(
+            &params.wasm_bincode,
+            self.blockchain.clone(),
+            &self.module_cache,
+            contract_id,
+            gas_limit,
+        )?;
+        let gas = runtime.deploy(&params.ix)?;
+        self.module_cache.persist(contract_id, &params.wasm_bincode)?;
+
+        for zkas_bincode in &params.zkas_bincodes {
+            let zkbin = ZkBinary::decode(zkas_bincode)?;
+            zkas_db.insert(serialize(&zkbin.namespace), zkas_bincode.clone())?;
+        }
+
+        let vks = build_verifying_keys(&self.blockchain, &contract_id)?;
+        self.verifying_keys.write().await.insert(contract_id.to_bytes(), vks);
+        Ok(gas)
+    }
+
+    /// Retrieve the fees paid and claimed by the given transaction, as reported
+    /// in the metadata of its money contract calls. Other contracts can't move
+    /// native tokens, so their calls are skipped.
+    pub fn tx_fees(&self, tx: &Transaction) -> Result<(u64, u64)> {
+        let mut fee_paid: u64 = 0;
+        let mut fee_claimed: u64 = 0;
+
+        for (idx, call) in tx.calls.iter().enumerate() {
+            if call.contract_id != *MONEY_CONTRACT_ID {
+                continue
+            }
+
+            let wasm = self.blockchain.wasm_bincode.get(call.contract_id)?;
+
+            let mut payload = vec![];
+            payload.write_u32(idx as u32)?; // Call index
+            tx.calls.encode(&mut payload)?; // Actual call data
+
+            let mut runtime = Runtime::new(
+                &wasm,
+                self.blockchain.clone(),
+                &self.module_cache,
+                call.contract_id,
+                call.gas_limit.min(MAX_GAS_LIMIT),
+            )?;
+            let (metadata, _) = runtime.metadata(&payload)?;
+
+            // The fees follow the ZK public inputs, the signature public keys,
+            // and the conflict keys. Only the fee calls set them.
+            let mut decoder = Cursor::new(&metadata);
+            let _: Vec<(String, Vec<pallas::Base>)> = Decodable::decode(&mut decoder)?;
+            let _: Vec<PublicKey> = Decodable::decode(&mut decoder)?;
+            let _: Vec<pallas::Base> = Decodable::decode(&mut decoder)?;
+
+            if decoder.position() < metadata.len() as u64 {
+                let (paid, claimed): (u64, u64) = Decodable::decode(&mut decoder)?;
+                fee_paid = fee_paid.saturating_add(paid);
+                fee_claimed = fee_claimed.saturating_add(claimed);
+            }
+        }
+
+        Ok((fee_paid, fee_claimed))
+    }
+
+    /// Verify that the fees claimed in a block don't exceed the fees paid
+    /// by the rest of its transactions.
+    pub fn verify_fees(&self, txs: &[Transaction]) -> Result<()> {
+        let mut fees_paid: u64 = 0;
+        let mut fees_claimed: u64 = 0;
+
+        for tx in txs {
+            let (paid, claimed) = self.tx_fees(tx)?;
+            fees_paid = fees_paid.saturating_add(paid);
+            fees_claimed = fees_claimed.saturating_add(claimed);
+        }
+
+        if fees_claimed > fees_paid {
+            return Err(VerifyFailed::FeeClaimExceedsFees(fees_claimed, fees_paid).into())
         }
 
         Ok(())
@@ -1105,3 +1465,326 @@ impl ValidatorState {
         Ok(true)
     }
 }
+
+/// Outcome of executing the contract calls of a transaction, before its ZK
+/// proofs are verified and its state updates are applied.
+struct ExecutedTx {
+    /// ZK public inputs of each call
+    zkp_table: Vec<Vec<(String, Vec<pallas::Base>)>>,
+    /// State updates produced by each call
+    updates: Vec<Vec<u8>>,
+    /// Gas consumed by each call
+    calls_gas: Vec<u64>,
+    /// Values the calls spend or create (like nullifiers and coins), along
+    /// with the contract they belong to. No other transaction in the same
+    /// batch is allowed to claim them.
+    conflict_keys: Vec<ConflictKey>,
+}
+
+/// Execute the "metadata" and "exec" sections of all the calls in the given
+/// transaction as part of the given slot, and verify the transaction's signatures.
+fn execute_transaction(
+    blockchain: &Blockchain,
+    module_cache: &ModuleCache,
+    tx: &Transaction,
+    slot: u64,
+) -> Result<ExecutedTx> {
+    let tx_hash = blake3::hash(&serialize(tx));
+    info!(target: "consensus::validator", "Verifying transaction {}", tx_hash);
+
+    // Table of public inputs used for ZK proof verification
+    let mut zkp_table = vec![];
+    // Table of public keys used for signature verification
+    let mut sig_table = vec![];
+    // State updates produced by contract execcution
+    let mut updates = vec![];
+    // Gas consumed by each contract call
+    let mut calls_gas = vec![];
+    // Values claimed by the contract calls
+    let mut conflict_keys = vec![];
+
+    // Iterate over all calls to get the metadata
+    for (idx, call) in tx.calls.iter().enumerate() {
+        info!(target: "consensus::validator", "Executing contract call {}", idx);
+        if call.gas_limit > MAX_GAS_LIMIT {
+            error!(
+                target: "consensus::validator",
+                "Contract call {} gas limit {} is above the maximum {}",
+                idx, call.gas_limit, MAX_GAS_LIMIT
+            );
+            return Err(Error::GasLimitTooHigh(call.gas_limit, MAX_GAS_LIMIT))
+        }
+
+        // Contract deployments are not executed by a wasm contract,
+        // but handled natively.
+        if call.contract_id == *DEPLOYOOOR_CONTRACT_ID {
+            info!(target: "consensus::validator", "Verifying contract deployment");
+            let (contract_id, sig_pub, deploy_gas) = match verify_deploy(
+                blockchain,
+                module_cache,
+                call,
+            ) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(target: "consensus::validator", "Failed to verify contract deployment: {}", e);
+                    return Err(e)
+                }
+            };
+
+            zkp_table.push(vec![]);
+            sig_table.push(sig_pub);
+            updates.push(call.data.clone());
+            calls_gas.push(deploy_gas);
+            conflict_keys.push((call.contract_id.to_bytes(), contract_id.to_bytes()));
+            continue
+        }
+
+        let wasm = match blockchain.wasm_bincode.get(call.contract_id) {
+            Ok(v) => {
+                info!(target: "consensus::validator", "Found wasm bincode for {}", call.contract_id);
+                v
+            }
+            Err(e) => {
+                error!(
+                    target: "consensus::validator",
+                    "Could not find wasm bincode for contract {}: {}",
+                    call.contract_id, e
+                );
+                return Err(Error::ContractNotFound(call.contract_id.to_string()))
+            }
+        };
+
+        // Write the actual payload data
+        let mut payload = vec![];
+        payload.write_u32(idx as u32)?; // Call index
+        tx.calls.encode(&mut payload)?; // Actual call data
+
+        // Instantiate the wasm runtime
+        let mut runtime = match Runtime::new(
+            &wasm,
+            blockchain.clone(),
+            module_cache,
+            call.contract_id,
+            call.gas_limit,
+        ) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(
+                    target: "consensus::validator",
+                    "Failed to instantiate WASM runtime for contract {}",
+                    call.contract_id
+                );
+                return Err(e)
+            }
+        };
+
+        runtime.ctx.as_mut(&mut runtime.store).verifying_slot = slot;
+
+        info!(target: "consensus::validator", "Executing \"metadata\" call");
+        let (metadata, metadata_gas) = match runtime.metadata(&payload) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "consensus::validator", "Failed to execute \"metadata\" call: {}", e);
+                return Err(e)
+            }
+        };
+
+        // Decode the metadata retrieved from the execution
+        let mut decoder = Cursor::new(&metadata);
+        let zkp_pub: Vec<(String, Vec<pallas::Base>)> = match Decodable::decode(&mut decoder) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "consensus::validator", "Failed to decode ZK public inputs from metadata: {}", e);
+                return Err(e.into())
+            }
+        };
+
+        let sig_pub: Vec<PublicKey> = match Decodable::decode(&mut decoder) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "consensus::validator", "Failed to decode signature pubkeys from metadata: {}", e);
+                return Err(e.into())
+            }
+        };
+
+        // Contracts have to list the values the call spends or creates, so
+        // we can find conflicting transactions. A call that changes state
+        // without listing them could be applied twice in the same batch.
+        let call_keys: Vec<pallas::Base> = match Decodable::decode(&mut decoder) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "consensus::validator", "Failed to decode conflict keys from metadata: {}", e);
+                return Err(e.into())
+            }
+        };
+
+        // TODO: Make sure we've read all the bytes above.
+        info!(target: "consensus::validator", "Successfully executed \"metadata\" call");
+        zkp_table.push(zkp_pub);
+        sig_table.push(sig_pub);
+        for key in call_keys {
+            conflict_keys.push((call.contract_id.to_bytes(), key.to_repr()));
+        }
+
+        // After getting the metadata, we run the "exec" function with the same
+        // runtime and the same payload.
+        info!(target: "consensus::validator", "Executing \"exec\" call");
+        match runtime.exec(&payload) {
+            Ok((v, exec_gas)) => {
+                info!(target: "consensus::validator", "Successfully executed \"exec\" call");
+                updates.push(v);
+                calls_gas.push(metadata_gas + exec_gas);
+            }
+            Err(e) => {
+                error!(
+                    target: "consensus::validator",
+                    "Failed to execute \"exec\" call for contract id {}: {}",
+                    call.contract_id, e
+                );
+                return Err(e)
+            }
+        };
+        // At this point we're done with the call and move on to the next one.
+    }
+
+    // When we're done looping and executing over the tx's contract calls, we
+    // verify the signatures. The ZK proofs are verified later, together with
+    // the ones of the other transactions in the batch.
+    info!(target: "consensus::validator", "Verifying signatures for transaction {}", tx_hash);
+    if sig_table.len() != tx.signatures.len() {
+        error!(target: "consensus::validator", "Incorrect number of signatures in tx {}", tx_hash);
+        return Err(Error::InvalidSignature)
+    }
+
+    match tx.verify_sigs(sig_table) {
+        Ok(()) => {
+            info!(target: "consensus::validator", "Signatures verification for tx {} successful", tx_hash)
+        }
+        Err(e) => {
+            error!(target: "consensus::validator", "Signature verification for tx {} failed: {}", tx_hash, e);
+            return Err(e)
+        }
+    };
+
+    Ok(ExecutedTx { zkp_table, updates, calls_gas, conflict_keys })
+}
+
+/// Create the transaction a block proposer uses to claim the fees paid in
+/// its block. The claimed coin is owned by the given public key.
+fn fee_claim_tx(public_key: PublicKey, value: u64) -> Result<Transaction> {
+    let params = FeeClaimParamsV1 {
+        public_key,
+        value,
+        serial: pallas::Base::random(&mut OsRng),
+        coin_blind: pallas::Base::random(&mut OsRng),
+    };
+
+    let mut data = vec![MONEY_CONTRACT_FEE_CLAIM];
+    params.encode(&mut data)?;
+    let calls =
+        vec![ContractCall { contract_id: *MONEY_CONTRACT_ID, data, gas_limit: MAX_GAS_LIMIT }];
+
+    // The claim carries no proofs, and no signatures since the block
+    // proposal itself is signed by the proposer.
+    Ok(Transaction { calls, proofs: vec![vec![]], signatures: vec![vec![]] })
+}
+
+/// Verify a contract deployment call. The deployed wasm bincode has to
+/// compile and export all the contract sections, and the zkas circuits
+/// have to decode. Their sizes are bounded, and the call is charged gas
+/// for the bincode bytes and circuit rows the nodes have to process.
+/// Returns the `ContractId` being deployed, along with the public key the
+/// call has to be signed with, and the charged gas. The public key is the
+/// one the ID is derived from, so only the holder of the deploy key is
+/// able to deploy or upgrade the contract.
+fn verify_deploy(
+    blockchain: &Blockchain,
+    module_cache: &ModuleCache,
+    call: &ContractCall,
+) -> Result<(ContractId, Vec<PublicKey>, u64)> {
+    let params: DeployParamsV1 = deserialize(&call.data)?;
+    let contract_id = ContractId::derive_public(params.public_key);
+    info!(target: "consensus::validator", "Verifying deployment of contract {}", contract_id);
+
+    if params.wasm_bincode.len() > MAX_DEPLOY_WASM_SIZE {
+        return Err(VerifyFailed::DeployTooLarge(format!(
+            "wasm bincode of {} bytes exceeds the maximum of {}",
+            params.wasm_bincode.len(),
+            MAX_DEPLOY_WASM_SIZE
+        ))
+        .into())
+    }
+
+    if params.zkas_bincodes.len() > MAX_DEPLOY_CIRCUITS {
+        return Err(VerifyFailed::DeployTooLarge(format!(
+            "{} zkas circuits exceed the maximum of {}",
+            params.zkas_bincodes.len(),
+            MAX_DEPLOY_CIRCUITS
+        ))
+        .into())
+    }
+
+    // Everything is charged for before the expensive work of compiling the
+    // bincode is done. The zkas decoder already rejects k above `MAX_K`.
+    let mut gas = params.wasm_bincode.len() as u64 * DEPLOY_GAS_PER_WASM_BYTE;
+    for zkas_bincode in &params.zkas_bincodes {
+        let zkbin = ZkBinary::decode(zkas_bincode)?;
+        gas += (1 << zkbin.k) * DEPLOY_GAS_PER_CIRCUIT_ROW;
+    }
+
+    if gas > call.gas_limit {
+        error!(
+            target: "consensus::validator",
+            "Deployment of contract {} needs {} gas, above its gas limit {}",
+            contract_id, gas, call.gas_limit
+        );
+        return Err(Error::GasLimitExceeded(call.gas_limit))
+    }
+
+    let runtime = Runtime::new(
+        &params.wasm_bincode,
+        blockchain.clone(),
+        module_cache,
+        contract_id,
+        call.gas_limit - gas,
+    )?;
+
+    for section in [
+        ContractSection::Deploy,
+        ContractSection::Exec,
+        ContractSection::Update,
+        ContractSection::Metadata,
+    ] {
+        runtime.instance.exports.get_function(section.name())?;
+    }
+
+    Ok((contract_id, vec![params.public_key], gas))
+}
+
+/// Create the verifying keys for all the circuits found in the zkas db
+/// of the given contract.
+fn build_verifying_keys(
+    blockchain: &Blockchain,
+    contract_id: &ContractId,
+) -> Result<Vec<(String, VerifyingKey)>> {
+    info!(target: "consensus::validator", "Looking up zkas db for ContractID {}", contract_id);
+    let zkas_db = blockchain.contracts.lookup(
+        &blockchain.sled_db,
+        contract_id,
+        SMART_CONTRACT_ZKAS_DB_NAME,
+    )?;
+
+    let mut vks = vec![];
+    for i in zkas_db.iter() {
+        let (zkas_ns, zkas_bincode) = i?;
+        let zkas_ns: String = deserialize(&zkas_ns)?;
+        info!(target: "consensus::validator", "Creating VerifyingKey for zkas circuit with namespace {}", zkas_ns);
+        let zkbin = ZkBinary::decode(&zkas_bincode)?;
+        let k = zkbin.k;
+        let circuit = ZkCircuit::new(empty_witnesses(&zkbin), zkbin);
+        let vk = VerifyingKey::build(k, &circuit);
+        vks.push((zkas_ns, vk));
+    }
+
+    Ok(vks)
+}
@@ -17,7 +17,7 @@
  */
 
 use darkfi_sdk::crypto::{
-    pallas, pasta_prelude::*, pedersen_commitment_u64, poseidon_hash, SecretKey,
+    pallas, pasta_prelude::*, pedersen_commitment_u64, poseidon_hash, SecretKey, DAO_CONTRACT_ID,
 };
 
 use halo2_proofs::circuit::Value;
@@ -31,7 +31,10 @@ use darkfi::{
 };
 
 use super::{DaoInfo, ProposalInfo};
-use crate::dao_model::{BlindAggregateVote, ExecCallParams};
+use crate::{
+    dao_model::{proposal_calls_commit, BlindAggregateVote, ExecCallParams},
+    DaoFunction,
+};
 
 pub struct ExecCall {
     pub proposal: ProposalInfo,
@@ -40,13 +43,6 @@ pub struct ExecCall {
     pub all_vote_value: u64,
     pub yes_vote_blind: pallas::Scalar,
     pub all_vote_blind: pallas::Scalar,
-    pub user_serial: pallas::Base,
-    pub user_coin_blind: pallas::Base,
-    pub dao_serial: pallas::Base,
-    pub dao_coin_blind: pallas::Base,
-    pub input_value: u64,
-    pub input_value_blind: pallas::Scalar,
-    pub hook_dao_exec: pallas::Base,
     pub signature_secret: SecretKey,
 }
 
@@ -59,9 +55,7 @@ impl ExecCall {
         debug!(target: "dao", "build()");
         let mut proofs = vec![];
 
-        let (proposal_dest_x, proposal_dest_y) = self.proposal.dest.xy();
-
-        let proposal_amount = pallas::Base::from(self.proposal.amount);
+        let proposal_calls_commit = proposal_calls_commit(&self.proposal.calls);
 
         let dao_proposer_limit = pallas::Base::from(self.dao.proposer_limit);
         let dao_quorum = pallas::Base::from(self.dao.quorum);
@@ -70,11 +64,6 @@ impl ExecCall {
 
         let (dao_pub_x, dao_pub_y) = self.dao.public_key.xy();
 
-        let user_spend_hook = pallas::Base::from(0);
-        let user_data = pallas::Base::from(0);
-        let input_value = pallas::Base::from(self.input_value);
-        let change = input_value - proposal_amount;
-
         let dao_bulla = poseidon_hash::<8>([
             dao_proposer_limit,
             dao_quorum,
@@ -86,39 +75,24 @@ impl ExecCall {
             self.dao.bulla_blind,
         ]);
 
-        let proposal_bulla = poseidon_hash::<8>([
-            proposal_dest_x,
-            proposal_dest_y,
-            proposal_amount,
-            self.proposal.serial,
-            self.proposal.token_id.inner(),
+        let proposal_bulla = poseidon_hash::<6>([
+            proposal_calls_commit,
             dao_bulla,
+            pallas::Base::from(self.proposal.creation_slot),
+            pallas::Base::from(self.proposal.duration),
             self.proposal.blind,
             // @tmp-workaround
             self.proposal.blind,
         ]);
 
-        let coin_0 = poseidon_hash::<8>([
-            proposal_dest_x,
-            proposal_dest_y,
-            proposal_amount,
-            self.proposal.token_id.inner(),
-            self.proposal.serial,
-            user_spend_hook,
-            user_data,
-            self.proposal.blind,
-        ]);
+        // The DAO bulla is only revealed when the proposal updates the DAO
+        let reveal_dao_bulla = self.proposal.calls.iter().any(|call| {
+            call.contract_id == *DAO_CONTRACT_ID &&
+                call.data.first() == Some(&(DaoFunction::Update as u8))
+        });
+        let revealed_dao_bulla = if reveal_dao_bulla { dao_bulla } else { pallas::Base::zero() };
 
-        let coin_1 = poseidon_hash::<8>([
-            dao_pub_x,
-            dao_pub_y,
-            change,
-            self.proposal.token_id.inner(),
-            self.dao_serial,
-            self.hook_dao_exec,
-            dao_bulla,
-            self.dao_coin_blind,
-        ]);
+        let input_user_data_enc = poseidon_hash::<2>([dao_bulla, self.proposal.user_data_blind]);
 
         let yes_vote_commit = pedersen_commitment_u64(self.yes_vote_value, self.yes_vote_blind);
         let yes_vote_commit_coords = yes_vote_commit.to_affine().coordinates().unwrap();
@@ -126,16 +100,11 @@ impl ExecCall {
         let all_vote_commit = pedersen_commitment_u64(self.all_vote_value, self.all_vote_blind);
         let all_vote_commit_coords = all_vote_commit.to_affine().coordinates().unwrap();
 
-        let input_value_commit = pedersen_commitment_u64(self.input_value, self.input_value_blind);
-        let input_value_commit_coords = input_value_commit.to_affine().coordinates().unwrap();
-
         let prover_witnesses = vec![
             // proposal params
-            Witness::Base(Value::known(proposal_dest_x)),
-            Witness::Base(Value::known(proposal_dest_y)),
-            Witness::Base(Value::known(proposal_amount)),
-            Witness::Base(Value::known(self.proposal.serial)),
-            Witness::Base(Value::known(self.proposal.token_id.inner())),
+            Witness::Base(Value::known(proposal_calls_commit)),
+            Witness::Base(Value::known(pallas::Base::from(self.proposal.creation_slot))),
+            Witness::Base(Value::known(pallas::Base::from(self.proposal.duration))),
             Witness::Base(Value::known(self.proposal.blind)),
             // DAO params
             Witness::Base(Value::known(dao_proposer_limit)),
@@ -151,33 +120,21 @@ impl ExecCall {
             Witness::Base(Value::known(pallas::Base::from(self.all_vote_value))),
             Witness::Scalar(Value::known(self.yes_vote_blind)),
             Witness::Scalar(Value::known(self.all_vote_blind)),
-            // outputs + inputs
-            Witness::Base(Value::known(self.user_serial)),
-            Witness::Base(Value::known(self.user_coin_blind)),
-            Witness::Base(Value::known(self.dao_serial)),
-            Witness::Base(Value::known(self.dao_coin_blind)),
-            Witness::Base(Value::known(input_value)),
-            Witness::Scalar(Value::known(self.input_value_blind)),
             // misc
-            Witness::Base(Value::known(self.hook_dao_exec)),
-            Witness::Base(Value::known(user_spend_hook)),
-            Witness::Base(Value::known(user_data)),
+            Witness::Base(Value::known(pallas::Base::from(reveal_dao_bulla as u64))),
+            Witness::Base(Value::known(self.proposal.user_data_blind)),
         ];
 
         debug!("proposal_bulla: {:?}", proposal_bulla);
         let public_inputs = vec![
             proposal_bulla,
-            coin_0,
-            coin_1,
+            proposal_calls_commit,
+            revealed_dao_bulla,
+            input_user_data_enc,
             *yes_vote_commit_coords.x(),
             *yes_vote_commit_coords.y(),
             *all_vote_commit_coords.x(),
             *all_vote_commit_coords.y(),
-            *input_value_commit_coords.x(),
-            *input_value_commit_coords.y(),
-            self.hook_dao_exec,
-            user_spend_hook,
-            user_data,
         ];
 
         let circuit = ZkCircuit::new(prover_witnesses, exec_zkbin.clone());
@@ -187,10 +144,10 @@ impl ExecCall {
 
         let params = ExecCallParams {
             proposal: proposal_bulla,
-            coin_0,
-            coin_1,
+            calls_commit: proposal_calls_commit,
+            dao_bulla: revealed_dao_bulla,
+            input_user_data_enc,
             blind_total_vote: BlindAggregateVote { yes_vote_commit, all_vote_commit },
-            input_value_commit,
         };
 
         Ok((params, proofs))
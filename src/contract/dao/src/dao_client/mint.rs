@@ -25,7 +25,7 @@ use darkfi_sdk::crypto::{pallas, poseidon_hash, PublicKey, TokenId};
 use log::debug;
 use rand::rngs::OsRng;
 
-use crate::dao_model::MintCallParams;
+use crate::dao_model::{DaoBulla, MintCallParams};
 
 #[derive(Clone)]
 pub struct DaoInfo {
@@ -38,6 +38,26 @@ pub struct DaoInfo {
     pub bulla_blind: pallas::Base,
 }
 
+impl DaoInfo {
+    /// Compute the bulla committing to the DAO parameters
+    pub fn bulla(&self) -> DaoBulla {
+        let (pub_x, pub_y) = self.public_key.xy();
+
+        let dao_bulla = poseidon_hash([
+            pallas::Base::from(self.proposer_limit),
+            pallas::Base::from(self.quorum),
+            pallas::Base::from(self.approval_ratio_quot),
+            pallas::Base::from(self.approval_ratio_base),
+            self.gov_token_id.inner(),
+            pub_x,
+            pub_y,
+            self.bulla_blind,
+        ]);
+
+        DaoBulla::from(dao_bulla)
+    }
+}
+
 pub fn make_mint_call(
     dao: &DaoInfo,
     dao_mint_zkbin: &ZkBinary,
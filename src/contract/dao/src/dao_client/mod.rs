@@ -22,6 +22,11 @@ pub use vote::{VoteCall, VoteInput, VoteNote};
 pub mod exec;
 pub use exec::ExecCall;
 
+/// Provides the `DAO::update()` call, replacing a DAO with one with new
+/// parameters as the result of a passed proposal.
+pub mod update;
+pub use update::make_update_call;
+
 // Wallet SQL table constant names. These have to represent the SQL schema.
 pub const DAO_DAOS_TABLE: &str = "dao_daos";
 pub const DAO_DAOS_COL_DAO_ID: &str = "dao_id";
@@ -48,11 +53,11 @@ pub const DAO_COINS_COL_DAO_ID: &str = "dao_id";
 pub const DAO_PROPOSALS_TABLE: &str = "dao_proposals";
 pub const DAO_PROPOSALS_COL_PROPOSAL_ID: &str = "proposal_id";
 pub const DAO_PROPOSALS_COL_DAO_ID: &str = "dao_id";
-pub const DAO_PROPOSALS_COL_RECV_PUBLIC: &str = "recv_public";
-pub const DAO_PROPOSALS_COL_AMOUNT: &str = "amount";
-pub const DAO_PROPOSALS_COL_SERIAL: &str = "serial";
-pub const DAO_PROPOSALS_COL_SENDCOIN_TOKEN_ID: &str = "sendcoin_token_id";
+pub const DAO_PROPOSALS_COL_CALLS: &str = "calls";
+pub const DAO_PROPOSALS_COL_CREATION_SLOT: &str = "creation_slot";
+pub const DAO_PROPOSALS_COL_DURATION: &str = "duration";
 pub const DAO_PROPOSALS_COL_BULLA_BLIND: &str = "bulla_blind";
+pub const DAO_PROPOSALS_COL_USER_DATA_BLIND: &str = "user_data_blind";
 pub const DAO_PROPOSALS_COL_LEAF_POSITION: &str = "leaf_position";
 pub const DAO_PROPOSALS_COL_TX_HASH: &str = "tx_hash";
 pub const DAO_PROPOSALS_COL_CALL_INDEX: &str = "call_index";
@@ -16,9 +16,12 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use darkfi_sdk::crypto::{
-    merkle_prelude::*, pallas, pasta_prelude::*, pedersen::pedersen_commitment_u64, poseidon_hash,
-    MerkleNode, MerklePosition, PublicKey, SecretKey, TokenId,
+use darkfi_sdk::{
+    crypto::{
+        merkle_prelude::*, pallas, pasta_prelude::*, pedersen::pedersen_commitment_u64,
+        poseidon_hash, MerkleNode, MerklePosition, PublicKey, SecretKey,
+    },
+    ContractCall,
 };
 use darkfi_serial::{SerialDecodable, SerialEncodable};
 use rand::rngs::OsRng;
@@ -30,7 +33,7 @@ use darkfi::{
 };
 
 use crate::{
-    dao_model::{ProposeCallParams, ProposeCallParamsInput},
+    dao_model::{proposal_calls_commit, ProposeCallParams, ProposeCallParamsInput},
     note,
 };
 
@@ -38,11 +41,18 @@ use super::DaoInfo;
 
 #[derive(SerialEncodable, SerialDecodable, Clone)]
 pub struct ProposalInfo {
-    pub dest: PublicKey,
-    pub amount: u64,
-    pub serial: pallas::Base,
-    pub token_id: TokenId,
+    /// Contract calls executed along with `DAO::exec()` once the proposal
+    /// passes. Any calls spending DAO coins must come last, since their
+    /// spend hook invokes the DAO contract.
+    pub calls: Vec<ContractCall>,
+    /// Slot the proposal was created at
+    pub creation_slot: u64,
+    /// Number of slots the proposal can be voted on
+    pub duration: u64,
     pub blind: pallas::Base,
+    /// Blind the proposal calls use to encrypt the user data of the DAO
+    /// coins they spend, which `DAO::exec()` proves belong to the DAO
+    pub user_data_blind: pallas::Base,
 }
 
 #[derive(SerialEncodable, SerialDecodable)]
@@ -173,9 +183,7 @@ impl ProposeCall {
 
         let token_commit = poseidon_hash::<2>([self.dao.gov_token_id.inner(), gov_token_blind]);
 
-        let (proposal_dest_x, proposal_dest_y) = self.proposal.dest.xy();
-
-        let proposal_amount = pallas::Base::from(self.proposal.amount);
+        let proposal_calls_commit = proposal_calls_commit(&self.proposal.calls);
 
         let dao_proposer_limit = pallas::Base::from(self.dao.proposer_limit);
         let dao_quorum = pallas::Base::from(self.dao.quorum);
@@ -197,13 +205,11 @@ impl ProposeCall {
 
         let dao_leaf_position: u64 = self.dao_leaf_position.into();
 
-        let proposal_bulla = poseidon_hash::<8>([
-            proposal_dest_x,
-            proposal_dest_y,
-            proposal_amount,
-            self.proposal.serial,
-            self.proposal.token_id.inner(),
+        let proposal_bulla = poseidon_hash::<6>([
+            proposal_calls_commit,
             dao_bulla,
+            pallas::Base::from(self.proposal.creation_slot),
+            pallas::Base::from(self.proposal.duration),
             self.proposal.blind,
             // @tmp-workaround
             self.proposal.blind,
@@ -216,11 +222,9 @@ impl ProposeCall {
             // Used for blinding exported gov token ID
             Witness::Base(halo2::Value::known(gov_token_blind)),
             // proposal params
-            Witness::Base(halo2::Value::known(proposal_dest_x)),
-            Witness::Base(halo2::Value::known(proposal_dest_y)),
-            Witness::Base(halo2::Value::known(proposal_amount)),
-            Witness::Base(halo2::Value::known(self.proposal.serial)),
-            Witness::Base(halo2::Value::known(self.proposal.token_id.inner())),
+            Witness::Base(halo2::Value::known(proposal_calls_commit)),
+            Witness::Base(halo2::Value::known(pallas::Base::from(self.proposal.creation_slot))),
+            Witness::Base(halo2::Value::known(pallas::Base::from(self.proposal.duration))),
             Witness::Base(halo2::Value::known(self.proposal.blind)),
             // DAO params
             Witness::Base(halo2::Value::known(dao_proposer_limit)),
@@ -238,6 +242,8 @@ impl ProposeCall {
             token_commit,
             self.dao_merkle_root.inner(),
             proposal_bulla,
+            pallas::Base::from(self.proposal.creation_slot),
+            pallas::Base::from(self.proposal.duration),
             *total_funds_coords.x(),
             *total_funds_coords.y(),
         ];
@@ -252,6 +258,8 @@ impl ProposeCall {
         let params = ProposeCallParams {
             dao_merkle_root: self.dao_merkle_root,
             proposal_bulla,
+            creation_slot: self.proposal.creation_slot,
+            duration: self.proposal.duration,
             token_commit,
             ciphertext: enc_note.ciphertext,
             ephem_public: enc_note.ephem_public,
@@ -0,0 +1,50 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    zk::{Proof, ProvingKey},
+    zkas::ZkBinary,
+    Result,
+};
+use log::debug;
+
+use super::{make_mint_call, DaoInfo};
+use crate::dao_model::UpdateCallParams;
+
+/// Build the `DAO::update()` call replacing `old_dao` with `new_dao`.
+/// The call has to be one of the calls of a proposal made by `old_dao`,
+/// and it's only valid when executed along with that proposal.
+/// The new DAO bulla is proven well-formed with the `DaoMint` circuit.
+/// Once executed, the contract rebuilds the DAO bulla tree from the DAOs
+/// which weren't replaced, in the order they were added, followed by
+/// `new_dao`. Proposals have to use a Merkle path from that tree.
+pub fn make_update_call(
+    old_dao: &DaoInfo,
+    new_dao: &DaoInfo,
+    dao_mint_zkbin: &ZkBinary,
+    dao_mint_pk: &ProvingKey,
+) -> Result<(UpdateCallParams, Vec<Proof>)> {
+    debug!(target: "dao", "Building DAO contract update transaction");
+
+    let (mint_params, proofs) = make_mint_call(new_dao, dao_mint_zkbin, dao_mint_pk)?;
+
+    let params =
+        UpdateCallParams { old_dao_bulla: old_dao.bulla(), new_dao_bulla: mint_params.dao_bulla };
+
+    Ok((params, proofs))
+}
@@ -32,7 +32,7 @@ use darkfi::{
 
 use super::{DaoInfo, ProposalInfo};
 use crate::{
-    dao_model::{VoteCallParams, VoteCallParamsInput},
+    dao_model::{proposal_calls_commit, VoteCallParams, VoteCallParamsInput},
     note,
 };
 
@@ -176,9 +176,7 @@ impl VoteCall {
 
         let token_commit = poseidon_hash::<2>([self.dao.gov_token_id.inner(), gov_token_blind]);
 
-        let (proposal_dest_x, proposal_dest_y) = self.proposal.dest.xy();
-
-        let proposal_amount = pallas::Base::from(self.proposal.amount);
+        let proposal_calls_commit = proposal_calls_commit(&self.proposal.calls);
 
         let dao_proposer_limit = pallas::Base::from(self.dao.proposer_limit);
         let dao_quorum = pallas::Base::from(self.dao.quorum);
@@ -198,13 +196,11 @@ impl VoteCall {
             self.dao.bulla_blind,
         ]);
 
-        let proposal_bulla = poseidon_hash::<8>([
-            proposal_dest_x,
-            proposal_dest_y,
-            proposal_amount,
-            self.proposal.serial,
-            self.proposal.token_id.inner(),
+        let proposal_bulla = poseidon_hash::<6>([
+            proposal_calls_commit,
             dao_bulla,
+            pallas::Base::from(self.proposal.creation_slot),
+            pallas::Base::from(self.proposal.duration),
             self.proposal.blind,
             // @tmp-workaround
             self.proposal.blind,
@@ -222,11 +218,9 @@ impl VoteCall {
 
         let prover_witnesses = vec![
             // proposal params
-            Witness::Base(halo2::Value::known(proposal_dest_x)),
-            Witness::Base(halo2::Value::known(proposal_dest_y)),
-            Witness::Base(halo2::Value::known(proposal_amount)),
-            Witness::Base(halo2::Value::known(self.proposal.serial)),
-            Witness::Base(halo2::Value::known(self.proposal.token_id.inner())),
+            Witness::Base(halo2::Value::known(proposal_calls_commit)),
+            Witness::Base(halo2::Value::known(pallas::Base::from(self.proposal.creation_slot))),
+            Witness::Base(halo2::Value::known(pallas::Base::from(self.proposal.duration))),
             Witness::Base(halo2::Value::known(self.proposal.blind)),
             // DAO params
             Witness::Base(halo2::Value::known(dao_proposer_limit)),
@@ -16,8 +16,13 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use darkfi_sdk::crypto::{pallas, pasta_prelude::*, MerkleNode, Nullifier, PublicKey};
-use darkfi_serial::{SerialDecodable, SerialEncodable};
+use darkfi_sdk::{
+    crypto::{
+        pallas, pasta_prelude::*, util::hash_to_base, MerkleNode, Nullifier, PublicKey,
+    },
+    ContractCall,
+};
+use darkfi_serial::{serialize, SerialDecodable, SerialEncodable};
 
 #[derive(SerialEncodable, SerialDecodable)]
 pub struct DaoBulla(pallas::Base);
@@ -48,11 +53,30 @@ pub struct MintCallUpdate {
 
 // DAO::propose()
 
+/// Personalization used when hashing the contract calls of a proposal
+pub const DAO_PROPOSAL_CALLS_DOMAIN: &[u8] = b"DarkFi:DaoCalls";
+
+/// Commit to the contract calls a proposal executes once it passes.
+/// Only the called contracts and their data are committed to, so the
+/// gas limits are left up to whoever executes the proposal.
+pub fn proposal_calls_commit(calls: &[ContractCall]) -> pallas::Base {
+    let mut data = vec![];
+    for call in calls {
+        data.extend_from_slice(&serialize(&call.contract_id));
+        data.extend_from_slice(&serialize(&call.data));
+    }
+    hash_to_base(DAO_PROPOSAL_CALLS_DOMAIN, &data)
+}
+
 #[derive(SerialEncodable, SerialDecodable)]
 pub struct ProposeCallParams {
     pub dao_merkle_root: MerkleNode,
     pub token_commit: pallas::Base,
     pub proposal_bulla: pallas::Base,
+    /// Slot the proposal was created at
+    pub creation_slot: u64,
+    /// Number of slots the proposal can be voted on
+    pub duration: u64,
     pub ciphertext: Vec<u8>,
     pub ephem_public: PublicKey,
     pub inputs: Vec<ProposeCallParamsInput>,
@@ -68,6 +92,27 @@ pub struct ProposeCallParamsInput {
 #[derive(SerialEncodable, SerialDecodable)]
 pub struct ProposeCallUpdate {
     pub proposal_bulla: pallas::Base,
+    pub creation_slot: u64,
+    pub duration: u64,
+}
+
+/// On-chain state of a proposal which is open for voting
+#[derive(SerialEncodable, SerialDecodable)]
+pub struct ProposalState {
+    /// Slot the proposal was created at
+    pub creation_slot: u64,
+    /// Number of slots the proposal can be voted on
+    pub duration: u64,
+    /// Aggregated votes the proposal received so far
+    pub votes: BlindAggregateVote,
+}
+
+impl ProposalState {
+    /// First slot at which the proposal no longer accepts votes
+    /// and can be executed.
+    pub fn deadline(&self) -> u64 {
+        self.creation_slot.saturating_add(self.duration)
+    }
 }
 
 // DAO::vote()
@@ -93,7 +138,7 @@ pub struct VoteCallParamsInput {
 #[derive(SerialEncodable, SerialDecodable)]
 pub struct VoteCallUpdate {
     pub proposal_bulla: pallas::Base,
-    pub proposal_votes: BlindAggregateVote,
+    pub proposal_state: ProposalState,
     pub vote_nullifiers: Vec<Nullifier>,
 }
 
@@ -127,13 +172,33 @@ impl Default for BlindAggregateVote {
 #[derive(SerialEncodable, SerialDecodable)]
 pub struct ExecCallParams {
     pub proposal: pallas::Base,
-    pub coin_0: pallas::Base,
-    pub coin_1: pallas::Base,
+    /// Commitment to the calls the proposal executes, see [`proposal_calls_commit`]
+    pub calls_commit: pallas::Base,
+    /// DAO bulla, only revealed when the proposal calls `DAO::update()`,
+    /// otherwise zero
+    pub dao_bulla: pallas::Base,
+    /// Encrypted user data of the DAO coins spent by the proposal calls
+    pub input_user_data_enc: pallas::Base,
     pub blind_total_vote: BlindAggregateVote,
-    pub input_value_commit: pallas::Point,
 }
 
 #[derive(SerialEncodable, SerialDecodable)]
 pub struct ExecCallUpdate {
     pub proposal: pallas::Base,
 }
+
+// DAO::update()
+
+#[derive(SerialEncodable, SerialDecodable)]
+pub struct UpdateCallParams {
+    /// Bulla of the DAO being replaced
+    pub old_dao_bulla: DaoBulla,
+    /// Bulla of the DAO with the updated parameters
+    pub new_dao_bulla: DaoBulla,
+}
+
+#[derive(SerialEncodable, SerialDecodable)]
+pub struct UpdateCallUpdate {
+    pub old_dao_bulla: DaoBulla,
+    pub new_dao_bulla: DaoBulla,
+}
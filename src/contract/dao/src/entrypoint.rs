@@ -20,30 +20,35 @@ use std::io::Cursor;
 
 use darkfi_sdk::{
     crypto::{
-        pallas, pasta_prelude::*, ContractId, MerkleNode, MerkleTree, PublicKey, DAO_CONTRACT_ID,
-        MONEY_CONTRACT_ID,
+        pallas, pasta_prelude::*, ContractId, MerkleNode, MerkleTree, PublicKey, MONEY_CONTRACT_ID,
     },
     db::{
-        db_contains_key, db_del, db_get, db_init, db_lookup, db_set, SMART_CONTRACT_ZKAS_DB_NAME,
+        db_contains_key, db_del, db_get, db_init, db_lookup, db_set, DbHandle,
+        SMART_CONTRACT_ZKAS_DB_NAME,
     },
     error::{ContractError, ContractResult},
-    merkle_add, msg, set_return_data, ContractCall,
+    incrementalmerkletree::Tree,
+    msg, set_return_data,
+    util::get_verifying_slot,
+    ContractCall,
 };
 use darkfi_serial::{deserialize, serialize, Decodable, Encodable, WriteExt};
 
 use darkfi_money_contract::{
-    model::MoneyTransferParams, MoneyFunction, MONEY_CONTRACT_COIN_ROOTS_TREE,
-    MONEY_CONTRACT_NULLIFIERS_TREE,
+    model::{MoneyFeeParams, MoneyTransferParams},
+    MoneyFunction, MONEY_CONTRACT_COIN_ROOTS_TREE, MONEY_CONTRACT_NULLIFIERS_TREE,
 };
 
 use crate::{
     dao_model::{
-        BlindAggregateVote, ExecCallParams, ExecCallUpdate, MintCallParams, MintCallUpdate,
-        ProposeCallParams, ProposeCallUpdate, VoteCallParams, VoteCallUpdate,
+        proposal_calls_commit, BlindAggregateVote, ExecCallParams, ExecCallUpdate, MintCallParams,
+        MintCallUpdate, ProposalState, ProposeCallParams, ProposeCallUpdate, UpdateCallParams,
+        UpdateCallUpdate, VoteCallParams, VoteCallUpdate,
     },
     DaoFunction, DAO_CONTRACT_ZKAS_DAO_EXEC_NS, DAO_CONTRACT_ZKAS_DAO_MINT_NS,
     DAO_CONTRACT_ZKAS_DAO_PROPOSE_BURN_NS, DAO_CONTRACT_ZKAS_DAO_PROPOSE_MAIN_NS,
     DAO_CONTRACT_ZKAS_DAO_VOTE_BURN_NS, DAO_CONTRACT_ZKAS_DAO_VOTE_MAIN_NS,
+    DAO_PROPOSAL_CREATION_WINDOW,
 };
 
 darkfi_sdk::define_contract!(
@@ -57,13 +62,21 @@ darkfi_sdk::define_contract!(
 pub const DB_INFO: &str = "dao_info";
 /// Name of the DAO bulla tree in DB_INFO
 pub const KEY_DAO_MERKLE_TREE: &str = "dao_merkle_tree";
+/// Name of the list of DAO bullas in the DAO bulla tree in DB_INFO
+pub const KEY_DAO_TREE_BULLAS: &str = "dao_tree_bullas";
+/// Name of the DAO bulla tree generation in DB_INFO. It's bumped each
+/// time `DAO::update()` rebuilds the tree.
+pub const KEY_DAO_TREE_GENERATION: &str = "dao_tree_generation";
 
 /// DAO bullas
 pub const DB_DAO_BULLAS: &str = "dao_bullas";
-/// Keeps track of all merkle roots DAO bullas
+/// Keeps track of all merkle roots DAO bullas. The value is the generation
+/// of the DAO bulla tree the root belongs to.
 pub const DB_DAO_MERKLE_ROOTS: &str = "dao_roots";
+/// DAO bullas replaced through `DAO::update()`
+pub const DB_DAO_BURNED_BULLAS: &str = "dao_burned_bullas";
 
-/// Proposal bullas. The key is the current aggregated vote
+/// Proposal bullas. The value is the current `ProposalState`
 pub const DB_PROPOSAL_BULLAS: &str = "dao_proposals";
 /// Nullifiers to prevent double voting
 pub const DAO_VOTE_NULLS: &str = "dao_vote_nulls";
@@ -114,6 +127,10 @@ fn init_contract(cid: ContractId, _ix: &[u8]) -> ContractResult {
             tree.encode(&mut tree_data)?;
 
             db_set(dao_info_db, &serialize(&KEY_DAO_MERKLE_TREE), &tree_data)?;
+
+            let bullas: Vec<pallas::Base> = vec![];
+            db_set(dao_info_db, &serialize(&KEY_DAO_TREE_BULLAS), &serialize(&bullas))?;
+            db_set(dao_info_db, &serialize(&KEY_DAO_TREE_GENERATION), &serialize(&0_u32))?;
         }
     };
 
@@ -129,7 +146,13 @@ fn init_contract(cid: ContractId, _ix: &[u8]) -> ContractResult {
         Err(_) => db_init(cid, DB_DAO_MERKLE_ROOTS)?,
     };
 
-    // Setup db for proposal votes (k: ProposalBulla, v: BlindAggregateVote)
+    // Setup db for DAO bullas replaced by governance
+    let _ = match db_lookup(cid, DB_DAO_BURNED_BULLAS) {
+        Ok(v) => v,
+        Err(_) => db_init(cid, DB_DAO_BURNED_BULLAS)?,
+    };
+
+    // Setup db for proposal votes (k: ProposalBulla, v: ProposalState)
     let _ = match db_lookup(cid, DB_PROPOSAL_BULLAS) {
         Ok(v) => v,
         Err(_) => db_init(cid, DB_PROPOSAL_BULLAS)?,
@@ -151,17 +174,14 @@ fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
     let func = DaoFunction::try_from(self_.data[0])?;
 
     if call.len() != 1 {
-        // Enforce a strict structure for our tx
-        assert_eq!(call.len(), 2);
-        assert_eq!(call_idx, 1);
-
-        // We can unpack user_data and check the function call is correct.
-        // But in this contract, only DAO::exec() can be invoked by other ones.
-        // So just check the function call is correct.
+        // In this contract, only DAO::exec() can be invoked along other
+        // calls, which are the ones the executed proposal commits to.
+        // DAO::update() is only valid as one of those calls.
+        // DAO::exec() enforces the structure of the tx itself.
 
         // NOTE: we may wish to improve this since it cripples user composability.
 
-        assert_eq!(func, DaoFunction::Exec);
+        assert!(func == DaoFunction::Exec || func == DaoFunction::Update);
     }
 
     match func {
@@ -201,9 +221,22 @@ fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
 
             // Is the DAO bulla generated in the ZK proof valid
             let dao_roots_db = db_lookup(cid, DB_DAO_MERKLE_ROOTS)?;
-            if !db_contains_key(dao_roots_db, &serialize(&params.dao_merkle_root))? {
+            let Some(root_generation) = db_get(dao_roots_db, &serialize(&params.dao_merkle_root))?
+            else {
                 msg!("Invalid DAO Merkle root: {}", params.dao_merkle_root);
                 return Err(ContractError::Custom(3))
+            };
+
+            // The root is from the current DAO bulla tree. DAOs replaced
+            // through DAO::update() are left out when the tree is rebuilt,
+            // so they can't make proposals anymore.
+            let info_db = db_lookup(cid, DB_INFO)?;
+            if deserialize::<u32>(&root_generation)? != get_dao_tree_generation(info_db)? {
+                msg!(
+                    "DAO Merkle root {} is from before a DAO was replaced",
+                    params.dao_merkle_root
+                );
+                return Err(ContractError::Custom(18))
             }
 
             let proposal_db = db_lookup(cid, DB_PROPOSAL_BULLAS)?;
@@ -214,7 +247,28 @@ fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
                 return Err(ContractError::Custom(4))
             }
 
-            let update = ProposeCallUpdate { proposal_bulla: params.proposal_bulla };
+            // The proposal must be open for voting for some time, and its
+            // creation slot must be recent so the voting period can't be
+            // cut short by backdating it.
+            let slot = get_verifying_slot();
+            if params.duration == 0 ||
+                params.creation_slot > slot ||
+                slot - params.creation_slot > DAO_PROPOSAL_CREATION_WINDOW
+            {
+                msg!(
+                    "Invalid proposal creation slot {} or duration {} at slot {}",
+                    params.creation_slot,
+                    params.duration,
+                    slot
+                );
+                return Err(ContractError::Custom(8))
+            }
+
+            let update = ProposeCallUpdate {
+                proposal_bulla: params.proposal_bulla,
+                creation_slot: params.creation_slot,
+                duration: params.duration,
+            };
             let mut update_data = vec![];
             update_data.write_u8(DaoFunction::Propose as u8)?;
             update.encode(&mut update_data)?;
@@ -231,11 +285,24 @@ fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
 
             // Check proposal bulla exists
             let proposal_votes_db = db_lookup(cid, DB_PROPOSAL_BULLAS)?;
-            let Some(proposal_votes) = db_get(proposal_votes_db, &serialize(&params.proposal_bulla))? else {
+            let Some(proposal_state) =
+                db_get(proposal_votes_db, &serialize(&params.proposal_bulla))?
+            else {
                 msg!("Invalid proposal {:?}", params.proposal_bulla);
                 return Err(ContractError::Custom(4))
             };
-            let mut proposal_votes: BlindAggregateVote = deserialize(&proposal_votes)?;
+            let mut proposal_state: ProposalState = deserialize(&proposal_state)?;
+
+            // Check the proposal voting period hasn't ended
+            let slot = get_verifying_slot();
+            if slot >= proposal_state.deadline() {
+                msg!(
+                    "Proposal {:?} voting ended at slot {}",
+                    params.proposal_bulla,
+                    proposal_state.deadline()
+                );
+                return Err(ContractError::Custom(9))
+            }
 
             // Check the Merkle roots and nullifiers for the input coins are valid
             let money_roots_db = db_lookup(money_cid, MONEY_CONTRACT_COIN_ROOTS_TREE)?;
@@ -266,15 +333,15 @@ fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
                     return Err(ContractError::Custom(7))
                 }
 
-                proposal_votes.all_vote_commit += input.vote_commit;
+                proposal_state.votes.all_vote_commit += input.vote_commit;
                 vote_nullifiers.push(input.nullifier);
             }
 
-            proposal_votes.yes_vote_commit += params.yes_vote_commit;
+            proposal_state.votes.yes_vote_commit += params.yes_vote_commit;
 
             let update = VoteCallUpdate {
                 proposal_bulla: params.proposal_bulla,
-                proposal_votes,
+                proposal_state,
                 vote_nullifiers,
             };
             let mut update_data = vec![];
@@ -292,43 +359,89 @@ fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
             // =============================
             // Enforce tx has correct format
             // =============================
-            // 1. There should be only two calls
-            assert!(call.len() == 2);
-
-            // 2. func_call_index == 1
-            assert!(call_idx == 1);
-
-            // 3. First item should be a MoneyTransfer call
-            assert!(call[0].contract_id == *MONEY_CONTRACT_ID);
-            assert!(call[0].data[0] == MoneyFunction::Transfer as u8);
-
-            // 4. MoneyTransfer has exactly 2 outputs
-            let mt_params: MoneyTransferParams = deserialize(&call[0].data[1..])?;
-            assert!(mt_params.outputs.len() == 2);
+            // 1. The proposal calls come first, after the fee call if there's
+            //    one, and DAO::exec() is the last call. Calls spending DAO
+            //    coins come right before it, as their spend hook invokes
+            //    this contract.
+            if call_idx == 0 || call_idx as usize != call.len() - 1 {
+                msg!("DAO::exec() must be the last call, following the proposal calls");
+                return Err(ContractError::Custom(11))
+            }
 
             // ======
             // Checks
             // ======
-            // 1. Check both coins in MoneyTransfer are equal to our coin_0, coin_1
-            assert!(mt_params.outputs[0].coin == params.coin_0);
-            assert!(mt_params.outputs[1].coin == params.coin_1);
-
-            // 2. Sum of MoneyTransfer input value commits == our input value commit
-            let mut input_valcoms = pallas::Point::identity();
-            for input in mt_params.inputs {
-                input_valcoms += input.value_commit;
+            // 2. The calls in the tx are exactly the ones the proposal commits to.
+            //    They can be preceded by a single Money::Fee() call paying the tx
+            //    fee, which isn't part of the proposal.
+            let exec_idx = call_idx as usize;
+            let commits_to =
+                |calls: &[ContractCall]| proposal_calls_commit(calls) == params.calls_commit;
+            let is_fee_call = |c: &ContractCall| {
+                c.contract_id == *MONEY_CONTRACT_ID && c.data[0] == MoneyFunction::Fee as u8
+            };
+            let proposal_start = if commits_to(&call[..exec_idx]) {
+                0
+            } else if exec_idx > 1 && is_fee_call(&call[0]) && commits_to(&call[1..exec_idx]) {
+                1
+            } else {
+                msg!("Transaction calls don't match the ones of proposal {:?}", params.proposal);
+                return Err(ContractError::Custom(12))
+            };
+
+            // 3. The fee isn't paid with a DAO coin, which can only be spent
+            //    by the proposal calls.
+            if proposal_start == 1 {
+                let fee_params: MoneyFeeParams = deserialize(&call[0].data[1..])?;
+                if fee_params.input.spend_hook == cid.inner() {
+                    msg!("DAO::exec() fee is paid with a coin which belongs to a DAO");
+                    return Err(ContractError::Custom(19))
+                }
+            }
+
+            // 4. Coins spent by the proposal calls belong to the DAO. Their
+            //    spend hook invokes this contract, and their encrypted user
+            //    data is the DAO bulla proven in the DAO::exec() proof.
+            for (i, proposal_call) in call.iter().enumerate().take(exec_idx).skip(proposal_start) {
+                if proposal_call.contract_id != *MONEY_CONTRACT_ID ||
+                    (proposal_call.data[0] != MoneyFunction::Transfer as u8 &&
+                        proposal_call.data[0] != MoneyFunction::OtcSwap as u8)
+                {
+                    continue
+                }
+
+                let xfer_params: MoneyTransferParams = deserialize(&proposal_call.data[1..])?;
+                for input in &xfer_params.inputs {
+                    if input.spend_hook != cid.inner() ||
+                        input.user_data_enc != params.input_user_data_enc
+                    {
+                        msg!("Call {} spends a coin which doesn't belong to the DAO", i);
+                        return Err(ContractError::Custom(17))
+                    }
+                }
             }
-            assert!(input_valcoms == params.input_value_commit);
 
-            // 3. Get the ProposalVote from DAO state
+            // 5. Get the ProposalVote from DAO state
             let proposal_db = db_lookup(cid, DB_PROPOSAL_BULLAS)?;
-            let Some(proposal_votes) = db_get(proposal_db, &serialize(&params.proposal))? else {
+            let Some(proposal_state) = db_get(proposal_db, &serialize(&params.proposal))? else {
                 msg!("Proposal {:?} not found in db", params.proposal);
                 return Err(ContractError::Custom(1));
             };
-            let proposal_votes: BlindAggregateVote = deserialize(&proposal_votes)?;
+            let proposal_state: ProposalState = deserialize(&proposal_state)?;
 
-            // 4. Check yes_vote_commit and all_vote_commit are the same as in BlindAggregateVote
+            // 6. Check the proposal voting period has ended
+            let slot = get_verifying_slot();
+            if slot < proposal_state.deadline() {
+                msg!(
+                    "Proposal {:?} is still being voted on until slot {}",
+                    params.proposal,
+                    proposal_state.deadline()
+                );
+                return Err(ContractError::Custom(10))
+            }
+
+            // 7. Check yes_vote_commit and all_vote_commit are the same as in BlindAggregateVote
+            let proposal_votes = proposal_state.votes;
             assert!(proposal_votes.yes_vote_commit == params.blind_total_vote.yes_vote_commit);
             assert!(proposal_votes.all_vote_commit == params.blind_total_vote.all_vote_commit);
 
@@ -341,6 +454,66 @@ fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
 
             Ok(())
         }
+
+        DaoFunction::Update => {
+            let params: UpdateCallParams = deserialize(&self_.data[1..])?;
+            let old_dao_bulla = params.old_dao_bulla.inner();
+            let new_dao_bulla = params.new_dao_bulla.inner();
+
+            // =============================
+            // Enforce tx has correct format
+            // =============================
+            // 1. DAO::update() is one of the calls of a proposal, so the tx
+            //    must end with the DAO::exec() call executing it.
+            let exec_call = &call[call.len() - 1];
+            if call_idx as usize == call.len() - 1 ||
+                exec_call.contract_id != cid ||
+                exec_call.data[0] != DaoFunction::Exec as u8
+            {
+                msg!("DAO::update() must be executed through a DAO::exec() call");
+                return Err(ContractError::Custom(13))
+            }
+
+            // ======
+            // Checks
+            // ======
+            // 2. The DAO executing the proposal is the one being replaced.
+            //    DAO::exec() reveals its bulla when the proposal contains
+            //    a DAO::update() call.
+            let exec_params: ExecCallParams = deserialize(&exec_call.data[1..])?;
+            if exec_params.dao_bulla != old_dao_bulla {
+                msg!("DAO {:?} is not the one executing the proposal", old_dao_bulla);
+                return Err(ContractError::Custom(14))
+            }
+
+            // 3. The old DAO exists and wasn't already replaced
+            let bulla_db = db_lookup(cid, DB_DAO_BULLAS)?;
+            let burned_db = db_lookup(cid, DB_DAO_BURNED_BULLAS)?;
+            if !db_contains_key(bulla_db, &serialize(&old_dao_bulla))? ||
+                db_contains_key(burned_db, &serialize(&old_dao_bulla))?
+            {
+                msg!("DAO {:?} doesn't exist or was already replaced", old_dao_bulla);
+                return Err(ContractError::Custom(15))
+            }
+
+            // 4. The new DAO bulla doesn't already exist
+            if db_contains_key(bulla_db, &serialize(&new_dao_bulla))? {
+                msg!("DAO already exists: {:?}", new_dao_bulla);
+                return Err(ContractError::Custom(16))
+            }
+
+            let update = UpdateCallUpdate {
+                old_dao_bulla: params.old_dao_bulla,
+                new_dao_bulla: params.new_dao_bulla,
+            };
+            let mut update_data = vec![];
+            update_data.write_u8(DaoFunction::Update as u8)?;
+            update.encode(&mut update_data)?;
+            set_return_data(&update_data)?;
+            msg!("[DAO Update] State update set!");
+
+            Ok(())
+        }
     }
 }
 
@@ -356,8 +529,9 @@ fn process_update(cid: ContractId, ix: &[u8]) -> ContractResult {
 
             db_set(bulla_db, &serialize(&dao_bulla), &[])?;
 
-            let node = MerkleNode::from(dao_bulla);
-            merkle_add(info_db, roots_db, &serialize(&KEY_DAO_MERKLE_TREE), &[node])?;
+            let mut dao_tree = DaoTree::load(info_db)?;
+            dao_tree.append(dao_bulla);
+            dao_tree.store(info_db, roots_db)?;
 
             Ok(())
         }
@@ -366,9 +540,13 @@ fn process_update(cid: ContractId, ix: &[u8]) -> ContractResult {
             let update: ProposeCallUpdate = deserialize(&ix[1..])?;
 
             let proposal_vote_db = db_lookup(cid, DB_PROPOSAL_BULLAS)?;
-            let pv = BlindAggregateVote::default();
+            let state = ProposalState {
+                creation_slot: update.creation_slot,
+                duration: update.duration,
+                votes: BlindAggregateVote::default(),
+            };
 
-            db_set(proposal_vote_db, &serialize(&update.proposal_bulla), &serialize(&pv))?;
+            db_set(proposal_vote_db, &serialize(&update.proposal_bulla), &serialize(&state))?;
 
             Ok(())
         }
@@ -384,7 +562,7 @@ fn process_update(cid: ContractId, ix: &[u8]) -> ContractResult {
             db_set(
                 proposal_vote_db,
                 &serialize(&update.proposal_bulla),
-                &serialize(&update.proposal_votes),
+                &serialize(&update.proposal_state),
             )?;
 
             // We are essentially doing: vote_nulls.append(update.nulls)
@@ -409,6 +587,36 @@ fn process_update(cid: ContractId, ix: &[u8]) -> ContractResult {
 
             Ok(())
         }
+
+        DaoFunction::Update => {
+            let update: UpdateCallUpdate = deserialize(&ix[1..])?;
+            let old_dao_bulla = update.old_dao_bulla.inner();
+            let new_dao_bulla = update.new_dao_bulla.inner();
+
+            let info_db = db_lookup(cid, DB_INFO)?;
+            let bulla_db = db_lookup(cid, DB_DAO_BULLAS)?;
+            let burned_db = db_lookup(cid, DB_DAO_BURNED_BULLAS)?;
+            let roots_db = db_lookup(cid, DB_DAO_MERKLE_ROOTS)?;
+
+            // The old DAO can't be replaced again
+            db_set(burned_db, &serialize(&old_dao_bulla), &[])?;
+
+            db_set(bulla_db, &serialize(&new_dao_bulla), &[])?;
+
+            // A bulla can't be removed from the Merkle tree, so the tree is
+            // rebuilt without the old DAO, which then can't make proposals
+            // anymore. Proposals it made before can still be executed, but
+            // they can only spend coins bound to the old DAO bulla.
+            let dao_tree = DaoTree::load(info_db)?;
+            let mut rebuilt = DaoTree::new(dao_tree.generation + 1);
+            for bulla in dao_tree.bullas.into_iter().filter(|x| *x != old_dao_bulla) {
+                rebuilt.append(bulla);
+            }
+            rebuilt.append(new_dao_bulla);
+            rebuilt.store(info_db, roots_db)?;
+
+            Ok(())
+        }
     }
 }
 
@@ -429,9 +637,13 @@ fn get_metadata(_: ContractId, ix: &[u8]) -> ContractResult {
             zk_public_values
                 .push((DAO_CONTRACT_ZKAS_DAO_MINT_NS.to_string(), vec![params.dao_bulla.inner()]));
 
+            // A DAO can't be created by more than one transaction
+            let conflict_keys: Vec<pallas::Base> = vec![params.dao_bulla.inner()];
+
             let mut metadata = vec![];
             zk_public_values.encode(&mut metadata)?;
             signature_pubkeys.encode(&mut metadata)?;
+            conflict_keys.encode(&mut metadata)?;
 
             // Using this, we pass the above data to the host.
             set_return_data(&metadata)?;
@@ -474,14 +686,20 @@ fn get_metadata(_: ContractId, ix: &[u8]) -> ContractResult {
                     params.token_commit,
                     params.dao_merkle_root.inner(),
                     params.proposal_bulla,
+                    pallas::Base::from(params.creation_slot),
+                    pallas::Base::from(params.duration),
                     *total_funds_coords.x(),
                     *total_funds_coords.y(),
                 ],
             ));
 
+            // A proposal can't be created by more than one transaction
+            let conflict_keys: Vec<pallas::Base> = vec![params.proposal_bulla];
+
             let mut metadata = vec![];
             zk_public_values.encode(&mut metadata)?;
             signature_pubkeys.encode(&mut metadata)?;
+            conflict_keys.encode(&mut metadata)?;
 
             // Using this, we pass the above data to the host.
             set_return_data(&metadata)?;
@@ -495,10 +713,14 @@ fn get_metadata(_: ContractId, ix: &[u8]) -> ContractResult {
             let mut zk_public_values: Vec<(String, Vec<pallas::Base>)> = vec![];
             let mut signature_pubkeys: Vec<PublicKey> = vec![];
 
+            // Vote nullifiers can't be claimed by more than one transaction
+            let mut conflict_keys: Vec<pallas::Base> = vec![];
+
             let mut all_vote_commit = pallas::Point::identity();
 
             for input in &params.inputs {
                 signature_pubkeys.push(input.signature_public);
+                conflict_keys.push(input.nullifier.inner());
                 all_vote_commit += input.vote_commit;
 
                 let value_coords = input.vote_commit.to_affine().coordinates().unwrap();
@@ -536,6 +758,7 @@ fn get_metadata(_: ContractId, ix: &[u8]) -> ContractResult {
             let mut metadata = vec![];
             zk_public_values.encode(&mut metadata)?;
             signature_pubkeys.encode(&mut metadata)?;
+            conflict_keys.encode(&mut metadata)?;
 
             // Using this, we pass the above data to the host.
             set_return_data(&metadata)?;
@@ -551,34 +774,132 @@ fn get_metadata(_: ContractId, ix: &[u8]) -> ContractResult {
             let blind_vote = params.blind_total_vote;
             let yes_vote_coords = blind_vote.yes_vote_commit.to_affine().coordinates().unwrap();
             let all_vote_coords = blind_vote.all_vote_commit.to_affine().coordinates().unwrap();
-            let input_value_coords = params.input_value_commit.to_affine().coordinates().unwrap();
 
             msg!("params.proposal: {:?}", params.proposal);
             zk_public_values.push((
                 DAO_CONTRACT_ZKAS_DAO_EXEC_NS.to_string(),
                 vec![
                     params.proposal,
-                    params.coin_0,
-                    params.coin_1,
+                    params.calls_commit,
+                    params.dao_bulla,
+                    params.input_user_data_enc,
                     *yes_vote_coords.x(),
                     *yes_vote_coords.y(),
                     *all_vote_coords.x(),
                     *all_vote_coords.y(),
-                    *input_value_coords.x(),
-                    *input_value_coords.y(),
-                    DAO_CONTRACT_ID.inner(),
-                    pallas::Base::zero(),
-                    pallas::Base::zero(),
                 ],
             ));
 
+            // A proposal can't be executed by more than one transaction
+            let conflict_keys: Vec<pallas::Base> = vec![params.proposal];
+
             let mut metadata = vec![];
             zk_public_values.encode(&mut metadata)?;
             signature_pubkeys.encode(&mut metadata)?;
+            conflict_keys.encode(&mut metadata)?;
 
             // Using this, we pass the above data to the host.
             set_return_data(&metadata)?;
             Ok(())
         }
+
+        DaoFunction::Update => {
+            let params: UpdateCallParams = deserialize(&self_.data[1..])?;
+
+            let mut zk_public_values: Vec<(String, Vec<pallas::Base>)> = vec![];
+            // The DAO is authorized by the DAO::exec() call of the proposal
+            let signature_pubkeys: Vec<PublicKey> = vec![];
+
+            // The new DAO bulla is proven well-formed like in DAO::mint()
+            zk_public_values.push((
+                DAO_CONTRACT_ZKAS_DAO_MINT_NS.to_string(),
+                vec![params.new_dao_bulla.inner()],
+            ));
+
+            // A DAO can't be replaced by more than one transaction, and the
+            // new DAO can't be created by another one
+            let conflict_keys: Vec<pallas::Base> =
+                vec![params.old_dao_bulla.inner(), params.new_dao_bulla.inner()];
+
+            let mut metadata = vec![];
+            zk_public_values.encode(&mut metadata)?;
+            signature_pubkeys.encode(&mut metadata)?;
+            conflict_keys.encode(&mut metadata)?;
+
+            // Using this, we pass the above data to the host.
+            set_return_data(&metadata)?;
+            Ok(())
+        }
+    }
+}
+
+/// The DAO bulla tree stored in DB_INFO, along with the bullas it holds
+/// in insertion order, so it can be rebuilt by `DAO::update()`.
+struct DaoTree {
+    tree: MerkleTree,
+    bullas: Vec<pallas::Base>,
+    generation: u32,
+}
+
+impl DaoTree {
+    /// Create an empty tree for the given generation.
+    fn new(generation: u32) -> Self {
+        Self { tree: MerkleTree::new(100), bullas: vec![], generation }
+    }
+
+    /// Read the current tree from DB_INFO.
+    fn load(info_db: DbHandle) -> ContractResult<Self> {
+        let Some(tree_data) = db_get(info_db, &serialize(&KEY_DAO_MERKLE_TREE))? else {
+            msg!("DAO Merkle tree not found in db");
+            return Err(ContractError::Internal)
+        };
+        let mut decoder = Cursor::new(&tree_data);
+        <u32 as Decodable>::decode(&mut decoder)?;
+        let tree: MerkleTree = Decodable::decode(&mut decoder)?;
+
+        let Some(bullas) = db_get(info_db, &serialize(&KEY_DAO_TREE_BULLAS))? else {
+            msg!("DAO tree bullas not found in db");
+            return Err(ContractError::Internal)
+        };
+
+        Ok(Self {
+            tree,
+            bullas: deserialize(&bullas)?,
+            generation: get_dao_tree_generation(info_db)?,
+        })
     }
+
+    fn append(&mut self, bulla: pallas::Base) {
+        self.tree.append(&MerkleNode::from(bulla));
+        self.bullas.push(bulla);
+    }
+
+    /// Write the tree to DB_INFO, and record its root as valid for the
+    /// tree's generation.
+    fn store(&self, info_db: DbHandle, roots_db: DbHandle) -> ContractResult {
+        let Some(root) = self.tree.root(0) else {
+            msg!("Unable to read the root of the DAO Merkle tree");
+            return Err(ContractError::Internal)
+        };
+
+        let mut tree_data = vec![];
+        tree_data.write_u32(self.bullas.len() as u32)?;
+        self.tree.encode(&mut tree_data)?;
+
+        db_set(info_db, &serialize(&KEY_DAO_MERKLE_TREE), &tree_data)?;
+        db_set(info_db, &serialize(&KEY_DAO_TREE_BULLAS), &serialize(&self.bullas))?;
+        db_set(info_db, &serialize(&KEY_DAO_TREE_GENERATION), &serialize(&self.generation))?;
+        db_set(roots_db, &serialize(&root), &serialize(&self.generation))?;
+
+        Ok(())
+    }
+}
+
+/// Fetch the current generation of the DAO bulla tree from DB_INFO.
+fn get_dao_tree_generation(info_db: DbHandle) -> ContractResult<u32> {
+    let Some(generation) = db_get(info_db, &serialize(&KEY_DAO_TREE_GENERATION))? else {
+        msg!("DAO tree generation not found in db");
+        return Err(ContractError::Internal)
+    };
+    Ok(deserialize(&generation)?)
 }
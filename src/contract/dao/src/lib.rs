@@ -46,6 +46,11 @@ pub const DAO_CONTRACT_ZKAS_DAO_VOTE_MAIN_NS: &str = "DaoVoteMain";
 pub const DAO_CONTRACT_ZKAS_DAO_PROPOSE_BURN_NS: &str = "DaoProposeInput";
 pub const DAO_CONTRACT_ZKAS_DAO_PROPOSE_MAIN_NS: &str = "DaoProposeMain";
 
+/// Maximum number of slots between a proposal's creation slot and the slot
+/// it gets included in, so proposals can't be backdated to cut their voting
+/// period short.
+pub const DAO_PROPOSAL_CREATION_WINDOW: u64 = 10;
+
 #[repr(u8)]
 #[derive(PartialEq, Debug)]
 pub enum DaoFunction {
@@ -53,6 +58,7 @@ pub enum DaoFunction {
     Propose = 0x01,
     Vote = 0x02,
     Exec = 0x03,
+    Update = 0x04,
 }
 
 impl TryFrom<u8> for DaoFunction {
@@ -64,6 +70,7 @@ impl TryFrom<u8> for DaoFunction {
             0x01 => Ok(DaoFunction::Propose),
             0x02 => Ok(DaoFunction::Vote),
             0x03 => Ok(DaoFunction::Exec),
+            0x04 => Ok(DaoFunction::Update),
             _ => Err(ContractError::InvalidFunction),
         }
     }
@@ -19,8 +19,8 @@ use std::collections::HashMap;
 
 use darkfi::{
     consensus::{
-        ValidatorState, ValidatorStatePtr, TESTNET_BOOTSTRAP_TIMESTAMP, TESTNET_GENESIS_HASH_BYTES,
-        TESTNET_GENESIS_TIMESTAMP, TESTNET_INITIAL_DISTRIBUTION,
+        ValidatorState, ValidatorStatePtr, TESTNET_BOOTSTRAP_TIMESTAMP, TESTNET_EMISSION,
+        TESTNET_GENESIS_HASH_BYTES, TESTNET_GENESIS_TIMESTAMP, TESTNET_INITIAL_DISTRIBUTION,
     },
     wallet::WalletDb,
     zk::{empty_witnesses, ProvingKey, ZkCircuit},
@@ -125,6 +125,7 @@ impl DaoTestHarness {
             *TESTNET_GENESIS_TIMESTAMP,
             *TESTNET_GENESIS_HASH_BYTES,
             *TESTNET_INITIAL_DISTRIBUTION,
+            *TESTNET_EMISSION,
             alice_wallet,
             faucet_pubkeys,
             false,
@@ -202,17 +203,18 @@ impl DaoTestHarness {
 
         info!(target: "dao", "Creating zk proving keys");
 
-        let k = 13;
         let mut proving_keys = HashMap::<[u8; 32], Vec<(&str, ProvingKey)>>::new();
 
-        let money_mint_pk = ProvingKey::build(k, &money_mint_circuit);
-        let money_burn_pk = ProvingKey::build(k, &money_burn_circuit);
-        let dao_mint_pk = ProvingKey::build(k, &dao_mint_circuit);
-        let dao_propose_burn_pk = ProvingKey::build(k, &dao_propose_burn_circuit);
-        let dao_propose_main_pk = ProvingKey::build(k, &dao_propose_main_circuit);
-        let dao_vote_burn_pk = ProvingKey::build(k, &dao_vote_burn_circuit);
-        let dao_vote_main_pk = ProvingKey::build(k, &dao_vote_main_circuit);
-        let dao_exec_pk = ProvingKey::build(k, &dao_exec_circuit);
+        let money_mint_pk = ProvingKey::build(money_mint_zkbin.k, &money_mint_circuit);
+        let money_burn_pk = ProvingKey::build(money_burn_zkbin.k, &money_burn_circuit);
+        let dao_mint_pk = ProvingKey::build(dao_mint_zkbin.k, &dao_mint_circuit);
+        let dao_propose_burn_pk =
+            ProvingKey::build(dao_propose_burn_zkbin.k, &dao_propose_burn_circuit);
+        let dao_propose_main_pk =
+            ProvingKey::build(dao_propose_main_zkbin.k, &dao_propose_main_circuit);
+        let dao_vote_burn_pk = ProvingKey::build(dao_vote_burn_zkbin.k, &dao_vote_burn_circuit);
+        let dao_vote_main_pk = ProvingKey::build(dao_vote_main_zkbin.k, &dao_vote_main_circuit);
+        let dao_exec_pk = ProvingKey::build(dao_exec_zkbin.k, &dao_exec_circuit);
 
         let pks = vec![
             (MONEY_CONTRACT_ZKAS_MINT_NS_V1, money_mint_pk.clone()),
@@ -20,8 +20,10 @@ use darkfi::{tx::Transaction, Result};
 use darkfi_sdk::{
     crypto::{
         merkle_prelude::*, pallas, pasta_prelude::*, pedersen_commitment_u64, poseidon_hash, Coin,
-        Keypair, MerkleNode, MerkleTree, SecretKey, TokenId, DAO_CONTRACT_ID, MONEY_CONTRACT_ID,
+        Keypair, MerkleNode, MerkleTree, Nullifier, SecretKey, TokenId, DAO_CONTRACT_ID,
+        DARK_TOKEN_ID, MONEY_CONTRACT_ID,
     },
+    tx::DEFAULT_GAS_LIMIT,
     ContractCall,
 };
 use darkfi_serial::{Decodable, Encodable};
@@ -32,7 +34,11 @@ use darkfi_dao_contract::{
     dao_client, dao_model, money_client, note, wallet_cache::WalletCache, DaoFunction,
 };
 
-use darkfi_money_contract::{client::EncryptedNote, model::MoneyTransferParams, MoneyFunction};
+use darkfi_money_contract::{
+    client::{build_fee_tx, Coin as MoneyCoin, EncryptedNote, OwnCoin},
+    model::MoneyTransferParams,
+    MoneyFunction,
+};
 
 mod harness;
 use harness::{init_logger, DaoTestHarness};
@@ -88,7 +94,11 @@ async fn integration_test() -> Result<()> {
 
     let mut data = vec![DaoFunction::Mint as u8];
     params.encode(&mut data)?;
-    let calls = vec![ContractCall { contract_id: dao_th.dao_contract_id, data }];
+    let calls = vec![ContractCall {
+        contract_id: dao_th.dao_contract_id,
+        data,
+        gas_limit: DEFAULT_GAS_LIMIT,
+    }];
     let proofs = vec![proofs];
     let mut tx = Transaction { calls, proofs, signatures: vec![] };
     let sigs = tx.create_sigs(&mut OsRng, &[])?;
@@ -159,7 +169,7 @@ async fn integration_test() -> Result<()> {
 
     let mut data = vec![MoneyFunction::Transfer as u8];
     params.encode(&mut data)?;
-    let calls = vec![ContractCall { contract_id, data }];
+    let calls = vec![ContractCall { contract_id, data, gas_limit: DEFAULT_GAS_LIMIT }];
     let proofs = vec![proofs];
     let mut tx = Transaction { calls, proofs, signatures: vec![] };
     let sigs = tx.create_sigs(&mut OsRng, &vec![dao_th.faucet_kp.secret])?;
@@ -280,7 +290,7 @@ async fn integration_test() -> Result<()> {
 
     let mut data = vec![MoneyFunction::Transfer as u8];
     params.encode(&mut data)?;
-    let calls = vec![ContractCall { contract_id, data }];
+    let calls = vec![ContractCall { contract_id, data, gas_limit: DEFAULT_GAS_LIMIT }];
     let proofs = vec![proofs];
     let mut tx = Transaction { calls, proofs, signatures: vec![] };
     let sigs = tx.create_sigs(&mut OsRng, &vec![dao_th.faucet_kp.secret])?;
@@ -353,9 +363,7 @@ async fn integration_test() -> Result<()> {
     // 2. proposals must be submitted by minimum amount
     // 3. all votes >= quorum
     // 4. outcome > approval_ratio
-    // 5. structure of outputs
-    //   output 0: value and address
-    //   output 1: change address
+    // 5. the executed calls are the ones committed to by the proposal
     // =======================================================
     debug!(target: "dao", "Stage 4. Propose the vote");
 
@@ -371,8 +379,6 @@ async fn integration_test() -> Result<()> {
         (leaf_position, merkle_path)
     };
 
-    // TODO: is it possible for an invalid transfer() to be constructed on exec()?
-    //       need to look into this
     let signature_secret = SecretKey::random(&mut OsRng);
     let input = dao_client::ProposeStakeInput {
         secret: dao_th.alice_kp.secret,
@@ -389,13 +395,90 @@ async fn integration_test() -> Result<()> {
         (merkle_path, root)
     };
 
+    // The proposal pays out of the DAO treasury. Its calls are built
+    // upfront and committed to, so they can't be changed on DAO::exec().
+
+    // Used to export user_data from this coin so it can be accessed by DAO::exec()
+    let user_data_blind = pallas::Base::random(&mut OsRng);
+
+    let dao_serial = pallas::Base::random(&mut OsRng);
+    let dao_coin_blind = pallas::Base::random(&mut OsRng);
+    let input_value_blind = pallas::Scalar::random(&mut OsRng);
+    let xfer_signature_secret = SecretKey::random(&mut OsRng);
+
+    let (treasury_leaf_position, treasury_merkle_path) = {
+        let tree = &cache.tree;
+        let leaf_position = dao_recv_coin.leaf_position;
+        let root = tree.root(0).unwrap();
+        let merkle_path = tree.authentication_path(leaf_position, &root).unwrap();
+        (leaf_position, merkle_path)
+    };
+
+    // TODO: this should be the contract/func ID
+    //let spend_hook = pallas::Base::from(110);
+    let spend_hook = DAO_CONTRACT_ID.inner();
+    // The user_data can be a simple hash of the items passed into the ZK proof
+    // up to corresponding linked ZK proof to interpret however they need.
+    // In out case, it's the bulla for the DAO
+    let user_data = dao_bulla.inner();
+
+    let xfer_call = money_client::TransferCall {
+        clear_inputs: vec![],
+        inputs: vec![money_client::TransferInput {
+            leaf_position: treasury_leaf_position,
+            merkle_path: treasury_merkle_path,
+            secret: dao_th.dao_kp.secret,
+            note: treasury_note,
+            user_data_blind,
+            value_blind: input_value_blind,
+            signature_secret: xfer_signature_secret,
+        }],
+        outputs: vec![
+            // Sending money
+            money_client::TransferOutput {
+                value: 1000,
+                token_id: xdrk_token_id,
+                //public: user_keypair.public,
+                public: receiver_keypair.public,
+                serial: pallas::Base::random(&mut OsRng),
+                coin_blind: pallas::Base::random(&mut OsRng),
+                spend_hook: pallas::Base::from(0),
+                user_data: pallas::Base::from(0),
+            },
+            // Change back to DAO
+            money_client::TransferOutput {
+                value: xdrk_supply - 1000,
+                token_id: xdrk_token_id,
+                public: dao_th.dao_kp.public,
+                serial: dao_serial,
+                coin_blind: dao_coin_blind,
+                spend_hook,
+                user_data,
+            },
+        ],
+    };
+    let (xfer_params, xfer_proofs) = xfer_call.make(
+        &dao_th.money_mint_zkbin,
+        &dao_th.money_mint_pk,
+        &dao_th.money_burn_zkbin,
+        &dao_th.money_burn_pk,
+    )?;
+
+    let mut data = vec![MoneyFunction::Transfer as u8];
+    xfer_params.encode(&mut data)?;
+    let xfer_call =
+        ContractCall { contract_id: *MONEY_CONTRACT_ID, data, gas_limit: DEFAULT_GAS_LIMIT };
+
+    // The proposal can be voted on for a few slots, after which it can be executed
+    let creation_slot = dao_th.alice_state.read().await.consensus.current_slot();
     let proposal = dao_client::ProposalInfo {
-        dest: receiver_keypair.public,
-        amount: 1000,
-        serial: pallas::Base::random(&mut OsRng),
-        token_id: xdrk_token_id,
+        calls: vec![xfer_call.clone()],
+        creation_slot,
+        duration: 5,
         blind: pallas::Base::random(&mut OsRng),
+        user_data_blind,
     };
+    let deadline = creation_slot + proposal.duration;
 
     let call = dao_client::ProposeCall {
         inputs: vec![input],
@@ -416,13 +499,18 @@ async fn integration_test() -> Result<()> {
 
     let mut data = vec![DaoFunction::Propose as u8];
     params.encode(&mut data)?;
-    let calls = vec![ContractCall { contract_id, data }];
+    let calls = vec![ContractCall { contract_id, data, gas_limit: DEFAULT_GAS_LIMIT }];
     let proofs = vec![proofs];
     let mut tx = Transaction { calls, proofs, signatures: vec![] };
     let sigs = tx.create_sigs(&mut OsRng, &vec![signature_secret])?;
     tx.signatures = vec![sigs];
 
-    dao_th.alice_state.read().await.verify_transactions(&[tx.clone()], true).await?;
+    dao_th
+        .alice_state
+        .read()
+        .await
+        .verify_transactions_at_slot(&[tx.clone()], creation_slot, true)
+        .await?;
 
     //// Wallet
 
@@ -441,9 +529,7 @@ async fn integration_test() -> Result<()> {
         (note.proposal, params.proposal_bulla)
     };
     debug!(target: "dao", "Proposal now active!");
-    debug!(target: "dao", "  destination: {:?}", proposal.dest);
-    debug!(target: "dao", "  amount: {}", proposal.amount);
-    debug!(target: "dao", "  token_id: {:?}", proposal.token_id);
+    debug!(target: "dao", "  calls: {}", proposal.calls.len());
     debug!(target: "dao", "  dao_bulla: {:?}", dao_bulla.inner());
     debug!(target: "dao", "Proposal bulla: {:?}", proposal_bulla);
 
@@ -521,13 +607,18 @@ async fn integration_test() -> Result<()> {
 
     let mut data = vec![DaoFunction::Vote as u8];
     params.encode(&mut data)?;
-    let calls = vec![ContractCall { contract_id, data }];
+    let calls = vec![ContractCall { contract_id, data, gas_limit: DEFAULT_GAS_LIMIT }];
     let proofs = vec![proofs];
     let mut tx = Transaction { calls, proofs, signatures: vec![] };
     let sigs = tx.create_sigs(&mut OsRng, &vec![signature_secret])?;
     tx.signatures = vec![sigs];
 
-    dao_th.alice_state.read().await.verify_transactions(&[tx.clone()], true).await?;
+    dao_th
+        .alice_state
+        .read()
+        .await
+        .verify_transactions_at_slot(&[tx.clone()], creation_slot, true)
+        .await?;
 
     // Secret vote info. Needs to be revealed at some point.
     // TODO: look into verifiable encryption for notes
@@ -589,13 +680,18 @@ async fn integration_test() -> Result<()> {
 
     let mut data = vec![DaoFunction::Vote as u8];
     params.encode(&mut data)?;
-    let calls = vec![ContractCall { contract_id, data }];
+    let calls = vec![ContractCall { contract_id, data, gas_limit: DEFAULT_GAS_LIMIT }];
     let proofs = vec![proofs];
     let mut tx = Transaction { calls, proofs, signatures: vec![] };
     let sigs = tx.create_sigs(&mut OsRng, &vec![signature_secret])?;
     tx.signatures = vec![sigs];
 
-    dao_th.alice_state.read().await.verify_transactions(&[tx.clone()], true).await?;
+    dao_th
+        .alice_state
+        .read()
+        .await
+        .verify_transactions_at_slot(&[tx.clone()], creation_slot, true)
+        .await?;
 
     let vote_note_2 = {
         let enc_note = note::EncryptedNote2 {
@@ -654,13 +750,27 @@ async fn integration_test() -> Result<()> {
 
     let mut data = vec![DaoFunction::Vote as u8];
     params.encode(&mut data)?;
-    let calls = vec![ContractCall { contract_id, data }];
+    let calls = vec![ContractCall { contract_id, data, gas_limit: DEFAULT_GAS_LIMIT }];
     let proofs = vec![proofs];
     let mut tx = Transaction { calls, proofs, signatures: vec![] };
     let sigs = tx.create_sigs(&mut OsRng, &vec![signature_secret])?;
     tx.signatures = vec![sigs];
 
-    dao_th.alice_state.read().await.verify_transactions(&[tx.clone()], true).await?;
+    // Votes are rejected once the voting period is over
+    assert!(dao_th
+        .alice_state
+        .read()
+        .await
+        .verify_transactions_at_slot(&[tx.clone()], deadline, false)
+        .await
+        .is_err());
+
+    dao_th
+        .alice_state
+        .read()
+        .await
+        .verify_transactions_at_slot(&[tx.clone()], creation_slot, true)
+        .await?;
 
     // Secret vote info. Needs to be revealed at some point.
     // TODO: look into verifiable encryption for notes
@@ -746,68 +856,259 @@ async fn integration_test() -> Result<()> {
 
     debug!(target: "dao", "Stage 6. Execute vote");
 
-    // Used to export user_data from this coin so it can be accessed by DAO::exec()
-    let user_data_blind = pallas::Base::random(&mut OsRng);
-
-    let user_serial = pallas::Base::random(&mut OsRng);
-    let user_coin_blind = pallas::Base::random(&mut OsRng);
-    let dao_serial = pallas::Base::random(&mut OsRng);
-    let dao_coin_blind = pallas::Base::random(&mut OsRng);
-    let input_value = treasury_note.value;
-    let input_value_blind = pallas::Scalar::random(&mut OsRng);
-    let xfer_signature_secret = SecretKey::random(&mut OsRng);
     let exec_signature_secret = SecretKey::random(&mut OsRng);
 
+    // DAO::exec() has to prove the coins spent by the proposal calls belong
+    // to the DAO, which fails with a user data blind they weren't spent with
+    let mut bogus_proposal = proposal.clone();
+    bogus_proposal.user_data_blind = pallas::Base::random(&mut OsRng);
+    let call = dao_client::ExecCall {
+        proposal: bogus_proposal,
+        dao: dao.clone(),
+        yes_vote_value: total_yes_vote_value,
+        all_vote_value: total_all_vote_value,
+        yes_vote_blind: total_yes_vote_blind,
+        all_vote_blind: total_all_vote_blind,
+        signature_secret: exec_signature_secret,
+    };
+    let (bogus_params, bogus_proofs) = call.make(&dao_th.dao_exec_zkbin, &dao_th.dao_exec_pk)?;
+
+    let mut data = vec![DaoFunction::Exec as u8];
+    bogus_params.encode(&mut data)?;
+    let bogus_call =
+        ContractCall { contract_id: *DAO_CONTRACT_ID, data, gas_limit: DEFAULT_GAS_LIMIT };
+
+    let mut tx = Transaction {
+        calls: vec![xfer_call.clone(), bogus_call],
+        proofs: vec![xfer_proofs.clone(), bogus_proofs],
+        signatures: vec![],
+    };
+    let xfer_sigs = tx.create_sigs(&mut OsRng, &vec![xfer_signature_secret])?;
+    let exec_sigs = tx.create_sigs(&mut OsRng, &vec![exec_signature_secret])?;
+    tx.signatures = vec![xfer_sigs, exec_sigs];
+    assert!(dao_th
+        .alice_state
+        .read()
+        .await
+        .verify_transactions_at_slot(&[tx], deadline, false)
+        .await
+        .is_err());
+
+    let call = dao_client::ExecCall {
+        proposal,
+        dao: dao.clone(),
+        yes_vote_value: total_yes_vote_value,
+        all_vote_value: total_all_vote_value,
+        yes_vote_blind: total_yes_vote_blind,
+        all_vote_blind: total_all_vote_blind,
+        signature_secret: exec_signature_secret,
+    };
+    let (exec_params, exec_proofs) = call.make(&dao_th.dao_exec_zkbin, &dao_th.dao_exec_pk)?;
+
+    let mut data = vec![DaoFunction::Exec as u8];
+    exec_params.encode(&mut data)?;
+    let exec_call =
+        ContractCall { contract_id: *DAO_CONTRACT_ID, data, gas_limit: DEFAULT_GAS_LIMIT };
+
+    // DAO::exec() can't be executed without the calls the proposal commits to
+    let mut tx = Transaction {
+        calls: vec![exec_call.clone()],
+        proofs: vec![exec_proofs.clone()],
+        signatures: vec![],
+    };
+    let exec_sigs = tx.create_sigs(&mut OsRng, &vec![exec_signature_secret])?;
+    tx.signatures = vec![exec_sigs];
+    assert!(dao_th
+        .alice_state
+        .read()
+        .await
+        .verify_transactions_at_slot(&[tx.clone()], deadline, false)
+        .await
+        .is_err());
+
+    // Whoever executes the proposal pays the tx fee with a native token coin
+    // of their own, through a Money::Fee() call preceding the proposal calls
+    let fee_kp = Keypair::random(&mut OsRng);
+    cache.track(fee_kp.secret);
+
+    let call = money_client::TransferCall {
+        clear_inputs: vec![money_client::TransferClearInput {
+            value: 100,
+            token_id: *DARK_TOKEN_ID,
+            signature_secret: dao_th.faucet_kp.secret,
+        }],
+        inputs: vec![],
+        outputs: vec![money_client::TransferOutput {
+            value: 100,
+            token_id: *DARK_TOKEN_ID,
+            public: fee_kp.public,
+            serial: pallas::Base::random(&mut OsRng),
+            coin_blind: pallas::Base::random(&mut OsRng),
+            spend_hook: pallas::Base::from(0),
+            user_data: pallas::Base::from(0),
+        }],
+    };
+    let (params, proofs) = call.make(
+        &dao_th.money_mint_zkbin,
+        &dao_th.money_mint_pk,
+        &dao_th.money_burn_zkbin,
+        &dao_th.money_burn_pk,
+    )?;
+
+    let mut data = vec![MoneyFunction::Transfer as u8];
+    params.encode(&mut data)?;
+    let calls =
+        vec![ContractCall { contract_id: *MONEY_CONTRACT_ID, data, gas_limit: DEFAULT_GAS_LIMIT }];
+    let mut tx = Transaction { calls, proofs: vec![proofs], signatures: vec![] };
+    let sigs = tx.create_sigs(&mut OsRng, &vec![dao_th.faucet_kp.secret])?;
+    tx.signatures = vec![sigs];
+    dao_th.alice_state.read().await.verify_transactions(&[tx], true).await?;
+
+    for output in params.outputs {
+        let enc_note =
+            EncryptedNote { ciphertext: output.ciphertext, ephem_public: output.ephem_public };
+        cache.try_decrypt_note(Coin(output.coin), &enc_note);
+    }
+
+    let fee_coin = cache.get_received(&fee_kp.secret).pop().unwrap();
+    let fee_coin = OwnCoin {
+        coin: MoneyCoin::from(fee_coin.coin.inner()),
+        nullifier: Nullifier::from(poseidon_hash([fee_kp.secret.inner(), fee_coin.note.serial])),
+        note: fee_coin.note,
+        secret: fee_kp.secret,
+        leaf_position: fee_coin.leaf_position,
+    };
+
+    let (fee_params, fee_proofs, fee_secrets) = build_fee_tx(
+        &fee_kp,
+        10,
+        &fee_coin,
+        &cache.tree,
+        &dao_th.money_mint_zkbin,
+        &dao_th.money_mint_pk,
+        &dao_th.money_burn_zkbin,
+        &dao_th.money_burn_pk,
+    )?;
+
+    let mut data = vec![MoneyFunction::Fee as u8];
+    fee_params.encode(&mut data)?;
+    let fee_call =
+        ContractCall { contract_id: *MONEY_CONTRACT_ID, data, gas_limit: DEFAULT_GAS_LIMIT };
+
+    let mut tx = Transaction {
+        calls: vec![fee_call, xfer_call, exec_call],
+        proofs: vec![fee_proofs, xfer_proofs, exec_proofs],
+        signatures: vec![],
+    };
+    let fee_sigs = tx.create_sigs(&mut OsRng, &fee_secrets)?;
+    let xfer_sigs = tx.create_sigs(&mut OsRng, &vec![xfer_signature_secret])?;
+    let exec_sigs = tx.create_sigs(&mut OsRng, &vec![exec_signature_secret])?;
+    tx.signatures = vec![fee_sigs, xfer_sigs, exec_sigs];
+    assert_eq!(dao_th.alice_state.read().await.tx_fees(&tx)?, (10, 0));
+
+    // The proposal can't be executed while it's still being voted on
+    assert!(dao_th
+        .alice_state
+        .read()
+        .await
+        .verify_transactions_at_slot(&[tx.clone()], deadline - 1, false)
+        .await
+        .is_err());
+
+    dao_th
+        .alice_state
+        .read()
+        .await
+        .verify_transactions_at_slot(&[tx.clone()], deadline, true)
+        .await?;
+
+    // The fee change is added to the tree before the proposal call outputs
+    let output = fee_params.output;
+    let enc_note =
+        EncryptedNote { ciphertext: output.ciphertext, ephem_public: output.ephem_public };
+    cache.try_decrypt_note(Coin(output.coin), &enc_note);
+
+    // DAO reads the change sent back to the treasury
+    {
+        let params: MoneyTransferParams = Decodable::decode(&tx.calls[1].data[1..])?;
+
+        for output in params.outputs {
+            let enc_note =
+                EncryptedNote { ciphertext: output.ciphertext, ephem_public: output.ephem_public };
+            cache.try_decrypt_note(Coin(output.coin), &enc_note);
+        }
+    }
+
+    let mut recv_coins = cache.get_received(&dao_th.dao_kp.secret);
+    assert_eq!(recv_coins.len(), 1);
+    let dao_change_coin = recv_coins.pop().unwrap();
+    assert_eq!(dao_change_coin.note.value, xdrk_supply - 1000);
+
+    // =======================================================
+    // Dao::Update
+    //
+    // Governance replaces the DAO with one using new parameters
+    // and keys. The treasury is moved over in the same proposal.
+    // =======================================================
+    debug!(target: "dao", "Stage 7. Update the DAO through a proposal");
+
+    let new_dao_kp = Keypair::random(&mut OsRng);
+    let new_dao = dao_client::DaoInfo {
+        quorum: 200,
+        public_key: new_dao_kp.public,
+        bulla_blind: pallas::Base::random(&mut OsRng),
+        ..dao.clone()
+    };
+    let new_dao_bulla = new_dao.bulla();
+
+    let (update_params, update_proofs) =
+        dao_client::make_update_call(&dao, &new_dao, &dao_th.dao_mint_zkbin, &dao_th.dao_mint_pk)?;
+    assert_eq!(update_params.old_dao_bulla.inner(), dao_bulla.inner());
+
+    let mut data = vec![DaoFunction::Update as u8];
+    update_params.encode(&mut data)?;
+    let update_call =
+        ContractCall { contract_id: *DAO_CONTRACT_ID, data, gas_limit: DEFAULT_GAS_LIMIT };
+
+    // DAO::update() is only valid when executed by a proposal
+    let tx = Transaction {
+        calls: vec![update_call.clone()],
+        proofs: vec![update_proofs.clone()],
+        signatures: vec![vec![]],
+    };
+    assert!(dao_th.alice_state.read().await.verify_transactions(&[tx], false).await.is_err());
+
     let (treasury_leaf_position, treasury_merkle_path) = {
         let tree = &cache.tree;
-        let leaf_position = dao_recv_coin.leaf_position;
+        let leaf_position = dao_change_coin.leaf_position;
         let root = tree.root(0).unwrap();
         let merkle_path = tree.authentication_path(leaf_position, &root).unwrap();
         (leaf_position, merkle_path)
     };
 
-    // TODO: this should be the contract/func ID
-    //let spend_hook = pallas::Base::from(110);
-    let spend_hook = DAO_CONTRACT_ID.inner();
-    // The user_data can be a simple hash of the items passed into the ZK proof
-    // up to corresponding linked ZK proof to interpret however they need.
-    // In out case, it's the bulla for the DAO
-    let user_data = dao_bulla.inner();
-
+    // The whole treasury goes to the new DAO
+    let user_data_blind = pallas::Base::random(&mut OsRng);
+    let xfer_signature_secret = SecretKey::random(&mut OsRng);
     let xfer_call = money_client::TransferCall {
         clear_inputs: vec![],
         inputs: vec![money_client::TransferInput {
             leaf_position: treasury_leaf_position,
             merkle_path: treasury_merkle_path,
             secret: dao_th.dao_kp.secret,
-            note: treasury_note,
+            note: dao_change_coin.note.clone(),
             user_data_blind,
-            value_blind: input_value_blind,
+            value_blind: pallas::Scalar::random(&mut OsRng),
             signature_secret: xfer_signature_secret,
         }],
-        outputs: vec![
-            // Sending money
-            money_client::TransferOutput {
-                value: 1000,
-                token_id: xdrk_token_id,
-                //public: user_keypair.public,
-                public: receiver_keypair.public,
-                serial: proposal.serial,
-                coin_blind: proposal.blind,
-                spend_hook: pallas::Base::from(0),
-                user_data: pallas::Base::from(0),
-            },
-            // Change back to DAO
-            money_client::TransferOutput {
-                value: xdrk_supply - 1000,
-                token_id: xdrk_token_id,
-                public: dao_th.dao_kp.public,
-                serial: dao_serial,
-                coin_blind: dao_coin_blind,
-                spend_hook,
-                user_data,
-            },
-        ],
+        outputs: vec![money_client::TransferOutput {
+            value: dao_change_coin.note.value,
+            token_id: xdrk_token_id,
+            public: new_dao_kp.public,
+            serial: pallas::Base::random(&mut OsRng),
+            coin_blind: pallas::Base::random(&mut OsRng),
+            spend_hook: DAO_CONTRACT_ID.inner(),
+            user_data: new_dao_bulla.inner(),
+        }],
     };
     let (xfer_params, xfer_proofs) = xfer_call.make(
         &dao_th.money_mint_zkbin,
@@ -818,40 +1119,318 @@ async fn integration_test() -> Result<()> {
 
     let mut data = vec![MoneyFunction::Transfer as u8];
     xfer_params.encode(&mut data)?;
-    let xfer_call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+    let xfer_call =
+        ContractCall { contract_id: *MONEY_CONTRACT_ID, data, gas_limit: DEFAULT_GAS_LIMIT };
+
+    // The coin spend invokes the DAO contract right after it
+    let creation_slot = dao_th.alice_state.read().await.consensus.current_slot();
+    let proposal = dao_client::ProposalInfo {
+        calls: vec![xfer_call.clone(), update_call.clone()],
+        creation_slot,
+        duration: 5,
+        blind: pallas::Base::random(&mut OsRng),
+        user_data_blind,
+    };
+    let deadline = creation_slot + proposal.duration;
+
+    let (money_leaf_position, money_merkle_path) = {
+        let tree = &cache.tree;
+        let leaf_position = gov_recv[0].leaf_position;
+        let root = tree.root(0).unwrap();
+        let merkle_path = tree.authentication_path(leaf_position, &root).unwrap();
+        (leaf_position, merkle_path)
+    };
+
+    let signature_secret = SecretKey::random(&mut OsRng);
+    let input = dao_client::ProposeStakeInput {
+        secret: dao_th.alice_kp.secret,
+        note: gov_recv[0].note.clone(),
+        leaf_position: money_leaf_position,
+        merkle_path: money_merkle_path,
+        signature_secret,
+    };
+
+    let (dao_merkle_path, dao_merkle_root) = {
+        let tree = &dao_tree;
+        let root = tree.root(0).unwrap();
+        let merkle_path = tree.authentication_path(dao_leaf_position, &root).unwrap();
+        (merkle_path, root)
+    };
 
+    let call = dao_client::ProposeCall {
+        inputs: vec![input],
+        proposal: proposal.clone(),
+        dao: dao.clone(),
+        dao_leaf_position,
+        dao_merkle_path,
+        dao_merkle_root,
+    };
+    let (params, proofs) = call.make(
+        &dao_th.dao_propose_burn_zkbin,
+        &dao_th.dao_propose_burn_pk,
+        &dao_th.dao_propose_main_zkbin,
+        &dao_th.dao_propose_main_pk,
+    )?;
+
+    let mut data = vec![DaoFunction::Propose as u8];
+    params.encode(&mut data)?;
+    let calls =
+        vec![ContractCall { contract_id: *DAO_CONTRACT_ID, data, gas_limit: DEFAULT_GAS_LIMIT }];
+    let mut tx = Transaction { calls, proofs: vec![proofs], signatures: vec![] };
+    let sigs = tx.create_sigs(&mut OsRng, &vec![signature_secret])?;
+    tx.signatures = vec![sigs];
+
+    dao_th
+        .alice_state
+        .read()
+        .await
+        .verify_transactions_at_slot(&[tx.clone()], creation_slot, true)
+        .await?;
+
+    // Alice alone passes the proposal
+    let (money_leaf_position, money_merkle_path) = {
+        let tree = &cache.tree;
+        let leaf_position = gov_recv[0].leaf_position;
+        let root = tree.root(0).unwrap();
+        let merkle_path = tree.authentication_path(leaf_position, &root).unwrap();
+        (leaf_position, merkle_path)
+    };
+
+    let signature_secret = SecretKey::random(&mut OsRng);
+    let input = dao_client::VoteInput {
+        secret: dao_th.alice_kp.secret,
+        note: gov_recv[0].note.clone(),
+        leaf_position: money_leaf_position,
+        merkle_path: money_merkle_path,
+        signature_secret,
+    };
+
+    let vote_keypair = Keypair::random(&mut OsRng);
+    let call = dao_client::VoteCall {
+        inputs: vec![input],
+        vote_option: true,
+        yes_vote_blind: pallas::Scalar::random(&mut OsRng),
+        vote_keypair,
+        proposal: proposal.clone(),
+        dao: dao.clone(),
+    };
+    let (params, proofs) = call.make(
+        &dao_th.dao_vote_burn_zkbin,
+        &dao_th.dao_vote_burn_pk,
+        &dao_th.dao_vote_main_zkbin,
+        &dao_th.dao_vote_main_pk,
+    )?;
+
+    let mut data = vec![DaoFunction::Vote as u8];
+    params.encode(&mut data)?;
+    let calls =
+        vec![ContractCall { contract_id: *DAO_CONTRACT_ID, data, gas_limit: DEFAULT_GAS_LIMIT }];
+    let mut tx = Transaction { calls, proofs: vec![proofs], signatures: vec![] };
+    let sigs = tx.create_sigs(&mut OsRng, &vec![signature_secret])?;
+    tx.signatures = vec![sigs];
+
+    dao_th
+        .alice_state
+        .read()
+        .await
+        .verify_transactions_at_slot(&[tx.clone()], creation_slot, true)
+        .await?;
+
+    let vote_note = {
+        let enc_note = note::EncryptedNote2 {
+            ciphertext: params.ciphertext,
+            ephem_public: params.ephem_public,
+        };
+        let note: dao_client::VoteNote = enc_note.decrypt(&vote_keypair.secret).unwrap();
+        note
+    };
+
+    let exec_signature_secret = SecretKey::random(&mut OsRng);
     let call = dao_client::ExecCall {
         proposal,
-        dao,
-        yes_vote_value: total_yes_vote_value,
-        all_vote_value: total_all_vote_value,
-        yes_vote_blind: total_yes_vote_blind,
-        all_vote_blind: total_all_vote_blind,
-        user_serial,
-        user_coin_blind,
-        dao_serial,
-        dao_coin_blind,
-        input_value,
-        input_value_blind,
-        hook_dao_exec: spend_hook,
+        dao: dao.clone(),
+        yes_vote_value: vote_note.all_vote_value,
+        all_vote_value: vote_note.all_vote_value,
+        yes_vote_blind: vote_note.yes_vote_blind,
+        all_vote_blind: vote_note.all_vote_blind,
         signature_secret: exec_signature_secret,
     };
     let (exec_params, exec_proofs) = call.make(&dao_th.dao_exec_zkbin, &dao_th.dao_exec_pk)?;
+    // The DAO bulla is revealed so DAO::update() can check it
+    assert_eq!(exec_params.dao_bulla, dao_bulla.inner());
 
     let mut data = vec![DaoFunction::Exec as u8];
     exec_params.encode(&mut data)?;
-    let exec_call = ContractCall { contract_id: *DAO_CONTRACT_ID, data };
+    let exec_call =
+        ContractCall { contract_id: *DAO_CONTRACT_ID, data, gas_limit: DEFAULT_GAS_LIMIT };
 
     let mut tx = Transaction {
-        calls: vec![xfer_call, exec_call],
-        proofs: vec![xfer_proofs, exec_proofs],
+        calls: vec![xfer_call, update_call, exec_call],
+        proofs: vec![xfer_proofs, update_proofs, exec_proofs],
         signatures: vec![],
     };
     let xfer_sigs = tx.create_sigs(&mut OsRng, &vec![xfer_signature_secret])?;
     let exec_sigs = tx.create_sigs(&mut OsRng, &vec![exec_signature_secret])?;
-    tx.signatures = vec![xfer_sigs, exec_sigs];
+    tx.signatures = vec![xfer_sigs, vec![], exec_sigs];
 
-    dao_th.alice_state.read().await.verify_transactions(&[tx.clone()], true).await?;
+    dao_th
+        .alice_state
+        .read()
+        .await
+        .verify_transactions_at_slot(&[tx.clone()], deadline, true)
+        .await?;
+
+    // The new DAO holds the treasury
+    cache.track(new_dao_kp.secret);
+    {
+        let params: MoneyTransferParams = Decodable::decode(&tx.calls[0].data[1..])?;
+
+        for output in params.outputs {
+            let enc_note =
+                EncryptedNote { ciphertext: output.ciphertext, ephem_public: output.ephem_public };
+            cache.try_decrypt_note(Coin(output.coin), &enc_note);
+        }
+    }
+
+    let mut recv_coins = cache.get_received(&new_dao_kp.secret);
+    assert_eq!(recv_coins.len(), 1);
+    let new_treasury_note = recv_coins.pop().unwrap().note;
+    assert_eq!(new_treasury_note.value, xdrk_supply - 1000);
+    assert_eq!(new_treasury_note.user_data, new_dao_bulla.inner());
+
+    debug!(target: "dao", "DAO updated to bulla: {:?}", new_dao_bulla.inner());
+
+    // The DAO tree was rebuilt without the old DAO bulla, so the old DAO
+    // can't make proposals anymore. The roots it was in aren't accepted.
+    let proposal = dao_client::ProposalInfo {
+        calls: vec![],
+        creation_slot: deadline,
+        duration: 5,
+        blind: pallas::Base::random(&mut OsRng),
+        user_data_blind: pallas::Base::random(&mut OsRng),
+    };
+
+    let (money_leaf_position, money_merkle_path) = {
+        let tree = &cache.tree;
+        let leaf_position = gov_recv[0].leaf_position;
+        let root = tree.root(0).unwrap();
+        let merkle_path = tree.authentication_path(leaf_position, &root).unwrap();
+        (leaf_position, merkle_path)
+    };
+
+    let signature_secret = SecretKey::random(&mut OsRng);
+    let input = dao_client::ProposeStakeInput {
+        secret: dao_th.alice_kp.secret,
+        note: gov_recv[0].note.clone(),
+        leaf_position: money_leaf_position,
+        merkle_path: money_merkle_path,
+        signature_secret,
+    };
+
+    let (dao_merkle_path, dao_merkle_root) = {
+        let tree = &dao_tree;
+        let root = tree.root(0).unwrap();
+        let merkle_path = tree.authentication_path(dao_leaf_position, &root).unwrap();
+        (merkle_path, root)
+    };
+
+    let call = dao_client::ProposeCall {
+        inputs: vec![input],
+        proposal,
+        dao,
+        dao_leaf_position,
+        dao_merkle_path,
+        dao_merkle_root,
+    };
+    let (params, proofs) = call.make(
+        &dao_th.dao_propose_burn_zkbin,
+        &dao_th.dao_propose_burn_pk,
+        &dao_th.dao_propose_main_zkbin,
+        &dao_th.dao_propose_main_pk,
+    )?;
+
+    let mut data = vec![DaoFunction::Propose as u8];
+    params.encode(&mut data)?;
+    let calls =
+        vec![ContractCall { contract_id: *DAO_CONTRACT_ID, data, gas_limit: DEFAULT_GAS_LIMIT }];
+    let mut tx = Transaction { calls, proofs: vec![proofs], signatures: vec![] };
+    let sigs = tx.create_sigs(&mut OsRng, &vec![signature_secret])?;
+    tx.signatures = vec![sigs];
+
+    assert!(dao_th
+        .alice_state
+        .read()
+        .await
+        .verify_transactions_at_slot(&[tx], deadline, false)
+        .await
+        .is_err());
+
+    // The contract rebuilt the DAO tree from the DAOs which weren't
+    // replaced, which is only the new DAO here
+    let mut dao_tree = MerkleTree::new(100);
+    let new_dao_leaf_position = {
+        let node = MerkleNode::from(new_dao_bulla.inner());
+        dao_tree.append(&node);
+        dao_tree.witness().unwrap()
+    };
+
+    // The new DAO makes proposals using the rebuilt tree
+    let proposal = dao_client::ProposalInfo {
+        calls: vec![],
+        creation_slot: deadline,
+        duration: 5,
+        blind: pallas::Base::random(&mut OsRng),
+        user_data_blind: pallas::Base::random(&mut OsRng),
+    };
+
+    let (money_leaf_position, money_merkle_path) = {
+        let tree = &cache.tree;
+        let leaf_position = gov_recv[0].leaf_position;
+        let root = tree.root(0).unwrap();
+        let merkle_path = tree.authentication_path(leaf_position, &root).unwrap();
+        (leaf_position, merkle_path)
+    };
+
+    let signature_secret = SecretKey::random(&mut OsRng);
+    let input = dao_client::ProposeStakeInput {
+        secret: dao_th.alice_kp.secret,
+        note: gov_recv[0].note.clone(),
+        leaf_position: money_leaf_position,
+        merkle_path: money_merkle_path,
+        signature_secret,
+    };
+
+    let (dao_merkle_path, dao_merkle_root) = {
+        let tree = &dao_tree;
+        let root = tree.root(0).unwrap();
+        let merkle_path = tree.authentication_path(new_dao_leaf_position, &root).unwrap();
+        (merkle_path, root)
+    };
+
+    let call = dao_client::ProposeCall {
+        inputs: vec![input],
+        proposal,
+        dao: new_dao,
+        dao_leaf_position: new_dao_leaf_position,
+        dao_merkle_path,
+        dao_merkle_root,
+    };
+    let (params, proofs) = call.make(
+        &dao_th.dao_propose_burn_zkbin,
+        &dao_th.dao_propose_burn_pk,
+        &dao_th.dao_propose_main_zkbin,
+        &dao_th.dao_propose_main_pk,
+    )?;
+
+    let mut data = vec![DaoFunction::Propose as u8];
+    params.encode(&mut data)?;
+    let calls =
+        vec![ContractCall { contract_id: *DAO_CONTRACT_ID, data, gas_limit: DEFAULT_GAS_LIMIT }];
+    let mut tx = Transaction { calls, proofs: vec![proofs], signatures: vec![] };
+    let sigs = tx.create_sigs(&mut OsRng, &vec![signature_secret])?;
+    tx.signatures = vec![sigs];
+
+    dao_th.alice_state.read().await.verify_transactions_at_slot(&[tx], deadline, false).await?;
 
     Ok(())
 }
@@ -53,7 +53,16 @@
 --       RECV_PUBKEY \
 --       AMOUNT \
 --       SERIAL \
---       SENDCOIN_TOKEN_ID
+--       SENDCOIN_TOKEN_ID \
+--       DURATION
+--
+-- DURATION is the number of slots the proposal can be voted on. Votes
+-- are rejected by the contract once it's over, and the proposal can
+-- only be executed afterwards.
+--
+-- A proposal commits to the contract calls it executes once it passes,
+-- here a payment out of the DAO treasury. DAO::exec() must be the last
+-- call of the executing transaction, right after those exact calls.
 --
 -- If we don't have enough tokens to meet the proposer_limit threshold
 -- then this call will simply fail with an error message. Nothing will
@@ -67,9 +76,9 @@
 -- You have received a proposal which is active. You can now vote on it.
 --
 --   $ drk dao proposals DAO_NAME
---   [0] f6cae63ced53d02b372206a8d3ed5ac03fde18da306a520285fd56e8d031f6cf
---   [1] 1372622f4a38be6eb1c90fa67864474c6603d9f8d4228106e20e2d0d04f2395e
---   [2] 88b18cbc38dbd3af8d25237af3903e985f70ea06d1e25966bf98e3f08e23c992
+--    ID  Calls  Voting ends at slot  Time left
+--    0   1      1210                 8 slots (~0h 12m)
+--    1   2      1195                 Ended
 --
 --   $ drk dao show_proposal 1
 --   Proposal: 1372622f4a38be6eb1c90fa67864474c6603d9f8d4228106e20e2d0d04f2395e
@@ -101,6 +110,23 @@
 --              For now I didn't put anything, but we should keep this minor
 --              point in mind and ruminate on it for later.
 
+-- # DAO::update()
+--
+-- The DAO parameters and keys can be changed through governance. First
+-- the new DAO data is created from the current one, overriding whatever
+-- should change. A new bulla blind is always used.
+--
+--   $ drk dao update --quorum 2000 --rotate-keys < dao.dat > new_dao.dat
+--
+-- A proposal is then made whose calls are DAO::update(), replacing the
+-- DAO with the new one, and the transfers moving the treasury coins to
+-- the new DAO. Once it passes and is executed, the old DAO bulla is
+-- burned and can't be updated again, while the new one is added to the
+-- DAO merkle tree, ready to be used.
+--
+-- DAO::exec() reveals the DAO bulla only when the proposal calls
+-- DAO::update(), so the contract can check the right DAO is replaced.
+
 CREATE TABLE IF NOT EXISTS dao_daos (
 	dao_id INTEGER PRIMARY KEY NOT NULL,
     name BLOB UNIQUE NOT NULL,
@@ -141,21 +167,23 @@ CREATE TABLE IF NOT EXISTS dao_coins (
 CREATE TABLE IF NOT EXISTS dao_proposals (
     proposal_id INTEGER PRIMARY KEY NOT NULL,
     dao_id INTEGER NOT NULL,
-    -- Public key of person that would receive the funds
-    recv_public BLOB NOT NULL,
-    -- Amount of funds that would be sent
-    amount INTEGER NOT NULL,
-    serial BLOB NOT NULL,
-    -- Token ID we propose to send
-    sendcoin_token_id BLOB NOT NULL,
+    -- Serialized contract calls executed once the proposal passes
+    calls BLOB NOT NULL,
+    -- Slot the proposal was created at
+    creation_slot INTEGER NOT NULL,
+    -- Number of slots the proposal can be voted on, after which
+    -- voting ends and it can be executed
+    duration INTEGER NOT NULL,
     bulla_blind BLOB NOT NULL,
+    -- Blind encrypting the user data of the DAO coins the calls spend
+    user_data_blind BLOB NOT NULL,
     -- these values are NULL until the proposal is minted on chain
     -- and received by the DAO
 	leaf_position BLOB,
     tx_hash BLOB,
-    call_index INTEGER
+    call_index INTEGER,
     -- this is NULL until we have voted on this proposal
-    our_vote_id INTEGER UNIQUE,
+    our_vote_id INTEGER UNIQUE
 );
 
 CREATE TABLE IF NOT EXISTS dao_votes (
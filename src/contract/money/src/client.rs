@@ -39,6 +39,7 @@ use darkfi_sdk::crypto::{
     pasta_prelude::*,
     pedersen_commitment_base, pedersen_commitment_u64, poseidon_hash, Keypair, MerkleNode,
     MerklePosition, MerkleTree, Nullifier, PublicKey, SecretKey, TokenId, ValueBlind, ValueCommit,
+    DARK_TOKEN_ID,
 };
 use darkfi_serial::{serialize, Decodable, Encodable, SerialDecodable, SerialEncodable};
 use halo2_proofs::circuit::Value;
@@ -46,8 +47,8 @@ use log::{debug, error, info};
 use rand::rngs::OsRng;
 
 use crate::model::{
-    ClearInput, Input, MoneyStakeParams, MoneyTransferParams, MoneyUnstakeParams, Output,
-    StakedInput, StakedOutput,
+    ClearInput, Input, MoneyFeeParams, MoneyStakeParams, MoneyTokenMintParams, MoneyTransferParams,
+    MoneyUnstakeParams, Output, StakedInput, StakedOutput,
 };
 
 // Wallet SQL table constant names. These have to represent the SQL schema.
@@ -65,6 +66,13 @@ pub const MONEY_KEYS_COL_IS_DEFAULT: &str = "is_default";
 pub const MONEY_KEYS_COL_PUBLIC: &str = "public";
 pub const MONEY_KEYS_COL_SECRET: &str = "secret";
 
+pub const MONEY_HD_SEED_TABLE: &str = "money_hd_seed";
+pub const MONEY_HD_SEED_COL_SEED: &str = "seed";
+
+pub const MONEY_HD_INDEXES_TABLE: &str = "money_hd_indexes";
+pub const MONEY_HD_INDEXES_COL_PURPOSE: &str = "purpose";
+pub const MONEY_HD_INDEXES_COL_NEXT_INDEX: &str = "next_index";
+
 pub const MONEY_COINS_TABLE: &str = "money_coins";
 pub const MONEY_COINS_COL_COIN: &str = "coin";
 pub const MONEY_COINS_COL_IS_SPENT: &str = "is_spent";
@@ -81,6 +89,11 @@ pub const MONEY_COINS_COL_NULLIFIER: &str = "nullifier";
 pub const MONEY_COINS_COL_LEAF_POSITION: &str = "leaf_position";
 pub const MONEY_COINS_COL_MEMO: &str = "memo";
 
+pub const MONEY_TOKENS_TABLE: &str = "money_tokens";
+pub const MONEY_TOKENS_COL_MINT_AUTHORITY: &str = "mint_authority";
+pub const MONEY_TOKENS_COL_TOKEN_ID: &str = "token_id";
+pub const MONEY_TOKENS_COL_IS_FROZEN: &str = "is_frozen";
+
 /// Byte length of the AEAD tag of the chacha20 cipher used for note encryption
 pub const AEAD_TAG_SIZE: usize = 16;
 
@@ -479,6 +492,128 @@ pub fn create_transfer_burn_proof(
     Ok((proof, revealed))
 }
 
+pub struct TokenMintRevealed {
+    pub mint_public: PublicKey,
+    pub token_id: TokenId,
+    pub fixed_supply: bool,
+    pub coin: Coin,
+    pub value_commit: ValueCommit,
+    pub token_commit: ValueCommit,
+}
+
+impl TokenMintRevealed {
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute(
+        mint_authority: SecretKey,
+        supply: u64,
+        fixed_supply: bool,
+        value_blind: ValueBlind,
+        token_blind: ValueBlind,
+        serial: pallas::Base,
+        spend_hook: pallas::Base,
+        user_data: pallas::Base,
+        coin_blind: pallas::Base,
+        public_key: PublicKey,
+    ) -> Self {
+        let mint_public = PublicKey::from_secret(mint_authority);
+        let token_id = TokenId::derive_public(mint_public);
+
+        // The minted coin is the same as any other coin, so it can be
+        // spent with the regular burn proof.
+        let transfer = TransferMintRevealed::compute(
+            supply,
+            token_id,
+            value_blind,
+            token_blind,
+            serial,
+            spend_hook,
+            user_data,
+            coin_blind,
+            public_key,
+        );
+
+        Self {
+            mint_public,
+            token_id,
+            fixed_supply,
+            coin: transfer.coin,
+            value_commit: transfer.value_commit,
+            token_commit: transfer.token_commit,
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<pallas::Base> {
+        let (mint_x, mint_y) = self.mint_public.xy();
+        let valcom_coords = self.value_commit.to_affine().coordinates().unwrap();
+        let tokcom_coords = self.token_commit.to_affine().coordinates().unwrap();
+
+        // NOTE: It's important to keep this order the same as the `constrain_instance`
+        //       calls in the zkas code.
+        vec![
+            mint_x,
+            mint_y,
+            self.token_id.inner(),
+            pallas::Base::from(self.fixed_supply as u64),
+            self.coin.inner(),
+            *valcom_coords.x(),
+            *valcom_coords.y(),
+            *tokcom_coords.x(),
+            *tokcom_coords.y(),
+        ]
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_token_mint_proof(
+    zkbin: &ZkBinary,
+    pk: &ProvingKey,
+    mint_authority: SecretKey,
+    supply: u64,
+    fixed_supply: bool,
+    value_blind: ValueBlind,
+    token_blind: ValueBlind,
+    serial: pallas::Base,
+    spend_hook: pallas::Base,
+    user_data: pallas::Base,
+    coin_blind: pallas::Base,
+    public_key: PublicKey,
+) -> Result<(Proof, TokenMintRevealed)> {
+    let revealed = TokenMintRevealed::compute(
+        mint_authority,
+        supply,
+        fixed_supply,
+        value_blind,
+        token_blind,
+        serial,
+        spend_hook,
+        user_data,
+        coin_blind,
+        public_key,
+    );
+
+    let (rcpt_x, rcpt_y) = public_key.xy();
+
+    // NOTE: It's important to keep these in the same order as the zkas code.
+    let prover_witnesses = vec![
+        Witness::Base(Value::known(mint_authority.inner())),
+        Witness::Base(Value::known(pallas::Base::from(supply))),
+        Witness::Base(Value::known(pallas::Base::from(fixed_supply as u64))),
+        Witness::Base(Value::known(rcpt_x)),
+        Witness::Base(Value::known(rcpt_y)),
+        Witness::Base(Value::known(serial)),
+        Witness::Base(Value::known(coin_blind)),
+        Witness::Base(Value::known(spend_hook)),
+        Witness::Base(Value::known(user_data)),
+        Witness::Scalar(Value::known(value_blind)),
+        Witness::Scalar(Value::known(token_blind)),
+    ];
+
+    let circuit = ZkCircuit::new(prover_witnesses, zkbin.clone());
+    let proof = Proof::create(pk, &[circuit], &revealed.to_vec(), &mut OsRng)?;
+
+    Ok((proof, revealed))
+}
+
 struct StakeLeadMintRevealed {
     pub value_commit: ValueCommit,
     pub pk: pallas::Base,
@@ -1261,6 +1396,209 @@ pub fn build_unstake_tx(
     Ok((params, proofs, vec![], lead_blinds, own_blinds))
 }
 
+/// Build money contract fee call parameters with the given data:
+/// * `keypair` - Caller's keypair, which receives the change
+/// * `fee` - Fee value to pay
+/// * `coin` - Coin in the native token to pay the fee with
+/// * `tree` - Current Merkle tree of coins
+/// * `mint_zkbin` - ZkBinary of the mint circuit
+/// * `mint_pk` - Proving key for the ZK mint proof
+/// * `burn_zkbin` - ZkBinary of the burn circuit
+/// * `burn_pk` - Proving key for the ZK burn proof
+///
+/// The call is meant to be appended to the calls of the transaction
+/// paying for it.
+#[allow(clippy::too_many_arguments)]
+pub fn build_fee_tx(
+    keypair: &Keypair,
+    fee: u64,
+    coin: &OwnCoin,
+    tree: &MerkleTree,
+    mint_zkbin: &ZkBinary,
+    mint_pk: &ProvingKey,
+    burn_zkbin: &ZkBinary,
+    burn_pk: &ProvingKey,
+) -> Result<(MoneyFeeParams, Vec<Proof>, Vec<SecretKey>)> {
+    debug!(target: "money", "Building money contract fee transaction");
+    if fee == 0 {
+        return Err(ClientFailed::InvalidAmount(fee).into())
+    }
+
+    if coin.note.token_id != *DARK_TOKEN_ID {
+        error!(target: "money", "Money::build_fee_tx(): Fee coin is not in the native token");
+        return Err(ClientFailed::InternalError("Fee coin is not in the native token".into()).into())
+    }
+
+    if coin.note.value < fee {
+        error!(target: "money", "Money::build_fee_tx(): Not enough value to pay the fee");
+        return Err(ClientFailed::NotEnoughValue(coin.note.value).into())
+    }
+
+    let root = tree.root(0).unwrap();
+    let merkle_path = tree.authentication_path(coin.leaf_position, &root).unwrap();
+
+    // The fee value blind is revealed, and balances the value commitments
+    // of the input and the change output.
+    let token_blind = ValueBlind::random(&mut OsRng);
+    let input_value_blind = ValueBlind::random(&mut OsRng);
+    let output_value_blind = ValueBlind::random(&mut OsRng);
+    let fee_value_blind = input_value_blind - output_value_blind;
+
+    let signature_secret = SecretKey::random(&mut OsRng);
+
+    // Coins bound to other contracts can't pay fees
+    let spend_hook = pallas::Base::zero();
+    let user_data = pallas::Base::zero();
+    let user_data_blind = pallas::Base::random(&mut OsRng);
+
+    info!(target: "money", "Creating fee burn proof for input");
+    let (burn_proof, burn_revealed) = create_transfer_burn_proof(
+        burn_zkbin,
+        burn_pk,
+        coin.note.value,
+        coin.note.token_id,
+        input_value_blind,
+        token_blind,
+        coin.note.serial,
+        spend_hook,
+        user_data,
+        user_data_blind,
+        coin.note.coin_blind,
+        coin.secret,
+        coin.leaf_position,
+        merkle_path,
+        signature_secret,
+    )?;
+
+    let change = coin.note.value - fee;
+    let serial = pallas::Base::random(&mut OsRng);
+    let coin_blind = pallas::Base::random(&mut OsRng);
+
+    info!(target: "money", "Creating fee mint proof for change output");
+    let (mint_proof, mint_revealed) = create_transfer_mint_proof(
+        mint_zkbin,
+        mint_pk,
+        change,
+        *DARK_TOKEN_ID,
+        output_value_blind,
+        token_blind,
+        serial,
+        spend_hook,
+        user_data,
+        coin_blind,
+        keypair.public,
+    )?;
+
+    let note = Note {
+        serial,
+        value: change,
+        token_id: *DARK_TOKEN_ID,
+        spend_hook,
+        user_data,
+        coin_blind,
+        value_blind: output_value_blind,
+        token_blind,
+        memo: vec![],
+    };
+
+    let encrypted_note = note.encrypt(&keypair.public)?;
+
+    let params = MoneyFeeParams {
+        input: Input {
+            value_commit: burn_revealed.value_commit,
+            token_commit: burn_revealed.token_commit,
+            nullifier: burn_revealed.nullifier,
+            merkle_root: burn_revealed.merkle_root,
+            spend_hook: burn_revealed.spend_hook,
+            user_data_enc: burn_revealed.user_data_enc,
+            signature_public: burn_revealed.signature_public,
+        },
+        output: Output {
+            value_commit: mint_revealed.value_commit,
+            token_commit: mint_revealed.token_commit,
+            coin: mint_revealed.coin.inner(),
+            ciphertext: encrypted_note.ciphertext,
+            ephem_public: encrypted_note.ephem_public,
+        },
+        fee_value: fee,
+        fee_value_blind,
+        token_blind,
+    };
+
+    Ok((params, vec![burn_proof, mint_proof], vec![signature_secret]))
+}
+
+/// Build a money contract transaction minting `amount` of the token
+/// derived from `mint_authority` to `recipient`. If `fixed_supply` is
+/// set, the token can't be minted anymore afterwards.
+#[allow(clippy::too_many_arguments)]
+pub fn build_token_mint_tx(
+    mint_authority: &Keypair,
+    amount: u64,
+    recipient: PublicKey,
+    fixed_supply: bool,
+    token_mint_zkbin: &ZkBinary,
+    token_mint_pk: &ProvingKey,
+) -> Result<(MoneyTokenMintParams, Vec<Proof>, Vec<SecretKey>)> {
+    debug!(target: "money", "Building money contract token mint transaction");
+    if amount == 0 {
+        return Err(ClientFailed::InvalidAmount(amount).into())
+    }
+
+    let value_blind = ValueBlind::random(&mut OsRng);
+    let token_blind = ValueBlind::random(&mut OsRng);
+    let serial = pallas::Base::random(&mut OsRng);
+    let coin_blind = pallas::Base::random(&mut OsRng);
+
+    // Minted coins are not bound to any contract
+    let spend_hook = pallas::Base::zero();
+    let user_data = pallas::Base::zero();
+
+    info!(target: "money", "Creating token mint proof for output");
+    let (proof, revealed) = create_token_mint_proof(
+        token_mint_zkbin,
+        token_mint_pk,
+        mint_authority.secret,
+        amount,
+        fixed_supply,
+        value_blind,
+        token_blind,
+        serial,
+        spend_hook,
+        user_data,
+        coin_blind,
+        recipient,
+    )?;
+
+    let note = Note {
+        serial,
+        value: amount,
+        token_id: revealed.token_id,
+        spend_hook,
+        user_data,
+        coin_blind,
+        value_blind,
+        token_blind,
+        memo: vec![],
+    };
+
+    let encrypted_note = note.encrypt(&recipient)?;
+
+    let params = MoneyTokenMintParams {
+        mint_public: mint_authority.public,
+        fixed_supply,
+        output: Output {
+            value_commit: revealed.value_commit,
+            token_commit: revealed.token_commit,
+            coin: revealed.coin.inner(),
+            ciphertext: encrypted_note.ciphertext,
+            ephem_public: encrypted_note.ephem_public,
+        },
+    };
+
+    Ok((params, vec![proof], vec![mint_authority.secret]))
+}
+
 fn compute_remainder_blind(
     clear_inputs: &[ClearInput],
     input_blinds: &[ValueBlind],
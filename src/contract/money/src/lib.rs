@@ -19,11 +19,12 @@
 #[cfg(not(feature = "no-entrypoint"))]
 use darkfi_sdk::{
     crypto::{
-        pallas, pasta_prelude::*, pedersen_commitment_base, pedersen_commitment_u64, Coin,
-        ContractId, MerkleNode, MerkleTree, PublicKey, DARK_TOKEN_ID,
+        pallas, pasta_prelude::*, pedersen_commitment_base, pedersen_commitment_u64, poseidon_hash,
+        Coin, ContractId, MerkleNode, MerkleTree, PublicKey, TokenId, DARK_TOKEN_ID,
     },
     db::{db_contains_key, db_get, db_init, db_lookup, db_set, SMART_CONTRACT_ZKAS_DB_NAME},
     error::ContractResult,
+    fee::FeeClaimParamsV1,
     merkle::merkle_add,
     msg, set_return_data, ContractCall,
 };
@@ -41,6 +42,10 @@ pub enum MoneyFunction {
     Stake = 0x02,
     Unstake = 0x03,
     Mint = 0x04,
+    Fee = 0x05,
+    // Has to match `darkfi_sdk::fee::MONEY_CONTRACT_FEE_CLAIM`
+    FeeClaim = 0x06,
+    Freeze = 0x07,
 }
 
 impl TryFrom<u8> for MoneyFunction {
@@ -53,6 +58,9 @@ impl TryFrom<u8> for MoneyFunction {
             0x02 => Ok(Self::Stake),
             0x03 => Ok(Self::Unstake),
             0x04 => Ok(Self::Mint),
+            0x05 => Ok(Self::Fee),
+            0x06 => Ok(Self::FeeClaim),
+            0x07 => Ok(Self::Freeze),
             _ => Err(ContractError::InvalidFunction),
         }
     }
@@ -63,8 +71,9 @@ pub mod model;
 
 #[cfg(not(feature = "no-entrypoint"))]
 use model::{
-    MoneyStakeParams, MoneyStakeUpdate, MoneyTransferParams, MoneyTransferUpdate,
-    MoneyUnstakeParams,
+    MoneyFeeParams, MoneyStakeParams, MoneyStakeUpdate, MoneyTokenFreezeParams,
+    MoneyTokenFreezeUpdate, MoneyTokenMintParams, MoneyTokenMintUpdate, MoneyTransferParams,
+    MoneyTransferUpdate, MoneyUnstakeParams,
 };
 
 #[cfg(feature = "client")]
@@ -209,6 +218,8 @@ fn get_metadata(_cid: ContractId, ix: &[u8]) -> ContractResult {
 
             let mut zk_public_values: Vec<(String, Vec<pallas::Base>)> = vec![];
             let mut signature_pubkeys: Vec<PublicKey> = vec![];
+            // Nullifiers and coins can't be claimed by more than one transaction
+            let mut conflict_keys: Vec<pallas::Base> = vec![];
 
             for input in &params.clear_inputs {
                 signature_pubkeys.push(input.signature_public);
@@ -235,6 +246,7 @@ fn get_metadata(_cid: ContractId, ix: &[u8]) -> ContractResult {
                 ));
 
                 signature_pubkeys.push(input.signature_public);
+                conflict_keys.push(input.nullifier.inner());
             }
 
             for output in &params.outputs {
@@ -251,11 +263,14 @@ fn get_metadata(_cid: ContractId, ix: &[u8]) -> ContractResult {
                         *token_coords.y(),
                     ],
                 ));
+
+                conflict_keys.push(output.coin);
             }
 
             let mut metadata = vec![];
             zk_public_values.encode(&mut metadata)?;
             signature_pubkeys.encode(&mut metadata)?;
+            conflict_keys.encode(&mut metadata)?;
 
             // Using this, we pass the above data to the host.
             set_return_data(&metadata)?;
@@ -267,6 +282,8 @@ fn get_metadata(_cid: ContractId, ix: &[u8]) -> ContractResult {
 
             let mut zk_public_values: Vec<(String, Vec<pallas::Base>)> = vec![];
             let mut signature_pubkeys: Vec<PublicKey> = vec![];
+            // Nullifiers can't be claimed by more than one transaction
+            let mut conflict_keys: Vec<pallas::Base> = vec![];
 
             for input in &params.inputs {
                 let value_coords = input.value_commit.to_affine().coordinates().unwrap();
@@ -289,6 +306,7 @@ fn get_metadata(_cid: ContractId, ix: &[u8]) -> ContractResult {
                 ));
 
                 signature_pubkeys.push(input.signature_public);
+                conflict_keys.push(input.nullifier.inner());
             }
 
             for output in &params.outputs {
@@ -308,6 +326,7 @@ fn get_metadata(_cid: ContractId, ix: &[u8]) -> ContractResult {
             let mut metadata = vec![];
             zk_public_values.encode(&mut metadata)?;
             signature_pubkeys.encode(&mut metadata)?;
+            conflict_keys.encode(&mut metadata)?;
 
             // Using this, we pass the above data to the host.
             set_return_data(&metadata)?;
@@ -318,6 +337,9 @@ fn get_metadata(_cid: ContractId, ix: &[u8]) -> ContractResult {
             let params: MoneyUnstakeParams = deserialize(&self_.data[1..])?;
 
             let mut zk_public_values: Vec<(String, Vec<pallas::Base>)> = vec![];
+            let signature_pubkeys: Vec<PublicKey> = vec![];
+            // Nullifiers and coins can't be claimed by more than one transaction
+            let mut conflict_keys: Vec<pallas::Base> = vec![];
 
             for input in &params.inputs {
                 let value_coords = input.value_commit.to_affine().coordinates().unwrap();
@@ -333,6 +355,8 @@ fn get_metadata(_cid: ContractId, ix: &[u8]) -> ContractResult {
                         input.nullifier.inner(),
                     ],
                 ));
+
+                conflict_keys.push(input.nullifier.inner());
             }
 
             for output in &params.outputs {
@@ -349,17 +373,154 @@ fn get_metadata(_cid: ContractId, ix: &[u8]) -> ContractResult {
                         *token_coords.y(),
                     ],
                 ));
+
+                conflict_keys.push(output.coin);
             }
+
             let mut metadata = vec![];
             zk_public_values.encode(&mut metadata)?;
+            signature_pubkeys.encode(&mut metadata)?;
+            conflict_keys.encode(&mut metadata)?;
 
             // Using this, we pass the above data to the host.
             set_return_data(&metadata)?;
             Ok(())
         }
         MoneyFunction::Mint => {
-            msg!("[Mint] Entered match arm");
-            unimplemented!();
+            let params: MoneyTokenMintParams = deserialize(&self_.data[1..])?;
+            let output = &params.output;
+
+            let (mint_x, mint_y) = params.mint_public.xy();
+            let token_id = TokenId::derive_public(params.mint_public);
+            let value_coords = output.value_commit.to_affine().coordinates().unwrap();
+            let token_coords = output.token_commit.to_affine().coordinates().unwrap();
+
+            let zk_public_values: Vec<(String, Vec<pallas::Base>)> = vec![(
+                MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1.to_string(),
+                vec![
+                    mint_x,
+                    mint_y,
+                    token_id.inner(),
+                    pallas::Base::from(params.fixed_supply as u64),
+                    output.coin,
+                    *value_coords.x(),
+                    *value_coords.y(),
+                    *token_coords.x(),
+                    *token_coords.y(),
+                ],
+            )];
+
+            let signature_pubkeys: Vec<PublicKey> = vec![params.mint_public];
+            // A token can only be minted once per block, so a mint can't slip
+            // in next to another one freezing the supply.
+            let conflict_keys: Vec<pallas::Base> = vec![token_id.inner(), output.coin];
+
+            let mut metadata = vec![];
+            zk_public_values.encode(&mut metadata)?;
+            signature_pubkeys.encode(&mut metadata)?;
+            conflict_keys.encode(&mut metadata)?;
+
+            // Using this, we pass the above data to the host.
+            set_return_data(&metadata)?;
+            Ok(())
+        }
+
+        MoneyFunction::Freeze => {
+            let params: MoneyTokenFreezeParams = deserialize(&self_.data[1..])?;
+
+            let zk_public_values: Vec<(String, Vec<pallas::Base>)> = vec![];
+            let signature_pubkeys: Vec<PublicKey> = vec![params.mint_public];
+            let conflict_keys: Vec<pallas::Base> =
+                vec![TokenId::derive_public(params.mint_public).inner()];
+
+            let mut metadata = vec![];
+            zk_public_values.encode(&mut metadata)?;
+            signature_pubkeys.encode(&mut metadata)?;
+            conflict_keys.encode(&mut metadata)?;
+
+            // Using this, we pass the above data to the host.
+            set_return_data(&metadata)?;
+            Ok(())
+        }
+
+        MoneyFunction::Fee => {
+            let params: MoneyFeeParams = deserialize(&self_.data[1..])?;
+            let input = &params.input;
+            let output = &params.output;
+
+            let mut zk_public_values: Vec<(String, Vec<pallas::Base>)> = vec![];
+
+            let value_coords = input.value_commit.to_affine().coordinates().unwrap();
+            let token_coords = input.token_commit.to_affine().coordinates().unwrap();
+            let (sig_x, sig_y) = input.signature_public.xy();
+
+            zk_public_values.push((
+                MONEY_CONTRACT_ZKAS_BURN_NS_V1.to_string(),
+                vec![
+                    input.nullifier.inner(),
+                    *value_coords.x(),
+                    *value_coords.y(),
+                    *token_coords.x(),
+                    *token_coords.y(),
+                    input.merkle_root.inner(),
+                    input.user_data_enc,
+                    sig_x,
+                    sig_y,
+                ],
+            ));
+
+            let value_coords = output.value_commit.to_affine().coordinates().unwrap();
+            let token_coords = output.token_commit.to_affine().coordinates().unwrap();
+
+            zk_public_values.push((
+                MONEY_CONTRACT_ZKAS_MINT_NS_V1.to_string(),
+                vec![
+                    output.coin,
+                    *value_coords.x(),
+                    *value_coords.y(),
+                    *token_coords.x(),
+                    *token_coords.y(),
+                ],
+            ));
+
+            let signature_pubkeys: Vec<PublicKey> = vec![input.signature_public];
+            // Nullifiers and coins can't be claimed by more than one transaction
+            let conflict_keys: Vec<pallas::Base> = vec![input.nullifier.inner(), output.coin];
+            // The fee paid and claimed by this call. The validator uses these
+            // to prioritize transactions, and to bound the block proposer's claim.
+            let fees: (u64, u64) = (params.fee_value, 0);
+
+            let mut metadata = vec![];
+            zk_public_values.encode(&mut metadata)?;
+            signature_pubkeys.encode(&mut metadata)?;
+            conflict_keys.encode(&mut metadata)?;
+            fees.encode(&mut metadata)?;
+
+            // Using this, we pass the above data to the host.
+            set_return_data(&metadata)?;
+            Ok(())
+        }
+
+        MoneyFunction::FeeClaim => {
+            let params: FeeClaimParamsV1 = deserialize(&self_.data[1..])?;
+
+            // The claimed coin is public, so there is nothing to prove. The
+            // call is not signed either, as it's only valid inside a block
+            // proposal, which is signed by the proposer.
+            let zk_public_values: Vec<(String, Vec<pallas::Base>)> = vec![];
+            let signature_pubkeys: Vec<PublicKey> = vec![];
+            let conflict_keys: Vec<pallas::Base> = vec![fee_claim_coin(&params)];
+            let fees: (u64, u64) = (0, params.value);
+
+            let mut metadata = vec![];
+            zk_public_values.encode(&mut metadata)?;
+            signature_pubkeys.encode(&mut metadata)?;
+            conflict_keys.encode(&mut metadata)?;
+            fees.encode(&mut metadata)?;
+
+            // Using this, we pass the above data to the host.
+            set_return_data(&metadata)?;
+            Ok(())
         }
     }
 }
@@ -743,7 +904,138 @@ fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
 
         MoneyFunction::Mint => {
             msg!("[Mint] Entered match arm");
-            unimplemented!();
+            let params: MoneyTokenMintParams = deserialize(&self_.data[1..])?;
+
+            // The ZK proof and the signature make sure the token ID belongs
+            // to the mint authority, so we only have to check the supply.
+            let token_id = TokenId::derive_public(params.mint_public);
+            let fixed_supply_db = db_lookup(cid, MONEY_CONTRACT_FIXED_SUPPLY_TREE)?;
+            if db_contains_key(fixed_supply_db, &serialize(&token_id))? {
+                msg!("[Mint] Error: Token {} has a fixed supply", token_id);
+                return Err(ContractError::Custom(29))
+            }
+
+            // Create a state update
+            let update = MoneyTokenMintUpdate {
+                coin: Coin::from(params.output.coin),
+                token_id,
+                fixed_supply: params.fixed_supply,
+            };
+            let mut update_data = vec![];
+            update_data.write_u8(MoneyFunction::Mint as u8)?;
+            update.encode(&mut update_data)?;
+            set_return_data(&update_data)?;
+            msg!("[Mint] State update set!");
+
+            Ok(())
+        }
+
+        MoneyFunction::Freeze => {
+            msg!("[Freeze] Entered match arm");
+            let params: MoneyTokenFreezeParams = deserialize(&self_.data[1..])?;
+
+            let token_id = TokenId::derive_public(params.mint_public);
+            let fixed_supply_db = db_lookup(cid, MONEY_CONTRACT_FIXED_SUPPLY_TREE)?;
+            if db_contains_key(fixed_supply_db, &serialize(&token_id))? {
+                msg!("[Freeze] Error: Token {} has a fixed supply", token_id);
+                return Err(ContractError::Custom(29))
+            }
+
+            // Create a state update
+            let update = MoneyTokenFreezeUpdate { token_id };
+            let mut update_data = vec![];
+            update_data.write_u8(MoneyFunction::Freeze as u8)?;
+            update.encode(&mut update_data)?;
+            set_return_data(&update_data)?;
+            msg!("[Freeze] State update set!");
+
+            Ok(())
+        }
+
+        MoneyFunction::Fee => {
+            msg!("[Fee] Entered match arm");
+            let params: MoneyFeeParams = deserialize(&self_.data[1..])?;
+            let input = &params.input;
+            let output = &params.output;
+
+            if params.fee_value == 0 {
+                msg!("[Fee] Error: Fee value is zero");
+                return Err(ContractError::Custom(27))
+            }
+
+            // Fees can't be paid with coins bound to another contract
+            if !bool::from(input.spend_hook.is_zero()) {
+                msg!("[Fee] Error: Input has a spend hook set");
+                return Err(ContractError::Custom(28))
+            }
+
+            // Fees are paid in the native token
+            let tokcom = pedersen_commitment_base(DARK_TOKEN_ID.inner(), params.token_blind);
+            if input.token_commit != tokcom || output.token_commit != tokcom {
+                msg!("[Fee] Error: Tried to pay fee with non-native token");
+                return Err(ContractError::Custom(25))
+            }
+
+            // The change and the revealed fee have to add up to the input value
+            let fee_commit = pedersen_commitment_u64(params.fee_value, params.fee_value_blind);
+            if input.value_commit != output.value_commit + fee_commit {
+                msg!("[Fee] Error: Value commitments do not balance");
+                return Err(ContractError::Custom(26))
+            }
+
+            let nullifiers_db = db_lookup(cid, MONEY_CONTRACT_NULLIFIERS_TREE)?;
+            let coin_roots_db = db_lookup(cid, MONEY_CONTRACT_COIN_ROOTS_TREE)?;
+
+            // The Merkle root is used to know whether this is a coin that existed
+            // in a previous state.
+            if !db_contains_key(coin_roots_db, &serialize(&input.merkle_root))? {
+                msg!("[Fee] Error: Merkle root not found in previous state");
+                return Err(ContractError::Custom(21))
+            }
+
+            // The nullifier should not already exist. It is the double-spend protection.
+            if db_contains_key(nullifiers_db, &serialize(&input.nullifier))? {
+                msg!("[Fee] Error: Duplicate nullifier found");
+                return Err(ContractError::Custom(22))
+            }
+
+            // Create a state update. The change output is added like any other coin.
+            let update = MoneyTransferUpdate {
+                nullifiers: vec![input.nullifier],
+                coins: vec![Coin::from(output.coin)],
+            };
+            let mut update_data = vec![];
+            update_data.write_u8(MoneyFunction::Fee as u8)?;
+            update.encode(&mut update_data)?;
+            set_return_data(&update_data)?;
+            msg!("[Fee] State update set!");
+
+            Ok(())
+        }
+
+        MoneyFunction::FeeClaim => {
+            msg!("[FeeClaim] Entered match arm");
+            let params: FeeClaimParamsV1 = deserialize(&self_.data[1..])?;
+
+            // The validator makes sure the claimed value doesn't exceed the
+            // fees paid in the block, so there's not much to check here.
+            if params.value == 0 {
+                msg!("[FeeClaim] Error: Claimed value is zero");
+                return Err(ContractError::Custom(27))
+            }
+
+            // Create a state update
+            let update = MoneyTransferUpdate {
+                nullifiers: vec![],
+                coins: vec![Coin::from(fee_claim_coin(&params))],
+            };
+            let mut update_data = vec![];
+            update_data.write_u8(MoneyFunction::FeeClaim as u8)?;
+            update.encode(&mut update_data)?;
+            set_return_data(&update_data)?;
+            msg!("[FeeClaim] State update set!");
+
+            Ok(())
         }
     }
 }
@@ -751,7 +1043,10 @@ fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
 #[cfg(not(feature = "no-entrypoint"))]
 fn process_update(cid: ContractId, update_data: &[u8]) -> ContractResult {
     match MoneyFunction::try_from(update_data[0])? {
-        MoneyFunction::Transfer | MoneyFunction::OtcSwap => {
+        MoneyFunction::Transfer |
+        MoneyFunction::OtcSwap |
+        MoneyFunction::Fee |
+        MoneyFunction::FeeClaim => {
             let update: MoneyTransferUpdate = deserialize(&update_data[1..])?;
 
             let info_db = db_lookup(cid, MONEY_CONTRACT_INFO_TREE)?;
@@ -798,8 +1093,53 @@ fn process_update(cid: ContractId, update_data: &[u8]) -> ContractResult {
         }
 
         MoneyFunction::Mint => {
-            msg!("[Mint] Entered match arm");
-            unimplemented!();
+            let update: MoneyTokenMintUpdate = deserialize(&update_data[1..])?;
+
+            let info_db = db_lookup(cid, MONEY_CONTRACT_INFO_TREE)?;
+            let coin_roots_db = db_lookup(cid, MONEY_CONTRACT_COIN_ROOTS_TREE)?;
+
+            if update.fixed_supply {
+                msg!("Freezing supply of token {}", update.token_id);
+                let fixed_supply_db = db_lookup(cid, MONEY_CONTRACT_FIXED_SUPPLY_TREE)?;
+                db_set(fixed_supply_db, &serialize(&update.token_id), &[])?;
+            }
+
+            msg!("Adding coin {:?} to Merkle tree", update.coin);
+            merkle_add(
+                info_db,
+                coin_roots_db,
+                &serialize(&MONEY_CONTRACT_COIN_MERKLE_TREE),
+                &[MerkleNode::from(update.coin.inner())],
+            )?;
+
+            Ok(())
+        }
+
+        MoneyFunction::Freeze => {
+            let update: MoneyTokenFreezeUpdate = deserialize(&update_data[1..])?;
+
+            msg!("Freezing supply of token {}", update.token_id);
+            let fixed_supply_db = db_lookup(cid, MONEY_CONTRACT_FIXED_SUPPLY_TREE)?;
+            db_set(fixed_supply_db, &serialize(&update.token_id), &[])?;
+
+            Ok(())
         }
     }
 }
+
+/// Compute the coin minted by a fee claim. It's a regular coin in the
+/// native token, without a spend hook or user data.
+#[cfg(not(feature = "no-entrypoint"))]
+fn fee_claim_coin(params: &FeeClaimParamsV1) -> pallas::Base {
+    let (pub_x, pub_y) = params.public_key.xy();
+    poseidon_hash([
+        pub_x,
+        pub_y,
+        pallas::Base::from(params.value),
+        DARK_TOKEN_ID.inner(),
+        params.serial,
+        pallas::Base::zero(),
+        pallas::Base::zero(),
+        params.coin_blind,
+    ])
+}
@@ -82,6 +82,42 @@ pub struct MoneyTransferParams {
     pub outputs: Vec<Output>,
 }
 
+/// Input and change output for paying a transaction fee in the native token.
+/// The fee value is revealed, and has to balance the value commitments.
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct MoneyFeeParams {
+    /// Anonymous input paying for the fee
+    pub input: Input,
+    /// Anonymous output holding the change
+    pub output: Output,
+    /// Revealed fee value
+    pub fee_value: u64,
+    /// Blinding factor for `fee_value`
+    pub fee_value_blind: ValueBlind,
+    /// Token blind to reveal token ID
+    pub token_blind: ValueBlind,
+}
+
+/// Output for minting an arbitrary token. The token ID is derived from
+/// the mint authority's public key, and the call is signed by it.
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct MoneyTokenMintParams {
+    /// Public key of the mint authority
+    pub mint_public: PublicKey,
+    /// Whether this is the token's last mint
+    pub fixed_supply: bool,
+    /// Anonymous output holding the minted supply
+    pub output: Output,
+}
+
+/// Parameters for freezing the supply of a token, so it can't be minted
+/// anymore. The call is signed by the mint authority.
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct MoneyTokenFreezeParams {
+    /// Public key of the mint authority
+    pub mint_public: PublicKey,
+}
+
 /// State update produced by a payment
 #[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
 pub struct MoneyTransferUpdate {
@@ -100,6 +136,24 @@ pub struct MoneyStakeUpdate {
     pub coins: Vec<Coin>,
 }
 
+/// State update produced by a token mint
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct MoneyTokenMintUpdate {
+    /// Minted coin
+    pub coin: Coin,
+    /// Token ID of the minted coin
+    pub token_id: TokenId,
+    /// Whether the token's supply gets frozen
+    pub fixed_supply: bool,
+}
+
+/// State update produced by freezing a token's supply
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct MoneyTokenFreezeUpdate {
+    /// Token ID to freeze
+    pub token_id: TokenId,
+}
+
 /// A transaction's clear input
 #[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
 pub struct ClearInput {
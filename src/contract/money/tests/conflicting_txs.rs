@@ -0,0 +1,121 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{tx::Transaction, Error, Result, VerifyFailed};
+use darkfi_sdk::{
+    crypto::{
+        merkle_prelude::*, pallas, pasta_prelude::*, poseidon_hash, MerkleNode, Nullifier, TokenId,
+    },
+    tx::DEFAULT_GAS_LIMIT,
+    ContractCall,
+};
+use darkfi_serial::Encodable;
+use log::info;
+use rand::rngs::OsRng;
+
+use darkfi_money_contract::{
+    client::{build_transfer_tx, Coin, EncryptedNote, OwnCoin},
+    MoneyFunction,
+};
+
+mod harness;
+use harness::{init_logger, MoneyTestHarness};
+
+#[async_std::test]
+async fn money_contract_conflicting_txs() -> Result<()> {
+    init_logger()?;
+
+    let th = MoneyTestHarness::new().await?;
+    let token_id = TokenId::from(pallas::Base::random(&mut OsRng));
+    let (alice_tx, _) = th.airdrop(100, token_id, &th.alice_kp.public)?;
+    let (bob_tx, _) = th.airdrop(100, token_id, &th.bob_kp.public)?;
+
+    info!(target: "money", "[Faucet] Verifying independent airdrops in a single batch");
+    let txs = [alice_tx.clone(), bob_tx];
+    let gas = th.faucet_state.read().await.verify_transactions(&txs, false).await?;
+    assert_eq!(gas.len(), 2);
+
+    // Both copies are valid on their own, but they create the same coin.
+    info!(target: "money", "[Faucet] Verifying the same airdrop twice in a single batch");
+    let txs = [alice_tx.clone(), alice_tx];
+    let res = th.faucet_state.read().await.verify_transactions(&txs, false).await;
+    assert!(matches!(res, Err(Error::VerifyFailed(VerifyFailed::ConflictingTransaction(_)))));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn money_contract_dependent_txs() -> Result<()> {
+    init_logger()?;
+
+    let mut th = MoneyTestHarness::new().await?;
+    let token_id = TokenId::from(pallas::Base::random(&mut OsRng));
+    let (airdrop_tx, airdrop_params) = th.airdrop(100, token_id, &th.alice_kp.public)?;
+
+    th.alice_merkle_tree.append(&MerkleNode::from(airdrop_params.outputs[0].coin));
+    let leaf_position = th.alice_merkle_tree.witness().unwrap();
+
+    let ciphertext = airdrop_params.outputs[0].ciphertext.clone();
+    let ephem_public = airdrop_params.outputs[0].ephem_public;
+    let note = EncryptedNote { ciphertext, ephem_public }.decrypt(&th.alice_kp.secret)?;
+    let alice_oc = OwnCoin {
+        coin: Coin::from(airdrop_params.outputs[0].coin),
+        note: note.clone(),
+        secret: th.alice_kp.secret,
+        nullifier: Nullifier::from(poseidon_hash([th.alice_kp.secret.inner(), note.serial])),
+        leaf_position,
+    };
+
+    info!(target: "money", "[Alice] Building a payment to Bob spending the airdropped coin");
+    let (params, proofs, secret_keys, _) = build_transfer_tx(
+        &th.alice_kp,
+        &th.bob_kp.public,
+        50,
+        token_id,
+        &[alice_oc],
+        &th.alice_merkle_tree,
+        &th.mint_zkbin,
+        &th.mint_pk,
+        &th.burn_zkbin,
+        &th.burn_pk,
+        false,
+    )?;
+
+    let mut data = vec![MoneyFunction::Transfer as u8];
+    params.encode(&mut data)?;
+    let calls = vec![ContractCall {
+        contract_id: th.money_contract_id,
+        data,
+        gas_limit: DEFAULT_GAS_LIMIT,
+    }];
+    let mut payment_tx = Transaction { calls, proofs: vec![proofs], signatures: vec![] };
+    let sigs = payment_tx.create_sigs(&mut OsRng, &secret_keys)?;
+    payment_tx.signatures = vec![sigs];
+
+    // All transactions of a batch are verified against the state before it,
+    // so the payment can't spend a coin created in the same batch.
+    info!(target: "money", "[Alice] Verifying the airdrop and the payment in a single batch");
+    let txs = [airdrop_tx.clone(), payment_tx.clone()];
+    assert!(th.alice_state.read().await.verify_transactions(&txs, false).await.is_err());
+
+    info!(target: "money", "[Alice] Verifying the airdrop and the payment in separate batches");
+    th.alice_state.read().await.verify_transactions(&[airdrop_tx], true).await?;
+    th.alice_state.read().await.verify_transactions(&[payment_tx], true).await?;
+
+    Ok(())
+}
@@ -0,0 +1,164 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use darkfi::{
+    consensus::{
+        validator::MAX_DEPLOY_CIRCUITS, ValidatorState, ValidatorStatePtr,
+        TESTNET_BOOTSTRAP_TIMESTAMP, TESTNET_EMISSION, TESTNET_GENESIS_HASH_BYTES,
+        TESTNET_GENESIS_TIMESTAMP, TESTNET_INITIAL_DISTRIBUTION,
+    },
+    tx::Transaction,
+    wallet::WalletDb,
+    Error, Result, VerifyFailed,
+};
+use darkfi_sdk::{
+    crypto::{ContractId, Keypair, PublicKey, SecretKey, DEPLOYOOOR_CONTRACT_ID},
+    deploy::DeployParamsV1,
+    tx::DEFAULT_GAS_LIMIT,
+    ContractCall,
+};
+use darkfi_serial::serialize;
+use log::info;
+use rand::rngs::OsRng;
+
+use darkfi_money_contract::{MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1};
+
+mod harness;
+use harness::init_logger;
+
+const WASM_BINCODE: &[u8] = include_bytes!("../money_contract.wasm");
+const ARITH_BINCODE: &[u8] = include_bytes!("../../../../proof/arithmetic.zk.bin");
+const ARITH_NS: &str = "Arith";
+
+fn deploy_tx(
+    deploy_key: SecretKey,
+    zkas_bincodes: Vec<Vec<u8>>,
+    ix: Vec<u8>,
+) -> Result<Transaction> {
+    let params = DeployParamsV1 {
+        wasm_bincode: WASM_BINCODE.to_vec(),
+        zkas_bincodes,
+        public_key: PublicKey::from_secret(deploy_key),
+        ix,
+    };
+
+    let calls = vec![ContractCall {
+        contract_id: *DEPLOYOOOR_CONTRACT_ID,
+        data: serialize(&params),
+        gas_limit: DEFAULT_GAS_LIMIT,
+    }];
+
+    let mut tx = Transaction { calls, proofs: vec![vec![]], signatures: vec![] };
+    let sigs = tx.create_sigs(&mut OsRng, &[deploy_key])?;
+    tx.signatures = vec![sigs];
+    Ok(tx)
+}
+
+async fn new_state(
+    sled_db: &sled::Db,
+    faucet_pubkeys: Vec<PublicKey>,
+) -> Result<ValidatorStatePtr> {
+    let wallet = WalletDb::new("sqlite::memory:", "foo").await?;
+    ValidatorState::new(
+        sled_db,
+        *TESTNET_BOOTSTRAP_TIMESTAMP,
+        *TESTNET_GENESIS_TIMESTAMP,
+        *TESTNET_GENESIS_HASH_BYTES,
+        *TESTNET_INITIAL_DISTRIBUTION,
+        *TESTNET_EMISSION,
+        wallet,
+        faucet_pubkeys,
+        false,
+    )
+    .await
+}
+
+async fn namespaces(state: &ValidatorStatePtr, contract_id: &ContractId) -> Vec<String> {
+    let state = state.read().await;
+    let verifying_keys = state.verifying_keys.read().await;
+    match verifying_keys.get(&contract_id.to_bytes()) {
+        Some(vks) => vks.iter().map(|(ns, _)| ns.clone()).collect(),
+        None => vec![],
+    }
+}
+
+#[async_std::test]
+async fn deploy_upgrade_restart() -> Result<()> {
+    init_logger()?;
+
+    let faucet_pubkeys = vec![Keypair::random(&mut OsRng).public];
+    let ix = serialize(&faucet_pubkeys);
+    let sled_db = sled::Config::new().temporary(true).open()?;
+    let state = new_state(&sled_db, faucet_pubkeys.clone()).await?;
+
+    let deploy_key = SecretKey::random(&mut OsRng);
+    let contract_id = ContractId::derive(deploy_key);
+
+    info!(target: "money", "Deploying contract {} with an extra circuit", contract_id);
+    let tx = deploy_tx(deploy_key, vec![ARITH_BINCODE.to_vec()], ix.clone())?;
+    let gas = state.read().await.verify_transactions(&[tx], true).await?;
+    assert!(gas[0] > 0 && gas[0] <= DEFAULT_GAS_LIMIT);
+    let deployed = namespaces(&state, &contract_id).await;
+    assert!(deployed.contains(&ARITH_NS.to_string()));
+    assert!(deployed.contains(&MONEY_CONTRACT_ZKAS_MINT_NS_V1.to_string()));
+    assert!(deployed.contains(&MONEY_CONTRACT_ZKAS_BURN_NS_V1.to_string()));
+
+    info!(target: "money", "Upgrading contract {} without the extra circuit", contract_id);
+    let tx = deploy_tx(deploy_key, vec![], ix.clone())?;
+    state.read().await.verify_transactions(&[tx], true).await?;
+    let upgraded = namespaces(&state, &contract_id).await;
+    assert!(!upgraded.contains(&ARITH_NS.to_string()));
+    assert!(upgraded.contains(&MONEY_CONTRACT_ZKAS_MINT_NS_V1.to_string()));
+    assert_eq!(upgraded.len(), deployed.len() - 1);
+
+    info!(target: "money", "Restarting the validator on the same database");
+    let restarted = new_state(&sled_db, faucet_pubkeys).await?;
+    let mut restored = namespaces(&restarted, &contract_id).await;
+    let mut upgraded = upgraded;
+    restored.sort();
+    upgraded.sort();
+    assert_eq!(restored, upgraded);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn deploy_limits() -> Result<()> {
+    init_logger()?;
+
+    let faucet_pubkeys = vec![Keypair::random(&mut OsRng).public];
+    let ix = serialize(&faucet_pubkeys);
+    let sled_db = sled::Config::new().temporary(true).open()?;
+    let state = new_state(&sled_db, faucet_pubkeys).await?;
+    let deploy_key = SecretKey::random(&mut OsRng);
+
+    info!(target: "money", "Verifying deployment with too many circuits");
+    let zkas_bincodes = vec![ARITH_BINCODE.to_vec(); MAX_DEPLOY_CIRCUITS + 1];
+    let tx = deploy_tx(deploy_key, zkas_bincodes, ix.clone())?;
+    let res = state.read().await.verify_transactions(&[tx], false).await;
+    assert!(matches!(res, Err(Error::VerifyFailed(VerifyFailed::DeployTooLarge(_)))));
+
+    // The gas limit is checked before the signatures, so the call can be
+    // modified without having to sign the transaction again.
+    info!(target: "money", "Verifying deployment with an insufficient gas limit");
+    let mut tx = deploy_tx(deploy_key, vec![ARITH_BINCODE.to_vec()], ix)?;
+    tx.calls[0].gas_limit = WASM_BINCODE.len() as u64;
+    let res = state.read().await.verify_transactions(&[tx], false).await;
+    assert!(matches!(res, Err(Error::GasLimitExceeded(_))));
+
+    Ok(())
+}
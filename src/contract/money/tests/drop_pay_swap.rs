@@ -32,6 +32,7 @@ use darkfi_sdk::{
     crypto::{
         merkle_prelude::*, pallas, pasta_prelude::*, poseidon_hash, MerkleNode, Nullifier, TokenId,
     },
+    tx::DEFAULT_GAS_LIMIT,
     ContractCall,
 };
 use darkfi_serial::Encodable;
@@ -110,7 +111,11 @@ async fn money_contract_transfer() -> Result<()> {
     info!(target: "money", "[Faucet] =====================================");
     let mut data = vec![MoneyFunction::Transfer as u8];
     alice_params.encode(&mut data)?;
-    let calls = vec![ContractCall { contract_id: th.money_contract_id, data }];
+    let calls = vec![ContractCall {
+        contract_id: th.money_contract_id,
+        data,
+        gas_limit: DEFAULT_GAS_LIMIT,
+    }];
     let proofs = vec![alice_proofs];
     let mut alicedrop_tx = Transaction { calls, proofs, signatures: vec![] };
     let sigs = alicedrop_tx.create_sigs(&mut OsRng, &alicedrop_secret_keys)?;
@@ -121,7 +126,11 @@ async fn money_contract_transfer() -> Result<()> {
     info!(target: "money", "[Faucet] ===================================");
     let mut data = vec![MoneyFunction::Transfer as u8];
     bob_params.encode(&mut data)?;
-    let calls = vec![ContractCall { contract_id: th.money_contract_id, data }];
+    let calls = vec![ContractCall {
+        contract_id: th.money_contract_id,
+        data,
+        gas_limit: DEFAULT_GAS_LIMIT,
+    }];
     let proofs = vec![bob_proofs];
     let mut bobdrop_tx = Transaction { calls, proofs, signatures: vec![] };
     let sigs = bobdrop_tx.create_sigs(&mut OsRng, &bobdrop_secret_keys)?;
@@ -227,7 +236,11 @@ async fn money_contract_transfer() -> Result<()> {
     info!(target: "money", "[Alice] ==========================");
     let mut data = vec![MoneyFunction::Transfer as u8];
     alice2bob_params.encode(&mut data)?;
-    let calls = vec![ContractCall { contract_id: th.money_contract_id, data }];
+    let calls = vec![ContractCall {
+        contract_id: th.money_contract_id,
+        data,
+        gas_limit: DEFAULT_GAS_LIMIT,
+    }];
     let proofs = vec![alice2bob_proofs];
     let mut alice2bob_tx = Transaction { calls, proofs, signatures: vec![] };
     let sigs = alice2bob_tx.create_sigs(&mut OsRng, &alice2bob_secret_keys)?;
@@ -322,7 +335,11 @@ async fn money_contract_transfer() -> Result<()> {
     info!(target: "money", "[Bob] ============================");
     let mut data = vec![MoneyFunction::Transfer as u8];
     bob2alice_params.encode(&mut data)?;
-    let calls = vec![ContractCall { contract_id: th.money_contract_id, data }];
+    let calls = vec![ContractCall {
+        contract_id: th.money_contract_id,
+        data,
+        gas_limit: DEFAULT_GAS_LIMIT,
+    }];
     let proofs = vec![bob2alice_proofs];
     let mut bob2alice_tx = Transaction { calls, proofs, signatures: vec![] };
     let sigs = bob2alice_tx.create_sigs(&mut OsRng, &bob2alice_secret_keys)?;
@@ -475,7 +492,11 @@ async fn money_contract_transfer() -> Result<()> {
     let mut data = vec![MoneyFunction::OtcSwap as u8];
     swap_full_params.encode(&mut data)?;
     let mut alicebob_swap_tx = Transaction {
-        calls: vec![ContractCall { contract_id: th.money_contract_id, data }],
+        calls: vec![ContractCall {
+            contract_id: th.money_contract_id,
+            data,
+            gas_limit: DEFAULT_GAS_LIMIT,
+        }],
         proofs: vec![swap_full_proofs],
         signatures: vec![],
     };
@@ -579,7 +600,11 @@ async fn money_contract_transfer() -> Result<()> {
     info!(target: "money", "[Alice] ============================");
     let mut data = vec![MoneyFunction::Transfer as u8];
     alice2alice_params.encode(&mut data)?;
-    let calls = vec![ContractCall { contract_id: th.money_contract_id, data }];
+    let calls = vec![ContractCall {
+        contract_id: th.money_contract_id,
+        data,
+        gas_limit: DEFAULT_GAS_LIMIT,
+    }];
     let proofs = vec![alice2alice_proofs];
     let mut alice2alice_tx = Transaction { calls, proofs, signatures: vec![] };
     let sigs = alice2alice_tx.create_sigs(&mut OsRng, &alice2alice_secret_keys)?;
@@ -656,7 +681,11 @@ async fn money_contract_transfer() -> Result<()> {
     info!(target: "money", "[Bob] ==========================");
     let mut data = vec![MoneyFunction::Transfer as u8];
     bob2bob_params.encode(&mut data)?;
-    let calls = vec![ContractCall { contract_id: th.money_contract_id, data }];
+    let calls = vec![ContractCall {
+        contract_id: th.money_contract_id,
+        data,
+        gas_limit: DEFAULT_GAS_LIMIT,
+    }];
     let proofs = vec![bob2bob_proofs];
     let mut bob2bob_tx = Transaction { calls, proofs, signatures: vec![] };
     let sigs = bob2bob_tx.create_sigs(&mut OsRng, &bob2bob_secret_keys)?;
@@ -780,7 +809,11 @@ async fn money_contract_transfer() -> Result<()> {
     let mut data = vec![MoneyFunction::OtcSwap as u8];
     swap_full_params.encode(&mut data)?;
     let mut alicebob_swap_tx = Transaction {
-        calls: vec![ContractCall { contract_id: th.money_contract_id, data }],
+        calls: vec![ContractCall {
+            contract_id: th.money_contract_id,
+            data,
+            gas_limit: DEFAULT_GAS_LIMIT,
+        }],
         proofs: vec![swap_full_proofs],
         signatures: vec![],
     };
@@ -0,0 +1,155 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Integration test for transaction fees.
+//!
+//! Alice pays a fee with an airdropped native token coin. We check that
+//! the fee is reported to the validator, that fee paying transactions are
+//! proposed first, that a pending fee transaction can only be replaced by a
+//! conflicting one paying more, and that fees can only be claimed within
+//! their bounds.
+
+use darkfi::{tx::Transaction, Error, Result, VerifyFailed};
+use darkfi_sdk::{
+    crypto::{
+        merkle_prelude::*, pallas, pasta_prelude::*, poseidon_hash, MerkleNode, Nullifier,
+        DARK_TOKEN_ID,
+    },
+    fee::FeeClaimParamsV1,
+    tx::DEFAULT_GAS_LIMIT,
+    ContractCall,
+};
+use darkfi_serial::Encodable;
+use log::info;
+use rand::rngs::OsRng;
+
+use darkfi_money_contract::{
+    client::{build_fee_tx, Coin, EncryptedNote, OwnCoin},
+    MoneyFunction,
+};
+
+mod harness;
+use harness::{init_logger, MoneyTestHarness};
+
+fn fee_claim_tx(th: &MoneyTestHarness, value: u64) -> Result<Transaction> {
+    let params = FeeClaimParamsV1 {
+        public_key: th.charlie_kp.public,
+        value,
+        serial: pallas::Base::random(&mut OsRng),
+        coin_blind: pallas::Base::random(&mut OsRng),
+    };
+
+    let mut data = vec![MoneyFunction::FeeClaim as u8];
+    params.encode(&mut data)?;
+    let calls = vec![ContractCall {
+        contract_id: th.money_contract_id,
+        data,
+        gas_limit: DEFAULT_GAS_LIMIT,
+    }];
+    Ok(Transaction { calls, proofs: vec![vec![]], signatures: vec![vec![]] })
+}
+
+fn fee_paying_tx(th: &MoneyTestHarness, coin: &OwnCoin, value: u64) -> Result<Transaction> {
+    let (params, proofs, secret_keys) = build_fee_tx(
+        &th.alice_kp,
+        value,
+        coin,
+        &th.alice_merkle_tree,
+        &th.mint_zkbin,
+        &th.mint_pk,
+        &th.burn_zkbin,
+        &th.burn_pk,
+    )?;
+
+    let mut data = vec![MoneyFunction::Fee as u8];
+    params.encode(&mut data)?;
+    let calls = vec![ContractCall {
+        contract_id: th.money_contract_id,
+        data,
+        gas_limit: DEFAULT_GAS_LIMIT,
+    }];
+    let mut tx = Transaction { calls, proofs: vec![proofs], signatures: vec![] };
+    let sigs = tx.create_sigs(&mut OsRng, &secret_keys)?;
+    tx.signatures = vec![sigs];
+    Ok(tx)
+}
+
+#[async_std::test]
+async fn money_contract_fees() -> Result<()> {
+    init_logger()?;
+
+    const FEE: u64 = 10;
+
+    let mut th = MoneyTestHarness::new().await?;
+
+    info!(target: "money", "[Faucet] Airdropping native tokens to Alice");
+    let (airdrop_tx, airdrop_params) = th.airdrop(100, *DARK_TOKEN_ID, &th.alice_kp.public)?;
+    th.alice_state.read().await.verify_transactions(&[airdrop_tx], true).await?;
+    th.alice_merkle_tree.append(&MerkleNode::from(airdrop_params.outputs[0].coin));
+    let leaf_position = th.alice_merkle_tree.witness().unwrap();
+
+    let ciphertext = airdrop_params.outputs[0].ciphertext.clone();
+    let ephem_public = airdrop_params.outputs[0].ephem_public;
+    let note = EncryptedNote { ciphertext, ephem_public }.decrypt(&th.alice_kp.secret)?;
+    let alice_oc = OwnCoin {
+        coin: Coin::from(airdrop_params.outputs[0].coin),
+        note: note.clone(),
+        secret: th.alice_kp.secret,
+        nullifier: Nullifier::from(poseidon_hash([th.alice_kp.secret.inner(), note.serial])),
+        leaf_position,
+    };
+
+    info!(target: "money", "[Alice] Building fee tx");
+    let fee_tx = fee_paying_tx(&th, &alice_oc, FEE)?;
+
+    info!(target: "money", "[Alice] Verifying fee tx");
+    th.alice_state.read().await.verify_transactions(&[fee_tx.clone()], false).await?;
+    assert_eq!(th.alice_state.read().await.tx_fees(&fee_tx)?, (FEE, 0));
+
+    info!(target: "money", "[Alice] Checking fee ordering of unproposed transactions");
+    let (free_tx, _) = th.airdrop(100, *DARK_TOKEN_ID, &th.bob_kp.public)?;
+    assert!(th.alice_state.write().await.append_tx(free_tx.clone()).await?);
+    assert!(th.alice_state.write().await.append_tx(fee_tx.clone()).await?);
+    let unproposed_txs = th.alice_state.read().await.unproposed_txs(-1);
+    assert_eq!(unproposed_txs, vec![fee_tx.clone(), free_tx.clone()]);
+
+    // Both fee transactions spend the same coin, so only one can be pending
+    info!(target: "money", "[Alice] Replacing the fee tx with one paying a higher fee");
+    let bump_tx = fee_paying_tx(&th, &alice_oc, FEE * 2)?;
+    assert!(th.alice_state.write().await.append_tx(bump_tx.clone()).await?);
+    let unproposed_txs = th.alice_state.read().await.unproposed_txs(-1);
+    assert_eq!(unproposed_txs, vec![bump_tx, free_tx]);
+
+    let res = th.alice_state.write().await.append_tx(fee_tx.clone()).await;
+    assert!(matches!(res, Err(Error::MempoolConflict(_, _))));
+
+    info!(target: "money", "[Alice] Checking fee claims");
+    let claim_tx = fee_claim_tx(&th, FEE)?;
+    th.alice_state.read().await.verify_transactions(&[claim_tx.clone()], false).await?;
+    assert_eq!(th.alice_state.read().await.tx_fees(&claim_tx)?, (0, FEE));
+    th.alice_state.read().await.verify_fees(&[fee_tx.clone(), claim_tx.clone()])?;
+
+    let res = th.alice_state.write().await.append_tx(claim_tx).await;
+    assert!(matches!(res, Err(Error::VerifyFailed(VerifyFailed::UnexpectedFeeClaim(_)))));
+
+    let greedy_claim_tx = fee_claim_tx(&th, FEE + 1)?;
+    let res = th.alice_state.read().await.verify_fees(&[fee_tx, greedy_claim_tx]);
+    assert!(matches!(res, Err(Error::VerifyFailed(VerifyFailed::FeeClaimExceedsFees(11, 10)))));
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use darkfi::{Error, Result};
+use darkfi_sdk::{
+    crypto::{pallas, pasta_prelude::*, TokenId},
+    tx::DEFAULT_GAS_LIMIT,
+};
+use log::info;
+use rand::rngs::OsRng;
+use std::collections::HashMap;
+
+mod harness;
+use harness::{init_logger, MoneyTestHarness};
+
+#[async_std::test]
+async fn money_contract_gas_limit() -> Result<()> {
+    init_logger()?;
+
+    let th = MoneyTestHarness::new().await?;
+    let token_id = TokenId::from(pallas::Base::random(&mut OsRng));
+    let (tx, _) = th.airdrop(100, token_id, &th.alice_kp.public)?;
+
+    info!(target: "money", "[Faucet] Verifying airdrop with the default gas limit");
+    let gas = th.faucet_state.read().await.verify_transactions(&[tx.clone()], false).await?;
+    assert!(gas[0] > 0 && gas[0] <= DEFAULT_GAS_LIMIT);
+
+    // The gas limit is checked before the signatures, so the calls can be
+    // modified without having to sign the transaction again.
+    info!(target: "money", "[Faucet] Verifying airdrop with an exact gas limit");
+    let mut exact_tx = tx.clone();
+    exact_tx.calls[0].gas_limit = gas[0];
+    let res = th.faucet_state.read().await.verify_transactions(&[exact_tx], false).await;
+    assert!(!matches!(res, Err(Error::GasLimitExceeded(_))));
+
+    info!(target: "money", "[Faucet] Verifying airdrop with an insufficient gas limit");
+    let mut starved_tx = tx.clone();
+    starved_tx.calls[0].gas_limit = gas[0] - 1;
+    let res = th.faucet_state.read().await.verify_transactions(&[starved_tx], false).await;
+    assert!(matches!(res, Err(Error::GasLimitExceeded(_))));
+
+    info!(target: "money", "[Faucet] Verifying airdrop with a gas limit above the maximum");
+    let mut greedy_tx = tx;
+    greedy_tx.calls[0].gas_limit = u64::MAX;
+    let res = th.faucet_state.read().await.verify_transactions(&[greedy_tx], false).await;
+    assert!(matches!(res, Err(Error::GasLimitTooHigh(..))));
+
+    Ok(())
+}
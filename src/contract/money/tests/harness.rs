@@ -19,8 +19,8 @@ use std::collections::HashMap;
 
 use darkfi::{
     consensus::{
-        ValidatorState, ValidatorStatePtr, TESTNET_BOOTSTRAP_TIMESTAMP, TESTNET_GENESIS_HASH_BYTES,
-        TESTNET_GENESIS_TIMESTAMP, TESTNET_INITIAL_DISTRIBUTION,
+        ValidatorState, ValidatorStatePtr, TESTNET_BOOTSTRAP_TIMESTAMP, TESTNET_EMISSION,
+        TESTNET_GENESIS_HASH_BYTES, TESTNET_GENESIS_TIMESTAMP, TESTNET_INITIAL_DISTRIBUTION,
     },
     tx::Transaction,
     wallet::WalletDb,
@@ -33,6 +33,7 @@ use darkfi_sdk::{
         pasta_prelude::*, ContractId, Keypair, MerkleTree, PublicKey, TokenId, MONEY_CONTRACT_ID,
     },
     db::SMART_CONTRACT_ZKAS_DB_NAME,
+    tx::DEFAULT_GAS_LIMIT,
     ContractCall,
 };
 use darkfi_serial::{serialize, Encodable};
@@ -107,6 +108,7 @@ impl MoneyTestHarness {
             *TESTNET_GENESIS_TIMESTAMP,
             *TESTNET_GENESIS_HASH_BYTES,
             *TESTNET_INITIAL_DISTRIBUTION,
+            *TESTNET_EMISSION,
             faucet_wallet,
             faucet_pubkeys.clone(),
             false,
@@ -119,6 +121,7 @@ impl MoneyTestHarness {
             *TESTNET_GENESIS_TIMESTAMP,
             *TESTNET_GENESIS_HASH_BYTES,
             *TESTNET_INITIAL_DISTRIBUTION,
+            *TESTNET_EMISSION,
             alice_wallet,
             faucet_pubkeys.clone(),
             false,
@@ -131,6 +134,7 @@ impl MoneyTestHarness {
             *TESTNET_GENESIS_TIMESTAMP,
             *TESTNET_GENESIS_HASH_BYTES,
             *TESTNET_INITIAL_DISTRIBUTION,
+            *TESTNET_EMISSION,
             bob_wallet,
             faucet_pubkeys.clone(),
             false,
@@ -143,6 +147,7 @@ impl MoneyTestHarness {
             *TESTNET_GENESIS_TIMESTAMP,
             *TESTNET_GENESIS_HASH_BYTES,
             *TESTNET_INITIAL_DISTRIBUTION,
+            *TESTNET_EMISSION,
             charlie_wallet,
             faucet_pubkeys.clone(),
             false,
@@ -169,10 +174,9 @@ impl MoneyTestHarness {
         let burn_circuit = ZkCircuit::new(burn_witnesses, burn_zkbin.clone());
 
         info!(target: "dao", "Creating zk proving keys");
-        let k = 13;
         let mut proving_keys = HashMap::<[u8; 32], Vec<(&str, ProvingKey)>>::new();
-        let mint_pk = ProvingKey::build(k, &mint_circuit);
-        let burn_pk = ProvingKey::build(k, &burn_circuit);
+        let mint_pk = ProvingKey::build(mint_zkbin.k, &mint_circuit);
+        let burn_pk = ProvingKey::build(burn_zkbin.k, &burn_circuit);
         let pks = vec![
             (MONEY_CONTRACT_ZKAS_MINT_NS_V1, mint_pk.clone()),
             (MONEY_CONTRACT_ZKAS_BURN_NS_V1, burn_pk.clone()),
@@ -231,7 +235,7 @@ impl MoneyTestHarness {
 
         let mut data = vec![MoneyFunction::Transfer as u8];
         params.encode(&mut data)?;
-        let calls = vec![ContractCall { contract_id, data }];
+        let calls = vec![ContractCall { contract_id, data, gas_limit: DEFAULT_GAS_LIMIT }];
         let proofs = vec![proofs];
         let mut tx = Transaction { calls, proofs, signatures: vec![] };
         let sigs = tx.create_sigs(&mut OsRng, &secret_keys)?;
@@ -0,0 +1,187 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Integration test for token issuance.
+//!
+//! A mint authority mints a new token to Alice, who then sends it on to
+//! Bob. Afterwards the authority freezes the token's supply, and we check
+//! that it can't be minted anymore.
+
+use darkfi::{
+    tx::Transaction,
+    zk::{empty_witnesses, Proof, ProvingKey, ZkCircuit},
+    zkas::ZkBinary,
+    Result,
+};
+use darkfi_sdk::{
+    crypto::{
+        merkle_prelude::*, poseidon_hash, Keypair, MerkleNode, Nullifier, SecretKey, TokenId,
+    },
+    db::SMART_CONTRACT_ZKAS_DB_NAME,
+    tx::DEFAULT_GAS_LIMIT,
+    ContractCall,
+};
+use darkfi_serial::{serialize, Encodable};
+use log::info;
+use rand::rngs::OsRng;
+
+use darkfi_money_contract::{
+    client::{build_token_mint_tx, build_transfer_tx, Coin, EncryptedNote, OwnCoin},
+    model::{MoneyTokenFreezeParams, MoneyTokenMintParams},
+    MoneyFunction, MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1,
+};
+
+mod harness;
+use harness::{init_logger, MoneyTestHarness};
+
+fn token_mint_tx(
+    th: &MoneyTestHarness,
+    params: MoneyTokenMintParams,
+    proofs: Vec<Proof>,
+    secret_keys: &[SecretKey],
+) -> Result<Transaction> {
+    let mut data = vec![MoneyFunction::Mint as u8];
+    params.encode(&mut data)?;
+    let calls = vec![ContractCall {
+        contract_id: th.money_contract_id,
+        data,
+        gas_limit: DEFAULT_GAS_LIMIT,
+    }];
+    let mut tx = Transaction { calls, proofs: vec![proofs], signatures: vec![] };
+    let sigs = tx.create_sigs(&mut OsRng, secret_keys)?;
+    tx.signatures = vec![sigs];
+    Ok(tx)
+}
+
+#[async_std::test]
+async fn money_contract_token_mint() -> Result<()> {
+    init_logger()?;
+
+    const SUPPLY: u64 = 1000;
+    const ALICE_SEND: u64 = 400;
+
+    let mut th = MoneyTestHarness::new().await?;
+    let mint_authority = Keypair::random(&mut OsRng);
+    let token_id = TokenId::derive(mint_authority.secret);
+
+    let alice_sled = th.alice_state.read().await.blockchain.sled_db.clone();
+    let db_handle = th.alice_state.read().await.blockchain.contracts.lookup(
+        &alice_sled,
+        &th.money_contract_id,
+        SMART_CONTRACT_ZKAS_DB_NAME,
+    )?;
+    let token_mint_zkbin =
+        db_handle.get(&serialize(&MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1))?.unwrap();
+    let token_mint_zkbin = ZkBinary::decode(&token_mint_zkbin)?;
+    let token_mint_witnesses = empty_witnesses(&token_mint_zkbin);
+    let token_mint_circuit = ZkCircuit::new(token_mint_witnesses, token_mint_zkbin.clone());
+    let token_mint_pk = ProvingKey::build(token_mint_zkbin.k, &token_mint_circuit);
+
+    info!(target: "money", "[Authority] Minting tokens for Alice");
+    let (params, proofs, secret_keys) = build_token_mint_tx(
+        &mint_authority,
+        SUPPLY,
+        th.alice_kp.public,
+        false,
+        &token_mint_zkbin,
+        &token_mint_pk,
+    )?;
+    let mint_tx = token_mint_tx(&th, params.clone(), proofs, &secret_keys)?;
+
+    info!(target: "money", "[Alice] Verifying token mint tx");
+    th.alice_state.read().await.verify_transactions(&[mint_tx.clone()], true).await?;
+    th.alice_merkle_tree.append(&MerkleNode::from(params.output.coin));
+    info!(target: "money", "[Bob] Verifying token mint tx");
+    th.bob_state.read().await.verify_transactions(&[mint_tx], true).await?;
+    th.bob_merkle_tree.append(&MerkleNode::from(params.output.coin));
+    let leaf_position = th.alice_merkle_tree.witness().unwrap();
+
+    let ciphertext = params.output.ciphertext.clone();
+    let ephem_public = params.output.ephem_public;
+    let note = EncryptedNote { ciphertext, ephem_public }.decrypt(&th.alice_kp.secret)?;
+    assert_eq!(note.value, SUPPLY);
+    assert_eq!(note.token_id, token_id);
+
+    let alice_oc = OwnCoin {
+        coin: Coin::from(params.output.coin),
+        note: note.clone(),
+        secret: th.alice_kp.secret,
+        nullifier: Nullifier::from(poseidon_hash([th.alice_kp.secret.inner(), note.serial])),
+        leaf_position,
+    };
+
+    info!(target: "money", "[Alice] Sending minted tokens to Bob");
+    let (params, proofs, secret_keys, _spent_coins) = build_transfer_tx(
+        &th.alice_kp,
+        &th.bob_kp.public,
+        ALICE_SEND,
+        token_id,
+        &[alice_oc],
+        &th.alice_merkle_tree,
+        &th.mint_zkbin,
+        &th.mint_pk,
+        &th.burn_zkbin,
+        &th.burn_pk,
+        false,
+    )?;
+
+    let mut data = vec![MoneyFunction::Transfer as u8];
+    params.encode(&mut data)?;
+    let calls = vec![ContractCall {
+        contract_id: th.money_contract_id,
+        data,
+        gas_limit: DEFAULT_GAS_LIMIT,
+    }];
+    let mut transfer_tx = Transaction { calls, proofs: vec![proofs], signatures: vec![] };
+    let sigs = transfer_tx.create_sigs(&mut OsRng, &secret_keys)?;
+    transfer_tx.signatures = vec![sigs];
+
+    info!(target: "money", "[Bob] Verifying transfer tx");
+    th.bob_state.read().await.verify_transactions(&[transfer_tx], true).await?;
+
+    info!(target: "money", "[Authority] Freezing the token supply");
+    let params = MoneyTokenFreezeParams { mint_public: mint_authority.public };
+    let mut data = vec![MoneyFunction::Freeze as u8];
+    params.encode(&mut data)?;
+    let calls = vec![ContractCall {
+        contract_id: th.money_contract_id,
+        data,
+        gas_limit: DEFAULT_GAS_LIMIT,
+    }];
+    let mut freeze_tx = Transaction { calls, proofs: vec![vec![]], signatures: vec![] };
+    let sigs = freeze_tx.create_sigs(&mut OsRng, &[mint_authority.secret])?;
+    freeze_tx.signatures = vec![sigs];
+
+    info!(target: "money", "[Bob] Verifying freeze tx");
+    th.bob_state.read().await.verify_transactions(&[freeze_tx.clone()], true).await?;
+
+    info!(target: "money", "[Bob] Checking the token can't be minted or frozen again");
+    let (params, proofs, secret_keys) = build_token_mint_tx(
+        &mint_authority,
+        SUPPLY,
+        th.bob_kp.public,
+        false,
+        &token_mint_zkbin,
+        &token_mint_pk,
+    )?;
+    let mint_tx = token_mint_tx(&th, params, proofs, &secret_keys)?;
+    assert!(th.bob_state.read().await.verify_transactions(&[mint_tx], false).await.is_err());
+    assert!(th.bob_state.read().await.verify_transactions(&[freeze_tx], false).await.is_err());
+
+    Ok(())
+}
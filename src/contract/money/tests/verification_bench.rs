@@ -24,6 +24,7 @@ use darkfi_sdk::{
         merkle_prelude::*, pallas, pasta_prelude::*, poseidon_hash, MerkleNode, Nullifier, TokenId,
         MONEY_CONTRACT_ID,
     },
+    tx::DEFAULT_GAS_LIMIT,
     ContractCall,
 };
 use darkfi_serial::Encodable;
@@ -111,7 +112,7 @@ async fn alice2alice_random_amounts() -> Result<()> {
 
         let mut data = vec![MoneyFunction::Transfer as u8];
         params.encode(&mut data)?;
-        let calls = vec![ContractCall { contract_id, data }];
+        let calls = vec![ContractCall { contract_id, data, gas_limit: DEFAULT_GAS_LIMIT }];
         let proofs = vec![proofs];
         let mut tx = Transaction { calls, proofs, signatures: vec![] };
         let sigs = tx.create_sigs(&mut OsRng, &secret_keys)?;
@@ -250,7 +251,7 @@ async fn alice2alice_random_amounts_multiplecoins() -> Result<()> {
 
             let mut data = vec![MoneyFunction::Transfer as u8];
             params.encode(&mut data)?;
-            let calls = vec![ContractCall { contract_id, data }];
+            let calls = vec![ContractCall { contract_id, data, gas_limit: DEFAULT_GAS_LIMIT }];
             let proofs = vec![proofs];
             let mut tx = Transaction { calls, proofs, signatures: vec![] };
             let sigs = tx.create_sigs(&mut OsRng, &secret_keys)?;
@@ -20,6 +20,17 @@ CREATE TABLE IF NOT EXISTS money_keys (
 	secret BLOB NOT NULL
 );
 
+-- The seed our keys are derived from, if the wallet was created from a mnemonic
+CREATE TABLE IF NOT EXISTS money_hd_seed (
+	seed BLOB NOT NULL
+);
+
+-- The next index to derive a key at, for each derivation purpose
+CREATE TABLE IF NOT EXISTS money_hd_indexes (
+	purpose INTEGER PRIMARY KEY NOT NULL,
+	next_index INTEGER NOT NULL
+);
+
 -- The coins we have the information to and can spend
 CREATE TABLE IF NOT EXISTS money_coins (
 	coin BLOB PRIMARY KEY NOT NULL,
@@ -37,3 +48,10 @@ CREATE TABLE IF NOT EXISTS money_coins (
 	leaf_position BLOB NOT NULL,
 	memo BLOB
 );
+
+-- The token mint authorities in our wallet
+CREATE TABLE IF NOT EXISTS money_tokens (
+	mint_authority BLOB PRIMARY KEY NOT NULL,
+	token_id BLOB NOT NULL,
+	is_frozen INTEGER NOT NULL
+);
@@ -142,6 +142,15 @@ pub enum Error {
     #[error("Malformed packet")]
     MalformedPacket,
 
+    #[error("Packet exceeds maximum size")]
+    PacketTooLarge,
+
+    #[error("Peer is banned")]
+    PeerBanned,
+
+    #[error("Peer authentication failed: {0}")]
+    PeerAuthFailed(String),
+
     #[error("Socks proxy error: {0}")]
     SocksError(String),
 
@@ -158,6 +167,9 @@ pub enum Error {
     #[error("Tor error: {0}")]
     TorError(String),
 
+    #[error("Nym error: {0}")]
+    NymError(String),
+
     #[error("Node is not connected to other nodes.")]
     NetworkNotConnected,
 
@@ -275,6 +287,12 @@ pub enum Error {
     #[error("Proposer is not eligible to produce proposals")]
     ProposalProposerNotEligible,
 
+    #[error("Mempool is full, transaction {0} doesn't pay enough fees to replace pending ones")]
+    MempoolFull(String),
+
+    #[error("Transaction {0} conflicts with pending tx {1} paying an equal or higher fee")]
+    MempoolConflict(String, String),
+
     // ===============
     // Database errors
     // ===============
@@ -286,6 +304,9 @@ pub enum Error {
     #[error(transparent)]
     SledError(#[from] sled::Error),
 
+    #[error("Database schema version {0} is newer than the supported version {1}")]
+    UnsupportedSchemaVersion(u32, u32),
+
     #[error("Transaction {0} not found in database")]
     TransactionNotFound(String),
 
@@ -352,6 +373,22 @@ pub enum Error {
     #[error("wasm runtime out of memory")]
     WasmerOomError(String),
 
+    #[cfg(feature = "wasm-runtime")]
+    #[error("Wasmer serialize error: {0}")]
+    WasmerSerializeError(String),
+
+    #[cfg(feature = "wasm-runtime")]
+    #[error("Wasmer deserialize error: {0}")]
+    WasmerDeserializeError(String),
+
+    #[cfg(feature = "wasm-runtime")]
+    #[error("Contract call exceeded its gas limit of {0}")]
+    GasLimitExceeded(u64),
+
+    #[cfg(feature = "wasm-runtime")]
+    #[error("Contract call gas limit {0} is above the maximum of {1}")]
+    GasLimitTooHigh(u64, u64),
+
     // TODO: FIXME: The strings are wrong
     #[cfg(feature = "darkfi-sdk")]
     #[error("contract initialize error")]
@@ -477,6 +514,21 @@ pub enum VerifyFailed {
     #[error("Failed verifying zk proofs: {0}")]
     ProofVerifyFailed(String),
 
+    #[error("Missing verifying key for circuit {0}")]
+    MissingVerifyingKey(String),
+
+    #[error("Contract deployment is too large: {0}")]
+    DeployTooLarge(String),
+
+    #[error("Transaction {0} conflicts with another transaction")]
+    ConflictingTransaction(String),
+
+    #[error("Transaction {0} claims fees outside of a block proposal")]
+    UnexpectedFeeClaim(String),
+
+    #[error("Block claims {0} in fees, but only {1} were paid")]
+    FeeClaimExceedsFees(u64, u64),
+
     #[error("Internal error: {0}")]
     InternalError(String),
 }
@@ -671,6 +723,20 @@ impl From<wasmer::MemoryError> for Error {
     }
 }
 
+#[cfg(feature = "wasm-runtime")]
+impl From<wasmer::SerializeError> for Error {
+    fn from(err: wasmer::SerializeError) -> Self {
+        Self::WasmerSerializeError(err.to_string())
+    }
+}
+
+#[cfg(feature = "wasm-runtime")]
+impl From<wasmer::DeserializeError> for Error {
+    fn from(err: wasmer::DeserializeError) -> Self {
+        Self::WasmerDeserializeError(err.to_string())
+    }
+}
+
 #[cfg(feature = "darkfi-sdk")]
 impl From<darkfi_sdk::error::ContractError> for Error {
     fn from(err: darkfi_sdk::error::ContractError) -> Self {
@@ -24,7 +24,9 @@ use smol::Executor;
 use url::Url;
 
 use super::{
-    transport::{TcpTransport, TorTransport, Transport, TransportListener, TransportName},
+    transport::{
+        NymTransport, TcpTransport, TorTransport, Transport, TransportListener, TransportName,
+    },
     Channel, ChannelPtr, SessionWeakPtr,
 };
 use crate::{
@@ -132,6 +134,19 @@ impl Acceptor {
 
                 accept!(listener, transport, upgrade);
             }
+            TransportName::Nym(upgrade) => {
+                let mut transport = NymTransport::new(NymTransport::get_client_env()?)?;
+
+                // Peers reach us through the mixnet address of our Nym client.
+                // The transport keeps the client connection for the listener.
+                let nym_url = transport.self_address(accept_url.scheme()).await?;
+
+                info!(target: "net::acceptor", "Nym address: {}", nym_url.to_string());
+
+                let listener = transport.clone().listen_on(accept_url.clone());
+
+                accept!(listener, transport, upgrade);
+            }
             _ => unimplemented!(),
         }
         Ok(())
@@ -16,20 +16,28 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use async_std::sync::{Arc, Mutex};
+use async_std::{
+    channel,
+    sync::{Arc, Mutex},
+};
 use futures::{
     io::{ReadHalf, WriteHalf},
     AsyncReadExt,
 };
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use rand::Rng;
 use serde_json::json;
 use smol::Executor;
 use url::Url;
 
 use super::{
-    message,
+    constants::{
+        MISBEHAVIOUR_MALFORMED_PACKET, MISBEHAVIOUR_OVERSIZED_PACKET,
+        MISBEHAVIOUR_UNDECODABLE_MESSAGE,
+    },
+    message::{self, PacketCipher},
     message_subscriber::{MessageSubscription, MessageSubsystem},
+    stats::{ChannelStats, RateLimiter, UNKNOWN_COMMAND},
     transport::TransportStream,
     Session, SessionBitflag, SessionWeakPtr,
 };
@@ -45,8 +53,10 @@ pub type ChannelPtr = Arc<Channel>;
 struct ChannelInfo {
     random_id: u32,
     remote_node_id: String,
+    remote_public_key: Option<String>,
     last_msg: String,
     last_status: String,
+    stats: ChannelStats,
     // Message log which is cleared on querying get_info
     log: Option<Mutex<Vec<(NanoTimestamp, String, String)>>>,
 }
@@ -61,8 +71,10 @@ impl ChannelInfo {
         Self {
             random_id: rand::thread_rng().gen(),
             remote_node_id: String::new(),
+            remote_public_key: None,
             last_msg: String::new(),
             last_status: String::new(),
+            stats: ChannelStats::default(),
             log,
         }
     }
@@ -82,8 +94,10 @@ impl ChannelInfo {
         json!({
             "random_id": self.random_id,
             "remote_node_id": self.remote_node_id,
+            "remote_public_key": self.remote_public_key,
             "last_msg": self.last_msg,
             "last_status": self.last_status,
+            "stats": self.stats.total.get_info(),
             "log": log,
         })
     }
@@ -101,6 +115,18 @@ pub struct Channel {
     stopped: Mutex<bool>,
     info: Mutex<ChannelInfo>,
     session: SessionWeakPtr,
+    /// Whether the authenticated handshake is run on this channel
+    auth_handshake: bool,
+    /// Maximum size of an inbound packet payload
+    max_frame_size: usize,
+    /// Maximum number of messages per second accepted from the peer
+    inbound_msg_rate_limit: u32,
+    /// Maximum number of payload bytes per second accepted from the peer
+    inbound_byte_rate_limit: u64,
+    /// Cipher sealing outbound packets, once encryption is enabled
+    send_cipher: Mutex<Option<PacketCipher>>,
+    /// Hands the cipher opening inbound packets over to the receive loop
+    recv_cipher: (channel::Sender<PacketCipher>, channel::Receiver<PacketCipher>),
 }
 
 impl Channel {
@@ -119,7 +145,7 @@ impl Channel {
         let message_subsystem = MessageSubsystem::new();
         Self::setup_dispatchers(&message_subsystem).await;
 
-        let channel_log = session.upgrade().unwrap().p2p().settings().channel_log;
+        let settings = session.upgrade().unwrap().p2p().settings();
 
         Arc::new(Self {
             reader,
@@ -129,8 +155,14 @@ impl Channel {
             stop_subscriber: Subscriber::new(),
             receive_task: StoppableTask::new(),
             stopped: Mutex::new(false),
-            info: Mutex::new(ChannelInfo::new(channel_log)),
+            info: Mutex::new(ChannelInfo::new(settings.channel_log)),
             session,
+            auth_handshake: settings.auth_handshake,
+            max_frame_size: settings.max_frame_size,
+            inbound_msg_rate_limit: settings.inbound_msg_rate_limit,
+            inbound_byte_rate_limit: settings.inbound_byte_rate_limit,
+            send_cipher: Mutex::new(None),
+            recv_cipher: channel::bounded(1),
         })
     }
 
@@ -138,6 +170,11 @@ impl Channel {
         self.info.lock().await.get_info().await
     }
 
+    /// Return the traffic counters of the channel, in total and per command.
+    pub async fn stats(&self) -> ChannelStats {
+        self.info.lock().await.stats.clone()
+    }
+
     /// Starts the channel. Runs a receive loop to start receiving messages or
     /// handles a network failure.
     pub fn start(self: Arc<Self>, executor: Arc<Executor<'_>>) {
@@ -235,7 +272,7 @@ impl Channel {
     async fn send_message<M: message::Message>(&self, message: M) -> Result<()> {
         let mut payload = Vec::new();
         message.encode(&mut payload)?;
-        let packet = message::Packet { command: String::from(M::name()), payload };
+        let mut packet = message::Packet { command: String::from(M::name()), payload };
         let time = NanoTimestamp::current_time();
         //let time = time::unix_timestamp()?;
 
@@ -246,8 +283,18 @@ impl Channel {
             };
         }
 
+        let command = packet.command.clone();
+        let payload_len = packet.payload.len();
+
+        // Packets must be sealed in the same order they are written
         let stream = &mut *self.writer.lock().await;
-        message::send_packet(stream, packet).await
+        if let Some(cipher) = &mut *self.send_cipher.lock().await {
+            packet = cipher.seal(packet)?;
+        }
+        message::send_packet(stream, packet).await?;
+
+        self.info.lock().await.stats.record_sent(&command, payload_len);
+        Ok(())
     }
 
     /// Subscribe to a messages on the message subsystem.
@@ -273,6 +320,45 @@ impl Channel {
         self.address.clone()
     }
 
+    /// Increase the misbehaviour score of the remote peer. If the peer
+    /// crosses the ban threshold, it gets banned and the channel is stopped.
+    /// Returns `true` if the peer got banned.
+    pub async fn misbehaved(&self, score: u32) -> bool {
+        warn!(
+            target: "net::channel::misbehaved()",
+            "Peer {} misbehaved, increasing its score by {}",
+            self.address(),
+            score
+        );
+
+        let hosts = self.session().p2p().hosts();
+        if !hosts.misbehaved(&self.address, score).await {
+            return false
+        }
+
+        warn!(target: "net::channel::misbehaved()", "Peer {} got banned", self.address());
+        self.stop().await;
+        true
+    }
+
+    /// Encrypt all further packets of the channel using the given ciphers.
+    /// Called by the authenticated handshake once keys are agreed on.
+    pub async fn enable_encryption(&self, send_cipher: PacketCipher, recv_cipher: PacketCipher) {
+        *self.send_cipher.lock().await = Some(send_cipher);
+        // The channel is bounded to a single cipher, handed over only once
+        let _ = self.recv_cipher.0.try_send(recv_cipher);
+    }
+
+    /// Return the base58 encoded public key the remote peer authenticated
+    /// with, if the authenticated handshake was performed.
+    pub async fn remote_public_key(&self) -> Option<String> {
+        self.info.lock().await.remote_public_key.clone()
+    }
+
+    pub async fn set_remote_public_key(&self, public_key: String) {
+        self.info.lock().await.remote_public_key = Some(public_key);
+    }
+
     pub async fn remote_node_id(&self) -> String {
         self.info.lock().await.remote_node_id.clone()
     }
@@ -297,6 +383,8 @@ impl Channel {
         message_subsystem.add_dispatch::<message::GetAddrsMessage>().await;
         message_subsystem.add_dispatch::<message::AddrsMessage>().await;
         message_subsystem.add_dispatch::<message::ExtAddrsMessage>().await;
+        message_subsystem.add_dispatch::<message::AuthHelloMessage>().await;
+        message_subsystem.add_dispatch::<message::AuthMessage>().await;
     }
 
     /// Convenience function that returns the Message Subsystem.
@@ -304,17 +392,81 @@ impl Channel {
         &self.message_subsystem
     }
 
+    /// Return the maximum payload size of a command: the size limit of its
+    /// message, bounded by the frame size cap.
+    async fn max_payload_size(&self, command: &str) -> usize {
+        match self.message_subsystem.max_size(command).await {
+            Some(max_size) => std::cmp::min(max_size, self.max_frame_size),
+            None => self.max_frame_size,
+        }
+    }
+
+    /// Read the next packet from the stream, opening it if the channel is
+    /// encrypted. Once encryption is enabled, plaintext packets are rejected
+    /// so they can't be injected into the channel. Payloads exceeding the
+    /// size limit of their command are rejected before being read.
+    async fn read_packet(
+        &self,
+        reader: &mut ReadHalf<Box<dyn TransportStream>>,
+        recv_cipher: &mut Option<PacketCipher>,
+    ) -> Result<message::Packet> {
+        let (command, payload_len) = message::read_packet_header(reader).await?;
+
+        if command != message::SEALED_COMMAND {
+            if recv_cipher.is_some() {
+                return Err(Error::MalformedPacket)
+            }
+            let max_size = self.max_payload_size(&command).await;
+            let payload = message::read_payload(reader, payload_len, max_size).await?;
+            return Ok(message::Packet { command, payload })
+        }
+
+        let payload = message::read_payload(reader, payload_len, self.max_frame_size).await?;
+        let packet = message::Packet { command, payload };
+
+        if !self.auth_handshake {
+            return Err(Error::MalformedPacket)
+        }
+
+        if recv_cipher.is_none() {
+            // The handshake hands the cipher over once it processed the peer's
+            // ephemeral key, which was dispatched before this packet was read.
+            let cipher = self.recv_cipher.1.recv().await.map_err(|_| Error::ChannelStopped)?;
+            *recv_cipher = Some(cipher);
+        }
+
+        let packet = recv_cipher.as_mut().unwrap().open(packet)?;
+        if packet.payload.len() > self.max_payload_size(&packet.command).await {
+            return Err(Error::PacketTooLarge)
+        }
+
+        Ok(packet)
+    }
+
     /// Run the receive loop. Start receiving messages or handle network
     /// failure.
     async fn main_receive_loop(self: Arc<Self>) -> Result<()> {
         debug!(target: "net::channel::main_receive_loop()", "START, address={}", self.address());
 
         let reader = &mut *self.reader.lock().await;
+        let mut recv_cipher: Option<PacketCipher> = None;
+        let mut rate_limiter =
+            RateLimiter::new(self.inbound_msg_rate_limit, self.inbound_byte_rate_limit);
 
         loop {
-            let packet = match message::read_packet(reader).await {
+            let packet = match self.read_packet(reader, &mut recv_cipher).await {
                 Ok(packet) => packet,
                 Err(err) => {
+                    match err {
+                        Error::MalformedPacket => {
+                            self.misbehaved(MISBEHAVIOUR_MALFORMED_PACKET).await;
+                        }
+                        Error::PacketTooLarge => {
+                            self.misbehaved(MISBEHAVIOUR_OVERSIZED_PACKET).await;
+                        }
+                        _ => {}
+                    }
+
                     if Self::is_eof_error(err.clone()) {
                         info!(
                             target: "net::channel::main_receive_loop()",
@@ -338,10 +490,16 @@ impl Channel {
                     return Err(Error::ChannelStopped)
                 }
             };
+
+            let command = match self.message_subsystem.max_size(&packet.command).await {
+                Some(_) => packet.command.as_str(),
+                None => UNKNOWN_COMMAND,
+            };
             {
                 let info = &mut *self.info.lock().await;
                 info.last_msg = packet.command.clone();
                 info.last_status = "recv".to_string();
+                info.stats.record_received(command, packet.payload.len());
                 let time = NanoTimestamp::current_time();
                 //let time = time::unix_timestamp()?;
                 if let Some(l) = &info.log {
@@ -349,8 +507,31 @@ impl Channel {
                 };
             }
 
+            // Stop reading from peers going over the rate limits until the
+            // end of the current window, so they get slowed down
+            if let Some(wait) = rate_limiter.record(packet.payload.len()) {
+                debug!(
+                    target: "net::channel::main_receive_loop()",
+                    "Peer {} exceeded the rate limits, pausing for {:?}",
+                    self.address(),
+                    wait
+                );
+                async_std::task::sleep(wait).await;
+            }
+
             // Send result to our subscribers
-            self.message_subsystem.notify(&packet.command, packet.payload).await;
+            if let Err(err) = self.message_subsystem.notify(&packet.command, packet.payload).await {
+                debug!(
+                    target: "net::channel::main_receive_loop()",
+                    "Failed dispatching {} from {}: {}",
+                    packet.command,
+                    self.address(),
+                    err
+                );
+                if self.misbehaved(MISBEHAVIOUR_UNDECODABLE_MESSAGE).await {
+                    return Err(Error::ChannelStopped)
+                }
+            }
         }
     }
 
@@ -23,7 +23,7 @@ use log::error;
 use url::Url;
 
 use super::{
-    transport::{TcpTransport, TorTransport, Transport, TransportName},
+    transport::{NymTransport, TcpTransport, TorTransport, Transport, TransportName},
     Channel, ChannelPtr, SessionWeakPtr, SettingsPtr,
 };
 use crate::{Error, Result};
@@ -106,6 +106,13 @@ impl Connector {
 
                 connect!(stream, transport, upgrade)
             }
+            TransportName::Nym(upgrade) => {
+                let transport = NymTransport::new(NymTransport::get_client_env()?)?;
+
+                let stream = transport.clone().dial(connect_url.clone(), Some(timeout));
+
+                connect!(stream, transport, upgrade)
+            }
             _ => unimplemented!(),
         }
     }
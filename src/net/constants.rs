@@ -16,6 +16,43 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+/// Maximum number of hosts kept in the host store
+pub const HOSTS_MAX_SIZE: usize = 5000;
+
+/// Consecutive failed connection attempts after which a host gets evicted
+pub const HOST_MAX_FAILURES: u32 = 5;
+
+/// Misbehaviour score at which a peer gets banned
+pub const BAN_THRESHOLD: u32 = 100;
+
+/// Duration (in seconds) of automatic bans
+pub const BAN_DURATION: u64 = 86400;
+
+/// Seconds it takes for a misbehaviour score to decrease by one point.
+/// Occasional offences of honest peers fade away instead of adding up to a ban.
+pub const MISBEHAVIOUR_DECAY_INTERVAL: u64 = 36;
+
+/// Misbehaviour penalty for sending a malformed packet
+pub const MISBEHAVIOUR_MALFORMED_PACKET: u32 = 50;
+
+/// Misbehaviour penalty for sending a packet bigger than allowed
+pub const MISBEHAVIOUR_OVERSIZED_PACKET: u32 = 50;
+
+/// Misbehaviour penalty for sending a message that can't be decoded
+pub const MISBEHAVIOUR_UNDECODABLE_MESSAGE: u32 = 20;
+
+/// Misbehaviour penalty for advertising more addresses than a host store can hold
+pub const MISBEHAVIOUR_OVERSIZED_ADDRS: u32 = 20;
+
+/// Misbehaviour penalty for relaying a transaction that fails verification
+pub const MISBEHAVIOUR_INVALID_TX: u32 = 10;
+
+/// Misbehaviour penalty for relaying an invalid consensus proposal
+pub const MISBEHAVIOUR_INVALID_PROPOSAL: u32 = 20;
+
+/// Misbehaviour penalty for relaying a finalized block that fails verification
+pub const MISBEHAVIOUR_INVALID_BLOCK: u32 = 25;
+
 /// Localnet addresses
 pub const LOCALNET: [&str; 5] = ["localhost", "0.0.0.0", "[::]", "127.0.0.1", "[::1]"];
 
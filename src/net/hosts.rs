@@ -16,34 +16,127 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{
-    collections::{HashMap, HashSet},
-    net::IpAddr,
-};
+use std::{collections::HashMap, net::IpAddr, path::PathBuf};
 
 use async_std::sync::{Arc, Mutex};
 use ipnet::{Ipv4Net, Ipv6Net};
 use iprange::IpRange;
-use log::{debug, error, warn};
-use url::Url;
+use log::{debug, error, info, warn};
+use rand::seq::SliceRandom;
+use url::{Host, Url};
 
-use super::constants::{IP4_PRIV_RANGES, IP6_PRIV_RANGES, LOCALNET};
-use crate::util::encoding::base32;
+use super::constants::{
+    BAN_DURATION, BAN_THRESHOLD, HOSTS_MAX_SIZE, HOST_MAX_FAILURES, IP4_PRIV_RANGES,
+    IP6_PRIV_RANGES, LOCALNET, MISBEHAVIOUR_DECAY_INTERVAL,
+};
+use crate::{
+    util::{
+        encoding::base32,
+        file::{load_file, save_file},
+        time::Timestamp,
+    },
+    Result,
+};
 
 /// Pointer to hosts class.
 pub type HostsPtr = Arc<Hosts>;
 
+/// Connection quality information we keep for each known host.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HostInfo {
+    /// Last time (UNIX seconds) the host was advertised to us or we connected to it
+    pub last_seen: u64,
+    /// Last time (UNIX seconds) a connection attempt to the host failed
+    pub last_failure: u64,
+    /// Number of successful connections to the host
+    pub successes: u32,
+    /// Number of consecutive failed connection attempts to the host
+    pub failures: u32,
+}
+
+impl HostInfo {
+    /// Score used to rank hosts when picking outbound addresses.
+    /// Every failed attempt outweighs several successful connections,
+    /// so hosts that went offline quickly fall behind fresh ones.
+    pub fn score(&self) -> i64 {
+        std::cmp::min(self.successes, 10) as i64 - 3 * self.failures as i64
+    }
+
+    /// Serialize into a line of the hosts file, prefixed by the host url.
+    fn to_line(&self, url: &Url) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\n",
+            url, self.last_seen, self.last_failure, self.successes, self.failures
+        )
+    }
+
+    /// Parse a line of the hosts file.
+    fn from_line(line: &str) -> Option<(Url, Self)> {
+        let data: Vec<&str> = line.trim_end().split('\t').collect();
+        if data.len() != 5 {
+            return None
+        }
+
+        let url = Url::parse(data[0]).ok()?;
+        let info = Self {
+            last_seen: data[1].parse().ok()?,
+            last_failure: data[2].parse().ok()?,
+            successes: data[3].parse().ok()?,
+            failures: data[4].parse().ok()?,
+        };
+
+        Some((url, info))
+    }
+}
+
+/// Prefix of the hosts file lines holding a ban.
+const BAN_LINE_PREFIX: &str = "ban\t";
+
+/// Returns the key peers are scored and banned by. Inbound channel
+/// addresses carry an ephemeral port, so usually only the host part is used.
+/// Peers that share their host with unrelated peers are keyed individually:
+/// Nym peers by their whole mixnet address, since the host part is their
+/// gateway, and loopback peers by their channel, since inbound Tor peers
+/// all connect through the local Tor daemon.
+fn peer_key(url: &Url) -> String {
+    if url.scheme().starts_with("nym") {
+        return format!("{}@{}", url.username(), url.host_str().unwrap_or_default())
+    }
+
+    let is_loopback = match url.host() {
+        Some(Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(Host::Ipv6(ip)) => ip.is_loopback(),
+        Some(Host::Domain(domain)) => domain == "localhost",
+        None => return url.to_string(),
+    };
+
+    let host = url.host_str().unwrap();
+    if is_loopback {
+        return format!("{}:{}", host, url.port().unwrap_or_default())
+    }
+
+    host.to_string()
+}
+
 /// Manages a store of network addresses.
 pub struct Hosts {
-    addrs: Mutex<HashSet<Url>>,
+    addrs: Mutex<HashMap<Url, HostInfo>>,
+    /// Banned peers, mapped to the time (UNIX seconds) their ban expires
+    bans: Mutex<HashMap<String, u64>>,
+    /// Misbehaviour scores of peers, along with the time (UNIX seconds)
+    /// they were last decayed
+    misbehaviour: Mutex<HashMap<String, (u32, u64)>>,
     localnet: bool,
     ipv4_range: IpRange<Ipv4Net>,
     ipv6_range: IpRange<Ipv6Net>,
+    /// Optional file the host store is persisted to
+    hosts_file: Option<PathBuf>,
 }
 
 impl Hosts {
-    /// Create a new host list.
-    pub fn new(localnet: bool) -> Arc<Self> {
+    /// Create a new host list. If a hosts file is provided, previously
+    /// saved hosts and bans are loaded from it.
+    pub fn new(localnet: bool, hosts_file: Option<PathBuf>) -> Arc<Self> {
         // Initialize ipv4_range and ipv6_range if needed
         let mut ipv4_range: IpRange<Ipv4Net> =
             IP4_PRIV_RANGES.iter().map(|s| s.parse().unwrap()).collect();
@@ -54,7 +147,20 @@ impl Hosts {
         ipv4_range.simplify();
         ipv6_range.simplify();
 
-        Arc::new(Self { addrs: Mutex::new(HashSet::new()), localnet, ipv4_range, ipv6_range })
+        let (addrs, bans) = match &hosts_file {
+            Some(path) => load_hosts(path),
+            None => (HashMap::new(), HashMap::new()),
+        };
+
+        Arc::new(Self {
+            addrs: Mutex::new(addrs),
+            bans: Mutex::new(bans),
+            misbehaviour: Mutex::new(HashMap::new()),
+            localnet,
+            ipv4_range,
+            ipv6_range,
+            hosts_file,
+        })
     }
 
     /// Add a new host to the host list, after filtering.
@@ -68,10 +174,7 @@ impl Hosts {
             debug!(target: "net::hosts::store()", "hosts::store() [Localnet mode, skipping filterring.]");
             input_addrs
         };
-        let mut addrs_map = self.addrs.lock().await;
-        for addr in addrs {
-            addrs_map.insert(addr);
-        }
+        self.insert(addrs).await;
         debug!(target: "net::hosts::store()", "hosts::store() [End]");
     }
 
@@ -87,21 +190,188 @@ impl Hosts {
             debug!(target: "net::hosts::store_ext()", "hosts::store_ext() [Localnet mode, skipping filterring.]");
             input_addrs
         };
+        self.insert(addrs).await;
+        debug!(target: "net::hosts::store_ext()", "hosts::store_ext() [End]");
+    }
+
+    /// Insert already filtered addresses, refreshing the last seen time
+    /// of the ones we already know, and evict the worst hosts if the
+    /// store grew over its limit. Banned hosts are skipped.
+    async fn insert(&self, addrs: Vec<Url>) {
+        let now = Timestamp::current_time().0 as u64;
+        let bans = self.bans.lock().await;
         let mut addrs_map = self.addrs.lock().await;
         for addr in addrs {
-            addrs_map.insert(addr);
+            if matches!(bans.get(&peer_key(&addr)), Some(until) if *until > now) {
+                debug!(target: "net::hosts::insert()", "hosts::insert() [Skipping banned {}]", addr);
+                continue
+            }
+            addrs_map.entry(addr).or_default().last_seen = now;
+        }
+
+        if addrs_map.len() > HOSTS_MAX_SIZE {
+            let mut ranked: Vec<(Url, i64, u64)> =
+                addrs_map.iter().map(|(k, v)| (k.clone(), v.score(), v.last_seen)).collect();
+            ranked.sort_by_key(|(_, score, last_seen)| (*score, *last_seen));
+            for (addr, _, _) in ranked.iter().take(addrs_map.len() - HOSTS_MAX_SIZE) {
+                debug!(target: "net::hosts::insert()", "hosts::insert() [Evicting {}]", addr);
+                addrs_map.remove(addr);
+            }
         }
-        debug!(target: "net::hosts::store_ext()", "hosts::store_ext() [End]");
     }
 
     /// Return the list of hosts.
     pub async fn load_all(&self) -> Vec<Url> {
-        self.addrs.lock().await.iter().cloned().collect()
+        self.addrs.lock().await.keys().cloned().collect()
+    }
+
+    /// Return the list of hosts, best scored first. Hosts with the same
+    /// score are shuffled, so outbound slots don't all pick the same peers.
+    pub async fn load_by_score(&self) -> Vec<Url> {
+        let mut ranked: Vec<(Url, i64)> =
+            self.addrs.lock().await.iter().map(|(k, v)| (k.clone(), v.score())).collect();
+        ranked.shuffle(&mut rand::thread_rng());
+        ranked.sort_by_key(|(_, score)| -score);
+        ranked.into_iter().map(|(addr, _)| addr).collect()
+    }
+
+    /// Return the quality information of a host, if known.
+    pub async fn get(&self, url: &Url) -> Option<HostInfo> {
+        self.addrs.lock().await.get(url).cloned()
     }
 
     /// Remove an Url from the list
     pub async fn remove(&self, url: &Url) -> bool {
-        self.addrs.lock().await.remove(url)
+        self.addrs.lock().await.remove(url).is_some()
+    }
+
+    /// Record a successful connection to a host.
+    pub async fn mark_success(&self, url: &Url) {
+        let now = Timestamp::current_time().0 as u64;
+        let mut addrs_map = self.addrs.lock().await;
+        let info = addrs_map.entry(url.clone()).or_default();
+        info.last_seen = now;
+        info.successes = info.successes.saturating_add(1);
+        info.failures = 0;
+    }
+
+    /// Record a failed connection attempt to a host. Once a host reaches
+    /// [`HOST_MAX_FAILURES`] consecutive failures it gets evicted.
+    /// Returns `true` if the host was evicted.
+    pub async fn mark_failure(&self, url: &Url) -> bool {
+        let now = Timestamp::current_time().0 as u64;
+        let mut addrs_map = self.addrs.lock().await;
+        let Some(info) = addrs_map.get_mut(url) else { return false };
+
+        info.last_failure = now;
+        info.failures += 1;
+
+        if info.failures >= HOST_MAX_FAILURES {
+            debug!(target: "net::hosts::mark_failure()", "hosts::mark_failure() [Evicting {}]", url);
+            addrs_map.remove(url);
+            return true
+        }
+
+        false
+    }
+
+    /// Increase the misbehaviour score of a peer. Scores decay by one point
+    /// every [`MISBEHAVIOUR_DECAY_INTERVAL`] seconds. Once the score reaches
+    /// [`BAN_THRESHOLD`] the peer gets banned for [`BAN_DURATION`] seconds.
+    /// Returns `true` if the peer got banned.
+    pub async fn misbehaved(&self, url: &Url, score: u32) -> bool {
+        self.misbehaved_at(url, score, Timestamp::current_time().0 as u64).await
+    }
+
+    async fn misbehaved_at(&self, url: &Url, score: u32, now: u64) -> bool {
+        let key = peer_key(url);
+        {
+            let mut misbehaviour = self.misbehaviour.lock().await;
+
+            // Decay all scores, forgetting the peers that got back to zero
+            misbehaviour.retain(|_, (total, last_decay)| {
+                let elapsed = now.saturating_sub(*last_decay) / MISBEHAVIOUR_DECAY_INTERVAL;
+                *total = total.saturating_sub(elapsed.try_into().unwrap_or(u32::MAX));
+                *last_decay += elapsed * MISBEHAVIOUR_DECAY_INTERVAL;
+                *total > 0
+            });
+
+            let (total, _) = misbehaviour.entry(key.clone()).or_insert((0, now));
+            *total = total.saturating_add(score);
+            debug!(target: "net::hosts::misbehaved()", "hosts::misbehaved() [{} scored {}]", key, total);
+
+            if *total < BAN_THRESHOLD {
+                return false
+            }
+        }
+
+        self.ban(&key, BAN_DURATION).await;
+        true
+    }
+
+    /// Ban a peer host for the given number of seconds. Known addresses
+    /// of the host are removed from the store, and its misbehaviour score
+    /// is reset.
+    pub async fn ban(&self, host: &str, duration: u64) {
+        let until = (Timestamp::current_time().0 as u64).saturating_add(duration);
+        warn!(target: "net::hosts::ban()", "Banning {} for {} seconds", host, duration);
+        self.bans.lock().await.insert(host.to_string(), until);
+        self.misbehaviour.lock().await.remove(host);
+        self.addrs.lock().await.retain(|url, _| peer_key(url) != host);
+    }
+
+    /// Lift the ban of a peer host. Returns `true` if the host was banned.
+    pub async fn unban(&self, host: &str) -> bool {
+        info!(target: "net::hosts::unban()", "Lifting ban of {}", host);
+        self.bans.lock().await.remove(host).is_some()
+    }
+
+    /// Check if the host of the given Url is currently banned.
+    /// Expired bans are cleaned up along the way.
+    pub async fn is_banned(&self, url: &Url) -> bool {
+        let key = peer_key(url);
+        let mut bans = self.bans.lock().await;
+        let Some(until) = bans.get(&key) else { return false };
+
+        if *until > Timestamp::current_time().0 as u64 {
+            return true
+        }
+
+        bans.remove(&key);
+        false
+    }
+
+    /// Return the list of active bans along with their expiry time (UNIX seconds).
+    pub async fn bans(&self) -> Vec<(String, u64)> {
+        let now = Timestamp::current_time().0 as u64;
+        let mut bans = self.bans.lock().await;
+        bans.retain(|_, until| *until > now);
+        bans.iter().map(|(host, until)| (host.clone(), *until)).collect()
+    }
+
+    /// Persist the host store and the active bans to the configured
+    /// hosts file, if any.
+    pub async fn save(&self) -> Result<()> {
+        let Some(path) = &self.hosts_file else { return Ok(()) };
+
+        let mut string = String::new();
+        for (url, info) in self.addrs.lock().await.iter() {
+            string.push_str(&info.to_line(url));
+        }
+
+        let bans = self.bans().await;
+        for (host, until) in &bans {
+            string.push_str(&format!("{}{}\t{}\n", BAN_LINE_PREFIX, host, until));
+        }
+
+        info!(
+            target: "net::hosts::save()",
+            "Saving {} hosts and {} bans to: {:?}",
+            string.lines().count() - bans.len(),
+            bans.len(),
+            path
+        );
+        save_file(path, &string)
     }
 
     /// Check if the host list is empty.
@@ -110,6 +380,47 @@ impl Hosts {
     }
 }
 
+/// Auxiliary function to load saved hosts and bans from a hosts file.
+/// Expired bans are dropped.
+fn load_hosts(path: &PathBuf) -> (HashMap<Url, HostInfo>, HashMap<String, u64>) {
+    let mut hosts = HashMap::new();
+    let mut bans = HashMap::new();
+    info!(target: "net::hosts::load_hosts()", "Retrieving saved hosts from: {:?}", path);
+    let contents = match load_file(path) {
+        Ok(c) => c,
+        Err(e) => {
+            warn!(target: "net::hosts::load_hosts()", "Failed retrieving saved hosts: {}", e);
+            return (hosts, bans)
+        }
+    };
+
+    let now = Timestamp::current_time().0 as u64;
+    for line in contents.lines() {
+        if let Some(ban) = line.strip_prefix(BAN_LINE_PREFIX) {
+            match ban.split_once('\t').and_then(|(h, u)| Some((h, u.parse::<u64>().ok()?))) {
+                Some((host, until)) => {
+                    if until > now {
+                        bans.insert(host.to_string(), until);
+                    }
+                }
+                None => {
+                    warn!(target: "net::hosts::load_hosts()", "Skipping malformed line: {}", line)
+                }
+            }
+            continue
+        }
+
+        match HostInfo::from_line(line) {
+            Some((url, info)) => {
+                hosts.insert(url, info);
+            }
+            None => warn!(target: "net::hosts::load_hosts()", "Skipping malformed line: {}", line),
+        }
+    }
+
+    (hosts, bans)
+}
+
 /// Auxiliary function to filter localnet hosts.
 fn filter_localnet(input_addrs: Vec<Url>) -> Vec<Url> {
     debug!(target: "net::hosts::filter_localnet()", "hosts::filter_localnet() [Input addresses: {:?}]", input_addrs);
@@ -308,8 +619,14 @@ mod tests {
     use url::Url;
 
     use crate::net::{
-        constants::{IP4_PRIV_RANGES, IP6_PRIV_RANGES},
-        hosts::{filter_invalid, filter_localnet, filter_non_resolving, is_valid_onion},
+        constants::{
+            BAN_THRESHOLD, HOST_MAX_FAILURES, IP4_PRIV_RANGES, IP6_PRIV_RANGES,
+            MISBEHAVIOUR_DECAY_INTERVAL,
+        },
+        hosts::{
+            filter_invalid, filter_localnet, filter_non_resolving, is_valid_onion, peer_key,
+            HostInfo, Hosts,
+        },
     };
 
     #[test]
@@ -482,4 +799,119 @@ mod tests {
         // Invalid onion
         assert!(!is_valid_onion("facebook.com"));
     }
+
+    #[test]
+    fn test_host_scoring() {
+        async_std::task::block_on(async {
+            let good = Url::parse("tls://127.0.0.1:13333").unwrap();
+            let bad = Url::parse("tls://127.0.0.1:13334").unwrap();
+            let fresh = Url::parse("tls://127.0.0.1:13335").unwrap();
+
+            let hosts = Hosts::new(true, None);
+            hosts.store(vec![good.clone(), bad.clone(), fresh.clone()]).await;
+
+            hosts.mark_success(&good).await;
+            assert!(!hosts.mark_failure(&bad).await);
+            assert_eq!(hosts.load_by_score().await, vec![good, fresh, bad.clone()]);
+
+            // Keep failing until the host gets evicted
+            for _ in 1..HOST_MAX_FAILURES - 1 {
+                assert!(!hosts.mark_failure(&bad).await);
+            }
+            assert!(hosts.mark_failure(&bad).await);
+            assert!(hosts.get(&bad).await.is_none());
+        });
+    }
+
+    #[test]
+    fn test_hosts_file() {
+        async_std::task::block_on(async {
+            let path = std::env::temp_dir().join("darkfi_test_hosts_file");
+            let _ = std::fs::remove_file(&path);
+            let url = Url::parse("tls://127.0.0.1:13333").unwrap();
+
+            let hosts = Hosts::new(true, Some(path.clone()));
+            hosts.store(vec![url.clone()]).await;
+            hosts.mark_success(&url).await;
+            let info = hosts.get(&url).await.unwrap();
+            hosts.save().await.unwrap();
+
+            let hosts = Hosts::new(true, Some(path.clone()));
+            assert_eq!(hosts.get(&url).await, Some(info.clone()));
+            assert_eq!(HostInfo::from_line(&info.to_line(&url)), Some((url, info)));
+
+            std::fs::remove_file(path).unwrap();
+        });
+    }
+
+    #[test]
+    fn test_bans() {
+        async_std::task::block_on(async {
+            let path = std::env::temp_dir().join("darkfi_test_hosts_bans");
+            let _ = std::fs::remove_file(&path);
+            let url = Url::parse("tls://10.0.0.1:13333").unwrap();
+            // Inbound connections come from an ephemeral port
+            let inbound = Url::parse("tls://10.0.0.1:52146").unwrap();
+            let other = Url::parse("tls://10.0.0.2:13333").unwrap();
+
+            let hosts = Hosts::new(true, Some(path.clone()));
+            hosts.store(vec![url.clone(), other.clone()]).await;
+
+            // Misbehaviour accumulates until the threshold is reached
+            assert!(!hosts.misbehaved(&inbound, BAN_THRESHOLD - 1).await);
+            assert!(!hosts.is_banned(&url).await);
+            assert!(hosts.misbehaved(&inbound, 1).await);
+            assert!(hosts.is_banned(&url).await);
+            assert!(!hosts.is_banned(&other).await);
+
+            // Scores decay over time
+            assert!(!hosts.misbehaved_at(&other, BAN_THRESHOLD - 1, 1000).await);
+            let later = 1000 + 10 * MISBEHAVIOUR_DECAY_INTERVAL;
+            assert!(!hosts.misbehaved_at(&other, 10, later).await);
+            assert!(hosts.misbehaved_at(&other, 1, later).await);
+            hosts.unban("10.0.0.2").await;
+            hosts.store(vec![other.clone()]).await;
+
+            // Banned hosts are evicted and can't be stored again
+            assert!(hosts.get(&url).await.is_none());
+            hosts.store(vec![url.clone()]).await;
+            assert_eq!(hosts.load_all().await, vec![other.clone()]);
+
+            // Bans are persisted along with the hosts
+            hosts.save().await.unwrap();
+            let hosts = Hosts::new(true, Some(path.clone()));
+            assert!(hosts.is_banned(&url).await);
+            assert_eq!(hosts.load_all().await, vec![other.clone()]);
+
+            assert!(hosts.unban("10.0.0.1").await);
+            assert!(!hosts.is_banned(&url).await);
+            assert!(hosts.bans().await.is_empty());
+
+            // Expired bans are lifted
+            hosts.ban("10.0.0.2", 0).await;
+            assert!(!hosts.is_banned(&other).await);
+            assert!(hosts.bans().await.is_empty());
+
+            std::fs::remove_file(path).unwrap();
+        });
+    }
+
+    #[test]
+    fn test_peer_key() {
+        // Inbound peers are keyed by their host, whatever their port
+        let url = Url::parse("tcp+tls://10.0.0.1:52146").unwrap();
+        assert_eq!(peer_key(&url), "10.0.0.1");
+
+        // Inbound Tor peers come from the local Tor daemon, so they're keyed
+        // by their channel
+        let tor_a = Url::parse("tcp://127.0.0.1:52146").unwrap();
+        let tor_b = Url::parse("tcp://127.0.0.1:52147").unwrap();
+        assert_ne!(peer_key(&tor_a), peer_key(&tor_b));
+
+        // Nym peers behind the same gateway are keyed by their own address
+        let nym_a = Url::parse("nym://idA.encA@gateway").unwrap();
+        let nym_b = Url::parse("nym+tls://idB.encB@gateway").unwrap();
+        assert_eq!(peer_key(&nym_a), "idA.encA@gateway");
+        assert_ne!(peer_key(&nym_a), peer_key(&nym_b));
+    }
 }
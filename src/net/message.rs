@@ -16,7 +16,10 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use darkfi_serial::{Decodable, Encodable, SerialDecodable, SerialEncodable, VarInt};
+use crypto_api_chachapoly::ChachaPolyIetf;
+use darkfi_serial::{
+    deserialize, serialize, Decodable, Encodable, SerialDecodable, SerialEncodable, VarInt,
+};
 use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use log::debug;
 use url::Url;
@@ -25,9 +28,35 @@ use crate::{Error, Result};
 
 const MAGIC_BYTES: [u8; 4] = [0xd9, 0xef, 0xb6, 0x7d];
 
+/// Command of the packets wrapping an encrypted packet, once the
+/// authenticated handshake has been performed.
+pub const SEALED_COMMAND: &str = "sealed";
+
+/// Size of the authentication tag appended to sealed packets.
+const AEAD_TAG_SIZE: usize = 16;
+
+/// Maximum length of a packet command.
+pub const MAX_COMMAND_LENGTH: usize = 64;
+
+/// Initial buffer size when reading a payload. The buffer then grows as
+/// data actually arrives, so a peer can't make us allocate a big payload
+/// by only sending its header.
+const PAYLOAD_READ_CHUNK: usize = 64 * 1024;
+
+/// Bound on the memory the length-prefixed fields of a message may make
+/// us allocate while decoding it, as a multiple of its payload size.
+pub const DECODE_ALLOC_FACTOR: usize = 32;
+
 /// Generic message template.
 pub trait Message: 'static + Encodable + Decodable + Send + Sync {
     fn name() -> &'static str;
+
+    /// Maximum size of the encoded message. Packets carrying a bigger
+    /// payload are rejected before being read, and the peer gets penalised.
+    /// Messages without a limit are only bound by the frame size cap.
+    fn max_size() -> usize {
+        usize::MAX
+    }
 }
 
 /// Outbound keep-alive message.
@@ -72,22 +101,48 @@ pub struct VerackMessage {
     pub app: String,
 }
 
+/// Ephemeral key sent at the start of the authenticated handshake.
+#[derive(SerialEncodable, SerialDecodable)]
+pub struct AuthHelloMessage {
+    pub ephemeral: [u8; 32],
+}
+
+/// Long-term node public key, along with a signature of the handshake
+/// transcript proving ownership of it. Sent encrypted.
+#[derive(SerialEncodable, SerialDecodable)]
+pub struct AuthMessage {
+    pub public_key: [u8; 32],
+    pub signature: [u8; 64],
+}
+
 impl Message for PingMessage {
     fn name() -> &'static str {
         "ping"
     }
+
+    fn max_size() -> usize {
+        4
+    }
 }
 
 impl Message for PongMessage {
     fn name() -> &'static str {
         "pong"
     }
+
+    fn max_size() -> usize {
+        4
+    }
 }
 
 impl Message for GetAddrsMessage {
     fn name() -> &'static str {
         "getaddr"
     }
+
+    fn max_size() -> usize {
+        0
+    }
 }
 
 impl Message for AddrsMessage {
@@ -106,12 +161,40 @@ impl Message for VersionMessage {
     fn name() -> &'static str {
         "version"
     }
+
+    fn max_size() -> usize {
+        1024
+    }
 }
 
 impl Message for VerackMessage {
     fn name() -> &'static str {
         "verack"
     }
+
+    fn max_size() -> usize {
+        256
+    }
+}
+
+impl Message for AuthHelloMessage {
+    fn name() -> &'static str {
+        "auth_hello"
+    }
+
+    fn max_size() -> usize {
+        32
+    }
+}
+
+impl Message for AuthMessage {
+    fn name() -> &'static str {
+        "auth"
+    }
+
+    fn max_size() -> usize {
+        96
+    }
 }
 
 /// Packets are the base type read from the network. Converted to messages and
@@ -121,8 +204,79 @@ pub struct Packet {
     pub payload: Vec<u8>,
 }
 
-/// Reads and decodes an inbound payload.
-pub async fn read_packet<R: AsyncRead + Unpin + Sized>(stream: &mut R) -> Result<Packet> {
+/// Encrypts or decrypts packets travelling in one direction of a channel,
+/// using a key agreed on during the authenticated handshake. A sealed packet
+/// hides both the command and the payload of the packet it wraps. Nonces are
+/// a packet counter, so dropped, replayed or reordered packets fail to open.
+pub struct PacketCipher {
+    key: [u8; 32],
+    counter: u64,
+}
+
+impl PacketCipher {
+    pub fn new(key: [u8; 32]) -> Self {
+        Self { key, counter: 0 }
+    }
+
+    /// Return the nonce for the next packet and advance the counter.
+    fn next_nonce(&mut self) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        nonce[..8].copy_from_slice(&self.counter.to_le_bytes());
+        self.counter += 1;
+        nonce
+    }
+
+    /// Wrap a packet into an encrypted [`SEALED_COMMAND`] packet.
+    pub fn seal(&mut self, packet: Packet) -> Result<Packet> {
+        let plaintext = serialize(&(packet.command, packet.payload));
+        let nonce = self.next_nonce();
+
+        let mut payload = vec![0u8; plaintext.len() + AEAD_TAG_SIZE];
+        if ChachaPolyIetf::aead_cipher()
+            .seal_to(&mut payload, &plaintext, &[], &self.key, &nonce)
+            .is_err()
+        {
+            return Err(Error::MalformedPacket)
+        }
+
+        Ok(Packet { command: SEALED_COMMAND.to_string(), payload })
+    }
+
+    /// Decrypt a [`SEALED_COMMAND`] packet into the packet it wraps.
+    pub fn open(&mut self, packet: Packet) -> Result<Packet> {
+        if packet.command != SEALED_COMMAND || packet.payload.len() < AEAD_TAG_SIZE {
+            return Err(Error::MalformedPacket)
+        }
+
+        let nonce = self.next_nonce();
+        let mut plaintext = vec![0u8; packet.payload.len() - AEAD_TAG_SIZE];
+        if ChachaPolyIetf::aead_cipher()
+            .open_to(&mut plaintext, &packet.payload, &[], &self.key, &nonce)
+            .is_err()
+        {
+            return Err(Error::MalformedPacket)
+        }
+
+        let (command, payload): (String, Vec<u8>) = deserialize(&plaintext)?;
+        Ok(Packet { command, payload })
+    }
+}
+
+/// Reads and decodes an inbound payload, rejecting payloads bigger than `max_size`.
+pub async fn read_packet<R: AsyncRead + Unpin + Sized>(
+    stream: &mut R,
+    max_size: usize,
+) -> Result<Packet> {
+    let (command, payload_len) = read_packet_header(stream).await?;
+    let payload = read_payload(stream, payload_len, max_size).await?;
+    Ok(Packet { command, payload })
+}
+
+/// Reads the header of an inbound packet, returning its command and the
+/// length of the payload following it.
+pub async fn read_packet_header<R: AsyncRead + Unpin + Sized>(
+    stream: &mut R,
+) -> Result<(String, u64)> {
     // Packets have a 4 byte header of magic digits
     // This is used for network debugging
     let mut magic = [0u8; 4];
@@ -136,24 +290,46 @@ pub async fn read_packet<R: AsyncRead + Unpin + Sized>(stream: &mut R) -> Result
     }
 
     // The type of the message
-    let command_len = VarInt::decode_async(stream).await?.0 as usize;
-    let mut cmd = vec![0u8; command_len];
+    let command_len = VarInt::decode_async(stream).await?.0;
+    if command_len > MAX_COMMAND_LENGTH as u64 {
+        return Err(Error::MalformedPacket)
+    }
+    let mut cmd = vec![0u8; command_len as usize];
     if command_len > 0 {
         stream.read_exact(&mut cmd).await?;
     }
     let cmd = String::from_utf8(cmd)?;
     debug!(target: "net::message", "read command: {}", cmd);
 
-    let payload_len = VarInt::decode_async(stream).await?.0 as usize;
+    let payload_len = VarInt::decode_async(stream).await?.0;
+
+    Ok((cmd, payload_len))
+}
+
+/// Reads the payload of an inbound packet, rejecting it without reading
+/// it if its length is bigger than `max_size`.
+pub async fn read_payload<R: AsyncRead + Unpin + Sized>(
+    stream: &mut R,
+    payload_len: u64,
+    max_size: usize,
+) -> Result<Vec<u8>> {
+    if payload_len > max_size as u64 {
+        debug!(
+            target: "net::message",
+            "payload of {} bytes exceeds limit of {} bytes", payload_len, max_size
+        );
+        return Err(Error::PacketTooLarge)
+    }
 
     // The message-dependent data (see message types)
-    let mut payload = vec![0u8; payload_len];
-    if payload_len > 0 {
-        stream.read_exact(&mut payload).await?;
+    let mut payload = Vec::with_capacity(std::cmp::min(payload_len as usize, PAYLOAD_READ_CHUNK));
+    stream.take(payload_len).read_to_end(&mut payload).await?;
+    if payload.len() as u64 != payload_len {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into())
     }
     debug!(target: "net::message", "read payload {} bytes", payload_len);
 
-    Ok(Packet { command: cmd, payload })
+    Ok(payload)
 }
 
 /// Sends an outbound packet by writing data to TCP stream.
@@ -180,3 +356,85 @@ pub async fn send_packet<W: AsyncWrite + Unpin + Sized>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::io::Cursor;
+
+    /// Encode a packet header claiming a payload of `payload_len` bytes.
+    fn header(command: &[u8], payload_len: u64) -> Vec<u8> {
+        let mut frame = MAGIC_BYTES.to_vec();
+        frame.extend_from_slice(&serialize(&VarInt(command.len() as u64)));
+        frame.extend_from_slice(command);
+        frame.extend_from_slice(&serialize(&VarInt(payload_len)));
+        frame
+    }
+
+    #[test]
+    fn test_read_packet_roundtrip() {
+        smol::block_on(async {
+            let packet = Packet { command: "ping".to_string(), payload: serialize(&42u32) };
+            let mut buf = vec![];
+            send_packet(&mut buf, packet).await.unwrap();
+
+            let packet = read_packet(&mut Cursor::new(buf), PingMessage::max_size()).await.unwrap();
+            assert_eq!(packet.command, "ping");
+            let ping: PingMessage = deserialize(&packet.payload).unwrap();
+            assert_eq!(ping.nonce, 42);
+        });
+    }
+
+    #[test]
+    fn test_read_packet_hostile_frames() {
+        smol::block_on(async {
+            // A huge advertised payload is rejected before anything gets allocated
+            let frame = header(b"ping", u64::MAX);
+            let res = read_packet(&mut Cursor::new(frame), 8 * 1024 * 1024).await;
+            assert!(matches!(res, Err(Error::PacketTooLarge)));
+
+            // Payloads are bounded by the size limit of their message
+            let mut frame = header(b"ping", 5);
+            frame.extend_from_slice(&[0u8; 5]);
+            let res = read_packet(&mut Cursor::new(frame), PingMessage::max_size()).await;
+            assert!(matches!(res, Err(Error::PacketTooLarge)));
+
+            // Oversized commands are malformed
+            let frame = header(&[b'a'; MAX_COMMAND_LENGTH + 1], 0);
+            let res = read_packet(&mut Cursor::new(frame), usize::MAX).await;
+            assert!(matches!(res, Err(Error::MalformedPacket)));
+
+            // So are frames with the wrong magic bytes
+            let mut frame = header(b"ping", 0);
+            frame[0] = 0;
+            let res = read_packet(&mut Cursor::new(frame), usize::MAX).await;
+            assert!(matches!(res, Err(Error::MalformedPacket)));
+
+            // A payload shorter than advertised fails instead of blocking
+            let mut frame = header(b"ping", 1024);
+            frame.extend_from_slice(&[0u8; 16]);
+            let res = read_packet(&mut Cursor::new(frame), usize::MAX).await;
+            assert!(matches!(res, Err(Error::Io(std::io::ErrorKind::UnexpectedEof))));
+        });
+    }
+
+    #[test]
+    fn test_packet_cipher() {
+        let key = [7u8; 32];
+        let mut sender = PacketCipher::new(key);
+        let mut receiver = PacketCipher::new(key);
+
+        let packet = Packet { command: "ping".to_string(), payload: serialize(&42u32) };
+        let sealed = sender.seal(packet).unwrap();
+        assert_eq!(sealed.command, SEALED_COMMAND);
+        let opened = receiver.open(sealed).unwrap();
+        assert_eq!(opened.command, "ping");
+        assert_eq!(opened.payload, serialize(&42u32));
+
+        // Tampered packets fail to open
+        let packet = Packet { command: "ping".to_string(), payload: serialize(&43u32) };
+        let mut sealed = sender.seal(packet).unwrap();
+        sealed.payload[0] ^= 1;
+        assert!(matches!(receiver.open(sealed), Err(Error::MalformedPacket)));
+    }
+}
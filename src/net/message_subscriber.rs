@@ -20,12 +20,13 @@ use std::{any::Any, collections::HashMap, io::Cursor, sync::Arc};
 
 use async_std::sync::Mutex;
 use async_trait::async_trait;
+use darkfi_serial::DecodeContext;
 use log::{debug, warn};
 use rand::Rng;
 
 use crate::{Error, Result};
 
-use super::message::Message;
+use super::message::{Message, DECODE_ALLOC_FACTOR};
 
 /// 64bit identifier for message subscription.
 pub type MessageSubscriptionId = u64;
@@ -59,10 +60,12 @@ impl<M: Message> MessageSubscription<M> {
 #[async_trait]
 /// Generic interface for message dispatcher.
 trait MessageDispatcherInterface: Send + Sync {
-    async fn trigger(&self, payload: Vec<u8>);
+    async fn trigger(&self, payload: Vec<u8>) -> Result<()>;
 
     async fn trigger_error(&self, err: Error);
 
+    fn max_size(&self) -> usize;
+
     fn as_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync>;
 }
 
@@ -147,14 +150,19 @@ impl<M: Message> MessageDispatcher<M> {
 // Local implementation of the Message Dispatcher Interface.
 impl<M: Message> MessageDispatcherInterface for MessageDispatcher<M> {
     /// Internal function to deserialize data into a message type and dispatch it across subscriber channels.
-    async fn trigger(&self, payload: Vec<u8>) {
+    /// Returns an error if the data can't be decoded.
+    async fn trigger(&self, payload: Vec<u8>) -> Result<()> {
         // deserialize data into type
         // send down the pipes
+        // Length prefixes in the payload can't make us allocate much
+        // more memory than what the payload itself takes.
+        let ctx = DecodeContext::new(payload.len().saturating_mul(DECODE_ALLOC_FACTOR));
         let cursor = Cursor::new(payload);
-        match M::decode(cursor) {
+        match ctx.decode::<M, _>(cursor) {
             Ok(message) => {
                 let message = Ok(Arc::new(message));
-                self._trigger_all(message).await
+                self._trigger_all(message).await;
+                Ok(())
             }
             Err(err) => {
                 debug!(
@@ -162,6 +170,7 @@ impl<M: Message> MessageDispatcherInterface for MessageDispatcher<M> {
                     "Unable to decode data. Dropping...: {}",
                     err
                 );
+                Err(err.into())
             }
         }
     }
@@ -171,6 +180,11 @@ impl<M: Message> MessageDispatcherInterface for MessageDispatcher<M> {
         self._trigger_all(Err(err)).await;
     }
 
+    /// Maximum size of the encoded message.
+    fn max_size(&self) -> usize {
+        M::max_size()
+    }
+
     /// Converts to Any trait. Enables the dynamic modification of static types.
     fn as_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
         self
@@ -218,21 +232,25 @@ impl MessageSubsystem {
         Ok(sub)
     }
 
+    /// Returns the maximum payload size of a command, if it has a dispatcher.
+    pub async fn max_size(&self, command: &str) -> Option<usize> {
+        self.dispatchers.lock().await.get(command).map(|dispatcher| dispatcher.max_size())
+    }
+
     /// Transmits a payload to a dispatcher. Returns an error if the payload
     /// fails to transmit.
-    pub async fn notify(&self, command: &str, payload: Vec<u8>) {
+    pub async fn notify(&self, command: &str, payload: Vec<u8>) -> Result<()> {
         let dispatcher = self.dispatchers.lock().await.get(command).cloned();
 
         match dispatcher {
-            Some(dispatcher) => {
-                dispatcher.trigger(payload).await;
-            }
+            Some(dispatcher) => dispatcher.trigger(payload).await,
             None => {
                 warn!(
                     target: "net::message_subscriber::notify()",
                     "Command '{}' did not find a dispatcher",
                     command
                 );
+                Ok(())
             }
         }
     }
@@ -306,7 +324,7 @@ mod tests {
         // receive message and publish
         //   1. based on string, lookup relevant dispatcher interface
         //   2. publish data there
-        subsystem.notify("verver", payload).await;
+        subsystem.notify("verver", payload).await.unwrap();
 
         // receive
         //    1. do a get easy
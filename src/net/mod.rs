@@ -103,6 +103,9 @@ pub mod session;
 /// Network configuration settings.
 pub mod settings;
 
+/// Traffic accounting of channels, and rate limiting of inbound messages.
+pub mod stats;
+
 /// Network transport implementations.
 pub mod transport;
 
@@ -22,8 +22,9 @@ use std::{
 };
 
 use async_std::sync::{Arc, Mutex};
+use ed25519_compact::{KeyPair, Seed};
 use futures::{select, stream::FuturesUnordered, try_join, FutureExt, StreamExt, TryFutureExt};
-use log::{debug, error, warn};
+use log::{debug, error, info, warn};
 use rand::Rng;
 use serde_json::json;
 use smol::Executor;
@@ -31,14 +32,15 @@ use url::Url;
 
 use crate::{
     system::{Subscriber, SubscriberPtr, Subscription},
-    util::async_util::sleep,
-    Result,
+    util::{async_util::sleep, path::expand_path},
+    Error, Result,
 };
 
 use super::{
     message::Message,
     protocol::{register_default_protocols, ProtocolRegistry},
     session::{InboundSession, ManualSession, OutboundSession, SeedSyncSession, Session},
+    stats::ChannelStats,
     Channel, ChannelPtr, Hosts, HostsPtr, Settings, SettingsPtr,
 };
 
@@ -94,6 +96,9 @@ pub struct P2p {
 
     settings: SettingsPtr,
 
+    /// Long-term node identity used in the authenticated handshake
+    auth_keypair: KeyPair,
+
     /// Flag to check if on discovery mode
     discovery: Mutex<bool>,
 }
@@ -109,21 +114,44 @@ impl P2p {
     pub async fn new(settings: Settings) -> Arc<Self> {
         let settings = Arc::new(settings);
 
+        let hosts_file = match settings.hosts_file.as_ref().map(|path| expand_path(path)) {
+            Some(Ok(path)) => Some(path),
+            Some(Err(e)) => {
+                error!(target: "net::p2p::new()", "Invalid hosts file path: {}", e);
+                None
+            }
+            None => None,
+        };
+
+        let auth_keypair = match settings.auth_secret.as_ref().map(|s| parse_auth_secret(s)) {
+            Some(Ok(keypair)) => keypair,
+            Some(Err(e)) => {
+                error!(target: "net::p2p::new()", "Invalid auth secret, using a random identity: {}", e);
+                KeyPair::generate()
+            }
+            None => KeyPair::generate(),
+        };
+
         let self_ = Arc::new(Self {
             pending: Mutex::new(HashSet::new()),
             channels: Mutex::new(HashMap::new()),
             channel_subscriber: Subscriber::new(),
             stop_subscriber: Subscriber::new(),
-            hosts: Hosts::new(settings.localnet),
+            hosts: Hosts::new(settings.localnet, hosts_file),
             protocol_registry: ProtocolRegistry::new(),
             session_manual: Mutex::new(None),
             session_inbound: Mutex::new(None),
             session_outbound: Mutex::new(None),
             state: Mutex::new(P2pState::Open),
             settings,
+            auth_keypair,
             discovery: Mutex::new(false),
         });
 
+        if self_.settings.auth_handshake {
+            info!(target: "net::p2p::new()", "Node public key: {}", self_.auth_public_key());
+        }
+
         let parent = Arc::downgrade(&self_);
 
         *self_.session_manual.lock().await = Some(ManualSession::new(parent.clone()));
@@ -153,6 +181,26 @@ impl P2p {
     }
     // ANCHOR_END: get_info
 
+    /// Return the traffic counters of every connected channel, along with
+    /// the totals per command and across all channels.
+    pub async fn get_stats(&self) -> serde_json::Value {
+        let mut channels = HashMap::new();
+        let mut total = ChannelStats::default();
+        for (addr, channel) in self.channels.lock().await.iter() {
+            let stats = channel.stats().await;
+            total.total.merge(&stats.total);
+            for (command, command_stats) in &stats.commands {
+                total.commands.entry(command.clone()).or_default().merge(command_stats);
+            }
+            channels.insert(addr.to_string(), stats.get_info());
+        }
+
+        json!({
+            "channels": channels,
+            "total": total.get_info(),
+        })
+    }
+
     /// Invoke startup and seeding sequence. Call from constructing thread.
     // ANCHOR: start
     pub async fn start(self: Arc<Self>, executor: Arc<Executor<'_>>) -> Result<()> {
@@ -332,6 +380,10 @@ impl P2p {
 
     // ANCHOR: stop
     pub async fn stop(&self) {
+        if let Err(e) = self.hosts.save().await {
+            error!(target: "net::p2p::stop()", "Failed saving hosts: {}", e);
+        }
+
         self.stop_subscriber.notify(()).await
     }
     // ANCHOR_END: stop
@@ -445,6 +497,25 @@ impl P2p {
         self.pending.lock().await.remove(addr);
     }
 
+    /// Ban a peer host for the given number of seconds, and stop any
+    /// channel we currently have open with it.
+    pub async fn ban(&self, host: &str, duration: u64) {
+        self.hosts.ban(host, duration).await;
+
+        let channels: Vec<ChannelPtr> = self
+            .channels
+            .lock()
+            .await
+            .iter()
+            .filter(|(addr, _)| addr.host_str() == Some(host))
+            .map(|(_, channel)| channel.clone())
+            .collect();
+
+        for channel in channels {
+            channel.stop().await;
+        }
+    }
+
     /// Return the number of connected channels.
     pub async fn connections_count(&self) -> usize {
         self.channels.lock().await.len()
@@ -460,6 +531,16 @@ impl P2p {
         self.hosts.clone()
     }
 
+    /// Return the long-term keypair used in the authenticated handshake.
+    pub fn auth_keypair(&self) -> &KeyPair {
+        &self.auth_keypair
+    }
+
+    /// Return the base58 encoded public key of the node identity.
+    pub fn auth_public_key(&self) -> String {
+        bs58::encode(*self.auth_keypair.pk).into_string()
+    }
+
     pub fn protocol_registry(&self) -> &ProtocolRegistry {
         &self.protocol_registry
     }
@@ -507,3 +588,12 @@ impl P2p {
         Some(values.nth(rand::thread_rng().gen_range(0..values.len())).unwrap().clone())
     }
 }
+
+/// Auxiliary function to parse a base58 encoded ed25519 seed into a keypair.
+fn parse_auth_secret(secret: &str) -> Result<KeyPair> {
+    let bytes = bs58::decode(secret).into_vec()?;
+    let Ok(seed) = Seed::from_slice(&bytes) else {
+        return Err(Error::ParseFailed("Auth secret must be a 32 byte seed"))
+    };
+    Ok(KeyPair::from_seed(seed))
+}
@@ -64,10 +64,17 @@ pub mod protocol_seed;
 /// other node and sending the version acknowledgement.
 pub mod protocol_version;
 
+/// Optional authenticated handshake run after the version exchange. Nodes
+/// exchange ephemeral keys to encrypt the channel, then prove ownership of
+/// their long-term public key by signing the handshake transcript. Peers
+/// can then be pinned by public key, independently of TLS certificates.
+pub mod protocol_auth;
+
 pub mod protocol_base;
 pub mod protocol_registry;
 
 pub use protocol_address::ProtocolAddress;
+pub use protocol_auth::ProtocolAuth;
 pub use protocol_jobs_manager::{ProtocolJobsManager, ProtocolJobsManagerPtr};
 pub use protocol_ping::ProtocolPing;
 pub use protocol_seed::ProtocolSeed;
@@ -19,7 +19,7 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use log::debug;
+use log::{debug, warn};
 use rand::seq::SliceRandom;
 use smol::Executor;
 
@@ -27,8 +27,10 @@ use crate::{util::async_util, Result};
 
 use super::{
     super::{
-        message, message_subscriber::MessageSubscription, ChannelPtr, HostsPtr, P2pPtr,
-        SettingsPtr, SESSION_OUTBOUND,
+        constants::{HOSTS_MAX_SIZE, MISBEHAVIOUR_OVERSIZED_ADDRS},
+        message,
+        message_subscriber::MessageSubscription,
+        ChannelPtr, HostsPtr, P2pPtr, SettingsPtr, SESSION_OUTBOUND,
     },
     ProtocolBase, ProtocolBasePtr, ProtocolJobsManager, ProtocolJobsManagerPtr,
 };
@@ -85,6 +87,23 @@ impl ProtocolAddress {
         })
     }
 
+    /// Penalise the peer if it advertised more addresses than a host
+    /// store can hold. Returns `true` if the addresses should be dropped.
+    async fn oversized_addrs(&self, len: usize) -> bool {
+        if len <= HOSTS_MAX_SIZE {
+            return false
+        }
+
+        warn!(
+            target: "net::protocol_address::oversized_addrs()",
+            "Peer {} advertised {} addrs, dropping them",
+            self.channel.address(),
+            len
+        );
+        self.channel.misbehaved(MISBEHAVIOUR_OVERSIZED_ADDRS).await;
+        true
+    }
+
     /// Handles receiving the address message. Loops to continually recieve
     /// address messages on the address subsciption. Adds the recieved
     /// addresses to the list of hosts.
@@ -97,6 +116,9 @@ impl ProtocolAddress {
                 "received {} addrs",
                 addrs_msg.addrs.len()
             );
+            if self.oversized_addrs(addrs_msg.addrs.len()).await {
+                continue
+            }
             self.hosts.store(addrs_msg.addrs.clone()).await;
         }
     }
@@ -113,6 +135,9 @@ impl ProtocolAddress {
                 "ProtocolAddress::handle_receive_ext_addrs() received {} addrs",
                 ext_addrs_msg.ext_addrs.len()
             );
+            if self.oversized_addrs(ext_addrs_msg.ext_addrs.len()).await {
+                continue
+            }
             self.hosts.store_ext(self.channel.address(), ext_addrs_msg.ext_addrs.clone()).await;
         }
     }
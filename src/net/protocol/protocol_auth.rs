@@ -0,0 +1,181 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use async_std::future::timeout;
+use std::{sync::Arc, time::Duration};
+
+use ed25519_compact::{x25519, KeyPair, PublicKey, Signature};
+use log::*;
+
+use crate::{Error, Result};
+
+use super::super::{
+    message::{self, PacketCipher},
+    message_subscriber::MessageSubscription,
+    ChannelPtr, SettingsPtr,
+};
+
+/// Domain separation context for the channel keys derivation.
+const KEY_CONTEXT: &str = "DarkFi P2P v1 channel key";
+/// Domain separation prefix of the signed handshake transcript.
+const SIGNATURE_CONTEXT: &[u8] = b"DarkFi P2P v1 auth";
+
+/// Implements the authenticated handshake run after the version exchange.
+///
+/// Both nodes send an ephemeral x25519 key, and derive a key for each
+/// direction of the channel from their Diffie-Hellman shared secret. Every
+/// packet sent afterwards is encrypted. Then both nodes send their long-term
+/// ed25519 public key, along with a signature of the handshake transcript.
+/// Since the transcript contains both ephemeral keys, a man in the middle
+/// running separate handshakes with each node can't relay the signatures.
+pub struct ProtocolAuth {
+    channel: ChannelPtr,
+    hello_sub: MessageSubscription<message::AuthHelloMessage>,
+    auth_sub: MessageSubscription<message::AuthMessage>,
+    settings: SettingsPtr,
+    keypair: KeyPair,
+}
+
+impl ProtocolAuth {
+    /// Create a new authentication protocol, using the given long-term keypair.
+    pub async fn new(channel: ChannelPtr, settings: SettingsPtr, keypair: KeyPair) -> Arc<Self> {
+        let hello_sub = channel
+            .clone()
+            .subscribe_msg::<message::AuthHelloMessage>()
+            .await
+            .expect("Missing auth_hello dispatcher!");
+
+        let auth_sub = channel
+            .clone()
+            .subscribe_msg::<message::AuthMessage>()
+            .await
+            .expect("Missing auth dispatcher!");
+
+        Arc::new(Self { channel, hello_sub, auth_sub, settings, keypair })
+    }
+
+    /// Run the handshake, timing out after the configured handshake duration.
+    /// The channel gets stopped if the peer fails to authenticate.
+    pub async fn run(self: Arc<Self>) -> Result<()> {
+        debug!(target: "net::protocol_auth::run()", "START");
+        let result = timeout(
+            Duration::from_secs(self.settings.channel_handshake_seconds.into()),
+            self.clone().handshake(),
+        )
+        .await;
+
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                error!(
+                    target: "net::protocol_auth::run()",
+                    "Authentication with {} failed: {}",
+                    self.channel.address(),
+                    e
+                );
+                self.channel.stop().await;
+                return Err(e)
+            }
+            Err(_) => {
+                self.channel.stop().await;
+                return Err(Error::ChannelTimeout)
+            }
+        }
+
+        debug!(target: "net::protocol_auth::run()", "END");
+        Ok(())
+    }
+
+    /// Exchange ephemeral keys, enable channel encryption, then exchange
+    /// and verify long-term identities.
+    async fn handshake(self: Arc<Self>) -> Result<()> {
+        let ephemeral = x25519::KeyPair::generate();
+        let hello = message::AuthHelloMessage { ephemeral: *ephemeral.pk };
+        self.channel.send(hello).await?;
+
+        let hello = self.hello_sub.receive().await?;
+        let their_ephemeral = x25519::PublicKey::new(hello.ephemeral);
+        let shared = match their_ephemeral.dh(&ephemeral.sk) {
+            Ok(v) => v,
+            Err(_) => return Err(Error::PeerAuthFailed("Invalid ephemeral key".to_string())),
+        };
+
+        let transcript = transcript_hash(&ephemeral.pk, &their_ephemeral);
+        let send_key = derive_key(&shared[..], &transcript, &ephemeral.pk);
+        let recv_key = derive_key(&shared[..], &transcript, &their_ephemeral);
+        self.channel
+            .enable_encryption(PacketCipher::new(send_key), PacketCipher::new(recv_key))
+            .await;
+
+        // From now on everything is encrypted
+        let signature = self.keypair.sk.sign(signed_data(&transcript, &ephemeral.pk), None);
+        let auth = message::AuthMessage { public_key: *self.keypair.pk, signature: *signature };
+        self.channel.send(auth).await?;
+
+        let auth = self.auth_sub.receive().await?;
+        let public_key = PublicKey::new(auth.public_key);
+        let signature = Signature::new(auth.signature);
+        if public_key.verify(signed_data(&transcript, &their_ephemeral), &signature).is_err() {
+            return Err(Error::PeerAuthFailed("Invalid transcript signature".to_string()))
+        }
+
+        let public_key = bs58::encode(*public_key).into_string();
+        if !self.settings.trusted_keys.is_empty() &&
+            !self.settings.trusted_keys.contains(&public_key)
+        {
+            return Err(Error::PeerAuthFailed(format!("Untrusted public key {}", public_key)))
+        }
+
+        info!(
+            target: "net::protocol_auth::handshake()",
+            "Authenticated {} as {}",
+            self.channel.address(),
+            public_key
+        );
+        self.channel.set_remote_public_key(public_key).await;
+
+        Ok(())
+    }
+}
+
+/// Hash both ephemeral keys in a canonical order, so both nodes end up
+/// with the same transcript.
+fn transcript_hash(a: &x25519::PublicKey, b: &x25519::PublicKey) -> [u8; 32] {
+    let (first, second) = if a[..] < b[..] { (a, b) } else { (b, a) };
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&first[..]);
+    hasher.update(&second[..]);
+    *hasher.finalize().as_bytes()
+}
+
+/// Derive the key encrypting the packets sent by the owner of `ephemeral`.
+fn derive_key(shared: &[u8], transcript: &[u8; 32], ephemeral: &x25519::PublicKey) -> [u8; 32] {
+    let mut material = shared.to_vec();
+    material.extend_from_slice(transcript);
+    material.extend_from_slice(&ephemeral[..]);
+    blake3::derive_key(KEY_CONTEXT, &material)
+}
+
+/// Data signed by the owner of `ephemeral`. Including the signer's ephemeral
+/// key prevents a signature from being reflected back to its sender.
+fn signed_data(transcript: &[u8; 32], ephemeral: &x25519::PublicKey) -> Vec<u8> {
+    let mut data = SIGNATURE_CONTEXT.to_vec();
+    data.extend_from_slice(transcript);
+    data.extend_from_slice(&ephemeral[..]);
+    data
+}
@@ -25,7 +25,7 @@ use smol::Executor;
 use url::Url;
 
 use crate::{
-    net::transport::TransportName,
+    net::{settings::get_address_transports, transport::TransportName},
     system::{StoppableTask, StoppableTaskPtr, Subscriber, SubscriberPtr, Subscription},
     util::async_util::sleep,
     Error, Result,
@@ -101,8 +101,9 @@ impl ManualSession {
         let transports = if outbound_transports.contains(&addr_transport) {
             vec![addr_transport]
         } else {
-            warn!(target: "net::manual_session", "Manual outbound address {} transport is not in accepted outbound transports, will try with: {:?}", addr, outbound_transports);
-            outbound_transports.clone()
+            let transports = get_address_transports(&addr_transport, outbound_transports);
+            warn!(target: "net::manual_session", "Manual outbound address {} transport is not in accepted outbound transports, will try with: {:?}", addr, transports);
+            transports
         };
 
         loop {
@@ -113,6 +114,12 @@ impl ManualSession {
                 break
             }
 
+            if self.p2p().hosts().is_banned(&addr).await {
+                warn!(target: "net::manual_session", "Manual peer [{}] is banned, not connecting", addr);
+                sleep(settings.connect_timeout_seconds.into()).await;
+                continue
+            }
+
             self.p2p().add_pending(addr.clone()).await;
 
             for transport in &transports {
@@ -19,12 +19,16 @@
 use std::sync::{Arc, Weak};
 
 use async_trait::async_trait;
-use log::debug;
+use log::{debug, warn};
 use smol::Executor;
 
-use crate::Result;
+use crate::{Error, Result};
 
-use super::{p2p::P2pPtr, protocol::ProtocolVersion, ChannelPtr};
+use super::{
+    p2p::P2pPtr,
+    protocol::{ProtocolAuth, ProtocolVersion},
+    ChannelPtr,
+};
 
 /// Seed sync session creates a connection to the seed nodes specified in settings.
 /// A new seed sync session is created every time we call p2p::start(). The seed
@@ -122,11 +126,18 @@ pub trait Session: Sync {
     ) -> Result<()> {
         debug!(target: "net", "Session::register_channel() [START]");
 
+        // Banned peers are dropped right away
+        let p2p = self.p2p();
+        if p2p.hosts().is_banned(&channel.address()).await {
+            warn!(target: "net", "Refusing channel with banned peer {}", channel.address());
+            channel.stop().await;
+            return Err(Error::PeerBanned)
+        }
+
         // Protocols should all be initialized but not started
         // We do this so that the protocols can begin receiving and buffering messages
         // while the handshake protocol is ongoing.
         // They are currently in sleep mode.
-        let p2p = self.p2p();
         let protocols =
             p2p.protocol_registry().attach(self.type_id(), channel.clone(), p2p.clone()).await;
 
@@ -134,8 +145,20 @@ pub trait Session: Sync {
         let protocol_version =
             ProtocolVersion::new(channel.clone(), p2p.settings().clone(), p2p.hosts().clone())
                 .await;
-        let handshake_task =
-            self.perform_handshake_protocols(protocol_version, channel.clone(), executor.clone());
+        // Subscribe to the authenticated handshake messages before the channel starts
+        let protocol_auth = match p2p.settings().auth_handshake {
+            true => Some(
+                ProtocolAuth::new(channel.clone(), p2p.settings(), p2p.auth_keypair().clone())
+                    .await,
+            ),
+            false => None,
+        };
+        let handshake_task = self.perform_handshake_protocols(
+            protocol_version,
+            protocol_auth,
+            channel.clone(),
+            executor.clone(),
+        );
 
         // Switch on the channel
         channel.start(executor.clone());
@@ -164,12 +187,18 @@ pub trait Session: Sync {
     async fn perform_handshake_protocols(
         &self,
         protocol_version: Arc<ProtocolVersion>,
+        protocol_auth: Option<Arc<ProtocolAuth>>,
         channel: ChannelPtr,
         executor: Arc<Executor<'_>>,
     ) -> Result<()> {
         // Perform handshake
         protocol_version.run(executor.clone()).await?;
 
+        // Authenticate the peer and encrypt the channel, if configured
+        if let Some(protocol_auth) = protocol_auth {
+            protocol_auth.run().await?;
+        }
+
         // Channel is now initialized
 
         // Add channel to p2p
@@ -21,13 +21,12 @@ use std::fmt;
 use async_std::sync::{Arc, Mutex, Weak};
 use async_trait::async_trait;
 use log::{debug, error, info, warn};
-use rand::seq::SliceRandom;
 use serde_json::{json, Value};
 use smol::Executor;
 use url::Url;
 
 use crate::{
-    net::{message, transport::TransportName},
+    net::{message, settings::get_address_transports, transport::TransportName},
     system::{StoppableTask, StoppableTaskPtr, Subscriber, SubscriberPtr, Subscription},
     util::async_util,
     Error, Result,
@@ -204,8 +203,9 @@ impl OutboundSession {
         let transports = if outbound_transports.contains(&addr_transport) {
             vec![addr_transport]
         } else {
-            warn!(target: "net::outbound_session", "#{} address {} transport is not in accepted outbound transports, will try with: {:?}", slot_number, addr, outbound_transports);
-            outbound_transports.clone()
+            let transports = get_address_transports(&addr_transport, outbound_transports);
+            warn!(target: "net::outbound_session", "#{} address {} transport is not in accepted outbound transports, will try with: {:?}", slot_number, addr, transports);
+            transports
         };
 
         for transport in transports {
@@ -229,6 +229,7 @@ impl OutboundSession {
 
                     // Remove pending lock since register_channel will add the channel to p2p
                     self.p2p().remove_pending(&addr).await;
+                    self.p2p().hosts().mark_success(&addr).await;
                     {
                         let info = &mut self.slot_info.lock().await[slot_number as usize];
                         info.channel = Some(channel.clone());
@@ -251,8 +252,11 @@ impl OutboundSession {
             }
         }
 
-        // Remove url from hosts
-        self.p2p().hosts().remove(&addr).await;
+        // Demote url, evicting it from hosts if it keeps failing
+        if self.p2p().hosts().mark_failure(&addr).await {
+            info!(target: "net::outbound_session", "#{} removed [{}] from hosts", slot_number, addr);
+        }
+        self.p2p().remove_pending(&addr).await;
 
         {
             let info = &mut self.slot_info.lock().await[slot_number as usize];
@@ -279,14 +283,8 @@ impl OutboundSession {
             let p2p = self.p2p();
             let self_inbound_addr = p2p.settings().external_addr.clone();
 
-            let mut addrs;
-
-            {
-                let hosts = p2p.hosts().load_all().await;
-                addrs = hosts;
-            }
-
-            addrs.shuffle(&mut rand::thread_rng());
+            // Well behaved hosts come first
+            let addrs = p2p.hosts().load_by_score().await;
 
             for addr in addrs {
                 if p2p.exists(&addr).await? {
@@ -298,6 +296,11 @@ impl OutboundSession {
                     continue
                 }
 
+                // Skip banned peers
+                if p2p.hosts().is_banned(&addr).await {
+                    continue
+                }
+
                 // Obtain a lock on this address to prevent duplicate connections
                 if !p2p.add_pending(addr.clone()).await {
                     continue
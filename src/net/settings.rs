@@ -47,6 +47,13 @@ pub struct Settings {
     pub channel_heartbeat_seconds: u32,
     /// Try to fill an outbound slot interval
     pub outbound_retry_seconds: u64,
+    /// Maximum size of an inbound packet payload, in bytes
+    pub max_frame_size: usize,
+    /// Maximum number of messages per second accepted from a peer, 0 for no limit.
+    /// Reading from peers going over the limit is paused until the next second.
+    pub inbound_msg_rate_limit: u32,
+    /// Maximum number of payload bytes per second accepted from a peer, 0 for no limit
+    pub inbound_byte_rate_limit: u64,
     /// P2P external addresses node advertises so other peers can reach us
     /// and connect to us, as long us inbound addresses are also configured
     pub external_addr: Vec<Url>,
@@ -67,6 +74,17 @@ pub struct Settings {
     pub peer_discovery: bool,
     /// Enable channel logging
     pub channel_log: bool,
+    /// Path to the file known hosts are persisted to, if any
+    pub hosts_file: Option<String>,
+    /// Run the authenticated handshake after the version exchange,
+    /// encrypting the channel with the agreed keys
+    pub auth_handshake: bool,
+    /// Base58 encoded ed25519 seed of the node identity used in the
+    /// authenticated handshake. A random identity is used when not set.
+    pub auth_secret: Option<String>,
+    /// Base58 encoded public keys of trusted peers. When not empty, peers
+    /// authenticating with any other key get disconnected.
+    pub trusted_keys: Vec<String>,
 }
 
 impl Default for Settings {
@@ -80,6 +98,9 @@ impl Default for Settings {
             channel_handshake_seconds: 4,
             channel_heartbeat_seconds: 10,
             outbound_retry_seconds: 20,
+            max_frame_size: 8 * 1024 * 1024,
+            inbound_msg_rate_limit: 0,
+            inbound_byte_rate_limit: 0,
             external_addr: Vec::new(),
             peers: Vec::new(),
             seeds: Vec::new(),
@@ -89,6 +110,10 @@ impl Default for Settings {
             localnet: false,
             peer_discovery: true,
             channel_log: false,
+            hosts_file: None,
+            auth_handshake: false,
+            auth_secret: None,
+            trusted_keys: Vec::new(),
         }
     }
 }
@@ -147,6 +172,18 @@ pub struct SettingsOpt {
     #[structopt(skip)]
     pub outbound_retry_seconds: Option<u64>,
 
+    /// Maximum size of an inbound packet payload, in bytes
+    #[structopt(skip)]
+    pub max_frame_size: Option<usize>,
+
+    /// Maximum number of messages per second accepted from a peer, 0 for no limit
+    #[structopt(long)]
+    pub inbound_msg_rate_limit: Option<u32>,
+
+    /// Maximum number of payload bytes per second accepted from a peer, 0 for no limit
+    #[structopt(long)]
+    pub inbound_byte_rate_limit: Option<u64>,
+
     /// Only used for debugging. Compromises privacy when set.
     #[serde(default)]
     #[structopt(skip)]
@@ -176,6 +213,26 @@ pub struct SettingsOpt {
     #[serde(default)]
     #[structopt(long)]
     pub channel_log: bool,
+
+    /// Path to the file known hosts are persisted to
+    #[serde(default)]
+    #[structopt(long)]
+    pub hosts_file: Option<String>,
+
+    /// Run the authenticated handshake with peers
+    #[serde(default)]
+    #[structopt(long)]
+    pub auth_handshake: bool,
+
+    /// Base58 encoded ed25519 seed of the node identity
+    #[serde(default)]
+    #[structopt(long)]
+    pub auth_secret: Option<String>,
+
+    /// Base58 encoded public keys of trusted peers
+    #[serde(default)]
+    #[structopt(long)]
+    pub trusted_keys: Vec<String>,
 }
 
 impl From<SettingsOpt> for Settings {
@@ -189,6 +246,9 @@ impl From<SettingsOpt> for Settings {
             channel_handshake_seconds: settings_opt.channel_handshake_seconds.unwrap_or(4),
             channel_heartbeat_seconds: settings_opt.channel_heartbeat_seconds.unwrap_or(10),
             outbound_retry_seconds: settings_opt.outbound_retry_seconds.unwrap_or(1200),
+            max_frame_size: settings_opt.max_frame_size.unwrap_or(8 * 1024 * 1024),
+            inbound_msg_rate_limit: settings_opt.inbound_msg_rate_limit.unwrap_or(0),
+            inbound_byte_rate_limit: settings_opt.inbound_byte_rate_limit.unwrap_or(0),
             external_addr: settings_opt.external_addr,
             peers: settings_opt.peers,
             seeds: settings_opt.seeds,
@@ -198,6 +258,10 @@ impl From<SettingsOpt> for Settings {
             localnet: settings_opt.localnet,
             peer_discovery: settings_opt.peer_discovery,
             channel_log: settings_opt.channel_log,
+            hosts_file: settings_opt.hosts_file,
+            auth_handshake: settings_opt.auth_handshake,
+            auth_secret: settings_opt.auth_secret,
+            trusted_keys: settings_opt.trusted_keys,
         }
     }
 }
@@ -221,6 +285,24 @@ pub fn get_outbound_transports(opt_outbound_transports: Vec<String>) -> Vec<Tran
     outbound_transports
 }
 
+/// Auxiliary function to select which of the preferred outbound transports
+/// can be used to reach an address using a different transport.
+/// Nym addresses live in their own namespace, so they can only be dialed
+/// through the Nym transport, which in turn can't dial anything else.
+pub fn get_address_transports(
+    addr_transport: &TransportName,
+    outbound_transports: &[TransportName],
+) -> Vec<TransportName> {
+    outbound_transports
+        .iter()
+        .filter(|transport| {
+            matches!(transport, TransportName::Nym(_)) ==
+                matches!(addr_transport, TransportName::Nym(_))
+        })
+        .cloned()
+        .collect()
+}
+
 /// Auxiliary function to set serde bool value to true.
 fn default_as_true() -> bool {
     true
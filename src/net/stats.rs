@@ -0,0 +1,193 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use serde_json::json;
+
+/// Command under which received messages without a dispatcher are counted.
+/// Peers choose the commands they send, so only those we know about get
+/// their own counters.
+pub const UNKNOWN_COMMAND: &str = "unknown";
+
+/// Byte and message counters of a channel, or of a single message command.
+/// Bytes are counted as message payload sizes, before encryption.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrafficStats {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub msgs_sent: u64,
+    pub msgs_received: u64,
+}
+
+impl TrafficStats {
+    fn add_sent(&mut self, bytes: usize) {
+        self.bytes_sent += bytes as u64;
+        self.msgs_sent += 1;
+    }
+
+    fn add_received(&mut self, bytes: usize) {
+        self.bytes_received += bytes as u64;
+        self.msgs_received += 1;
+    }
+
+    /// Add the counters of `other` to ours.
+    pub fn merge(&mut self, other: &TrafficStats) {
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_received += other.bytes_received;
+        self.msgs_sent += other.msgs_sent;
+        self.msgs_received += other.msgs_received;
+    }
+
+    pub fn get_info(&self) -> serde_json::Value {
+        json!({
+            "bytes_sent": self.bytes_sent,
+            "bytes_received": self.bytes_received,
+            "msgs_sent": self.msgs_sent,
+            "msgs_received": self.msgs_received,
+        })
+    }
+}
+
+/// Traffic accounting of a channel, in total and per message command.
+#[derive(Clone, Debug, Default)]
+pub struct ChannelStats {
+    pub total: TrafficStats,
+    pub commands: HashMap<String, TrafficStats>,
+}
+
+impl ChannelStats {
+    /// Account for a message of `bytes` payload size sent with `command`.
+    pub fn record_sent(&mut self, command: &str, bytes: usize) {
+        self.total.add_sent(bytes);
+        self.commands.entry(command.to_string()).or_default().add_sent(bytes);
+    }
+
+    /// Account for a message of `bytes` payload size received with `command`.
+    pub fn record_received(&mut self, command: &str, bytes: usize) {
+        self.total.add_received(bytes);
+        self.commands.entry(command.to_string()).or_default().add_received(bytes);
+    }
+
+    pub fn get_info(&self) -> serde_json::Value {
+        let commands: HashMap<&String, serde_json::Value> =
+            self.commands.iter().map(|(command, stats)| (command, stats.get_info())).collect();
+
+        json!({
+            "total": self.total.get_info(),
+            "commands": commands,
+        })
+    }
+}
+
+/// Limits the rate of messages and bytes received from a peer over one
+/// second windows. A limit of 0 disables it.
+pub struct RateLimiter {
+    max_msgs: u32,
+    max_bytes: u64,
+    window_start: Instant,
+    msgs: u32,
+    bytes: u64,
+}
+
+impl RateLimiter {
+    pub fn new(max_msgs: u32, max_bytes: u64) -> Self {
+        Self { max_msgs, max_bytes, window_start: Instant::now(), msgs: 0, bytes: 0 }
+    }
+
+    /// Account for a received message of `bytes` payload size. If the peer
+    /// went over the limits of the current window, returns how long to wait
+    /// until the window ends.
+    pub fn record(&mut self, bytes: usize) -> Option<Duration> {
+        self.record_at(Instant::now(), bytes)
+    }
+
+    fn record_at(&mut self, now: Instant, bytes: usize) -> Option<Duration> {
+        const WINDOW: Duration = Duration::from_secs(1);
+
+        if now.duration_since(self.window_start) >= WINDOW {
+            self.window_start = now;
+            self.msgs = 0;
+            self.bytes = 0;
+        }
+
+        self.msgs = self.msgs.saturating_add(1);
+        self.bytes = self.bytes.saturating_add(bytes as u64);
+
+        if (self.max_msgs != 0 && self.msgs > self.max_msgs) ||
+            (self.max_bytes != 0 && self.bytes > self.max_bytes)
+        {
+            return Some(WINDOW - now.duration_since(self.window_start))
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_stats() {
+        let mut stats = ChannelStats::default();
+        stats.record_sent("ping", 4);
+        stats.record_received("pong", 4);
+        stats.record_received("addr", 100);
+
+        assert_eq!(
+            stats.total,
+            TrafficStats { bytes_sent: 4, bytes_received: 104, msgs_sent: 1, msgs_received: 2 }
+        );
+        assert_eq!(stats.commands["addr"].bytes_received, 100);
+        assert_eq!(stats.commands["ping"].msgs_sent, 1);
+        assert_eq!(stats.commands["ping"].msgs_received, 0);
+
+        let mut total = TrafficStats::default();
+        total.merge(&stats.total);
+        total.merge(&stats.total);
+        assert_eq!(total.bytes_received, 208);
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let mut limiter = RateLimiter::new(2, 0);
+        let start = limiter.window_start;
+        assert!(limiter.record_at(start, 10).is_none());
+        assert!(limiter.record_at(start, 10).is_none());
+        assert!(limiter.record_at(start, 10).is_some());
+        // A new window resets the counters
+        assert!(limiter.record_at(start + Duration::from_secs(1), 10).is_none());
+
+        let mut limiter = RateLimiter::new(0, 100);
+        let start = limiter.window_start;
+        assert!(limiter.record_at(start, 100).is_none());
+        let wait = limiter.record_at(start + Duration::from_millis(300), 1).unwrap();
+        assert_eq!(wait, Duration::from_millis(700));
+
+        // No limits configured
+        let mut limiter = RateLimiter::new(0, 0);
+        let start = limiter.window_start;
+        for _ in 0..1000 {
+            assert!(limiter.record_at(start, usize::MAX).is_none());
+        }
+    }
+}
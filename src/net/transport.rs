@@ -37,6 +37,9 @@ pub use tor::TorTransport;
 mod unix;
 pub use unix::UnixTransport;
 
+mod nym;
+pub use nym::{NymListener, NymStream, NymTransport};
+
 /// A helper function to convert SocketAddr to Url and add scheme
 pub(crate) fn socket_addr_to_url(addr: SocketAddr, scheme: &str) -> Result<Url> {
     let url = Url::parse(&format!("{}://{}", scheme, addr))?;
@@ -0,0 +1,727 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, Weak,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use async_std::{net::TcpStream, sync::Arc, task};
+use async_trait::async_trait;
+use async_tungstenite::{tungstenite::Message as WsMessage, WebSocketStream};
+use futures::{prelude::*, stream::SplitStream};
+use futures_rustls::{TlsAcceptor, TlsStream};
+use log::{debug, error, warn};
+use rand::{rngs::OsRng, RngCore};
+use smol::channel;
+use url::Url;
+
+use super::{TlsUpgrade, Transport, TransportListener, TransportStream};
+use crate::{Error, Result};
+
+/// Length of a serialized Nym recipient: identity key, encryption key
+/// and gateway identity, 32 bytes each.
+pub const RECIPIENT_LENGTH: usize = 96;
+
+/// Binary representation of a Nym mixnet address
+pub type Recipient = [u8; RECIPIENT_LENGTH];
+
+// Nym client websocket API request tags
+const SEND_REQUEST_TAG: u8 = 0x00;
+const SELF_ADDRESS_REQUEST_TAG: u8 = 0x02;
+
+// Nym client websocket API response tags
+const ERROR_RESPONSE_TAG: u8 = 0x00;
+const RECEIVED_RESPONSE_TAG: u8 = 0x01;
+const SELF_ADDRESS_RESPONSE_TAG: u8 = 0x02;
+
+// Frame kinds multiplexing our streams on top of the mixnet messages
+const FRAME_OPEN: u8 = 0x00;
+const FRAME_DATA: u8 = 0x01;
+const FRAME_CLOSE: u8 = 0x02;
+
+/// Length of a frame header: kind, connection id and sequence number
+const FRAME_HEADER_LENGTH: usize = 17;
+
+/// Maximum number of out-of-order frames buffered for a single stream
+const MAX_PENDING_FRAMES: usize = 1024;
+
+/// Maximum number of streams multiplexed over a single Nym client
+const MAX_STREAMS: usize = 1024;
+
+/// Maximum number of unopened streams we buffer frames for
+const MAX_EARLY_STREAMS: usize = 64;
+
+/// Maximum number of frames buffered for a single unopened stream
+const MAX_EARLY_FRAMES: usize = 64;
+
+/// Time after which frames of a stream that was never opened are dropped
+const EARLY_FRAMES_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connections to local Nym clients, keyed by the client websocket url.
+/// A Nym client serves a single websocket connection, so every transport
+/// instance pointing to the same client shares it.
+static NYM_CLIENTS: Mutex<Vec<(Url, Weak<NymClient>)>> = Mutex::new(Vec::new());
+
+/// Encode a binary recipient into a `nym://` url of the form
+/// `nym://<identity>.<encryption>@<gateway>`.
+pub fn recipient_to_url(recipient: &Recipient, scheme: &str) -> Result<Url> {
+    let identity = bs58::encode(&recipient[..32]).into_string();
+    let encryption = bs58::encode(&recipient[32..64]).into_string();
+    let gateway = bs58::encode(&recipient[64..]).into_string();
+    Ok(Url::parse(&format!("{}://{}.{}@{}", scheme, identity, encryption, gateway))?)
+}
+
+/// Decode a `nym://<identity>.<encryption>@<gateway>` url into a binary recipient.
+pub fn url_to_recipient(url: &Url) -> Result<Recipient> {
+    let keys: Vec<&str> = url.username().split('.').collect();
+    let gateway = url.host_str();
+    if keys.len() != 2 || gateway.is_none() {
+        return Err(Error::NymError(format!("Invalid Nym address: {}", url)))
+    }
+
+    let mut recipient = [0u8; RECIPIENT_LENGTH];
+    for (i, part) in [keys[0], keys[1], gateway.unwrap()].iter().enumerate() {
+        let bytes = bs58::decode(part).into_vec()?;
+        if bytes.len() != 32 {
+            return Err(Error::NymError(format!("Invalid Nym address: {}", url)))
+        }
+        recipient[i * 32..(i + 1) * 32].copy_from_slice(&bytes);
+    }
+
+    Ok(recipient)
+}
+
+/// Build a `Send` request for the Nym client carrying `data` to `recipient`.
+fn send_request(recipient: &Recipient, data: &[u8]) -> Vec<u8> {
+    let mut req = Vec::with_capacity(2 + RECIPIENT_LENGTH + 8 + data.len());
+    req.push(SEND_REQUEST_TAG);
+    // We never attach reply SURBs, our frames carry the return address.
+    req.push(0);
+    req.extend_from_slice(recipient);
+    req.extend_from_slice(&(data.len() as u64).to_be_bytes());
+    req.extend_from_slice(data);
+    req
+}
+
+/// Build a stream frame to be sent through the mixnet.
+fn frame(kind: u8, conn_id: u64, seq: u64, data: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LENGTH + data.len());
+    frame.push(kind);
+    frame.extend_from_slice(&conn_id.to_le_bytes());
+    frame.extend_from_slice(&seq.to_le_bytes());
+    frame.extend_from_slice(data);
+    frame
+}
+
+/// Parse a `Received` response from the Nym client, returning the message data.
+fn parse_received(resp: &[u8]) -> Result<&[u8]> {
+    let malformed = || Error::NymError("Malformed response from Nym client".to_string());
+
+    if resp.len() < 2 || resp[0] != RECEIVED_RESPONSE_TAG {
+        return Err(malformed())
+    }
+
+    let mut pos = 2;
+    // Skip over the reply SURB, if any.
+    if resp[1] == 1 {
+        let surb_len = resp.get(pos..pos + 8).ok_or_else(malformed)?;
+        let surb_len = u64::from_be_bytes(surb_len.try_into().unwrap()) as usize;
+        pos = pos.checked_add(8 + surb_len).ok_or_else(malformed)?;
+    }
+
+    let data_len = resp.get(pos..pos + 8).ok_or_else(malformed)?;
+    let data_len = u64::from_be_bytes(data_len.try_into().unwrap()) as usize;
+    pos += 8;
+
+    resp.get(pos..pos.checked_add(data_len).ok_or_else(malformed)?).ok_or_else(malformed)
+}
+
+/// Receiving end of a multiplexed stream. The mixnet does not preserve
+/// message ordering, so frames are buffered until they can be delivered
+/// in sequence.
+#[derive(Default)]
+struct StreamState {
+    /// Channel to the local stream, `None` until the open frame is delivered
+    sender: Option<channel::Sender<Vec<u8>>>,
+    /// Sequence number of the next frame to deliver
+    next_seq: u64,
+    /// Frames received ahead of `next_seq`, keyed by sequence number
+    pending: BTreeMap<u64, (u8, Vec<u8>)>,
+}
+
+impl StreamState {
+    fn new(sender: channel::Sender<Vec<u8>>) -> Self {
+        Self { sender: Some(sender), ..Default::default() }
+    }
+}
+
+/// Frames that overtook the open frame of their stream.
+struct EarlyFrames {
+    /// Time the first of these frames was received
+    received: Instant,
+    /// Frames keyed by sequence number
+    frames: BTreeMap<u64, (u8, Vec<u8>)>,
+}
+
+/// Shared connection to a local Nym client websocket.
+struct NymClient {
+    /// Our own mixnet address, as reported by the Nym client
+    self_address: Recipient,
+    /// Queue of raw requests to be written to the websocket
+    outgoing: channel::Sender<Vec<u8>>,
+    /// Open streams multiplexed over this client, keyed by connection id
+    streams: Mutex<HashMap<u64, StreamState>>,
+    /// Frames of inbound streams that weren't opened yet, keyed by connection id
+    early: Mutex<HashMap<u64, EarlyFrames>>,
+    /// Queue of inbound streams, when a listener is active
+    listener: Mutex<Option<channel::Sender<NymStream>>>,
+}
+
+impl NymClient {
+    /// Get the shared client for the given websocket url, connecting to it
+    /// if there is no live connection yet.
+    async fn get(client_url: &Url) -> Result<Arc<Self>> {
+        {
+            let mut clients = NYM_CLIENTS.lock().unwrap();
+            clients.retain(|(_, client)| client.strong_count() > 0);
+            if let Some(client) =
+                clients.iter().find(|(url, _)| url == client_url).and_then(|(_, c)| c.upgrade())
+            {
+                return Ok(client)
+            }
+        }
+
+        let client = Self::connect(client_url).await?;
+        NYM_CLIENTS.lock().unwrap().push((client_url.clone(), Arc::downgrade(&client)));
+        Ok(client)
+    }
+
+    /// Open the websocket, query our own address and start the IO loops.
+    async fn connect(client_url: &Url) -> Result<Arc<Self>> {
+        debug!(target: "net::nym", "Connecting to Nym client at {}", client_url);
+        let socket_addr = client_url.socket_addrs(|| None)?[0];
+        let stream = TcpStream::connect(socket_addr).await?;
+        let (ws, _) = async_tungstenite::client_async(client_url.as_str(), stream).await?;
+        let (mut sink, mut ws_stream) = ws.split();
+
+        sink.send(WsMessage::Binary(vec![SELF_ADDRESS_REQUEST_TAG])).await?;
+        let self_address = loop {
+            match ws_stream.next().await {
+                Some(Ok(WsMessage::Binary(resp))) => match resp.first() {
+                    Some(&SELF_ADDRESS_RESPONSE_TAG) if resp.len() == 1 + RECIPIENT_LENGTH => {
+                        break resp[1..].try_into().unwrap()
+                    }
+                    Some(&ERROR_RESPONSE_TAG) => {
+                        return Err(Error::NymError(format!(
+                            "Nym client error: {}",
+                            String::from_utf8_lossy(resp.get(10..).unwrap_or_default())
+                        )))
+                    }
+                    _ => continue,
+                },
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(e.into()),
+                None => return Err(Error::NymError("Nym client closed the connection".into())),
+            }
+        };
+
+        let (outgoing, outgoing_recv) = channel::unbounded::<Vec<u8>>();
+        let client = Arc::new(Self {
+            self_address,
+            outgoing,
+            streams: Mutex::new(HashMap::new()),
+            early: Mutex::new(HashMap::new()),
+            listener: Mutex::new(None),
+        });
+
+        task::spawn(async move {
+            while let Ok(req) = outgoing_recv.recv().await {
+                if let Err(e) = sink.send(WsMessage::Binary(req)).await {
+                    error!(target: "net::nym", "Failed writing to Nym client: {}", e);
+                    break
+                }
+            }
+            let _ = sink.close().await;
+        });
+
+        task::spawn(Self::read_loop(Arc::downgrade(&client), ws_stream));
+
+        Ok(client)
+    }
+
+    /// Read messages coming from the mixnet and dispatch them to their streams.
+    async fn read_loop(client: Weak<Self>, mut ws_stream: SplitStream<WebSocketStream<TcpStream>>) {
+        while let Some(msg) = ws_stream.next().await {
+            let resp = match msg {
+                Ok(WsMessage::Binary(resp)) => resp,
+                Ok(WsMessage::Close(_)) => break,
+                Ok(_) => continue,
+                Err(e) => {
+                    error!(target: "net::nym", "Failed reading from Nym client: {}", e);
+                    break
+                }
+            };
+
+            let Some(client) = client.upgrade() else { break };
+
+            if resp.first() == Some(&ERROR_RESPONSE_TAG) {
+                warn!(target: "net::nym", "Nym client returned an error response");
+                continue
+            }
+
+            let data = match parse_received(&resp) {
+                Ok(data) => data,
+                Err(e) => {
+                    debug!(target: "net::nym", "Dropping message: {}", e);
+                    continue
+                }
+            };
+
+            if data.len() < FRAME_HEADER_LENGTH {
+                debug!(target: "net::nym", "Dropping truncated frame");
+                continue
+            }
+
+            let conn_id = u64::from_le_bytes(data[1..9].try_into().unwrap());
+            let seq = u64::from_le_bytes(data[9..17].try_into().unwrap());
+            client.dispatch(data[0], conn_id, seq, &data[FRAME_HEADER_LENGTH..]);
+        }
+
+        debug!(target: "net::nym", "Nym client read loop stopped");
+    }
+
+    /// Buffer a received frame and deliver every frame of its stream that
+    /// is now in sequence.
+    fn dispatch(self: &Arc<Self>, kind: u8, conn_id: u64, seq: u64, payload: &[u8]) {
+        // A stream the listener didn't take, dropped once the lock is released
+        let mut rejected = None;
+        let mut streams = self.streams.lock().unwrap();
+
+        if !streams.contains_key(&conn_id) {
+            if self.listener.lock().unwrap().is_none() {
+                debug!(target: "net::nym", "Not listening, dropping frame");
+                return
+            }
+
+            // Only the open frame, which is the first of a stream, creates
+            // its state. Frames of inbound streams may overtake it, so they
+            // are kept aside for a while. Frames of streams we already
+            // closed end up there as well, and simply expire.
+            if kind != FRAME_OPEN || seq != 0 {
+                self.buffer_early_frame(kind, conn_id, seq, payload);
+                return
+            }
+
+            if streams.len() >= MAX_STREAMS {
+                warn!(target: "net::nym", "Too many streams, dropping open frame");
+                return
+            }
+
+            let mut state = StreamState::default();
+            if let Some(early) = self.early.lock().unwrap().remove(&conn_id) {
+                state.pending = early.frames;
+            }
+            streams.insert(conn_id, state);
+        }
+
+        let state = streams.get_mut(&conn_id).unwrap();
+        if seq < state.next_seq || state.pending.len() >= MAX_PENDING_FRAMES {
+            debug!(target: "net::nym", "Dropping duplicate or excess frame");
+            return
+        }
+        state.pending.insert(seq, (kind, payload.to_vec()));
+
+        let mut closed = false;
+        while let Some((kind, data)) = state.pending.remove(&state.next_seq) {
+            state.next_seq += 1;
+
+            match kind {
+                FRAME_OPEN => {
+                    if state.sender.is_some() {
+                        debug!(target: "net::nym", "Dropping open frame of an open stream");
+                        continue
+                    }
+
+                    let Ok(peer) = Recipient::try_from(data.as_slice()) else {
+                        debug!(target: "net::nym", "Dropping malformed open frame");
+                        closed = true;
+                        break
+                    };
+
+                    let Some(listener) = self.listener.lock().unwrap().clone() else {
+                        debug!(target: "net::nym", "Not listening, dropping open frame");
+                        closed = true;
+                        break
+                    };
+
+                    let (stream, sender) = NymStream::with_channel(self.clone(), conn_id, peer);
+                    state.sender = Some(sender);
+                    if let Err(e) = listener.try_send(stream) {
+                        rejected = Some(e.into_inner());
+                        break
+                    }
+                }
+                FRAME_DATA => match &state.sender {
+                    Some(sender) => {
+                        let _ = sender.try_send(data);
+                    }
+                    None => debug!(target: "net::nym", "Dropping data frame of unopened stream"),
+                },
+                FRAME_CLOSE => {
+                    closed = true;
+                    break
+                }
+                _ => debug!(target: "net::nym", "Dropping frame of unknown kind"),
+            }
+        }
+
+        if closed {
+            streams.remove(&conn_id);
+        }
+
+        drop(streams);
+        drop(rejected);
+    }
+
+    /// Keep a frame received before the open frame of its stream,
+    /// within the early frames limits.
+    fn buffer_early_frame(&self, kind: u8, conn_id: u64, seq: u64, payload: &[u8]) {
+        let now = Instant::now();
+        let mut early = self.early.lock().unwrap();
+        early.retain(|_, e| now.duration_since(e.received) < EARLY_FRAMES_TIMEOUT);
+
+        if !early.contains_key(&conn_id) && early.len() >= MAX_EARLY_STREAMS {
+            debug!(target: "net::nym", "Too many unopened streams, dropping frame");
+            return
+        }
+
+        let entry = early
+            .entry(conn_id)
+            .or_insert_with(|| EarlyFrames { received: now, frames: BTreeMap::new() });
+        if entry.frames.len() >= MAX_EARLY_FRAMES {
+            debug!(target: "net::nym", "Too many frames for unopened stream, dropping frame");
+            return
+        }
+        entry.frames.insert(seq, (kind, payload.to_vec()));
+    }
+}
+
+/// A bidirectional byte stream to a peer, carried over the Nym mixnet.
+pub struct NymStream {
+    client: Arc<NymClient>,
+    conn_id: u64,
+    peer: Recipient,
+    /// Sequence number of the next frame we send
+    send_seq: AtomicU64,
+    incoming: channel::Receiver<Vec<u8>>,
+    read_buf: Vec<u8>,
+}
+
+impl NymStream {
+    /// Create a new stream and register it with the client.
+    fn new(client: Arc<NymClient>, conn_id: u64, peer: Recipient) -> Self {
+        let (stream, sender) = Self::with_channel(client.clone(), conn_id, peer);
+        client.streams.lock().unwrap().insert(conn_id, StreamState::new(sender));
+        stream
+    }
+
+    /// Create a new stream, returning the sender feeding it. The caller
+    /// is responsible for registering it with the client.
+    fn with_channel(
+        client: Arc<NymClient>,
+        conn_id: u64,
+        peer: Recipient,
+    ) -> (Self, channel::Sender<Vec<u8>>) {
+        let (sender, incoming) = channel::unbounded();
+        let stream =
+            Self { client, conn_id, peer, send_seq: AtomicU64::new(0), incoming, read_buf: vec![] };
+        (stream, sender)
+    }
+
+    /// Mixnet address of the remote end.
+    pub fn peer_address(&self) -> &Recipient {
+        &self.peer
+    }
+
+    fn send_frame(&self, kind: u8, data: &[u8]) -> io::Result<()> {
+        let seq = self.send_seq.fetch_add(1, Ordering::SeqCst);
+        let req = send_request(&self.peer, &frame(kind, self.conn_id, seq, data));
+        self.client
+            .outgoing
+            .try_send(req)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Nym client disconnected"))
+    }
+}
+
+impl Drop for NymStream {
+    fn drop(&mut self) {
+        if self.client.streams.lock().unwrap().remove(&self.conn_id).is_some() {
+            let _ = self.send_frame(FRAME_CLOSE, &[]);
+        }
+    }
+}
+
+impl AsyncRead for NymStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.read_buf.is_empty() {
+            match self.incoming.poll_next_unpin(cx) {
+                Poll::Ready(Some(data)) => self.read_buf = data,
+                // Sender was dropped, so the peer closed the stream.
+                Poll::Ready(None) => return Poll::Ready(Ok(0)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        let n = std::cmp::min(buf.len(), self.read_buf.len());
+        buf[..n].copy_from_slice(&self.read_buf[..n]);
+        self.read_buf.drain(..n);
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl AsyncWrite for NymStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(self.send_frame(FRAME_DATA, buf).map(|_| buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if self.client.streams.lock().unwrap().remove(&self.conn_id).is_some() {
+            return Poll::Ready(self.send_frame(FRAME_CLOSE, &[]))
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl TransportStream for NymStream {}
+
+/// Accepts streams opened towards our own mixnet address.
+pub struct NymListener {
+    client: Arc<NymClient>,
+    incoming: channel::Receiver<NymStream>,
+}
+
+impl NymListener {
+    /// Our own mixnet address, which peers have to dial.
+    pub fn self_address(&self) -> &Recipient {
+        &self.client.self_address
+    }
+
+    /// Wait for the next inbound stream.
+    pub async fn accept(&self) -> Result<(NymStream, Url)> {
+        let stream = match self.incoming.recv().await {
+            Ok(s) => s,
+            Err(_) => return Err(Error::NymError("Nym client disconnected".to_string())),
+        };
+        let url = recipient_to_url(stream.peer_address(), "nym")?;
+        Ok((stream, url))
+    }
+}
+
+impl Drop for NymListener {
+    fn drop(&mut self) {
+        *self.client.listener.lock().unwrap() = None;
+    }
+}
+
+#[async_trait]
+impl TransportListener for NymListener {
+    async fn next(&self) -> Result<(Box<dyn TransportStream>, Url)> {
+        let (stream, url) = match self.accept().await {
+            Ok((s, u)) => (s, u),
+            Err(err) => {
+                error!(target: "net::nym", "Error listening for connections: {}", err);
+                return Err(Error::AcceptConnectionFailed(
+                    recipient_to_url(self.self_address(), "nym")?.to_string(),
+                ))
+            }
+        };
+        Ok((Box::new(stream), url))
+    }
+}
+
+#[async_trait]
+impl TransportListener for (TlsAcceptor, NymListener) {
+    async fn next(&self) -> Result<(Box<dyn TransportStream>, Url)> {
+        let (stream, url) = match self.1.accept().await {
+            Ok((s, u)) => (s, u),
+            Err(err) => {
+                error!(target: "net::nym", "Error listening for connections: {}", err);
+                return Err(Error::AcceptConnectionFailed(
+                    recipient_to_url(self.1.self_address(), "nym+tls")?.to_string(),
+                ))
+            }
+        };
+
+        let stream = self.0.accept(stream).await;
+        let mut url = url;
+        url.set_scheme("nym+tls").unwrap();
+
+        if let Err(err) = stream {
+            error!(target: "net::nym", "Error wrapping the connection {} with tls: {}", url, err);
+            return Err(Error::AcceptTlsConnectionFailed(
+                recipient_to_url(self.1.self_address(), "nym+tls")?.to_string(),
+            ))
+        }
+
+        Ok((Box::new(TlsStream::Server(stream?)), url))
+    }
+}
+
+/// Implements communication through the Nym mixnet.
+///
+/// A Nym client must be running locally, and the transport speaks to it
+/// through its websocket interface. Nym addresses are represented as
+/// `nym://<identity>.<encryption>@<gateway>` urls.
+///
+/// The mixnet only carries messages, so connections are emulated by
+/// multiplexing frames tagged with a random connection id. When dialing,
+/// an open frame carrying our own address is sent first, so the listener
+/// knows where to send its replies. Messages may be delivered out of
+/// order, so every frame also carries a per-connection sequence number
+/// and the receiving end reorders them before handing data to the stream.
+#[derive(Clone)]
+pub struct NymTransport {
+    /// Websocket url of the local Nym client
+    client_url: Url,
+    /// Connection to the Nym client, kept once established
+    client: Option<Arc<NymClient>>,
+}
+
+impl NymTransport {
+    /// Creates a new NymTransport
+    ///
+    /// # Arguments
+    ///
+    /// * `client_url` - url of the Nym client websocket. For example ws://127.0.0.1:1977
+    pub fn new(client_url: Url) -> Result<Self> {
+        match client_url.scheme() {
+            "ws" => Ok(Self { client_url, client: None }),
+            x => Err(Error::UnsupportedTransport(x.to_string())),
+        }
+    }
+
+    /// Query the environment for the Nym client url, or fallback to default
+    pub fn get_client_env() -> Result<Url> {
+        Ok(Url::parse(
+            &std::env::var("DARKFI_NYM_CLIENT_URL")
+                .unwrap_or_else(|_| "ws://127.0.0.1:1977".to_string()),
+        )?)
+    }
+
+    /// Returns the mixnet address of our local Nym client, as a url with the given scheme.
+    /// The client connection is kept, so later listening or dialing reuses it.
+    pub async fn self_address(&mut self, scheme: &str) -> Result<Url> {
+        let client = self.client().await?;
+        let url = recipient_to_url(&client.self_address, scheme);
+        self.client = Some(client);
+        url
+    }
+
+    /// Get the connection to our Nym client, connecting if needed.
+    async fn client(&self) -> Result<Arc<NymClient>> {
+        match &self.client {
+            Some(client) => Ok(client.clone()),
+            None => NymClient::get(&self.client_url).await,
+        }
+    }
+
+    async fn do_listen(self) -> Result<NymListener> {
+        let client = self.client().await?;
+
+        let (sender, incoming) = channel::unbounded();
+        {
+            let mut listener = client.listener.lock().unwrap();
+            if listener.is_some() {
+                return Err(Error::NymError("Nym client already has a listener".to_string()))
+            }
+            *listener = Some(sender);
+        }
+
+        Ok(NymListener { client, incoming })
+    }
+
+    async fn do_dial(self, url: Url) -> Result<NymStream> {
+        let peer = url_to_recipient(&url)?;
+        let client = self.client().await?;
+
+        let stream = NymStream::new(client.clone(), OsRng.next_u64(), peer);
+        stream.send_frame(FRAME_OPEN, &client.self_address)?;
+        Ok(stream)
+    }
+}
+
+impl Transport for NymTransport {
+    type Acceptor = NymListener;
+    type Connector = NymStream;
+
+    type Listener = Pin<Box<dyn Future<Output = Result<Self::Acceptor>> + Send>>;
+    type Dial = Pin<Box<dyn Future<Output = Result<Self::Connector>> + Send>>;
+
+    type TlsListener = Pin<Box<dyn Future<Output = Result<(TlsAcceptor, Self::Acceptor)>> + Send>>;
+    type TlsDialer = Pin<Box<dyn Future<Output = Result<TlsStream<Self::Connector>>> + Send>>;
+
+    fn listen_on(self, url: Url) -> Result<Self::Listener> {
+        match url.scheme() {
+            "nym" | "nym+tls" => {}
+            x => return Err(Error::UnsupportedTransport(x.to_string())),
+        }
+
+        debug!(target: "net::nym", "{} transport: listening through {}", url.scheme(), self.client_url);
+        Ok(Box::pin(self.do_listen()))
+    }
+
+    fn upgrade_listener(self, acceptor: Self::Acceptor) -> Result<Self::TlsListener> {
+        let tlsupgrade = TlsUpgrade::new();
+        Ok(Box::pin(tlsupgrade.upgrade_listener_tls(acceptor)))
+    }
+
+    fn dial(self, url: Url, _timeout: Option<Duration>) -> Result<Self::Dial> {
+        match url.scheme() {
+            "nym" | "nym+tls" => {}
+            x => return Err(Error::UnsupportedTransport(x.to_string())),
+        }
+
+        debug!(target: "net::nym", "{} transport: dialing {}", url.scheme(), url);
+        Ok(Box::pin(self.do_dial(url)))
+    }
+
+    fn upgrade_dialer(self, connector: Self::Connector) -> Result<Self::TlsDialer> {
+        let tlsupgrade = TlsUpgrade::new();
+        Ok(Box::pin(tlsupgrade.upgrade_dialer_tls(connector)))
+    }
+}
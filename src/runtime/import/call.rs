@@ -0,0 +1,147 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::io::Cursor;
+
+use darkfi_sdk::{
+    crypto::ContractId,
+    error::{CALLER_ACCESS_DENIED, CALL_CONTRACT_FAILED, REENTRANT_CALL},
+};
+use darkfi_serial::Decodable;
+use log::{debug, error};
+use wasmer::{FunctionEnvMut, WasmPtr};
+use wasmer_middlewares::metering::{get_remaining_points, set_remaining_points, MeteringPoints};
+
+use crate::runtime::vm_runtime::{ContractSection, Env, Runtime};
+
+/// Maximum depth of nested contract calls
+const MAX_CALL_DEPTH: usize = 8;
+
+/// Exec, metadata and query sections can call this. Invokes the read-only
+/// query section of another deployed contract and returns an object index
+/// holding the data it returned. The called contract runs with the gas the
+/// caller has left, and whatever it consumes is charged to the caller.
+pub(crate) fn call_contract(mut ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i64 {
+    let env = ctx.data();
+    match env.contract_section {
+        ContractSection::Exec | ContractSection::Metadata | ContractSection::Query => {
+            let memory_view = env.memory_view(&ctx);
+
+            let Ok(mem_slice) = ptr.slice(&memory_view, len) else {
+                error!(target: "runtime::call::call_contract()", "Failed to make slice from ptr");
+                return CALL_CONTRACT_FAILED
+            };
+
+            let mut buf = vec![0_u8; len as usize];
+            if let Err(e) = mem_slice.read_slice(&mut buf) {
+                error!(target: "runtime::call::call_contract()", "Failed to read from memory slice: {}", e);
+                return CALL_CONTRACT_FAILED
+            };
+
+            let mut buf_reader = Cursor::new(buf);
+
+            let cid: ContractId = match Decodable::decode(&mut buf_reader) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(target: "runtime::call::call_contract()", "Failed to decode ContractId: {}", e);
+                    return CALL_CONTRACT_FAILED
+                }
+            };
+
+            let payload: Vec<u8> = match Decodable::decode(&mut buf_reader) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(target: "runtime::call::call_contract()", "Failed to decode payload: {}", e);
+                    return CALL_CONTRACT_FAILED
+                }
+            };
+
+            // A contract must not be entered again while it is still executing,
+            // otherwise it could observe its own state halfway through a call.
+            if cid == env.contract_id || env.call_stack.contains(&cid) {
+                error!(target: "runtime::call::call_contract()", "Re-entrant call into {}", cid);
+                return REENTRANT_CALL
+            }
+
+            let mut call_stack = env.call_stack.clone();
+            call_stack.push(env.contract_id);
+            if call_stack.len() > MAX_CALL_DEPTH {
+                error!(target: "runtime::call::call_contract()", "Maximum call depth exceeded");
+                return CALL_CONTRACT_FAILED
+            }
+
+            let wasm = match env.blockchain.wasm_bincode.get(cid) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(target: "runtime::call::call_contract()", "Failed to find bincode of {}: {}", cid, e);
+                    return CALL_CONTRACT_FAILED
+                }
+            };
+
+            let blockchain = env.blockchain.clone();
+            let module_cache = env.module_cache.clone();
+            let Some(instance) = env.instance.clone() else {
+                error!(target: "runtime::call::call_contract()", "Instance is not set");
+                return CALL_CONTRACT_FAILED
+            };
+            let verifying_slot = env.verifying_slot;
+
+            // The called contract can use up whatever gas the caller has left.
+            let gas_left = match get_remaining_points(&mut ctx, &instance) {
+                MeteringPoints::Remaining(rem) => rem,
+                MeteringPoints::Exhausted => return CALL_CONTRACT_FAILED,
+            };
+
+            let mut runtime = match Runtime::new(&wasm, blockchain, &module_cache, cid, gas_left) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(target: "runtime::call::call_contract()", "Failed to instantiate {}: {}", cid, e);
+                    return CALL_CONTRACT_FAILED
+                }
+            };
+            let mut env_mut = runtime.ctx.as_mut(&mut runtime.store);
+            env_mut.call_stack = call_stack;
+            env_mut.verifying_slot = verifying_slot;
+
+            debug!(target: "runtime::call::call_contract()", "Calling {}", cid);
+            let result = runtime.query(&payload);
+
+            // Charge the caller for the gas consumed, whether the call succeeded or not.
+            let gas_used = runtime.gas_used();
+            set_remaining_points(&mut ctx, &instance, gas_left - gas_used);
+
+            let return_data = match result {
+                Ok((v, _)) => v,
+                Err(e) => {
+                    error!(target: "runtime::call::call_contract()", "Call into {} failed: {}", cid, e);
+                    return CALL_CONTRACT_FAILED
+                }
+            };
+
+            // Copy Vec<u8> to the VM
+            let env = ctx.data();
+            let mut objects = env.objects.borrow_mut();
+            objects.push(return_data);
+            (objects.len() - 1) as i64
+        }
+        _ => {
+            error!(target: "runtime::call::call_contract()", "call_contract called in unauthorized section");
+            CALLER_ACCESS_DENIED
+        }
+    }
+}
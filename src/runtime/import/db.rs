@@ -152,7 +152,8 @@ pub(crate) fn db_lookup(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) ->
         ContractSection::Deploy |
         ContractSection::Exec |
         ContractSection::Update |
-        ContractSection::Metadata => {
+        ContractSection::Metadata |
+        ContractSection::Query => {
             let memory_view = env.memory_view(&ctx);
             let db = &env.blockchain.sled_db;
             let contracts = &env.blockchain.contracts;
@@ -372,7 +373,10 @@ pub(crate) fn db_del(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i3
 pub(crate) fn db_get(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i64 {
     let env = ctx.data();
     match env.contract_section {
-        ContractSection::Deploy | ContractSection::Exec | ContractSection::Metadata => {
+        ContractSection::Deploy |
+        ContractSection::Exec |
+        ContractSection::Metadata |
+        ContractSection::Query => {
             let memory_view = env.memory_view(&ctx);
 
             let Ok(mem_slice) = ptr.slice(&memory_view, len) else {
@@ -452,7 +456,8 @@ pub(crate) fn db_contains_key(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u
         ContractSection::Deploy |
         ContractSection::Exec |
         ContractSection::Update |
-        ContractSection::Metadata => {
+        ContractSection::Metadata |
+        ContractSection::Query => {
             let memory_view = env.memory_view(&ctx);
 
             let Ok(mem_slice) = ptr.slice(&memory_view, len) else {
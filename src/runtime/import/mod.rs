@@ -16,6 +16,9 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+/// Host functions for calling other contracts
+pub(crate) mod call;
+
 /// Host functions for interacting with db backend
 pub(crate) mod db;
 
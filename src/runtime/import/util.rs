@@ -42,7 +42,7 @@ pub(crate) fn drk_log(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) {
 pub(crate) fn set_return_data(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i64 {
     let env = ctx.data();
     match env.contract_section {
-        ContractSection::Exec | ContractSection::Metadata => {
+        ContractSection::Exec | ContractSection::Metadata | ContractSection::Query => {
             let memory_view = env.memory_view(&ctx);
 
             let Ok(slice) = ptr.slice(&memory_view, len) else {
@@ -148,3 +148,8 @@ pub(crate) fn get_object_size(ctx: FunctionEnvMut<Env>, idx: u32) -> i64 {
     let obj = &objects[idx as usize];
     obj.len() as i64
 }
+
+/// Returns the slot the contract calls are being verified against.
+pub(crate) fn get_verifying_slot(ctx: FunctionEnvMut<Env>) -> u64 {
+    ctx.data().verifying_slot
+}
@@ -19,6 +19,9 @@
 /// Main wasm vm runtime implementation
 pub mod vm_runtime;
 
+/// Cache of compiled wasm modules
+pub mod module_cache;
+
 /// VM memory access (read/write)
 pub(crate) mod memory;
 
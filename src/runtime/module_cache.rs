@@ -0,0 +1,224 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use darkfi_sdk::crypto::ContractId;
+use log::{debug, warn};
+use wasmer::{wasmparser::Operator, CompilerConfig, Engine, Module, Store};
+use wasmer_compiler_singlepass::Singlepass;
+use wasmer_middlewares::Metering;
+
+use super::vm_runtime::MAX_GAS_LIMIT;
+use crate::{blockchain::WasmModuleStore, Result};
+
+/// Maximum number of compiled modules kept in memory
+pub const MAX_CACHED_MODULES: usize = 64;
+
+/// Compiled modules along with the tick they were last used at, keyed by the
+/// bytes of the contract ID they belong to and the hash of the wasm bincode
+/// they were compiled from.
+type ModuleMap = HashMap<([u8; 32], blake3::Hash), (Module, u64)>;
+
+/// Cache of compiled wasm modules, shared between [`Runtime`] instances so
+/// that a contract is only compiled once instead of on every call.
+///
+/// Modules are keyed by contract ID and the hash of the contract's wasm
+/// bincode, so when a contract gets redeployed with a different bincode, the
+/// new one is compiled. At most [`MAX_CACHED_MODULES`] are kept in memory,
+/// evicting the least recently used ones, so modules of stale bincodes or
+/// deployments that never made it on chain don't pile up.
+///
+/// Optionally, compiled modules of applied deployments can be serialized
+/// into a sled tree with [`ModuleCache::persist`], so they survive restarts.
+///
+/// [`Runtime`]: super::vm_runtime::Runtime
+#[derive(Clone)]
+pub struct ModuleCache {
+    /// Engine all cached modules are loaded into, and which runtimes
+    /// instantiating them should create their store with
+    engine: Engine,
+    /// Compiled modules, along with a counter used to track their last use
+    modules: Arc<Mutex<(ModuleMap, u64)>>,
+    /// Optional persistent storage of serialized modules
+    store: Option<WasmModuleStore>,
+}
+
+impl Default for ModuleCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModuleCache {
+    /// Create a new in-memory `ModuleCache`
+    pub fn new() -> Self {
+        Self {
+            engine: Engine::headless(),
+            modules: Arc::new(Mutex::new((HashMap::new(), 0))),
+            store: None,
+        }
+    }
+
+    /// Create a new `ModuleCache` which also loads compiled modules from,
+    /// and persists them into, the given [`WasmModuleStore`].
+    pub fn with_store(store: WasmModuleStore) -> Self {
+        Self { store: Some(store), ..Self::new() }
+    }
+
+    /// The engine stores instantiating the cached modules have to use
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// Fetch the compiled module for the given contract and its wasm bincode,
+    /// compiling it if it isn't found in the cache. Modules are only kept in
+    /// memory, see [`ModuleCache::persist`] for storing them.
+    pub fn get(&self, contract_id: ContractId, wasm_bytes: &[u8]) -> Result<Module> {
+        let key = (contract_id.to_bytes(), blake3::hash(wasm_bytes));
+
+        {
+            let (modules, tick) = &mut *self.modules.lock().unwrap();
+            if let Some((module, last_used)) = modules.get_mut(&key) {
+                debug!(target: "runtime::module_cache", "Found cached module for {}", contract_id);
+                *tick += 1;
+                *last_used = *tick;
+                return Ok(module.clone())
+            }
+        }
+
+        // Compilation is expensive, so it's done without holding the lock.
+        // Concurrent misses for the same module may compile it twice, which
+        // is harmless.
+        let module = match self.load(contract_id, &key.1) {
+            Some(module) => module,
+            None => {
+                debug!(target: "runtime::module_cache", "Compiling module for {}", contract_id);
+                let serialized = compile(wasm_bytes)?;
+                // SAFETY: The bytes were produced by `Module::serialize` just above.
+                unsafe { Module::deserialize(&Store::new(self.engine.clone()), serialized)? }
+            }
+        };
+
+        let (modules, tick) = &mut *self.modules.lock().unwrap();
+        *tick += 1;
+        modules.insert(key, (module.clone(), *tick));
+
+        // Evict the least recently used modules once over capacity
+        while modules.len() > MAX_CACHED_MODULES {
+            let Some(lru) = modules.iter().min_by_key(|(_, (_, used))| *used).map(|(k, _)| *k)
+            else {
+                break
+            };
+            modules.remove(&lru);
+        }
+
+        Ok(module)
+    }
+
+    /// Store the compiled module of the given contract and its wasm bincode
+    /// in the persistent store, if there is one, so it doesn't have to be
+    /// compiled again after a restart. This should only be called once a
+    /// deployment was applied, so unaccepted deployments never hit the disk.
+    pub fn persist(&self, contract_id: ContractId, wasm_bytes: &[u8]) -> Result<()> {
+        let Some(store) = &self.store else { return Ok(()) };
+
+        let hash = blake3::hash(wasm_bytes);
+        if let Ok(Some((stored_hash, _))) = store.get(contract_id) {
+            if &stored_hash == hash.as_bytes() {
+                return Ok(())
+            }
+        }
+
+        let module = self.get(contract_id, wasm_bytes)?;
+        store.insert(contract_id, hash.as_bytes(), &module.serialize()?)
+    }
+
+    /// Remove the compiled modules of the given contract from the cache.
+    pub fn remove(&self, contract_id: ContractId) -> Result<()> {
+        self.modules.lock().unwrap().0.retain(|(id, _), _| id != &contract_id.to_bytes());
+        if let Some(store) = &self.store {
+            store.remove(contract_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Try to load a previously serialized module from the persistent store.
+    fn load(&self, contract_id: ContractId, hash: &blake3::Hash) -> Option<Module> {
+        let (stored_hash, serialized) = match &self.store {
+            Some(store) => store.get(contract_id).ok()??,
+            None => return None,
+        };
+
+        if &stored_hash != hash.as_bytes() {
+            return None
+        }
+
+        // SAFETY: The store only holds modules serialized by `compile()`.
+        // Artifacts from an incompatible wasmer version fail to deserialize,
+        // in which case we just compile the module again.
+        match unsafe { Module::deserialize(&Store::new(self.engine.clone()), serialized) } {
+            Ok(module) => {
+                debug!(target: "runtime::module_cache", "Loaded stored module for {}", contract_id);
+                Some(module)
+            }
+            Err(e) => {
+                warn!(target: "runtime::module_cache", "Failed to load stored module for {}: {}", contract_id, e);
+                None
+            }
+        }
+    }
+}
+
+/// This function will be called for each `Operator` encountered during
+/// the wasm module execution. It should return the cost of the operator
+/// that it received as its first argument.
+/// https://docs.rs/wasmparser/latest/wasmparser/enum.Operator.html
+fn cost_function(operator: &Operator) -> u64 {
+    match operator {
+        Operator::LocalGet { .. } => 1,
+        Operator::I32Const { .. } => 1,
+        Operator::I32Add { .. } => 2,
+        _ => 0,
+    }
+}
+
+/// Compile the given wasm bincode with gas metering and return the serialized
+/// module.
+fn compile(wasm_bytes: &[u8]) -> Result<Vec<u8>> {
+    // `Metering` needs to be conigured with a limit and a cost function.
+    // For each `Operator`, the metering middleware will call the cost
+    // function and subtract the cost from the remaining points.
+    // The limit is set to the maximum here, and the runtime sets the actual
+    // gas limit of the call when it instantiates the module.
+    let metering = Arc::new(Metering::new(MAX_GAS_LIMIT, cost_function));
+
+    // Define the compiler and middleware, engine, and store.
+    // A `Metering` middleware can only be used for a single module, so
+    // every compilation gets its own.
+    let mut compiler_config = Singlepass::new();
+    compiler_config.push_middleware(metering);
+    let store = Store::new(compiler_config);
+
+    let module = Module::new(&store, wasm_bytes)?;
+    Ok(module.serialize()?.to_vec())
+}
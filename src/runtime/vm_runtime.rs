@@ -16,32 +16,25 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{
-    cell::{Cell, RefCell},
-    sync::Arc,
-};
+use std::cell::{Cell, RefCell};
 
 use darkfi_sdk::{crypto::ContractId, entrypoint};
 use darkfi_serial::serialize;
 use log::{debug, error, info};
 use wasmer::{
-    imports, wasmparser::Operator, AsStoreRef, CompilerConfig, Function, FunctionEnv, Instance,
-    Memory, MemoryView, Module, Pages, Store, Value, WASM_PAGE_SIZE,
-};
-use wasmer_compiler_singlepass::Singlepass;
-use wasmer_middlewares::{
-    metering::{get_remaining_points, MeteringPoints},
-    Metering,
+    imports, AsStoreRef, Function, FunctionEnv, Instance, Memory, MemoryView, Pages, Store, Value,
+    WASM_PAGE_SIZE,
 };
+use wasmer_middlewares::metering::{get_remaining_points, set_remaining_points, MeteringPoints};
 
-use super::{import, import::db::DbHandle, memory::MemoryManipulation};
+use super::{import, import::db::DbHandle, memory::MemoryManipulation, module_cache::ModuleCache};
 use crate::{blockchain::Blockchain, Error, Result};
 
 /// Name of the wasm linear memory in our guest module
 const MEMORY: &str = "memory";
 
-/// Gas limit for a contract
-const GAS_LIMIT: u64 = 200000000;
+/// Maximum gas limit a single contract call can request
+pub const MAX_GAS_LIMIT: u64 = 200000000;
 
 #[derive(Clone, Copy)]
 pub enum ContractSection {
@@ -53,6 +46,8 @@ pub enum ContractSection {
     Update,
     /// Metadata
     Metadata,
+    /// Read-only function of a contract, callable by other contracts
+    Query,
     /// Placeholder state before any initialization
     Null,
 }
@@ -64,6 +59,7 @@ impl ContractSection {
             Self::Exec => "__entrypoint",
             Self::Update => "__update",
             Self::Metadata => "__metadata",
+            Self::Query => "__query",
             Self::Null => unreachable!(),
         }
     }
@@ -73,6 +69,8 @@ impl ContractSection {
 pub struct Env {
     /// Blockchain access
     pub blockchain: Blockchain,
+    /// Compiled wasm modules, used to instantiate called contracts
+    pub module_cache: ModuleCache,
     /// sled tree handles used with `db_*`
     pub db_handles: RefCell<Vec<DbHandle>>,
     /// sled tree batches, indexed the same as `db_handles`.
@@ -83,12 +81,18 @@ pub struct Env {
     pub contract_bincode: Vec<u8>,
     /// The contract section being executed
     pub contract_section: ContractSection,
+    /// IDs of the contracts further up the call chain that called into this one
+    pub call_stack: Vec<ContractId>,
+    /// Slot the contract calls are verified against
+    pub verifying_slot: u64,
     /// State update produced by a smart contract function call
     pub contract_return_data: Cell<Option<Vec<u8>>>,
     /// Logs produced by the contract
     pub logs: RefCell<Vec<String>>,
     /// Direct memory access to the VM
     pub memory: Option<Memory>,
+    /// The instance being executed, needed for gas accounting in host functions
+    pub instance: Option<Instance>,
     /// Object store for transferring memory from the host to VM
     pub objects: RefCell<Vec<Vec<u8>>>,
 }
@@ -115,37 +119,26 @@ pub struct Runtime {
     pub instance: Instance,
     pub store: Store,
     pub ctx: FunctionEnv<Env>,
+    /// Gas available to this runtime over all of its calls
+    gas_limit: u64,
 }
 
 impl Runtime {
     /// Create a new wasm runtime instance that contains the given wasm module.
-    pub fn new(wasm_bytes: &[u8], blockchain: Blockchain, contract_id: ContractId) -> Result<Self> {
+    /// The module is compiled only if it isn't found in the given `module_cache`.
+    /// `gas_limit` is the total amount of gas the calls made on this instance
+    /// are allowed to consume.
+    pub fn new(
+        wasm_bytes: &[u8],
+        blockchain: Blockchain,
+        module_cache: &ModuleCache,
+        contract_id: ContractId,
+        gas_limit: u64,
+    ) -> Result<Self> {
         info!(target: "runtime::vm_runtime", "Instantiating a new runtime");
-        // This function will be called for each `Operator` encountered during
-        // the wasm module execution. It should return the cost of the operator
-        // that it received as its first argument.
-        // https://docs.rs/wasmparser/latest/wasmparser/enum.Operator.html
-        let cost_function = |operator: &Operator| -> u64 {
-            match operator {
-                Operator::LocalGet { .. } => 1,
-                Operator::I32Const { .. } => 1,
-                Operator::I32Add { .. } => 2,
-                _ => 0,
-            }
-        };
-
-        // `Metering` needs to be conigured with a limit and a cost function.
-        // For each `Operator`, the metering middleware will call the cost
-        // function and subtract the cost from the remaining points.
-        let metering = Arc::new(Metering::new(GAS_LIMIT, cost_function));
-
-        // Define the compiler and middleware, engine, and store
-        let mut compiler_config = Singlepass::new();
-        compiler_config.push_middleware(metering);
-        let mut store = Store::new(compiler_config);
-
-        debug!(target: "runtime::vm_runtime", "Compiling module");
-        let module = Module::new(&store, wasm_bytes)?;
+        debug!(target: "runtime::vm_runtime", "Loading module");
+        let module = module_cache.get(contract_id, wasm_bytes)?;
+        let mut store = Store::new(module_cache.engine().clone());
 
         // Initialize data
         let db_handles = RefCell::new(vec![]);
@@ -158,14 +151,18 @@ impl Runtime {
             &mut store,
             Env {
                 blockchain,
+                module_cache: module_cache.clone(),
                 db_handles,
                 db_batches,
                 contract_id,
                 contract_bincode: wasm_bytes.to_vec(),
                 contract_section: ContractSection::Null,
+                call_stack: vec![],
+                verifying_slot: 0,
                 contract_return_data: Cell::new(None),
                 logs,
                 memory: None,
+                instance: None,
                 objects: RefCell::new(vec![]),
             },
         );
@@ -238,24 +235,43 @@ impl Runtime {
                     import::util::get_object_size,
                 ),
 
+                "get_verifying_slot_" => Function::new_typed_with_env(
+                    &mut store,
+                    &ctx,
+                    import::util::get_verifying_slot,
+                ),
+
                 "merkle_add_" => Function::new_typed_with_env(
                     &mut store,
                     &ctx,
                     import::merkle::merkle_add,
                 ),
+
+                "call_contract_" => Function::new_typed_with_env(
+                    &mut store,
+                    &ctx,
+                    import::call::call_contract,
+                ),
             }
         };
 
         debug!(target: "runtime::vm_runtime", "Instantiating module");
         let instance = Instance::new(&mut store, &module, &imports)?;
 
+        // Modules are compiled with the maximum gas limit, so here we set
+        // the amount of gas this runtime was actually given.
+        set_remaining_points(&mut store, &instance, gas_limit);
+
         let mut env_mut = ctx.as_mut(&mut store);
         env_mut.memory = Some(instance.exports.get_with_generics(MEMORY)?);
+        env_mut.instance = Some(instance.clone());
 
-        Ok(Self { instance, store, ctx })
+        Ok(Self { instance, store, ctx, gas_limit })
     }
 
-    fn call(&mut self, section: ContractSection, payload: &[u8]) -> Result<Vec<u8>> {
+    /// Call the given contract section and return its return data along with
+    /// the amount of gas consumed by the call.
+    fn call(&mut self, section: ContractSection, payload: &[u8]) -> Result<(Vec<u8>, u64)> {
         debug!(target: "runtime::vm_runtime", "Calling {} method", section.name());
 
         let mut env_mut = self.ctx.as_mut(&mut self.store);
@@ -273,6 +289,8 @@ impl Runtime {
         self.set_memory_page_size(pages_required as u32)?;
         self.copy_to_memory(&payload)?;
 
+        let gas_before = self.gas_used();
+
         debug!(target: "runtime::vm_runtime", "Getting {} function", section.name());
         let entrypoint = self.instance.exports.get_function(section.name())?;
 
@@ -286,12 +304,21 @@ impl Runtime {
             Err(e) => {
                 self.print_logs();
                 debug!(target: "runtime::vm_runtime", "{}", self.gas_info());
+                // Running out of gas traps the execution, so we report it as such.
+                if let MeteringPoints::Exhausted =
+                    get_remaining_points(&mut self.store, &self.instance)
+                {
+                    error!(target: "runtime::vm_runtime", "Contract ran out of gas");
+                    return Err(Error::GasLimitExceeded(self.gas_limit))
+                }
                 // WasmerRuntimeError panics are handled here. Return from run() immediately.
                 error!(target: "runtime::vm_runtime", "Wasmer Runtime Error: {:#?}", e);
                 return Err(e.into())
             }
         };
 
+        let gas = self.gas_used() - gas_before;
+
         debug!(target: "runtime::vm_runtime", "wasm executed successfully");
         debug!(target: "runtime::vm_runtime", "Contract returned: {:?}", ret[0]);
 
@@ -308,7 +335,7 @@ impl Runtime {
         };
 
         match retval {
-            entrypoint::SUCCESS => Ok(retdata),
+            entrypoint::SUCCESS => Ok((retdata, gas)),
             // FIXME: we should be able to see the error returned from the contract
             // We can put sdk::Error inside of this.
             _ => {
@@ -326,10 +353,11 @@ impl Runtime {
     /// state, and it can create, delete, modify, read, and write to databases it's allowed to.
     /// The permissions for this are handled by the `ContractId` in the sled db API so we
     /// assume that the contract is only able to do write operations on its own sled trees.
-    pub fn deploy(&mut self, payload: &[u8]) -> Result<()> {
+    /// Returns the gas consumed by the call.
+    pub fn deploy(&mut self, payload: &[u8]) -> Result<u64> {
         info!(target: "runtime::vm_runtime", "[wasm-runtime] Running deploy");
         debug!(target: "runtime::vm_runtime", "[wasm-runtime] payload: {:?}", payload);
-        let _ = self.call(ContractSection::Deploy, payload)?;
+        let (_, gas) = self.call(ContractSection::Deploy, payload)?;
 
         // If the above didn't fail, we write the batches.
         // TODO: Make all the writes atomic in a transaction over all trees.
@@ -343,14 +371,15 @@ impl Runtime {
         // Update the wasm bincode in the WasmStore
         env_mut.blockchain.wasm_bincode.insert(env_mut.contract_id, &env_mut.contract_bincode)?;
 
-        Ok(())
+        Ok(gas)
     }
 
     /// This funcion runs when someone wants to execute a smart contract.
     /// The runtime will look for an [`ENTRYPOINT`] symbol in the wasm code, and
     /// execute it if found. A payload is also passed as an instruction that can
     /// be used inside the vm by the runtime.
-    pub fn exec(&mut self, payload: &[u8]) -> Result<Vec<u8>> {
+    /// Returns the state update along with the gas consumed by the call.
+    pub fn exec(&mut self, payload: &[u8]) -> Result<(Vec<u8>, u64)> {
         debug!(target: "runtime::vm_runtime", "exec: {:?}", payload);
         self.call(ContractSection::Exec, payload)
     }
@@ -360,9 +389,10 @@ impl Runtime {
     /// The runtime will lok for an [`UPDATE`] symbol in the wasm code, and execute
     /// it if found. The function does not take an arbitrary payload, but just takes
     /// a state update from `env` and passes it into the wasm runtime.
-    pub fn apply(&mut self, update: &[u8]) -> Result<()> {
+    /// Returns the gas consumed by the call.
+    pub fn apply(&mut self, update: &[u8]) -> Result<u64> {
         debug!(target: "runtime::vm_runtime", "apply: {:?}", update);
-        let _ = self.call(ContractSection::Update, update)?;
+        let (_, gas) = self.call(ContractSection::Update, update)?;
 
         // If the above didn't fail, we write the batches.
         // TODO: Make all the writes atomic in a transaction over all trees.
@@ -373,13 +403,24 @@ impl Runtime {
             db.flush()?;
         }
 
-        Ok(())
+        Ok(gas)
     }
 
-    pub fn metadata(&mut self, payload: &[u8]) -> Result<Vec<u8>> {
+    /// Returns the call metadata along with the gas consumed by the call.
+    pub fn metadata(&mut self, payload: &[u8]) -> Result<(Vec<u8>, u64)> {
         self.call(ContractSection::Metadata, payload)
     }
 
+    /// This function runs when another contract invokes this one through
+    /// `call_contract`. The runtime will look for a `__query` symbol in the
+    /// wasm code, and execute it if found. The section can read the contract's
+    /// databases, but not write to them.
+    /// Returns the query result along with the gas consumed by the call.
+    pub fn query(&mut self, payload: &[u8]) -> Result<(Vec<u8>, u64)> {
+        debug!(target: "runtime::vm_runtime", "query: {:?}", payload);
+        self.call(ContractSection::Query, payload)
+    }
+
     fn print_logs(&self) {
         let logs = self.ctx.as_ref(&self.store).logs.borrow();
         for msg in logs.iter() {
@@ -387,15 +428,23 @@ impl Runtime {
         }
     }
 
+    /// Total gas consumed by the calls made on this runtime so far.
+    pub fn gas_used(&mut self) -> u64 {
+        match get_remaining_points(&mut self.store, &self.instance) {
+            MeteringPoints::Remaining(rem) => self.gas_limit - rem,
+            MeteringPoints::Exhausted => self.gas_limit,
+        }
+    }
+
     fn gas_info(&mut self) -> String {
         let remaining_points = get_remaining_points(&mut self.store, &self.instance);
 
         match remaining_points {
             MeteringPoints::Remaining(rem) => {
-                format!("Gas used: {}/{}", GAS_LIMIT - rem, GAS_LIMIT)
+                format!("Gas used: {}/{}", self.gas_limit - rem, self.gas_limit)
             }
             MeteringPoints::Exhausted => {
-                format!("Gas fully exhausted: {}/{}", GAS_LIMIT + 1, GAS_LIMIT)
+                format!("Gas fully exhausted: {}/{}", self.gas_limit + 1, self.gas_limit)
             }
         }
     }
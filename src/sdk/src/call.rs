@@ -0,0 +1,58 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_serial::Encodable;
+
+use super::{
+    crypto::ContractId,
+    error::{ContractError, GenericResult},
+    util::{get_object_bytes, get_object_size},
+};
+
+/// Everyone except deploy() and update() can call this. Invokes the read-only
+/// `query` entrypoint of another deployed contract with the given payload, and
+/// returns the data the called contract set with `set_return_data`.
+/// The gas consumed by the called contract is charged to the caller. A contract
+/// that is already executing further up the call chain can not be called again.
+///
+/// ```
+///     data = call_contract(contract_id, payload);
+/// ```
+pub fn call_contract(contract_id: ContractId, payload: &[u8]) -> GenericResult<Vec<u8>> {
+    let mut len = 0;
+    let mut buf = vec![];
+    len += contract_id.encode(&mut buf)?;
+    len += payload.to_vec().encode(&mut buf)?;
+
+    let ret = unsafe { call_contract_(buf.as_ptr(), len as u32) };
+
+    if ret < 0 {
+        return Err(ContractError::from(ret))
+    }
+
+    let obj = ret as u32;
+    let obj_size = get_object_size(obj);
+    let mut buf = vec![0u8; obj_size as usize];
+    get_object_bytes(&mut buf, obj);
+
+    Ok(buf)
+}
+
+extern "C" {
+    fn call_contract_(ptr: *const u8, len: u32) -> i64;
+}
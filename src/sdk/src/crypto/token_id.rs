@@ -41,8 +41,12 @@ pub struct TokenId(pallas::Base);
 impl TokenId {
     /// Derives a `TokenId` given a `SecretKey` (mint authority)
     pub fn derive(mint_authority: SecretKey) -> Self {
-        let public_key = PublicKey::from_secret(mint_authority);
-        let (x, y) = public_key.xy();
+        Self::derive_public(PublicKey::from_secret(mint_authority))
+    }
+
+    /// Derives a `TokenId` given a `PublicKey` (mint authority)
+    pub fn derive_public(mint_public: PublicKey) -> Self {
+        let (x, y) = mint_public.xy();
         let hash = poseidon_hash::<2>([x, y]);
         Self(hash)
     }
@@ -29,6 +29,15 @@ pub fn hash_to_scalar(persona: &[u8], a: &[u8], b: &[u8]) -> pallas::Scalar {
     pallas::Scalar::from_bytes_wide(ret.as_array())
 }
 
+/// Hash `data` with a prefix `persona` and return a `pallas::Base` element
+/// from the digest.
+pub fn hash_to_base(persona: &[u8], data: &[u8]) -> pallas::Base {
+    let mut hasher = blake2b_simd::Params::new().hash_length(64).personal(persona).to_state();
+    hasher.update(data);
+    let ret = hasher.finalize();
+    pallas::Base::from_bytes_wide(ret.as_array())
+}
+
 /// Converts from pallas::Base to pallas::Scalar (aka $x \pmod{r_\mathbb{P}}$).
 ///
 /// This requires no modular reduction because Pallas' base field is smaller than its
@@ -0,0 +1,38 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+
+use super::crypto::PublicKey;
+
+/// Parameters for a contract call to `DEPLOYOOOR_CONTRACT_ID`, which deploys
+/// the given wasm bincode and zkas circuits on chain. The `ContractId` of the
+/// deployed contract is derived from `public_key`, and the transaction has to
+/// be signed with its secret counterpart (the deploy key). Deploying again
+/// with the same key upgrades the existing contract.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct DeployParamsV1 {
+    /// Wasm bincode of the contract
+    pub wasm_bincode: Vec<u8>,
+    /// Compiled zkas circuits used by the contract
+    pub zkas_bincodes: Vec<Vec<u8>>,
+    /// Public key of the deploy key
+    pub public_key: PublicKey,
+    /// Payload passed to the contract's init function
+    pub ix: Vec<u8>,
+}
@@ -25,6 +25,30 @@ pub const SUCCESS: i64 = 0;
 
 #[macro_export]
 macro_rules! define_contract {
+    (
+        init: $init_func:ident,
+        exec: $exec_func:ident,
+        apply: $apply_func:ident,
+        metadata: $metadata_func:ident,
+        query: $query_func:ident
+    ) => {
+        $crate::define_contract!(
+            init: $init_func,
+            exec: $exec_func,
+            apply: $apply_func,
+            metadata: $metadata_func
+        );
+        /// Read-only entrypoint other contracts can invoke with `call_contract`
+        #[no_mangle]
+        pub unsafe extern "C" fn __query(input: *mut u8) -> i64 {
+            let (contract_id, instruction_data) = $crate::entrypoint::deserialize(input);
+
+            match $query_func(contract_id, &instruction_data) {
+                Ok(()) => $crate::entrypoint::SUCCESS,
+                Err(e) => e.into(),
+            }
+        }
+    };
     (
         init: $init_func:ident,
         exec: $exec_func:ident,
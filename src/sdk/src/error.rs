@@ -74,6 +74,12 @@ pub enum ContractError {
 
     #[error("Invalid function call")]
     InvalidFunction,
+
+    #[error("Contract call failed")]
+    CallContractFailed,
+
+    #[error("Re-entrant contract call")]
+    ReentrantCall,
 }
 
 /// Builtin return values occupy the upper 32 bits
@@ -99,6 +105,8 @@ pub const DB_GET_FAILED: i64 = to_builtin!(13);
 pub const DB_CONTAINS_KEY_FAILED: i64 = to_builtin!(14);
 pub const INVALID_FUNCTION: i64 = to_builtin!(15);
 pub const DB_DEL_FAILED: i64 = to_builtin!(16);
+pub const CALL_CONTRACT_FAILED: i64 = to_builtin!(17);
+pub const REENTRANT_CALL: i64 = to_builtin!(18);
 
 impl From<ContractError> for i64 {
     fn from(err: ContractError) -> Self {
@@ -118,6 +126,8 @@ impl From<ContractError> for i64 {
             ContractError::DbContainsKeyFailed => DB_CONTAINS_KEY_FAILED,
             ContractError::InvalidFunction => INVALID_FUNCTION,
             ContractError::DbDelFailed => DB_DEL_FAILED,
+            ContractError::CallContractFailed => CALL_CONTRACT_FAILED,
+            ContractError::ReentrantCall => REENTRANT_CALL,
             ContractError::Custom(error) => {
                 if error == 0 {
                     CUSTOM_ZERO
@@ -148,6 +158,8 @@ impl From<i64> for ContractError {
             DB_CONTAINS_KEY_FAILED => Self::DbContainsKeyFailed,
             INVALID_FUNCTION => Self::InvalidFunction,
             DB_DEL_FAILED => Self::DbDelFailed,
+            CALL_CONTRACT_FAILED => Self::CallContractFailed,
+            REENTRANT_CALL => Self::ReentrantCall,
             _ => Self::Custom(error as u32),
         }
     }
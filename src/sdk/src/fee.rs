@@ -0,0 +1,44 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+
+use super::crypto::{pallas, PublicKey};
+
+/// Function code of the money contract's fee claim call.
+/// Has to match `MoneyFunction::FeeClaim`.
+pub const MONEY_CONTRACT_FEE_CLAIM: u8 = 0x06;
+
+/// Parameters for the money contract's fee claim call, which the block
+/// proposer uses to collect the transaction fees paid in its block. The
+/// call is built by the validator itself, so this lives in the SDK rather
+/// than in the money contract.
+///
+/// The minted coin is public: it holds `value` native tokens, spendable
+/// by the holder of the secret key for `public_key`.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct FeeClaimParamsV1 {
+    /// Public key of the coin's owner
+    pub public_key: PublicKey,
+    /// Claimed value
+    pub value: u64,
+    /// Unique serial number of the coin
+    pub serial: pallas::Base,
+    /// Random blinding factor for the coin
+    pub coin_blind: pallas::Base,
+}
@@ -19,15 +19,25 @@
 pub use incrementalmerkletree;
 pub use pasta_curves as pasta;
 
+/// Cross-contract calls
+pub mod call;
+pub use call::call_contract;
+
 /// Database functions
 pub mod db;
 
+/// Contract deployment
+pub mod deploy;
+
 /// Entrypoint used for the wasm binaries
 pub mod entrypoint;
 
 /// Error handling
 pub mod error;
 
+/// Transaction fees
+pub mod fee;
+
 /// Logging infrastructure
 pub mod log;
 
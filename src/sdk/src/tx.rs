@@ -20,10 +20,16 @@ use darkfi_serial::{SerialDecodable, SerialEncodable};
 
 use super::crypto::ContractId;
 
+/// Gas limit clients can use for a contract call when they don't
+/// estimate its cost beforehand.
+pub const DEFAULT_GAS_LIMIT: u64 = 200000000;
+
 /// A ContractCall is the part of a transaction that executes a certain
 /// `contract_id` with `data` as the call's payload.
 #[derive(Debug, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable)]
 pub struct ContractCall {
     pub contract_id: ContractId,
     pub data: Vec<u8>,
+    /// Maximum amount of gas the call is allowed to consume when executed
+    pub gas_limit: u64,
 }
@@ -39,9 +39,16 @@ pub fn get_object_size(object_index: u32) -> i64 {
     unsafe { get_object_size_(object_index as u32) }
 }
 
+/// Get the slot the current contract calls are verified against.
+/// Contracts use this to enforce time bounds.
+pub fn get_verifying_slot() -> u64 {
+    unsafe { get_verifying_slot_() }
+}
+
 extern "C" {
     fn set_return_data_(ptr: *const u8, len: u32) -> i64;
     fn put_object_bytes_(ptr: *const u8, len: u32) -> i64;
     fn get_object_bytes_(ptr: *mut u8, len: u32) -> i64;
     fn get_object_size_(len: u32) -> i64;
+    fn get_verifying_slot_() -> u64;
 }
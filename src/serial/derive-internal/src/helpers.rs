@@ -17,7 +17,7 @@
  */
 
 use quote::ToTokens;
-use syn::{Attribute, Meta};
+use syn::{Attribute, Error, ExprPath, Lit, LitInt, Meta};
 //use syn::{spanned::Spanned, Attribute, Error, Meta, NestedMeta, Path};
 
 pub fn contains_skip(attrs: &[Attribute]) -> bool {
@@ -31,6 +31,42 @@ pub fn contains_skip(attrs: &[Attribute]) -> bool {
     false
 }
 
+/// Find the value of a `#[name = value]` attribute, if it's present.
+fn name_value(attrs: &[Attribute], name: &str) -> Option<Lit> {
+    for attr in attrs.iter() {
+        if let Ok(Meta::NameValue(meta)) = attr.parse_meta() {
+            if meta.path.to_token_stream().to_string().as_str() == name {
+                return Some(meta.lit)
+            }
+        }
+    }
+    None
+}
+
+pub fn max_len(attrs: &[Attribute]) -> syn::Result<Option<LitInt>> {
+    match name_value(attrs, "max_len") {
+        Some(Lit::Int(lit)) => Ok(Some(lit)),
+        Some(lit) => Err(Error::new(lit.span(), "max_len requires an integer literal.")),
+        None => Ok(None),
+    }
+}
+
+pub fn serial_version(attrs: &[Attribute]) -> syn::Result<Option<u8>> {
+    match name_value(attrs, "serial_version") {
+        Some(Lit::Int(lit)) => Ok(Some(lit.base10_parse()?)),
+        Some(lit) => Err(Error::new(lit.span(), "serial_version requires a u8 literal.")),
+        None => Ok(None),
+    }
+}
+
+pub fn serial_migrate(attrs: &[Attribute]) -> syn::Result<Option<ExprPath>> {
+    match name_value(attrs, "serial_migrate") {
+        Some(Lit::Str(lit)) => Ok(Some(lit.parse()?)),
+        Some(lit) => Err(Error::new(lit.span(), "serial_migrate requires a function path string.")),
+        None => Ok(None),
+    }
+}
+
 /*
 pub fn contains_initialize_with(attrs: &[Attribute]) -> syn::Result<Option<Path>> {
     for attr in attrs.iter() {
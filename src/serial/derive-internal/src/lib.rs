@@ -19,10 +19,58 @@
 //! Derive (de)serialization for structs, see src/serial/derive
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::quote;
-use syn::{Fields, Ident, Index, ItemEnum, ItemStruct, WhereClause};
+use syn::{Attribute, Error, Field, Fields, Ident, Index, ItemEnum, ItemStruct, WhereClause};
 
 mod helpers;
-use helpers::contains_skip;
+use helpers::{contains_skip, max_len, serial_migrate, serial_version};
+
+/// Expression decoding the given field, honoring its `#[max_len = N]`
+/// attribute if there is one.
+fn field_de(field: &Field, cratename: &Ident) -> syn::Result<TokenStream2> {
+    match max_len(&field.attrs)? {
+        Some(max_len) => Ok(quote! { #cratename::decode_with_max_len(&mut d, #max_len)? }),
+        None => Ok(quote! { #cratename::Decodable::decode(&mut d)? }),
+    }
+}
+
+/// Statements writing the `#[serial_version = N]` byte of a type, if any.
+fn version_ser(attrs: &[Attribute], cratename: &Ident) -> syn::Result<TokenStream2> {
+    match serial_version(attrs)? {
+        Some(version) => Ok(quote! { len += #cratename::Encodable::encode(&#version, &mut s)?; }),
+        None => Ok(TokenStream2::new()),
+    }
+}
+
+/// Statements reading and checking the `#[serial_version = N]` byte of a
+/// type, handing older versions over to its `#[serial_migrate]` function.
+fn version_de(name: &Ident, attrs: &[Attribute], cratename: &Ident) -> syn::Result<TokenStream2> {
+    let migrate = serial_migrate(attrs)?;
+
+    let Some(version) = serial_version(attrs)? else {
+        if let Some(migrate) = migrate {
+            return Err(Error::new_spanned(migrate, "serial_migrate requires serial_version."))
+        }
+        return Ok(TokenStream2::new())
+    };
+
+    let migrate = match migrate {
+        Some(migrate) => quote! {
+            if version < #version {
+                return #migrate(version, &mut d);
+            }
+        },
+        None => TokenStream2::new(),
+    };
+
+    Ok(quote! {
+        let version: u8 = #cratename::Decodable::decode(&mut d)?;
+        if version != #version {
+            #migrate
+            let msg = format!("Unsupported {} version: {}", stringify!(#name), version);
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, msg));
+        }
+    })
+}
 
 pub fn enum_ser(input: &ItemEnum, cratename: Ident) -> syn::Result<TokenStream2> {
     let name = &input.ident;
@@ -44,7 +92,7 @@ pub fn enum_ser(input: &ItemEnum, cratename: Ident) -> syn::Result<TokenStream2>
                 for field in &fields.named {
                     let field_name = field.ident.as_ref().unwrap();
                     if contains_skip(&field.attrs) {
-                        variant_header.extend(quote! { _ #field_name, }); // TODO: Test this
+                        variant_header.extend(quote! { #field_name: _, });
                         continue
                     } else {
                         let field_type = &field.ty;
@@ -57,7 +105,7 @@ pub fn enum_ser(input: &ItemEnum, cratename: Ident) -> syn::Result<TokenStream2>
                         variant_header.extend(quote! { #field_name, });
                     }
                     variant_body.extend(quote! {
-                        len += self.#field_name.encode(&mut s);
+                        len += #field_name.encode(&mut s)?;
                     })
                 }
                 variant_header = quote! { { #variant_header } };
@@ -87,7 +135,7 @@ pub fn enum_ser(input: &ItemEnum, cratename: Ident) -> syn::Result<TokenStream2>
                             Ident::new(format!("id{}", field_idx).as_str(), Span::call_site());
                         variant_header.extend(quote! { #field_ident, });
                         variant_body.extend(quote! {
-                            len += self.#field_ident.encode(&mut s)?;
+                            len += #field_ident.encode(&mut s)?;
                         })
                     }
                 }
@@ -109,6 +157,8 @@ pub fn enum_ser(input: &ItemEnum, cratename: Ident) -> syn::Result<TokenStream2>
         ))
     }
 
+    let version = version_ser(&input.attrs, &cratename)?;
+
     Ok(quote! {
         impl #impl_generics #cratename::Encodable for #name #ty_generics #where_clause {
             fn encode<S: std::io::Write>(&self, mut s: S) -> ::core::result::Result<usize, std::io::Error> {
@@ -116,8 +166,10 @@ pub fn enum_ser(input: &ItemEnum, cratename: Ident) -> syn::Result<TokenStream2>
                     #variant_idx_body
                 };
 
+                let mut len = 0;
+                #version
                 s.write_all(&variant_idx.to_le_bytes())?;
-                let mut len = 1;
+                len += 1;
 
                 match self {
                     #fields_body
@@ -159,8 +211,9 @@ pub fn enum_de(input: &ItemEnum, cratename: Ident) -> syn::Result<TokenStream2>
                             .unwrap(),
                         );
 
+                        let field_de = field_de(field, &cratename)?;
                         variant_header.extend(quote! {
-                            #field_name: #cratename::Decodable::decode(&mut d)?,
+                            #field_name: #field_de,
                         });
                     }
                 }
@@ -179,7 +232,8 @@ pub fn enum_de(input: &ItemEnum, cratename: Ident) -> syn::Result<TokenStream2>
                             .unwrap(),
                         );
 
-                        variant_header.extend(quote! { #cratename::Decodable::decode(&mut d)?, });
+                        let field_de = field_de(field, &cratename)?;
+                        variant_header.extend(quote! { #field_de, });
                     }
                 }
                 variant_header = quote! { ( #variant_header ) };
@@ -196,9 +250,12 @@ pub fn enum_de(input: &ItemEnum, cratename: Ident) -> syn::Result<TokenStream2>
         let variant_idx: u8 = #cratename::Decodable::decode(&mut d)?;
     };
 
+    let version = version_de(name, &input.attrs, &cratename)?;
+
     Ok(quote! {
         impl #impl_generics #cratename::Decodable for #name #ty_generics #where_clause {
             fn decode<D: std::io::Read>(mut d: D) -> ::core::result::Result<Self, std::io::Error> {
+                #version
                 #variant_idx
 
                 let return_value = match variant_idx {
@@ -261,10 +318,13 @@ pub fn struct_ser(input: &ItemStruct, cratename: Ident) -> syn::Result<TokenStre
         Fields::Unit => {}
     }
 
+    let version = version_ser(&input.attrs, &cratename)?;
+
     Ok(quote! {
         impl #impl_generics #cratename::Encodable for #name #ty_generics #where_clause {
             fn encode<S: std::io::Write>(&self, mut s: S) -> ::core::result::Result<usize, std::io::Error> {
                 let mut len = 0;
+                #version
                 #body
                 Ok(len)
             }
@@ -299,8 +359,9 @@ pub fn struct_de(input: &ItemStruct, cratename: Ident) -> syn::Result<TokenStrea
                         .unwrap(),
                     );
 
+                    let field_de = field_de(field, &cratename)?;
                     quote! {
-                        #field_name: #cratename::Decodable::decode(&mut d)?,
+                        #field_name: #field_de,
                     }
                 };
                 body.extend(delta);
@@ -311,9 +372,10 @@ pub fn struct_de(input: &ItemStruct, cratename: Ident) -> syn::Result<TokenStrea
         }
         Fields::Unnamed(fields) => {
             let mut body = TokenStream2::new();
-            for _ in 0..fields.unnamed.len() {
+            for field in fields.unnamed.iter() {
+                let field_de = field_de(field, &cratename)?;
                 let delta = quote! {
-                    #cratename::Decodable::decode(&mut d)?,
+                    #field_de,
                 };
                 body.extend(delta);
             }
@@ -328,9 +390,12 @@ pub fn struct_de(input: &ItemStruct, cratename: Ident) -> syn::Result<TokenStrea
         }
     };
 
+    let version = version_de(name, &input.attrs, &cratename)?;
+
     Ok(quote! {
         impl #impl_generics #cratename::Decodable for #name #ty_generics #where_clause {
             fn decode<D: std::io::Read>(mut d: D) -> ::core::result::Result<Self, std::io::Error> {
+                #version
                 Ok(#return_value)
             }
         }
@@ -24,7 +24,14 @@ use syn::{Ident, ItemEnum, ItemStruct, ItemUnion};
 
 use darkfi_derive_internal::{enum_de, enum_ser, struct_de, struct_ser};
 
-#[proc_macro_derive(SerialEncodable, attributes(skip_serialize))]
+/// Derive `Encodable` for a struct or an enum.
+///
+/// A `#[serial_version = N]` attribute on the type makes the encoding
+/// start with the version byte `N`. This changes the encoding of untagged
+/// types, along with any hashes taken over it, so existing data that was
+/// stored or hashed untagged can't be decoded with it anymore. Fields
+/// marked `#[skip_serialize]` are not encoded.
+#[proc_macro_derive(SerialEncodable, attributes(skip_serialize, max_len, serial_version, serial_migrate))]
 pub fn darkfi_serialize(input: TokenStream) -> TokenStream {
     let found_crate = crate_name("darkfi-serial").expect("darkfi-serial is found in Cargo.toml");
 
@@ -52,7 +59,19 @@ pub fn darkfi_serialize(input: TokenStream) -> TokenStream {
     })
 }
 
-#[proc_macro_derive(SerialDecodable, attributes(skip_serialize))]
+/// Derive `Decodable` for a struct or an enum.
+///
+/// Types with a `#[serial_version = N]` attribute only decode data tagged
+/// with version `N`, unless they also declare `#[serial_migrate = "path"]`.
+/// In that case, data tagged with an older version is handed over to the
+/// function at `path`, with the signature
+/// `fn migrate<D: Read>(version: u8, d: D) -> Result<Self, std::io::Error>`,
+/// which decodes the old layout and converts it into the current one.
+///
+/// Fields marked `#[max_len = N]` fail to decode if their length prefix is
+/// bigger than `N`, and fields marked `#[skip_serialize]` are initialized
+/// with their `Default` value.
+#[proc_macro_derive(SerialDecodable, attributes(skip_serialize, max_len, serial_version, serial_migrate))]
 pub fn darkfi_deserialize(input: TokenStream) -> TokenStream {
     let found_crate = crate_name("darkfi-serial").expect("darkfi-serial is found in Cargo.toml");
 
@@ -0,0 +1,160 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Bounded decoding of length-prefixed data.
+//!
+//! Collections and strings are prefixed with their length, which is
+//! attacker-controlled when decoding data coming from the network.
+//! The helpers in this module make sure such a prefix can't be used
+//! to make the decoder allocate more memory than the data justifies.
+use std::{
+    cell::Cell,
+    io::{Cursor, Error, ErrorKind, Read},
+    mem::size_of,
+};
+
+use crate::{Decodable, VarInt};
+
+/// Maximum amount of bytes a collection preallocates when decoding,
+/// regardless of the length it claims to have. Anything beyond this
+/// is allocated incrementally, as the elements are actually read.
+pub const MAX_PREALLOC: usize = 64 * 1024;
+
+thread_local! {
+    /// Allocation budget left in the decoding context of this thread, if any
+    static ALLOC_BUDGET: Cell<Option<usize>> = const { Cell::new(None) };
+    /// Limit for the next length prefix read, set by `#[max_len = N]` fields
+    static MAX_LEN: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Decoding context bounding the total amount of memory length-prefixed
+/// types are allowed to allocate while decoding a single object.
+///
+/// The budget is charged with the in-memory size of every collection
+/// as its length prefix is read, so decoding fails before anything is
+/// allocated once the budget is exhausted. Contexts can be nested, in
+/// which case the inner one never gets more than what's left of the
+/// outer budget.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DecodeContext {
+    /// Maximum total amount of bytes that can be allocated
+    pub max_alloc: usize,
+}
+
+impl DecodeContext {
+    /// Create a new decoding context with the given allocation budget
+    pub fn new(max_alloc: usize) -> Self {
+        Self { max_alloc }
+    }
+
+    /// Decode an object from the given reader within this context.
+    pub fn decode<T: Decodable, D: Read>(&self, d: D) -> Result<T, Error> {
+        let _guard = BudgetGuard::enter(self.max_alloc);
+        T::decode(d)
+    }
+
+    /// Deserialize an object from a slice within this context, but do
+    /// not error if the entire slice is not consumed.
+    pub fn deserialize_partial<T: Decodable>(&self, data: &[u8]) -> Result<(T, usize), Error> {
+        let mut decoder = Cursor::new(data);
+        let rv = self.decode(&mut decoder)?;
+        let consumed = decoder.position() as usize;
+
+        Ok((rv, consumed))
+    }
+
+    /// Deserialize an object from a slice within this context.
+    /// Will error if said deserialization doesn't consume the entire slice.
+    pub fn deserialize<T: Decodable>(&self, data: &[u8]) -> Result<T, Error> {
+        let (rv, consumed) = self.deserialize_partial(data)?;
+
+        // Fail if data is not consumed entirely.
+        if consumed != data.len() {
+            return Err(Error::new(ErrorKind::Other, "Data not consumed fully on deserialization"))
+        }
+
+        Ok(rv)
+    }
+}
+
+/// Installs an allocation budget for the current thread, handing what's
+/// left of it back to the enclosing context (if any) when dropped.
+struct BudgetGuard {
+    prev: Option<usize>,
+    budget: usize,
+}
+
+impl BudgetGuard {
+    fn enter(max_alloc: usize) -> Self {
+        let prev = ALLOC_BUDGET.with(|b| b.get());
+        let budget = prev.map_or(max_alloc, |p| p.min(max_alloc));
+        ALLOC_BUDGET.with(|b| b.set(Some(budget)));
+        Self { prev, budget }
+    }
+}
+
+impl Drop for BudgetGuard {
+    fn drop(&mut self) {
+        let left = ALLOC_BUDGET.with(|b| b.get()).unwrap_or(0);
+        let spent = self.budget - left;
+        ALLOC_BUDGET.with(|b| b.set(self.prev.map(|p| p - spent)));
+    }
+}
+
+/// Decode an object, making the first length prefix read while doing so
+/// fail if it's bigger than `max_len`. This is what `#[max_len = N]`
+/// fields in derived `Decodable` implementations expand to.
+pub fn decode_with_max_len<T: Decodable, D: Read>(d: D, max_len: u64) -> Result<T, Error> {
+    let prev = MAX_LEN.with(|m| m.replace(Some(max_len)));
+    let res = T::decode(d);
+    MAX_LEN.with(|m| m.set(prev));
+    res
+}
+
+/// Read the length prefix of a collection of `T` elements, charging the
+/// memory it implies to the current decoding context. Returns the length
+/// along with the capacity that's safe to preallocate for it.
+pub fn decode_len<T, D: Read>(d: D) -> Result<(u64, usize), Error> {
+    let len = VarInt::decode(d)?.0;
+
+    if let Some(max_len) = MAX_LEN.with(|m| m.take()) {
+        if len > max_len {
+            let msg = format!("Length {} exceeds maximum length {}", len, max_len);
+            return Err(Error::new(ErrorKind::InvalidData, msg))
+        }
+    }
+
+    let elem_size = size_of::<T>();
+    let Some(alloc) = len.checked_mul(elem_size as u64) else {
+        return Err(Error::new(ErrorKind::InvalidData, "Length prefix overflows"))
+    };
+
+    ALLOC_BUDGET.with(|b| {
+        if let Some(budget) = b.get() {
+            if alloc > budget as u64 {
+                let msg = format!("Allocation of {} bytes exceeds decoding budget", alloc);
+                return Err(Error::new(ErrorKind::InvalidData, msg))
+            }
+            b.set(Some(budget - alloc as usize));
+        }
+        Ok(())
+    })?;
+
+    let prealloc = len.min((MAX_PREALLOC / elem_size.max(1)) as u64) as usize;
+    Ok((len, prealloc))
+}
@@ -24,6 +24,9 @@ pub use darkfi_derive::{SerialDecodable, SerialEncodable};
 #[cfg(feature = "async")]
 mod async_serial;
 
+mod context;
+pub use context::{decode_len, decode_with_max_len, DecodeContext, MAX_PREALLOC};
+
 mod endian;
 mod types;
 
@@ -458,8 +461,8 @@ impl<T: Encodable> Encodable for Vec<T> {
 impl<T: Decodable> Decodable for Vec<T> {
     #[inline]
     fn decode<D: Read>(mut d: D) -> Result<Self, Error> {
-        let len = VarInt::decode(&mut d)?.0;
-        let mut ret = Vec::with_capacity(len as usize);
+        let (len, prealloc) = decode_len::<T, _>(&mut d)?;
+        let mut ret = Vec::with_capacity(prealloc);
         for _ in 0..len {
             ret.push(Decodable::decode(&mut d)?);
         }
@@ -886,4 +889,193 @@ mod tests {
         assert_eq!(ts1, ts1_n);
         assert_eq!(ts1_n, TestStruct1(baz));
     }
+
+    #[test]
+    fn deserialize_huge_len_prefix() {
+        // A length prefix claiming u64::MAX elements must not be trusted
+        // for preallocation, and decoding should simply run out of data.
+        let mut data = vec![0xff];
+        data.extend_from_slice(&u64_to_array_le(u64::MAX));
+        data.extend_from_slice(&[0u8; 16]);
+        assert!(deserialize::<Vec<u8>>(&data).is_err());
+        assert!(deserialize::<Vec<u64>>(&data).is_err());
+        assert!(deserialize::<Vec<Vec<u8>>>(&data).is_err());
+        assert!(deserialize::<String>(&data).is_err());
+    }
+
+    #[test]
+    fn decode_context_budget() {
+        let ctx = DecodeContext::new(1024);
+
+        let data = serialize(&vec![0u8; 1024]);
+        assert_eq!(ctx.deserialize::<Vec<u8>>(&data).unwrap(), vec![0u8; 1024]);
+        let data = serialize(&vec![0u8; 1025]);
+        assert!(ctx.deserialize::<Vec<u8>>(&data).is_err());
+        assert!(deserialize::<Vec<u8>>(&data).is_ok());
+
+        // The budget is shared by all the collections of the object
+        let data = serialize(&vec![vec![0u8; 100]; 9]);
+        assert!(ctx.deserialize::<Vec<Vec<u8>>>(&data).is_err());
+        let data = serialize(&vec![vec![0u64; 10]; 5]);
+        assert!(ctx.deserialize::<Vec<Vec<u64>>>(&data).is_ok());
+
+        // Nested contexts can't exceed what's left of the outer budget,
+        // and what they spend is charged to the outer one.
+        let data = serialize(&vec![0u8; 200]);
+        assert!(DecodeContext::new(100).deserialize::<NestedDecode>(&data).is_err());
+        assert_eq!(DecodeContext::new(200).deserialize::<NestedDecode>(&data).unwrap().0.len(), 200);
+        let data = serialize(&(vec![0u8; 200], vec![0u8; 200]));
+        let outer = DecodeContext::new(300);
+        assert!(outer.deserialize::<(NestedDecode, Vec<u8>)>(&data).is_err());
+        let outer = DecodeContext::new(400);
+        assert!(outer.deserialize::<(NestedDecode, Vec<u8>)>(&data).is_ok());
+    }
+
+    /// Decodes a byte vector within its own decoding context
+    struct NestedDecode(Vec<u8>);
+
+    impl Decodable for NestedDecode {
+        fn decode<D: Read>(d: D) -> Result<Self, Error> {
+            Ok(Self(DecodeContext::new(1024).decode(d)?))
+        }
+    }
+
+    #[derive(Debug, PartialEq, SerialEncodable, SerialDecodable)]
+    struct TestStruct2 {
+        #[max_len = 4]
+        foo: Vec<u64>,
+        #[max_len = 8]
+        bar: String,
+        baz: Vec<u8>,
+    }
+
+    #[derive(Debug, PartialEq, SerialEncodable, SerialDecodable)]
+    enum TestEnum2 {
+        First(#[max_len = 2] Vec<u8>),
+        Second {
+            #[max_len = 3]
+            foo: Vec<Vec<u8>>,
+        },
+    }
+
+    #[test]
+    fn derive_max_len() {
+        let ts2 = TestStruct2 { foo: vec![1, 2, 3, 4], bar: "foobar".to_string(), baz: vec![0; 32] };
+        assert_eq!(deserialize::<TestStruct2>(&serialize(&ts2)).unwrap(), ts2);
+
+        let ts2 = TestStruct2 { foo: vec![1, 2, 3, 4, 5], bar: "foo".to_string(), baz: vec![] };
+        assert!(deserialize::<TestStruct2>(&serialize(&ts2)).is_err());
+
+        let ts2 = TestStruct2 { foo: vec![], bar: "foobarbaz".to_string(), baz: vec![] };
+        assert!(deserialize::<TestStruct2>(&serialize(&ts2)).is_err());
+
+        let te2 = TestEnum2::First(vec![1, 2]);
+        assert_eq!(deserialize::<TestEnum2>(&serialize(&te2)).unwrap(), te2);
+        let te2 = TestEnum2::First(vec![1, 2, 3]);
+        assert!(deserialize::<TestEnum2>(&serialize(&te2)).is_err());
+
+        // The limit only applies to the outermost length prefix
+        let te2 = TestEnum2::Second { foo: vec![vec![0; 10]; 3] };
+        assert_eq!(deserialize::<TestEnum2>(&serialize(&te2)).unwrap(), te2);
+        let te2 = TestEnum2::Second { foo: vec![vec![]; 4] };
+        assert!(deserialize::<TestEnum2>(&serialize(&te2)).is_err());
+    }
+
+    #[derive(Debug, PartialEq, SerialEncodable, SerialDecodable)]
+    struct TestStructV1 {
+        foo: u64,
+    }
+
+    #[derive(Debug, PartialEq, SerialEncodable, SerialDecodable)]
+    #[serial_version = 2]
+    #[serial_migrate = "TestStructV2::migrate"]
+    struct TestStructV2 {
+        foo: u64,
+        bar: String,
+    }
+
+    impl TestStructV2 {
+        fn migrate<D: Read>(version: u8, d: D) -> Result<Self, Error> {
+            match version {
+                1 => {
+                    let v1: TestStructV1 = Decodable::decode(d)?;
+                    Ok(Self { foo: v1.foo, bar: String::new() })
+                }
+                _ => Err(Error::new(ErrorKind::InvalidData, "Unknown TestStruct version")),
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq, SerialEncodable, SerialDecodable)]
+    #[serial_version = 1]
+    enum TestEnumV1 {
+        First,
+        Second(u64),
+    }
+
+    #[test]
+    fn derive_serial_version() {
+        let ts2 = TestStructV2 { foo: 42, bar: "foobar".to_string() };
+        let ts2_s = serialize(&ts2);
+        assert_eq!(ts2_s[0], 2);
+        assert_eq!(ts2_s[1..], serialize(&(42u64, "foobar".to_string())));
+        assert_eq!(deserialize::<TestStructV2>(&ts2_s).unwrap(), ts2);
+
+        // Older versions go through the migration
+        let mut ts1_s = vec![1];
+        ts1_s.extend_from_slice(&serialize(&TestStructV1 { foo: 42 }));
+        let ts2 = deserialize::<TestStructV2>(&ts1_s).unwrap();
+        assert_eq!(ts2, TestStructV2 { foo: 42, bar: String::new() });
+
+        // Unknown versions are rejected
+        ts1_s[0] = 0;
+        assert!(deserialize::<TestStructV2>(&ts1_s).is_err());
+        let mut ts3_s = serialize(&ts2);
+        ts3_s[0] = 3;
+        assert!(deserialize::<TestStructV2>(&ts3_s).is_err());
+
+        let te1 = TestEnumV1::Second(5);
+        let te1_s = serialize(&te1);
+        assert_eq!(te1_s, vec![1, 1, 5, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(deserialize::<TestEnumV1>(&te1_s).unwrap(), te1);
+        assert_eq!(serialize(&TestEnumV1::First), vec![1, 0]);
+        assert!(deserialize::<TestEnumV1>(&[0, 0]).is_err());
+        assert!(deserialize::<TestEnumV1>(&[2, 0]).is_err());
+    }
+
+    /// Simple xorshift PRNG, so fuzzing inputs are reproducible
+    fn xorshift(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    fn fuzz_length_prefixes() {
+        let ctx = DecodeContext::new(64 * 1024);
+        let mut state = 0x2545f4914f6cdd1d;
+
+        for _ in 0..10000 {
+            // Random bytes, biased towards large VarInt prefixes
+            let len = (xorshift(&mut state) % 64) as usize;
+            let mut data: Vec<u8> = (0..len).map(|_| xorshift(&mut state) as u8).collect();
+            if !data.is_empty() && xorshift(&mut state) & 1 == 0 {
+                data[0] = 0xfd + (xorshift(&mut state) % 3) as u8;
+            }
+
+            // None of these should panic or allocate past the budget
+            let _ = ctx.deserialize::<Vec<u8>>(&data);
+            let _ = ctx.deserialize::<Vec<u64>>(&data);
+            let _ = ctx.deserialize::<Vec<Vec<u8>>>(&data);
+            let _ = ctx.deserialize::<Vec<(u64, String)>>(&data);
+            let _ = ctx.deserialize::<String>(&data);
+            let _ = ctx.deserialize::<TestStruct0>(&data);
+            let _ = ctx.deserialize::<TestStruct2>(&data);
+            let _ = ctx.deserialize::<TestEnum2>(&data);
+            let _ = ctx.deserialize::<TestStructV2>(&data);
+            let _ = deserialize::<Vec<Vec<u64>>>(&data);
+            let _ = deserialize::<Option<Vec<String>>>(&data);
+        }
+    }
 }
@@ -22,7 +22,7 @@ use std::{
     io::{Error, Read, Write},
 };
 
-use crate::{Decodable, Encodable, VarInt};
+use crate::{decode_len, Decodable, Encodable, VarInt};
 
 impl<T: Encodable> Encodable for HashSet<T> {
     fn encode<S: Write>(&self, mut s: S) -> Result<usize, Error> {
@@ -37,8 +37,8 @@ impl<T: Encodable> Encodable for HashSet<T> {
 
 impl<T: Decodable + std::cmp::Eq + std::hash::Hash> Decodable for HashSet<T> {
     fn decode<D: Read>(mut d: D) -> Result<Self, Error> {
-        let len = VarInt::decode(&mut d)?.0;
-        let mut ret = HashSet::new();
+        let (len, prealloc) = decode_len::<T, _>(&mut d)?;
+        let mut ret = HashSet::with_capacity(prealloc);
         for _ in 0..len {
             let entry: T = Decodable::decode(&mut d)?;
             ret.insert(entry);
@@ -61,7 +61,7 @@ impl<T: Encodable, U: Encodable> Encodable for BTreeMap<T, U> {
 
 impl<T: Decodable + std::cmp::Ord, U: Decodable> Decodable for BTreeMap<T, U> {
     fn decode<D: Read>(mut d: D) -> Result<Self, Error> {
-        let len = VarInt::decode(&mut d)?.0;
+        let (len, _) = decode_len::<(T, U), _>(&mut d)?;
         let mut ret = BTreeMap::new();
         for _ in 0..len {
             let key: T = Decodable::decode(&mut d)?;
@@ -85,7 +85,7 @@ impl<T: Encodable> Encodable for BTreeSet<T> {
 
 impl<T: Decodable + std::cmp::Ord> Decodable for BTreeSet<T> {
     fn decode<D: Read>(mut d: D) -> Result<Self, Error> {
-        let len = VarInt::decode(&mut d)?.0;
+        let (len, _) = decode_len::<T, _>(&mut d)?;
         let mut ret = BTreeSet::new();
         for _ in 0..len {
             let key: T = Decodable::decode(&mut d)?;
@@ -97,8 +97,8 @@ impl<T: Decodable + std::cmp::Ord> Decodable for BTreeSet<T> {
 
 impl<T: Decodable + std::cmp::Eq + std::hash::Hash, U: Decodable> Decodable for HashMap<T, U> {
     fn decode<D: Read>(mut d: D) -> Result<Self, Error> {
-        let len = VarInt::decode(&mut d)?.0;
-        let mut ret = HashMap::new();
+        let (len, prealloc) = decode_len::<(T, U), _>(&mut d)?;
+        let mut ret = HashMap::with_capacity(prealloc);
         for _ in 0..len {
             let key: T = Decodable::decode(&mut d)?;
             let entry: U = Decodable::decode(&mut d)?;
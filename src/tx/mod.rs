@@ -22,7 +22,7 @@ use async_std::sync::{Arc, RwLock};
 use darkfi_sdk::{
     crypto::{
         schnorr::{SchnorrPublic, SchnorrSecret, Signature},
-        PublicKey, SecretKey,
+        ContractId, PublicKey, SecretKey,
     },
     pasta::pallas,
     tx::ContractCall,
@@ -57,6 +57,9 @@ pub struct Transaction {
 
 type VerifyingKeyMap = Arc<RwLock<HashMap<[u8; 32], Vec<(String, VerifyingKey)>>>>;
 
+/// Transaction indices and the proofs, with their public inputs, verified in a batch
+type ProofGroup<'a> = (Vec<usize>, Vec<(&'a Proof, &'a [pallas::Base])>);
+
 impl Transaction {
     /// Verify ZK proofs for the entire transaction.
     pub async fn verify_zkps(
@@ -64,37 +67,88 @@ impl Transaction {
         verifying_keys: VerifyingKeyMap,
         zkp_table: Vec<Vec<(String, Vec<pallas::Base>)>>,
     ) -> Result<()> {
-        // TODO: Are we sure we should assert here?
-        assert_eq!(self.calls.len(), self.proofs.len());
-        assert_eq!(self.calls.len(), zkp_table.len());
-
-        for (call, (proofs, pubvals)) in zip!(self.calls, self.proofs, zkp_table) {
-            assert_eq!(proofs.len(), pubvals.len());
-
-            for (proof, (zk_ns, public_vals)) in proofs.iter().zip(pubvals.iter()) {
-                if let Some(vks) = verifying_keys.read().await.get(&call.contract_id.to_bytes()) {
-                    if let Some(vk) = vks.iter().find(|x| &x.0 == zk_ns) {
-                        // We have a verifying key for this
-                        debug!("public inputs: {:#?}", public_vals);
-                        if let Err(e) = proof.verify(&vk.1, public_vals) {
-                            error!(
-                                target: "",
-                                "Failed verifying {}::{} ZK proof: {:#?}",
-                                call.contract_id, zk_ns, e
-                            );
-                            return Err(VerifyFailed::ProofVerifyFailed(e.to_string()).into())
-                        }
-                        debug!("Successfully verified {}::{} ZK proof", call.contract_id, zk_ns);
-                        continue
-                    }
+        Self::verify_zkps_batch(&[(self, zkp_table)], verifying_keys).await
+    }
+
+    /// Verify ZK proofs for multiple transactions at once. The proofs are
+    /// grouped by the circuit they were created for, and every group is
+    /// verified as a single batch.
+    pub async fn verify_zkps_batch(
+        txs: &[(&Self, Vec<Vec<(String, Vec<pallas::Base>)>>)],
+        verifying_keys: VerifyingKeyMap,
+    ) -> Result<()> {
+        // Proofs along with their public inputs, grouped by contract and circuit.
+        // We also keep the index of the transaction each proof belongs to.
+        let mut groups: HashMap<([u8; 32], &str), ProofGroup> = HashMap::new();
+
+        for (tx_idx, (tx, zkp_table)) in txs.iter().enumerate() {
+            // Transactions may come from the network, so a mismatch here is
+            // a verification failure rather than a bug on our side.
+            if tx.calls.len() != tx.proofs.len() || tx.calls.len() != zkp_table.len() {
+                error!(
+                    "Transaction {} has {} calls, {} proof sets and {} ZK public input sets",
+                    tx_idx,
+                    tx.calls.len(),
+                    tx.proofs.len(),
+                    zkp_table.len()
+                );
+                return Err(VerifyFailed::ProofVerifyFailed(
+                    "Calls, proofs and public inputs length mismatch".to_string(),
+                )
+                .into())
+            }
+
+            for (call, (proofs, pubvals)) in zip!(tx.calls, tx.proofs, zkp_table) {
+                if proofs.len() != pubvals.len() {
+                    error!(
+                        "Transaction {} call to {} has {} proofs, expected {}",
+                        tx_idx,
+                        call.contract_id,
+                        proofs.len(),
+                        pubvals.len()
+                    );
+                    return Err(VerifyFailed::ProofVerifyFailed(
+                        "Proofs and public inputs length mismatch".to_string(),
+                    )
+                    .into())
                 }
 
-                let e = format!("{}:{} circuit VK nonexistent", call.contract_id, zk_ns);
-                error!("{}", e);
-                return Err(VerifyFailed::ProofVerifyFailed(e).into())
+                for (proof, (zk_ns, public_vals)) in proofs.iter().zip(pubvals.iter()) {
+                    debug!("public inputs: {:#?}", public_vals);
+                    let group =
+                        groups.entry((call.contract_id.to_bytes(), zk_ns.as_str())).or_default();
+                    group.0.push(tx_idx);
+                    group.1.push((proof, public_vals.as_slice()));
+                }
             }
         }
 
+        let verifying_keys = verifying_keys.read().await;
+        for ((contract_id, zk_ns), (tx_indices, proofs)) in groups {
+            // The IDs came from valid `ContractId`s, so this won't fail.
+            let contract_id = ContractId::from_bytes(contract_id).unwrap();
+
+            let Some(vk) = verifying_keys
+                .get(&contract_id.to_bytes())
+                .and_then(|vks| vks.iter().find(|x| x.0 == zk_ns))
+            else {
+                let circuit = format!("{}:{}", contract_id, zk_ns);
+                error!("{} circuit VK nonexistent", circuit);
+                return Err(VerifyFailed::MissingVerifyingKey(circuit).into())
+            };
+
+            if let Err((i, e)) = Proof::verify_batch(&vk.1, &proofs) {
+                error!(
+                    target: "",
+                    "Failed verifying {}::{} ZK proof of transaction {}: {:#?}",
+                    contract_id, zk_ns, tx_indices[i], e
+                );
+                return Err(VerifyFailed::ProofVerifyFailed(e.to_string()).into())
+            }
+
+            debug!("Successfully verified {} {}::{} ZK proof(s)", proofs.len(), contract_id, zk_ns);
+        }
+
         Ok(())
     }
 
@@ -104,9 +158,26 @@ impl Transaction {
         let data_hash = blake3::hash(&tx_data);
         debug!("tx.verify_sigs: data_hash: {:?}", data_hash.as_bytes());
 
-        assert!(pub_table.len() == self.signatures.len());
+        if pub_table.len() != self.signatures.len() {
+            error!(
+                "tx::verify_sigs has {} signature sets, expected {}",
+                self.signatures.len(),
+                pub_table.len()
+            );
+            return Err(Error::InvalidSignature)
+        }
 
         for (i, (sigs, pubkeys)) in self.signatures.iter().zip(pub_table.iter()).enumerate() {
+            if sigs.len() != pubkeys.len() {
+                error!(
+                    "tx::verify_sigs[{}] has {} signatures, expected {}",
+                    i,
+                    sigs.len(),
+                    pubkeys.len()
+                );
+                return Err(Error::InvalidSignature)
+            }
+
             for (pubkey, signature) in pubkeys.iter().zip(sigs) {
                 debug!("Verifying signature with public key: {}", pubkey);
                 if !pubkey.verify(&data_hash.as_bytes()[..], signature) {
@@ -0,0 +1,217 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Chip, Layouter},
+    pasta::pallas,
+    plonk,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Expression, Selector},
+    poly::Rotation,
+};
+
+#[derive(Clone, Debug)]
+pub struct ConditionalSelectConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    cond: Column<Advice>,
+    out: Column<Advice>,
+    q_select: Selector,
+}
+
+/// Selects between two field elements depending on a boolean condition:
+/// `out = cond ? a : b`. The condition is constrained to be either 0 or 1.
+pub struct ConditionalSelectChip {
+    config: ConditionalSelectConfig,
+}
+
+impl Chip<pallas::Base> for ConditionalSelectChip {
+    type Config = ConditionalSelectConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl ConditionalSelectChip {
+    pub fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        a: Column<Advice>,
+        b: Column<Advice>,
+        cond: Column<Advice>,
+        out: Column<Advice>,
+    ) -> ConditionalSelectConfig {
+        let q_select = meta.selector();
+
+        meta.create_gate("Conditional select: out = cond * a + (1 - cond) * b", |meta| {
+            let q_select = meta.query_selector(q_select);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let cond = meta.query_advice(cond, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+
+            let one = Expression::Constant(pallas::Base::one());
+            let bool_check = cond.clone() * (one - cond.clone());
+            let select = cond * (a - b.clone()) + b - out;
+
+            Constraints::with_selector(q_select, [("bool check", bool_check), ("select", select)])
+        });
+
+        ConditionalSelectConfig { a, b, cond, out, q_select }
+    }
+
+    pub fn construct(config: ConditionalSelectConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn conditional_select(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        cond: &AssignedCell<pallas::Base, pallas::Base>,
+        a: &AssignedCell<pallas::Base, pallas::Base>,
+        b: &AssignedCell<pallas::Base, pallas::Base>,
+    ) -> Result<AssignedCell<pallas::Base, pallas::Base>, plonk::Error> {
+        layouter.assign_region(
+            || "out = cond ? a : b",
+            |mut region| {
+                self.config.q_select.enable(&mut region, 0)?;
+
+                a.copy_advice(|| "copy a", &mut region, self.config.a, 0)?;
+                b.copy_advice(|| "copy b", &mut region, self.config.b, 0)?;
+                cond.copy_advice(|| "copy cond", &mut region, self.config.cond, 0)?;
+
+                let out = cond.value().zip(a.value()).zip(b.value()).map(|((cond, a), b)| {
+                    if *cond == pallas::Base::one() {
+                        *a
+                    } else {
+                        *b
+                    }
+                });
+                region.assign_advice(|| "out", self.config.out, 0, || out)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk::assign_free_advice;
+
+    use halo2_proofs::{
+        circuit::{floor_planner, Value},
+        dev::MockProver,
+        plonk::{Circuit, Instance as InstanceColumn},
+    };
+
+    #[derive(Clone)]
+    struct CondSelectCircuitConfig {
+        primary: Column<InstanceColumn>,
+        advices: [Column<Advice>; 4],
+        cond_select_config: ConditionalSelectConfig,
+    }
+
+    #[derive(Default)]
+    struct CondSelectCircuit {
+        cond: Value<pallas::Base>,
+        a: Value<pallas::Base>,
+        b: Value<pallas::Base>,
+    }
+
+    impl Circuit<pallas::Base> for CondSelectCircuit {
+        type Config = CondSelectCircuitConfig;
+        type FloorPlanner = floor_planner::V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advices = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+
+            let primary = meta.instance_column();
+            meta.enable_equality(primary);
+
+            for advice in advices.iter() {
+                meta.enable_equality(*advice);
+            }
+
+            let cond_select_config = ConditionalSelectChip::configure(
+                meta, advices[0], advices[1], advices[2], advices[3],
+            );
+
+            CondSelectCircuitConfig { primary, advices, cond_select_config }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> std::result::Result<(), plonk::Error> {
+            let chip = ConditionalSelectChip::construct(config.cond_select_config);
+
+            let cond = assign_free_advice(
+                layouter.namespace(|| "Load cond"),
+                config.advices[0],
+                self.cond,
+            )?;
+            let a = assign_free_advice(layouter.namespace(|| "Load a"), config.advices[1], self.a)?;
+            let b = assign_free_advice(layouter.namespace(|| "Load b"), config.advices[2], self.b)?;
+
+            let out =
+                chip.conditional_select(layouter.namespace(|| "cond_select"), &cond, &a, &b)?;
+            layouter.constrain_instance(out.cell(), config.primary, 0)?;
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn cond_select() {
+        let a = pallas::Base::from(42);
+        let b = pallas::Base::from(69);
+
+        for (cond, expected) in [(pallas::Base::one(), a), (pallas::Base::zero(), b)] {
+            let circuit = CondSelectCircuit {
+                cond: Value::known(cond),
+                a: Value::known(a),
+                b: Value::known(b),
+            };
+            let prover = MockProver::run(4, &circuit, vec![vec![expected]]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // A non-boolean condition must not satisfy the circuit
+        let circuit = CondSelectCircuit {
+            cond: Value::known(pallas::Base::from(2)),
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        let prover = MockProver::run(4, &circuit, vec![vec![b]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
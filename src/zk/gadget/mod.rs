@@ -30,3 +30,9 @@ pub mod less_than;
 
 /// is_zero comparison gadget
 pub mod is_zero;
+
+/// Conditional selection gadget
+pub mod cond_select;
+
+/// Zero-conditional selection gadget, based on is_zero
+pub mod zero_cond;
@@ -0,0 +1,257 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Chip, Layouter, Value},
+    pasta::pallas,
+    plonk,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::is_zero::{IsZeroChip, IsZeroConfig};
+
+#[derive(Clone, Debug)]
+pub struct ZeroCondConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    out: Column<Advice>,
+    q_zero_cond: Selector,
+    q_is_zero: Selector,
+    is_zero: IsZeroConfig<pallas::Base>,
+}
+
+/// Zero-conditional gadget built on top of the `is_zero` chip. Provides
+/// `zero_cond`, where `out = a == 0 ? 0 : b`, and `is_zero`, where
+/// `out = a == 0 ? 1 : 0`.
+pub struct ZeroCondChip {
+    config: ZeroCondConfig,
+}
+
+impl Chip<pallas::Base> for ZeroCondChip {
+    type Config = ZeroCondConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl ZeroCondChip {
+    pub fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        a: Column<Advice>,
+        b: Column<Advice>,
+        out: Column<Advice>,
+        a_inv: Column<Advice>,
+    ) -> ZeroCondConfig {
+        // Both selectors enable the is_zero gate, and simple selectors
+        // can't be added together.
+        let q_zero_cond = meta.complex_selector();
+        let q_is_zero = meta.complex_selector();
+
+        let is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_zero_cond) + meta.query_selector(q_is_zero),
+            |meta| meta.query_advice(a, Rotation::cur()),
+            a_inv,
+        );
+
+        meta.create_gate("Zero conditional: out = a == 0 ? 0 : b", |meta| {
+            let q_zero_cond = meta.query_selector(q_zero_cond);
+            let b = meta.query_advice(b, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+
+            let one = Expression::Constant(pallas::Base::one());
+            Constraints::with_selector(q_zero_cond, Some(out - (one - is_zero.expr()) * b))
+        });
+
+        meta.create_gate("Is zero: out = a == 0 ? 1 : 0", |meta| {
+            let q_is_zero = meta.query_selector(q_is_zero);
+            let out = meta.query_advice(out, Rotation::cur());
+
+            Constraints::with_selector(q_is_zero, Some(out - is_zero.expr()))
+        });
+
+        ZeroCondConfig { a, b, out, q_zero_cond, q_is_zero, is_zero }
+    }
+
+    pub fn construct(config: ZeroCondConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn zero_cond(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        a: &AssignedCell<pallas::Base, pallas::Base>,
+        b: &AssignedCell<pallas::Base, pallas::Base>,
+    ) -> Result<AssignedCell<pallas::Base, pallas::Base>, plonk::Error> {
+        let is_zero_chip = IsZeroChip::construct(self.config.is_zero.clone());
+
+        layouter.assign_region(
+            || "out = a == 0 ? 0 : b",
+            |mut region| {
+                self.config.q_zero_cond.enable(&mut region, 0)?;
+
+                a.copy_advice(|| "copy a", &mut region, self.config.a, 0)?;
+                b.copy_advice(|| "copy b", &mut region, self.config.b, 0)?;
+                is_zero_chip.assign(&mut region, 0, a.value().copied())?;
+
+                let out = a.value().zip(b.value()).map(|(a, b)| {
+                    if *a == pallas::Base::zero() {
+                        pallas::Base::zero()
+                    } else {
+                        *b
+                    }
+                });
+                region.assign_advice(|| "out", self.config.out, 0, || out)
+            },
+        )
+    }
+
+    pub fn is_zero(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        a: &AssignedCell<pallas::Base, pallas::Base>,
+    ) -> Result<AssignedCell<pallas::Base, pallas::Base>, plonk::Error> {
+        let is_zero_chip = IsZeroChip::construct(self.config.is_zero.clone());
+
+        layouter.assign_region(
+            || "out = a == 0 ? 1 : 0",
+            |mut region| {
+                self.config.q_is_zero.enable(&mut region, 0)?;
+
+                a.copy_advice(|| "copy a", &mut region, self.config.a, 0)?;
+                region.assign_advice(
+                    || "b",
+                    self.config.b,
+                    0,
+                    || Value::known(pallas::Base::zero()),
+                )?;
+                is_zero_chip.assign(&mut region, 0, a.value().copied())?;
+
+                let out = a.value().map(|a| {
+                    if *a == pallas::Base::zero() {
+                        pallas::Base::one()
+                    } else {
+                        pallas::Base::zero()
+                    }
+                });
+                region.assign_advice(|| "out", self.config.out, 0, || out)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk::assign_free_advice;
+
+    use halo2_proofs::{
+        circuit::floor_planner,
+        dev::MockProver,
+        plonk::{Circuit, Instance as InstanceColumn},
+    };
+
+    #[derive(Clone)]
+    struct ZeroCondCircuitConfig {
+        primary: Column<InstanceColumn>,
+        advices: [Column<Advice>; 4],
+        zero_cond_config: ZeroCondConfig,
+    }
+
+    #[derive(Default)]
+    struct ZeroCondCircuit {
+        a: Value<pallas::Base>,
+        b: Value<pallas::Base>,
+    }
+
+    impl Circuit<pallas::Base> for ZeroCondCircuit {
+        type Config = ZeroCondCircuitConfig;
+        type FloorPlanner = floor_planner::V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advices = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+
+            let primary = meta.instance_column();
+            meta.enable_equality(primary);
+
+            for advice in advices.iter() {
+                meta.enable_equality(*advice);
+            }
+
+            let zero_cond_config =
+                ZeroCondChip::configure(meta, advices[0], advices[1], advices[2], advices[3]);
+
+            ZeroCondCircuitConfig { primary, advices, zero_cond_config }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> std::result::Result<(), plonk::Error> {
+            let chip = ZeroCondChip::construct(config.zero_cond_config);
+
+            let a = assign_free_advice(layouter.namespace(|| "Load a"), config.advices[0], self.a)?;
+            let b = assign_free_advice(layouter.namespace(|| "Load b"), config.advices[1], self.b)?;
+
+            let out = chip.zero_cond(layouter.namespace(|| "zero_cond"), &a, &b)?;
+            layouter.constrain_instance(out.cell(), config.primary, 0)?;
+
+            let out = chip.is_zero(layouter.namespace(|| "is_zero"), &a)?;
+            layouter.constrain_instance(out.cell(), config.primary, 1)?;
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn zero_cond() {
+        let zero = pallas::Base::zero();
+        let one = pallas::Base::one();
+        let b = pallas::Base::from(69);
+
+        for (a, public_inputs) in [(zero, vec![zero, one]), (pallas::Base::from(42), vec![b, zero])]
+        {
+            let circuit = ZeroCondCircuit { a: Value::known(a), b: Value::known(b) };
+            let prover = MockProver::run(4, &circuit, vec![public_inputs]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // Claiming a non-zero value is zero must not satisfy the circuit
+        let circuit =
+            ZeroCondCircuit { a: Value::known(pallas::Base::from(42)), b: Value::known(b) };
+        let prover = MockProver::run(4, &circuit, vec![vec![zero, one]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
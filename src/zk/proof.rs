@@ -20,7 +20,7 @@ use darkfi_serial::{SerialDecodable, SerialEncodable};
 use halo2_proofs::{
     pasta::{pallas, vesta},
     plonk,
-    plonk::{Circuit, SingleVerifier},
+    plonk::{BatchVerifier, Circuit, SingleVerifier},
     poly::commitment::Params,
     transcript::{Blake2bRead, Blake2bWrite},
 };
@@ -101,6 +101,37 @@ impl Proof {
         plonk::verify_proof(&vk.params, &vk.vk, strategy, &[&[instances]], &mut transcript)
     }
 
+    /// Verify several proofs created for the same circuit at once. This
+    /// shares a single MSM between all of them, which is a lot cheaper than
+    /// verifying them one by one. If the batch fails to verify, the proofs
+    /// are verified individually to find the culprit, and the index of the
+    /// failing proof is returned along with its error.
+    pub fn verify_batch(
+        vk: &VerifyingKey,
+        proofs: &[(&Proof, &[pallas::Base])],
+    ) -> std::result::Result<(), (usize, plonk::Error)> {
+        if let [(proof, instances)] = proofs {
+            return proof.verify(vk, instances).map_err(|e| (0, e))
+        }
+
+        let mut batch = BatchVerifier::new();
+        for (proof, instances) in proofs {
+            batch.add_proof(vec![vec![instances.to_vec()]], proof.0.clone());
+        }
+
+        if batch.finalize(&vk.params, &vk.vk) {
+            return Ok(())
+        }
+
+        for (i, (proof, instances)) in proofs.iter().enumerate() {
+            proof.verify(vk, instances).map_err(|e| (i, e))?;
+        }
+
+        // The batch failed, but every proof is valid on its own. This should
+        // not happen, so we just report the first one.
+        Err((0, plonk::Error::ConstraintSystemFailure))
+    }
+
     pub fn new(bytes: Vec<u8>) -> Self {
         Proof(bytes)
     }
@@ -53,9 +53,11 @@ use super::{
     assign_free_advice,
     gadget::{
         arithmetic::{ArithChip, ArithConfig, ArithInstruction},
+        cond_select::{ConditionalSelectChip, ConditionalSelectConfig},
         less_than::{LessThanChip, LessThanConfig},
         native_range_check::{NativeRangeCheckChip, NativeRangeCheckConfig},
         small_range_check::{SmallRangeCheckChip, SmallRangeCheckConfig},
+        zero_cond::{ZeroCondChip, ZeroCondConfig},
     },
 };
 use crate::zkas::{
@@ -79,6 +81,8 @@ pub struct VmConfig {
     native_253_range_check_config: NativeRangeCheckConfig<3, 253, 85>,
     lessthan_config: LessThanConfig<3, 253, 85>,
     boolcheck_config: SmallRangeCheckConfig,
+    cond_select_config: ConditionalSelectConfig,
+    zero_cond_config: ZeroCondConfig,
 }
 
 impl VmConfig {
@@ -105,6 +109,14 @@ impl VmConfig {
     fn arithmetic_chip(&self) -> ArithChip {
         ArithChip::construct(self.arith_config.clone())
     }
+
+    fn cond_select_chip(&self) -> ConditionalSelectChip {
+        ConditionalSelectChip::construct(self.cond_select_config.clone())
+    }
+
+    fn zero_cond_chip(&self) -> ZeroCondChip {
+        ZeroCondChip::construct(self.zero_cond_config.clone())
+    }
 }
 
 pub struct ZkCircuit {
@@ -263,6 +275,15 @@ impl Circuit<pallas::Base> for ZkCircuit {
         // chip with a range of 2, which enforces one bit, i.e. 0 or 1.
         let boolcheck_config = SmallRangeCheckChip::configure(meta, advices[9], 2);
 
+        // Configuration for conditional selection, which also enforces
+        // the condition to be a boolean.
+        let cond_select_config =
+            ConditionalSelectChip::configure(meta, advices[1], advices[2], advices[3], advices[4]);
+
+        // Configuration for the is_zero based zero_cond and is_zero opcodes.
+        let zero_cond_config =
+            ZeroCondChip::configure(meta, advices[1], advices[2], advices[3], advices[4]);
+
         VmConfig {
             primary,
             advices,
@@ -277,6 +298,8 @@ impl Circuit<pallas::Base> for ZkCircuit {
             native_253_range_check_config,
             lessthan_config,
             boolcheck_config,
+            cond_select_config,
+            zero_cond_config,
         }
     }
 
@@ -335,6 +358,10 @@ impl Circuit<pallas::Base> for ZkCircuit {
         // Construct the boolean check chip.
         let boolcheck_chip = SmallRangeCheckChip::construct(config.boolcheck_config.clone());
 
+        // Construct the conditional selection chips.
+        let cond_select_chip = config.cond_select_chip();
+        let zero_cond_chip = config.zero_cond_chip();
+
         // ==========================
         // Constants setup
         // ==========================
@@ -592,6 +619,56 @@ impl Circuit<pallas::Base> for ZkCircuit {
                     stack.push(StackVar::Base(ret));
                 }
 
+                Opcode::EcCondSelect => {
+                    trace!(target: "zk::vm", "Executing `EcCondSelect{:?}` opcode", opcode.1);
+                    let args = &opcode.1;
+
+                    let cond: AssignedCell<Fp, Fp> = stack[args[0].1].clone().into();
+
+                    let lhs: Point<pallas::Affine, EccChip<OrchardFixedBases>> =
+                        stack[args[1].1].clone().into();
+
+                    let rhs: Point<pallas::Affine, EccChip<OrchardFixedBases>> =
+                        stack[args[2].1].clone().into();
+
+                    // Select the coordinates one by one, then witness the
+                    // resulting point and constrain it to those coordinates.
+                    let x = cond_select_chip.conditional_select(
+                        layouter.namespace(|| "EcCondSelect(): x"),
+                        &cond,
+                        &lhs.inner().x(),
+                        &rhs.inner().x(),
+                    )?;
+
+                    let y = cond_select_chip.conditional_select(
+                        layouter.namespace(|| "EcCondSelect(): y"),
+                        &cond,
+                        &lhs.inner().y(),
+                        &rhs.inner().y(),
+                    )?;
+
+                    let point = cond.value().zip(lhs.inner().point()).zip(rhs.inner().point()).map(
+                        |((cond, lhs), rhs)| if *cond == pallas::Base::one() { lhs } else { rhs },
+                    );
+
+                    let ret = Point::new(
+                        ecc_chip.clone(),
+                        layouter.namespace(|| "EcCondSelect(): witness point"),
+                        point,
+                    )?;
+
+                    layouter.assign_region(
+                        || "constrain selected point coordinates",
+                        |mut region| {
+                            region.constrain_equal(ret.inner().x().cell(), x.cell())?;
+                            region.constrain_equal(ret.inner().y().cell(), y.cell())
+                        },
+                    )?;
+
+                    trace!(target: "zk::vm", "Pushing result to stack index {}", stack.len());
+                    stack.push(StackVar::EcPoint(ret));
+                }
+
                 Opcode::PoseidonHash => {
                     trace!(target: "zk::vm", "Executing `PoseidonHash{:?}` opcode", opcode.1);
                     let args = &opcode.1;
@@ -798,6 +875,54 @@ impl Circuit<pallas::Base> for ZkCircuit {
                         .small_range_check(layouter.namespace(|| "copy boolean check"), w)?;
                 }
 
+                Opcode::CondSelect => {
+                    trace!(target: "zk::vm", "Executing `CondSelect{:?}` opcode", opcode.1);
+                    let args = &opcode.1;
+
+                    let cond = &stack[args[0].1].clone().into();
+                    let lhs = &stack[args[1].1].clone().into();
+                    let rhs = &stack[args[2].1].clone().into();
+
+                    let out = cond_select_chip.conditional_select(
+                        layouter.namespace(|| "CondSelect()"),
+                        cond,
+                        lhs,
+                        rhs,
+                    )?;
+
+                    trace!(target: "zk::vm", "Pushing assignment to stack index {}", stack.len());
+                    stack.push(StackVar::Base(out));
+                }
+
+                Opcode::ZeroCondSelect => {
+                    trace!(target: "zk::vm", "Executing `ZeroCondSelect{:?}` opcode", opcode.1);
+                    let args = &opcode.1;
+
+                    let lhs = &stack[args[0].1].clone().into();
+                    let rhs = &stack[args[1].1].clone().into();
+
+                    let out = zero_cond_chip.zero_cond(
+                        layouter.namespace(|| "ZeroCondSelect()"),
+                        lhs,
+                        rhs,
+                    )?;
+
+                    trace!(target: "zk::vm", "Pushing assignment to stack index {}", stack.len());
+                    stack.push(StackVar::Base(out));
+                }
+
+                Opcode::IsZero => {
+                    trace!(target: "zk::vm", "Executing `IsZero{:?}` opcode", opcode.1);
+                    let args = &opcode.1;
+
+                    let arg = &stack[args[0].1].clone().into();
+
+                    let out = zero_cond_chip.is_zero(layouter.namespace(|| "IsZero()"), arg)?;
+
+                    trace!(target: "zk::vm", "Pushing assignment to stack index {}", stack.len());
+                    stack.push(StackVar::Base(out));
+                }
+
                 Opcode::ConstrainEqualBase => {
                     trace!(target: "zk::vm", "Executing `ConstrainEqualBase{:?}` opcode", opcode.1);
                     let args = &opcode.1;
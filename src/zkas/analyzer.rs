@@ -195,6 +195,8 @@ impl Analyzer {
                                     Var::Variable(c) => (c.typ, c.line, c.column),
                                 };
 
+                                self.check_param_type(v, var_type);
+
                                 if var_type != f_arg_types[inner_idx] {
                                     self.error.abort(
                                         &format!(
@@ -280,6 +282,8 @@ impl Analyzer {
                             Var::Variable(c) => (c.typ, c.line, c.column),
                         };
 
+                        self.check_param_type(v, var_type);
+
                         // FIXME: Better array handling
                         if arg_types[0] == VarType::BaseArray {
                             if var_type != VarType::Base {
@@ -360,6 +364,21 @@ impl Analyzer {
         //println!("==================LITERALS================\n{:#?}", self.literals);
     }
 
+    /// Variables passed to a function parameter carry the parameter's
+    /// declared type from the parser, which must match the actual type.
+    fn check_param_type(&self, v: &Variable, var_type: VarType) {
+        if v.typ != VarType::Dummy && v.typ != var_type {
+            self.error.abort(
+                &format!(
+                    "Incorrect function argument type for `{}`. Expected `{:?}`, got `{:?}`.",
+                    v.name, v.typ, var_type
+                ),
+                v.line,
+                v.column,
+            );
+        }
+    }
+
     fn lookup_var(&self, name: &str) -> Option<Var> {
         if let Some(r) = self.lookup_constant(name) {
             return Some(Var::Constant(r))
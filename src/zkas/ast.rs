@@ -86,3 +86,13 @@ impl Default for Statement {
         Self { typ: StatementType::Noop, opcode: Opcode::Noop, lhs: None, rhs: vec![], line: 0 }
     }
 }
+
+#[derive(Clone, Debug)]
+pub struct Function {
+    pub name: String,
+    pub params: Vec<Variable>,
+    pub statements: Vec<Statement>,
+    pub returns: bool,
+    pub line: usize,
+    pub column: usize,
+}
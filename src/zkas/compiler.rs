@@ -27,11 +27,12 @@ use super::{
 };
 
 /// Version of the binary
-pub const BINARY_VERSION: u8 = 2;
+pub const BINARY_VERSION: u8 = 3;
 /// Magic bytes prepended to the binary
 pub const MAGIC_BYTES: [u8; 4] = [0x0b, 0x01, 0xb1, 0x35];
 
 pub struct Compiler {
+    k: u32,
     namespace: String,
     constants: Vec<Constant>,
     witnesses: Vec<Witness>,
@@ -46,6 +47,7 @@ impl Compiler {
     pub fn new(
         filename: &str,
         source: Chars,
+        k: u32,
         namespace: String,
         constants: Vec<Constant>,
         witnesses: Vec<Witness>,
@@ -58,7 +60,7 @@ impl Compiler {
         let lines: Vec<String> = source.as_str().lines().map(|x| x.to_string()).collect();
         let error = ErrorEmitter::new("Compiler", filename, lines);
 
-        Self { namespace, constants, witnesses, statements, literals, debug_info, error }
+        Self { k, namespace, constants, witnesses, statements, literals, debug_info, error }
     }
 
     pub fn compile(&self) -> Vec<u8> {
@@ -68,6 +70,9 @@ impl Compiler {
         bincode.extend_from_slice(&MAGIC_BYTES);
         bincode.push(BINARY_VERSION);
 
+        // Write the circuit's size parameter `k`
+        bincode.extend_from_slice(&self.k.to_le_bytes());
+
         // Write the circuit's namespace
         bincode.extend_from_slice(&serialize(&self.namespace));
 
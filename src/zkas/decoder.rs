@@ -18,13 +18,20 @@
 
 use darkfi_serial::{deserialize_partial, VarInt};
 
-use super::{compiler::MAGIC_BYTES, types::StackType, LitType, Opcode, VarType};
+use super::{
+    compiler::{BINARY_VERSION, MAGIC_BYTES},
+    parser::MAX_K,
+    types::StackType,
+    LitType, Opcode, VarType,
+};
 use crate::{Error::ZkasDecoderError as ZkasErr, Result};
 
 /// A ZkBinary decoded from compiled zkas code.
 /// This is used by the zkvm.
 #[derive(Clone, Debug)]
 pub struct ZkBinary {
+    /// The circuit size parameter, the circuit has `2^k` rows
+    pub k: u32,
     pub namespace: String,
     pub constants: Vec<(VarType, String)>,
     pub literals: Vec<(LitType, String)>,
@@ -39,15 +46,34 @@ fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
 
 impl ZkBinary {
     pub fn decode(bytes: &[u8]) -> Result<Self> {
+        // Magic bytes, binary version, and k
+        if bytes.len() < 9 {
+            return Err(ZkasErr("Binary is too short.".to_string()))
+        }
+
         let magic_bytes = &bytes[0..4];
         if magic_bytes != MAGIC_BYTES {
             return Err(ZkasErr("Magic bytes are incorrect.".to_string()))
         }
 
-        let _binary_version = &bytes[4];
+        let binary_version = bytes[4];
+        if binary_version != BINARY_VERSION {
+            return Err(ZkasErr(format!(
+                "Unsupported binary version {}, expected {}.",
+                binary_version, BINARY_VERSION
+            )))
+        }
 
-        // After the binary version, we're supposed to have the contract namespace
-        let (namespace, _) = deserialize_partial(&bytes[5..])?;
+        // After the binary version, we have the circuit's k parameter.
+        // Binaries may come from untrusted sources, and proving and verifying
+        // keys grow with `2^k`, so enforce the same bounds as the parser.
+        let k = u32::from_le_bytes(bytes[5..9].try_into().unwrap());
+        if k == 0 || k > MAX_K {
+            return Err(ZkasErr(format!("Circuit k must be between 1 and {}, got {}.", MAX_K, k)))
+        }
+
+        // After k, we're supposed to have the contract namespace
+        let (namespace, _) = deserialize_partial(&bytes[9..])?;
 
         let constants_offset = match find_subslice(bytes, b".constant") {
             Some(v) => v,
@@ -102,7 +128,7 @@ impl ZkBinary {
 
         // TODO: Debug info
 
-        Ok(Self { namespace, constants, literals, witnesses, opcodes })
+        Ok(Self { k, namespace, constants, literals, witnesses, opcodes })
     }
 
     fn parse_constants(bytes: &[u8]) -> Result<Vec<(VarType, String)>> {
@@ -220,3 +246,45 @@ impl ZkBinary {
         Ok(opcodes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zkas::{Analyzer, Compiler, Lexer, Parser};
+
+    fn compile(source: &str) -> Vec<u8> {
+        let lexer = Lexer::new("test.zk", source.chars());
+        let tokens = lexer.lex();
+        let parser = Parser::new("test.zk", source.chars(), tokens);
+        let (k, namespace, constants, witnesses, statements) = parser.parse();
+        let mut analyzer =
+            Analyzer::new("test.zk", source.chars(), constants, witnesses, statements);
+        analyzer.analyze_types();
+        let compiler = Compiler::new(
+            "test.zk",
+            source.chars(),
+            k,
+            namespace,
+            analyzer.constants,
+            analyzer.witnesses,
+            analyzer.statements,
+            analyzer.literals,
+            false,
+        );
+        compiler.compile()
+    }
+
+    #[test]
+    fn test_decode_k_bounds() {
+        let mut bincode = compile(include_str!("../../proof/arithmetic.zk"));
+        assert_eq!(ZkBinary::decode(&bincode).unwrap().k, 13);
+
+        for k in [0, MAX_K + 1, u32::MAX] {
+            bincode[5..9].copy_from_slice(&k.to_le_bytes());
+            assert!(ZkBinary::decode(&bincode).is_err());
+        }
+
+        bincode[5..9].copy_from_slice(&MAX_K.to_le_bytes());
+        assert_eq!(ZkBinary::decode(&bincode).unwrap().k, MAX_K);
+    }
+}
@@ -22,6 +22,9 @@ use super::error::ErrorEmitter;
 
 const SPECIAL_CHARS: [char; 7] = ['{', '}', '(', ')', ',', ';', '='];
 
+/// Additional characters allowed inside strings
+const PATH_CHARS: [char; 3] = ['/', '.', '-'];
+
 fn is_letter(ch: char) -> bool {
     ('a'..='z').contains(&ch) || ('A'..='Z').contains(&ch) || ch == '_'
 }
@@ -201,6 +204,12 @@ impl<'a> Lexer<'a> {
                 continue
             }
 
+            // Path separators are allowed in strings for `import` paths.
+            if in_string && PATH_CHARS.contains(&c) {
+                buf.push(c);
+                continue
+            }
+
             if in_string && c == '"' {
                 // " I need to fix my vis lexer
                 if buf.is_empty() {
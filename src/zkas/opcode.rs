@@ -46,6 +46,9 @@ pub enum Opcode {
     /// Get the y coordinate of an elliptic curve point
     EcGetY = 0x09,
 
+    /// Select one of two elliptic curve points, given a boolean condition
+    EcCondSelect = 0x0a,
+
     /// Poseidon hash of N Base field elements
     PoseidonHash = 0x10,
 
@@ -78,6 +81,15 @@ pub enum Opcode {
     /// Check if a field element fits in a boolean (Either 0 or 1)
     BoolCheck = 0x53,
 
+    /// Select one of two Base field elements, given a boolean condition
+    CondSelect = 0x60,
+
+    /// Output zero if a Base field element is zero, otherwise a second one
+    ZeroCondSelect = 0x61,
+
+    /// Output one if a Base field element is zero, otherwise zero
+    IsZero = 0x62,
+
     /// Constrain equality of two Base field elements inside the circuit
     ConstrainEqualBase = 0xe0,
 
@@ -101,6 +113,7 @@ impl Opcode {
             "ec_mul_var_base" => Some(Self::EcMulVarBase),
             "ec_get_x" => Some(Self::EcGetX),
             "ec_get_y" => Some(Self::EcGetY),
+            "ec_cond_select" => Some(Self::EcCondSelect),
             "poseidon_hash" => Some(Self::PoseidonHash),
             "merkle_root" => Some(Self::MerkleRoot),
             "base_add" => Some(Self::BaseAdd),
@@ -111,6 +124,9 @@ impl Opcode {
             "less_than_strict" => Some(Self::LessThanStrict),
             "less_than_loose" => Some(Self::LessThanLoose),
             "bool_check" => Some(Self::BoolCheck),
+            "cond_select" => Some(Self::CondSelect),
+            "zero_cond" => Some(Self::ZeroCondSelect),
+            "is_zero" => Some(Self::IsZero),
             "constrain_equal_base" => Some(Self::ConstrainEqualBase),
             "constrain_equal_point" => Some(Self::ConstrainEqualPoint),
             "constrain_instance" => Some(Self::ConstrainInstance),
@@ -128,6 +144,7 @@ impl Opcode {
             0x05 => Some(Self::EcMulVarBase),
             0x08 => Some(Self::EcGetX),
             0x09 => Some(Self::EcGetY),
+            0x0a => Some(Self::EcCondSelect),
             0x10 => Some(Self::PoseidonHash),
             0x20 => Some(Self::MerkleRoot),
             0x30 => Some(Self::BaseAdd),
@@ -138,6 +155,9 @@ impl Opcode {
             0x51 => Some(Self::LessThanStrict),
             0x52 => Some(Self::LessThanLoose),
             0x53 => Some(Self::BoolCheck),
+            0x60 => Some(Self::CondSelect),
+            0x61 => Some(Self::ZeroCondSelect),
+            0x62 => Some(Self::IsZero),
             0xe0 => Some(Self::ConstrainEqualBase),
             0xe1 => Some(Self::ConstrainEqualPoint),
             0xf0 => Some(Self::ConstrainInstance),
@@ -172,6 +192,10 @@ impl Opcode {
 
             Opcode::EcGetY => (vec![VarType::Base], vec![VarType::EcPoint]),
 
+            Opcode::EcCondSelect => {
+                (vec![VarType::EcPoint], vec![VarType::Base, VarType::EcPoint, VarType::EcPoint])
+            }
+
             Opcode::PoseidonHash => (vec![VarType::Base], vec![VarType::BaseArray]),
 
             Opcode::MerkleRoot => {
@@ -194,6 +218,14 @@ impl Opcode {
 
             Opcode::BoolCheck => (vec![], vec![VarType::Base]),
 
+            Opcode::CondSelect => {
+                (vec![VarType::Base], vec![VarType::Base, VarType::Base, VarType::Base])
+            }
+
+            Opcode::ZeroCondSelect => (vec![VarType::Base], vec![VarType::Base, VarType::Base]),
+
+            Opcode::IsZero => (vec![VarType::Base], vec![VarType::Base]),
+
             Opcode::ConstrainEqualBase => (vec![], vec![VarType::Base, VarType::Base]),
 
             Opcode::ConstrainEqualPoint => (vec![], vec![VarType::EcPoint, VarType::EcPoint]),
@@ -0,0 +1,110 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use async_std::future::timeout;
+use smol::Executor;
+use url::Url;
+
+use darkfi::net::{message, P2p, P2pPtr, Settings};
+
+const ALICE_SECRET: [u8; 32] = [1; 32];
+
+async fn spawn_node(settings: Settings, ex: Arc<Executor<'_>>) -> P2pPtr {
+    let p2p = P2p::new(settings).await;
+    p2p.clone().start(ex.clone()).await.unwrap();
+    ex.spawn(p2p.clone().run(ex.clone())).detach();
+    p2p
+}
+
+#[test]
+fn authenticated_handshake() {
+    let ex = Arc::new(Executor::new());
+
+    smol::block_on(ex.run(async {
+        let addr = Url::parse("tcp://127.0.0.1:27931").unwrap();
+
+        let alice = spawn_node(
+            Settings {
+                inbound: vec![addr.clone()],
+                localnet: true,
+                peer_discovery: false,
+                auth_handshake: true,
+                auth_secret: Some(bs58::encode(ALICE_SECRET).into_string()),
+                ..Default::default()
+            },
+            ex.clone(),
+        )
+        .await;
+
+        // Bob only trusts Alice
+        let bob = P2p::new(Settings {
+            peers: vec![addr.clone()],
+            localnet: true,
+            peer_discovery: false,
+            auth_handshake: true,
+            trusted_keys: vec![alice.auth_public_key()],
+            ..Default::default()
+        })
+        .await;
+        let channel_sub = bob.subscribe_channel().await;
+        bob.clone().start(ex.clone()).await.unwrap();
+        ex.spawn(bob.clone().run(ex.clone())).detach();
+
+        let channel =
+            timeout(Duration::from_secs(10), channel_sub.receive()).await.unwrap().unwrap();
+        assert_eq!(channel.remote_public_key().await, Some(alice.auth_public_key()));
+
+        // Messages go through the encrypted channel, Alice answers pings with pongs
+        let pong_sub = channel.subscribe_msg::<message::PongMessage>().await.unwrap();
+        channel.send(message::PingMessage { nonce: 42 }).await.unwrap();
+        loop {
+            let pong = timeout(Duration::from_secs(10), pong_sub.receive()).await.unwrap().unwrap();
+            if pong.nonce == 42 {
+                break
+            }
+        }
+
+        // Alice authenticated Bob as well
+        let alice_channels: Vec<_> = alice.channels().lock().await.values().cloned().collect();
+        assert_eq!(alice_channels.len(), 1);
+        assert_eq!(alice_channels[0].remote_public_key().await, Some(bob.auth_public_key()));
+
+        // Carol expects another key, so she refuses to talk to Alice
+        let carol = spawn_node(
+            Settings {
+                peers: vec![addr],
+                localnet: true,
+                peer_discovery: false,
+                manual_attempt_limit: 1,
+                auth_handshake: true,
+                trusted_keys: vec![bob.auth_public_key()],
+                ..Default::default()
+            },
+            ex.clone(),
+        )
+        .await;
+        async_std::task::sleep(Duration::from_secs(3)).await;
+        assert!(carol.channels().lock().await.is_empty());
+
+        alice.stop().await;
+        bob.stop().await;
+        carol.stop().await;
+    }));
+}
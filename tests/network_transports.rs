@@ -16,17 +16,20 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{env::var, fs};
+use std::{collections::HashMap, env::var, fs, time::Duration};
 
 use async_std::{
     io,
     io::{ReadExt, WriteExt},
-    stream::StreamExt,
+    net::TcpListener,
+    sync::{Arc, Mutex},
     task,
 };
+use async_tungstenite::tungstenite::Message;
+use futures::{SinkExt, StreamExt};
 use url::Url;
 
-use darkfi::net::transport::{TcpTransport, TorTransport, Transport, UnixTransport};
+use darkfi::net::transport::{NymTransport, TcpTransport, TorTransport, Transport, UnixTransport};
 
 #[async_std::test]
 async fn unix_transport() {
@@ -110,6 +113,145 @@ async fn tcp_tls_transport() {
     assert_eq!(buf, payload);
 }
 
+type NymRoutes = Arc<Mutex<HashMap<Vec<u8>, smol::channel::Sender<Vec<u8>>>>>;
+
+/// Stand-in for a local Nym client: answers self address requests and
+/// delivers send requests to the stand-in owning the recipient address.
+/// With `reorder` set, messages are delayed so that they overtake each other,
+/// like they may do in the mixnet.
+async fn nym_client_standin(
+    listener: TcpListener,
+    address: Vec<u8>,
+    routes: NymRoutes,
+    reorder: bool,
+) {
+    let (stream, _) = listener.accept().await.unwrap();
+    let ws = async_tungstenite::accept_async(stream).await.unwrap();
+    let (mut sink, mut ws_stream) = ws.split();
+
+    let (sender, receiver) = smol::channel::unbounded::<Vec<u8>>();
+    routes.lock().await.insert(address.clone(), sender.clone());
+
+    let _ = task::spawn(async move {
+        while let Ok(msg) = receiver.recv().await {
+            sink.send(Message::Binary(msg)).await.unwrap();
+        }
+    });
+
+    let mut sent = 0;
+    while let Some(Ok(Message::Binary(req))) = ws_stream.next().await {
+        match req[0] {
+            // Self address request
+            0x02 => sender.send([&[0x02], &address[..]].concat()).await.unwrap(),
+            // Send request: tag, reply surb flag, recipient, length, data
+            0x00 => {
+                let data = &req[106..];
+                let resp = [&[0x01, 0x00], &(data.len() as u64).to_be_bytes()[..], data].concat();
+                let Some(route) = routes.lock().await.get(&req[2..98]).cloned() else { continue };
+                if !reorder {
+                    route.send(resp).await.unwrap();
+                    continue
+                }
+
+                // Every group of four messages is delivered in reverse order
+                let delay = Duration::from_millis(40 - 10 * (sent % 4));
+                sent += 1;
+                let _ = task::spawn(async move {
+                    task::sleep(delay).await;
+                    route.send(resp).await.unwrap();
+                });
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Set up two Nym client stand-ins on the given ports, and an echo server
+/// listening through the first one. Returns the message routes of the
+/// stand-ins, the server's mixnet address and a transport to dial it with.
+async fn nym_fixture(ports: [u16; 2], reorder: bool) -> (NymRoutes, Url, NymTransport) {
+    let routes = Arc::new(Mutex::new(HashMap::new()));
+    for port in ports {
+        let listener = TcpListener::bind(("127.0.0.1", port)).await.unwrap();
+        let address = vec![port as u8; 96];
+        let _ = task::spawn(nym_client_standin(listener, address, routes.clone(), reorder));
+    }
+
+    let client_url = |port| Url::parse(&format!("ws://127.0.0.1:{}", port)).unwrap();
+    let mut server = NymTransport::new(client_url(ports[0])).unwrap();
+    let client = NymTransport::new(client_url(ports[1])).unwrap();
+
+    let url = Url::parse("nym://127.0.0.1").unwrap();
+    let listener = server.clone().listen_on(url).unwrap().await.unwrap();
+
+    let _ = task::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (mut reader, mut writer) = smol::io::split(stream);
+            io::copy(&mut reader, &mut writer).await.unwrap();
+        }
+    });
+
+    let url = server.self_address("nym").await.unwrap();
+    (routes, url, client)
+}
+
+#[async_std::test]
+async fn nym_transport() {
+    let (_, url, client) = nym_fixture([5434, 5435], false).await;
+
+    let payload = b"ohai nym";
+
+    let mut client = client.dial(url, None).unwrap().await.unwrap();
+    client.write_all(payload).await.unwrap();
+    let mut buf = vec![0_u8; 8];
+    client.read_exact(&mut buf).await.unwrap();
+
+    assert_eq!(buf, payload);
+}
+
+#[async_std::test]
+async fn nym_transport_reordered() {
+    let (_, url, client) = nym_fixture([5436, 5437], true).await;
+
+    // Each write goes out as its own frame, so the stream spans many
+    // mixnet messages which arrive out of order.
+    let chunks: Vec<Vec<u8>> = (0..10_u8).map(|i| vec![i; 16]).collect();
+
+    let mut client = client.dial(url, None).unwrap().await.unwrap();
+    for chunk in &chunks {
+        client.write_all(chunk).await.unwrap();
+    }
+    let mut buf = vec![0_u8; 160];
+    client.read_exact(&mut buf).await.unwrap();
+
+    assert_eq!(buf, chunks.concat());
+}
+
+#[async_std::test]
+async fn nym_transport_stray_frames() {
+    let (routes, url, client) = nym_fixture([5438, 5439], false).await;
+
+    // Flood the listener with data frames of streams that were never opened
+    let route = routes.lock().await.get(&vec![5438_u16 as u8; 96]).cloned().unwrap();
+    for conn_id in 0..2048_u64 {
+        let frame =
+            [&[0x01], &conn_id.to_le_bytes()[..], &1_u64.to_le_bytes()[..], &b"junk"[..]].concat();
+        let resp = [&[0x01, 0x00], &(frame.len() as u64).to_be_bytes()[..], &frame[..]].concat();
+        route.send(resp).await.unwrap();
+    }
+
+    // New streams can still be opened
+    let payload = b"ohai nym";
+
+    let mut client = client.dial(url, None).unwrap().await.unwrap();
+    client.write_all(payload).await.unwrap();
+    let mut buf = vec![0_u8; 8];
+    client.read_exact(&mut buf).await.unwrap();
+
+    assert_eq!(buf, payload);
+}
+
 #[async_std::test]
 #[ignore]
 async fn tor_transport_no_control() {
@@ -0,0 +1,188 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    blockchain::Blockchain,
+    runtime::{module_cache::ModuleCache, vm_runtime::Runtime},
+    util::time::Timestamp,
+    Error, Result,
+};
+use darkfi_sdk::{crypto::ContractId, error::ContractError, pasta::pallas};
+use darkfi_serial::serialize;
+
+/// Maximum depth of nested contract calls, as enforced by the runtime
+const MAX_CALL_DEPTH: u64 = 8;
+
+/// A contract whose exec, query and update sections all call into the
+/// query section of `target`, failing with the error code of the call.
+fn forwarder(target: ContractId) -> Vec<u8> {
+    let target: String = serialize(&target).iter().map(|b| format!("\\{:02x}", b)).collect();
+    let wat = format!(
+        r#"(module
+            (import "env" "call_contract_" (func $call_contract (param i32 i32) (result i64)))
+            (memory (export "memory") 1)
+            ;; Serialized target ContractId followed by an empty payload
+            (data (i32.const 1024) "{}\00")
+            (func $forward (result i64)
+                (local $ret i64)
+                (local.set $ret (call $call_contract (i32.const 1024) (i32.const 33)))
+                (if (result i64) (i64.lt_s (local.get $ret) (i64.const 0))
+                    (then (local.get $ret))
+                    (else (i64.const 0))))
+            (func (export "__entrypoint") (param i32) (result i64) (call $forward))
+            (func (export "__query") (param i32) (result i64) (call $forward))
+            (func (export "__update") (param i32) (result i64) (call $forward)))"#,
+        target
+    );
+    wasmer::wat2wasm(wat.as_bytes()).unwrap().to_vec()
+}
+
+/// A contract whose query section burns some gas and succeeds.
+fn leaf() -> Vec<u8> {
+    let wat = r#"(module
+        (memory (export "memory") 1)
+        (func (export "__query") (param i32) (result i64)
+            (local $i i32)
+            (loop $burn
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br_if $burn (i32.lt_u (local.get $i) (i32.const 1000))))
+            (i64.const 0)))"#;
+    wasmer::wat2wasm(wat.as_bytes()).unwrap().to_vec()
+}
+
+/// A contract whose query section tries to write to a database.
+fn writer() -> Vec<u8> {
+    let wat = r#"(module
+        (import "env" "db_set_" (func $db_set (param i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (func (export "__query") (param i32) (result i64)
+            (if (result i64) (i32.eqz (call $db_set (i32.const 0) (i32.const 0)))
+                (then (i64.const 0))
+                (else (i64.const 1)))))"#;
+    wasmer::wat2wasm(wat.as_bytes()).unwrap().to_vec()
+}
+
+fn contract_id(n: u64) -> ContractId {
+    ContractId::from(pallas::Base::from(n))
+}
+
+fn contract_error(res: Result<(Vec<u8>, u64)>) -> ContractError {
+    match res {
+        Err(Error::ContractError(e)) => e,
+        Err(e) => panic!("Expected a contract error, got: {}", e),
+        Ok(_) => panic!("Expected a contract error, call succeeded"),
+    }
+}
+
+#[test]
+fn runtime_call_contract() -> Result<()> {
+    let sled_db = sled::Config::new().temporary(true).open()?;
+    let blockchain = Blockchain::new(&sled_db, Timestamp::current_time(), blake3::hash(b"test"))?;
+    let module_cache = ModuleCache::new();
+    let gas_limit = 1_000_000;
+
+    // A chain of forwarders, one more than the maximum call depth, ending
+    // with a leaf contract.
+    let leaf_id = contract_id(100 + MAX_CALL_DEPTH + 1);
+    blockchain.wasm_bincode.insert(leaf_id, &leaf())?;
+    for n in 100..=100 + MAX_CALL_DEPTH {
+        blockchain.wasm_bincode.insert(contract_id(n), &forwarder(contract_id(n + 1)))?;
+    }
+
+    let runtime = |cid: ContractId, gas_limit: u64| -> Result<Runtime> {
+        let wasm = blockchain.wasm_bincode.get(cid)?;
+        Runtime::new(&wasm, blockchain.clone(), &module_cache, cid, gas_limit)
+    };
+
+    // The gas consumed by the called contract is charged to the caller
+    let (_, leaf_gas) = runtime(leaf_id, gas_limit)?.query(&[])?;
+    let caller_id = contract_id(100 + MAX_CALL_DEPTH);
+    let (_, caller_gas) = runtime(caller_id, gas_limit)?.exec(&[])?;
+    assert!(caller_gas > leaf_gas);
+
+    // The caller can't hand out more gas than it has left
+    assert!(runtime(caller_id, leaf_gas / 2)?.exec(&[]).is_err());
+
+    // Calls can be nested up to the maximum depth
+    assert!(runtime(contract_id(101), gas_limit)?.exec(&[]).is_ok());
+    assert!(matches!(
+        contract_error(runtime(contract_id(100), gas_limit)?.exec(&[])),
+        ContractError::CallContractFailed
+    ));
+
+    // Only exec, metadata and query sections can call other contracts
+    let mut rt = runtime(caller_id, gas_limit)?;
+    let res = rt.apply(&[]).map(|gas| (vec![], gas));
+    assert!(matches!(contract_error(res), ContractError::CallerAccessDenied));
+
+    Ok(())
+}
+
+#[test]
+fn runtime_call_contract_reentrancy() -> Result<()> {
+    let sled_db = sled::Config::new().temporary(true).open()?;
+    let blockchain = Blockchain::new(&sled_db, Timestamp::current_time(), blake3::hash(b"test"))?;
+    let module_cache = ModuleCache::new();
+    let gas_limit = 1_000_000;
+
+    // A contract calling into itself
+    let self_id = contract_id(200);
+    blockchain.wasm_bincode.insert(self_id, &forwarder(self_id))?;
+
+    // Two contracts calling into each other
+    let (a_id, b_id) = (contract_id(201), contract_id(202));
+    blockchain.wasm_bincode.insert(a_id, &forwarder(b_id))?;
+    blockchain.wasm_bincode.insert(b_id, &forwarder(a_id))?;
+
+    let runtime = |cid: ContractId| -> Result<Runtime> {
+        let wasm = blockchain.wasm_bincode.get(cid)?;
+        Runtime::new(&wasm, blockchain.clone(), &module_cache, cid, gas_limit)
+    };
+
+    assert!(matches!(contract_error(runtime(self_id)?.exec(&[])), ContractError::ReentrantCall));
+
+    // The re-entrant call fails inside of the called contract, which in
+    // turn makes the outer call fail.
+    assert!(matches!(contract_error(runtime(a_id)?.exec(&[])), ContractError::CallContractFailed));
+    assert!(matches!(contract_error(runtime(b_id)?.query(&[])), ContractError::CallContractFailed));
+
+    Ok(())
+}
+
+#[test]
+fn runtime_query_is_read_only() -> Result<()> {
+    let sled_db = sled::Config::new().temporary(true).open()?;
+    let blockchain = Blockchain::new(&sled_db, Timestamp::current_time(), blake3::hash(b"test"))?;
+    let module_cache = ModuleCache::new();
+
+    let writer_id = contract_id(300);
+    let wasm = writer();
+    blockchain.wasm_bincode.insert(writer_id, &wasm)?;
+
+    // Writes are denied to the query section
+    let mut runtime = Runtime::new(&wasm, blockchain.clone(), &module_cache, writer_id, 1_000_000)?;
+    assert!(matches!(contract_error(runtime.query(&[])), ContractError::Custom(1)));
+
+    // Which also holds when the query is called by another contract
+    let caller_id = contract_id(301);
+    let wasm = forwarder(writer_id);
+    let mut runtime = Runtime::new(&wasm, blockchain, &module_cache, caller_id, 1_000_000)?;
+    assert!(matches!(contract_error(runtime.exec(&[])), ContractError::CallContractFailed));
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use halo2_proofs::{arithmetic::Field, circuit::Value, pasta::pallas};
+use rand::rngs::OsRng;
+
+use darkfi::{
+    zk::{
+        proof::{ProvingKey, VerifyingKey},
+        vm::ZkCircuit,
+        vm_stack::{empty_witnesses, Witness},
+        Proof,
+    },
+    zkas::ZkBinary,
+    Result,
+};
+
+#[test]
+fn zk_batch_verify() -> Result<()> {
+    let bincode = include_bytes!("../proof/arithmetic.zk.bin");
+    let zkbin = ZkBinary::decode(bincode)?;
+
+    let verifier_witnesses = empty_witnesses(&zkbin);
+    let circuit = ZkCircuit::new(verifier_witnesses, zkbin.clone());
+    let proving_key = ProvingKey::build(zkbin.k, &circuit);
+    let verifying_key = VerifyingKey::build(zkbin.k, &circuit);
+
+    let mut proofs = vec![];
+    for _ in 0..4 {
+        let a = pallas::Base::random(&mut OsRng);
+        let b = pallas::Base::random(&mut OsRng);
+
+        let prover_witnesses =
+            vec![Witness::Base(Value::known(a)), Witness::Base(Value::known(b))];
+        let public_inputs = vec![a + b, a * b, a - b];
+
+        let circuit = ZkCircuit::new(prover_witnesses, zkbin.clone());
+        let proof = Proof::create(&proving_key, &[circuit], &public_inputs, &mut OsRng)?;
+        proofs.push((proof, public_inputs));
+    }
+
+    let batch: Vec<_> = proofs.iter().map(|(p, i)| (p, i.as_slice())).collect();
+    assert!(Proof::verify_batch(&verifying_key, &batch).is_ok());
+
+    // Tamper with the public inputs of one proof. The batch has to fail,
+    // and point us to the right proof.
+    let bad_inputs = vec![pallas::Base::one(), pallas::Base::one(), pallas::Base::one()];
+    let mut batch = batch;
+    batch[2].1 = bad_inputs.as_slice();
+    match Proof::verify_batch(&verifying_key, &batch) {
+        Err((i, _)) => assert_eq!(i, 2),
+        Ok(()) => panic!("Batch with an invalid proof verified"),
+    }
+
+    Ok(())
+}
@@ -116,15 +116,20 @@ fn zkvm_opcodes() -> Result<()> {
         pub_y,
         ephem_x,
         ephem_y,
+        a,
+        pallas::Base::zero(),
+        b,
+        pallas::Base::one(),
+        pallas::Base::zero(),
     ];
 
     let circuit = ZkCircuit::new(prover_witnesses, zkbin.clone());
-    let proving_key = ProvingKey::build(13, &circuit);
+    let proving_key = ProvingKey::build(zkbin.k, &circuit);
     let proof = Proof::create(&proving_key, &[circuit], &public_inputs, &mut OsRng)?;
 
     let verifier_witnesses = empty_witnesses(&zkbin);
-    let circuit = ZkCircuit::new(verifier_witnesses, zkbin);
-    let verifying_key = VerifyingKey::build(13, &circuit);
+    let circuit = ZkCircuit::new(verifier_witnesses, zkbin.clone());
+    let verifying_key = VerifyingKey::build(zkbin.k, &circuit);
     proof.verify(&verifying_key, &public_inputs)?;
 
     Ok(())